@@ -0,0 +1,140 @@
+//! This module defines the functionality used to resolve the previous slugs
+//! of the categories and subcategories that have been renamed (aliases) to
+//! their current names, so that the links shared before a reorganization keep
+//! working.
+//!
+//! The slug of a category is its normalized name, and the slug of a
+//! subcategory is the slug of its category followed by its own normalized name
+//! (i.e. `app-definition-and-development/database`). Category permalinks use
+//! the category and subcategory names in the query string, so the aliases are
+//! included in the base dataset and the web application replaces the previous
+//! names in the query string with the current ones. In addition, each alias is
+//! served at `categories/<slug>/`, either as a lightweight page redirecting to
+//! the current permalink or as a 301 redirect (when the redirects are deployed
+//! to S3 using the redirects file generated).
+
+use super::{data::normalize_name, sitemap::permalink, LandscapeData};
+use anyhow::{format_err, Result};
+use askama::Template;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Path where the aliases redirects will be written to in the output directory.
+pub(crate) const ALIASES_PATH: &str = "categories";
+
+/// Template used to render the page that redirects an alias to the current
+/// permalink of the category (or subcategory).
+#[derive(Debug, Clone, Template)]
+#[template(path = "alias-redirect.html")]
+pub(crate) struct AliasRedirectPage<'a> {
+    pub foundation: &'a str,
+    pub name: &'a str,
+    pub url: &'a str,
+}
+
+/// Redirect from the previous slug of a category (or subcategory) to its
+/// current permalink.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AliasRedirect {
+    /// Previous slug of the category (or subcategory).
+    pub slug: String,
+    /// Category (or subcategory) the alias points to.
+    pub target: AliasTarget,
+}
+
+impl AliasRedirect {
+    /// Return the current name of the category (or subcategory), as displayed
+    /// in the redirect page.
+    pub(crate) fn name(&self) -> String {
+        match &self.target.subcategory {
+            Some(subcategory) => format!("{} / {subcategory}", self.target.category),
+            None => self.target.category.clone(),
+        }
+    }
+
+    /// Return the path of the redirect in the output directory.
+    pub(crate) fn path(&self) -> String {
+        format!("{ALIASES_PATH}/{}/", self.slug)
+    }
+
+    /// Return the url of the current permalink, relative to the redirect page
+    /// (so that it works when the landscape is served from a base path).
+    pub(crate) fn relative_url(&self) -> String {
+        let depth = self.path().matches('/').count();
+        self.url(&vec![".."; depth].join("/"))
+    }
+
+    /// Return the url of the current permalink using the base url provided.
+    pub(crate) fn url(&self, base_url: &str) -> String {
+        let mut params = vec![("category", self.target.category.as_str())];
+        if let Some(subcategory) = &self.target.subcategory {
+            params.push(("subcategory", subcategory.as_str()));
+        }
+        permalink(base_url, &params)
+    }
+}
+
+/// Current names of the category (and subcategory) an alias points to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct AliasTarget {
+    pub category: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subcategory: Option<String>,
+}
+
+/// Prepare the redirects for the aliases provided, checking that they point
+/// to categories (or subcategories) in the landscape and that they don't
+/// collide with the slugs currently in use. When the landscape categories have
+/// been filtered, the aliases pointing to the ones filtered out are ignored.
+pub(crate) fn get_aliases_redirects(
+    aliases: &BTreeMap<String, String>,
+    landscape_data: &LandscapeData,
+    categories_filtered: bool,
+) -> Result<Vec<AliasRedirect>> {
+    // Collect the slugs currently in use
+    let mut live_slugs: HashMap<String, AliasTarget> = HashMap::new();
+    for category in &landscape_data.categories {
+        let category_slug = normalize_name(&category.name);
+        for subcategory in &category.subcategories {
+            live_slugs.insert(
+                format!("{category_slug}/{}", normalize_name(subcategory)),
+                AliasTarget {
+                    category: category.name.clone(),
+                    subcategory: Some(subcategory.clone()),
+                },
+            );
+        }
+        live_slugs.insert(
+            category_slug,
+            AliasTarget {
+                category: category.name.clone(),
+                subcategory: None,
+            },
+        );
+    }
+
+    // Prepare the redirect of each alias
+    let mut redirects = vec![];
+    for (from, to) in aliases {
+        if live_slugs.contains_key(from) {
+            return Err(format_err!(
+                "invalid alias {from}: it collides with the slug of a category (or subcategory) in use"
+            ));
+        }
+        let Some(target) = live_slugs.get(to) else {
+            if categories_filtered {
+                continue;
+            }
+            return Err(format_err!(
+                "invalid alias {from}: {to} does not match any category (or subcategory)"
+            ));
+        };
+        redirects.push(AliasRedirect {
+            slug: from.clone(),
+            target: target.clone(),
+        });
+    }
+
+    Ok(redirects)
+}
@@ -0,0 +1,139 @@
+//! This module defines the functionality used to prepare the analytics (or
+//! tracking) snippet injected into the landscape index document.
+//!
+//! The snippet is generated from the analytics providers configured in the
+//! settings file (Google Analytics and Plausible), optionally followed by a
+//! custom snippet. Custom snippets are validated before being injected, so
+//! that malformed HTML doesn't break the index document.
+
+use super::settings::Analytics;
+use anyhow::{format_err, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::Write as _;
+
+/// Default Plausible script url.
+const PLAUSIBLE_SCRIPT_URL: &str = "https://plausible.io/js/script.js";
+
+/// Elements allowed in the custom analytics snippets.
+const ALLOWED_ELEMENTS: [&str; 6] = ["iframe", "img", "link", "meta", "noscript", "script"];
+
+/// Void elements (they don't have a closing tag).
+const VOID_ELEMENTS: [&str; 3] = ["img", "link", "meta"];
+
+lazy_static! {
+    /// Tag regular expression (matched at the beginning of the input).
+    static ref TAG: Regex = Regex::new(
+        r#"^<(?P<closing>/)?(?P<name>[a-zA-Z][a-zA-Z0-9\-]*)(\s+[^\s"'>/=]+(\s*=\s*("[^"]*"|'[^']*'|[^\s"'=<>`]+))?)*\s*(?P<self_closing>/)?>"#
+    )
+    .expect("exprs in TAG to be valid");
+}
+
+/// Render the analytics snippet for the configuration provided.
+pub(crate) fn render_snippet(analytics: &Analytics) -> String {
+    let mut snippet = String::new();
+
+    // Google Analytics
+    if let Some(ga) = &analytics.google {
+        let id = &ga.measurement_id;
+        let _ = write!(
+            snippet,
+            r#"<script async src="https://www.googletagmanager.com/gtag/js?id={id}"></script>"#
+        );
+        snippet.push_str("\n<script>\n");
+        snippet.push_str("window.dataLayer = window.dataLayer || [];\n");
+        snippet.push_str("function gtag(){dataLayer.push(arguments);}\n");
+        snippet.push_str("gtag('js', new Date());\n");
+        let _ = writeln!(snippet, "gtag('config', '{id}');");
+        snippet.push_str("</script>\n");
+    }
+
+    // Plausible
+    if let Some(plausible) = &analytics.plausible {
+        let script_url = plausible.script_url.as_deref().unwrap_or(PLAUSIBLE_SCRIPT_URL);
+        let _ = write!(
+            snippet,
+            r#"<script defer data-domain="{}" src="{script_url}"></script>"#,
+            plausible.domain
+        );
+        snippet.push('\n');
+    }
+
+    // Custom snippet
+    if let Some(custom_snippet) = &analytics.snippet {
+        snippet.push_str(custom_snippet.trim());
+        snippet.push('\n');
+    }
+
+    snippet
+}
+
+/// Check the custom analytics snippet provided is well formed. Only some
+/// elements are allowed (scripts, noscript fallbacks, links, meta tags and
+/// tracking pixels), all of them must be closed properly and no text is
+/// allowed outside of them.
+pub(crate) fn validate_snippet(snippet: &str) -> Result<()> {
+    let invalid = |reason: &str| Err(format_err!("invalid analytics snippet: {reason}"));
+
+    let mut open_elements: Vec<String> = vec![];
+    let mut rest = snippet;
+    loop {
+        // Only whitespace is allowed outside of the elements (noscript
+        // fallbacks can contain some text)
+        let text_end = rest.find('<').unwrap_or(rest.len());
+        let in_noscript = open_elements.last().is_some_and(|name| name == "noscript");
+        if !rest[..text_end].trim().is_empty() && !in_noscript {
+            return invalid("text is not allowed outside of the elements");
+        }
+        rest = &rest[text_end..];
+        if rest.is_empty() {
+            break;
+        }
+
+        // Skip comments
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let Some(end) = comment.find("-->") else {
+                return invalid("unclosed comment");
+            };
+            rest = &comment[end + 3..];
+            continue;
+        }
+
+        // Check tag
+        let Some(tag) = TAG.captures(rest) else {
+            return invalid("malformed tag");
+        };
+        let tag_len = tag[0].len();
+        let name = tag["name"].to_ascii_lowercase();
+        if !ALLOWED_ELEMENTS.contains(&name.as_str()) {
+            return invalid(&format!(
+                "element {name} is not allowed (allowed elements: {})",
+                ALLOWED_ELEMENTS.join(", ")
+            ));
+        }
+        rest = &rest[tag_len..];
+        if tag.name("closing").is_some() {
+            if open_elements.pop().as_ref() != Some(&name) {
+                return invalid(&format!("unexpected closing tag for element {name}"));
+            }
+        } else if !VOID_ELEMENTS.contains(&name.as_str()) {
+            if tag.name("self_closing").is_some() {
+                return invalid(&format!("element {name} must be closed using a closing tag"));
+            }
+            if name == "script" {
+                // Scripts content is skipped up to their closing tag
+                let Some(end) = rest.to_ascii_lowercase().find("</script>") else {
+                    return invalid("unclosed element script");
+                };
+                rest = &rest[end + "</script>".len()..];
+            } else {
+                open_elements.push(name);
+            }
+        }
+    }
+    if let Some(name) = open_elements.last() {
+        return invalid(&format!("unclosed element {name}"));
+    }
+
+    Ok(())
+}
@@ -1,21 +1,29 @@
 //! This module defines the cache used to cache files across builds.
 
 use anyhow::{format_err, Result};
+use chrono::{DateTime, Duration, Utc};
 use std::{fs, io::Write, path::PathBuf, time::SystemTime};
-use tracing::instrument;
+use tracing::{info, instrument};
 
 /// Path where the cache files will be written to inside the cache directory.
-const CACHE_PATH: &str = "landscape";
+pub(crate) const CACHE_PATH: &str = "landscape";
+
+/// Default number of days the data collected from external services is valid.
+pub(crate) const DEFAULT_CACHE_TTL: u32 = 7;
 
 /// Cache used to store data collected from external services.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub(crate) struct Cache {
-    cache_dir: PathBuf,
+    dir: PathBuf,
+    ttl: Duration,
+    refresh: bool,
 }
 
 impl Cache {
-    /// Create a new Cache instance.
-    pub(crate) fn new(cache_dir: &Option<PathBuf>) -> Result<Self> {
+    /// Create a new Cache instance. Data collected from external services is
+    /// valid for the number of days provided (ttl), unless a refresh has been
+    /// requested, in which case all cached data will be considered expired.
+    pub(crate) fn new(cache_dir: Option<&PathBuf>, ttl: u32, refresh: bool) -> Result<Self> {
         // Try to use user's cache directory if no cache_dir has been provided
         let cache_dir = match cache_dir {
             Some(cache_dir) => Some(cache_dir.clone()),
@@ -27,7 +35,14 @@ impl Cache {
             if !cache_dir.exists() {
                 fs::create_dir_all(&cache_dir)?;
             }
-            return Ok(Self { cache_dir });
+            if refresh {
+                info!("cache refresh requested: data from external services will be collected again");
+            }
+            return Ok(Self {
+                dir: cache_dir,
+                ttl: Duration::days(i64::from(ttl)),
+                refresh,
+            });
         }
 
         Err(format_err!(
@@ -35,11 +50,16 @@ impl Cache {
         ))
     }
 
+    /// Check if the data generated at the timestamp provided is still valid.
+    pub(crate) fn is_fresh(&self, generated_at: DateTime<Utc>) -> bool {
+        !self.refresh && generated_at + self.ttl > Utc::now()
+    }
+
     /// Read data from the cache file provided if available.
     #[instrument(skip_all, err)]
     pub(crate) fn read(&self, file_name: &str) -> Result<Option<(Option<SystemTime>, Vec<u8>)>> {
         // Check if the path exists
-        let path = self.cache_dir.join(file_name);
+        let path = self.dir.join(file_name);
         if !path.exists() {
             return Ok(None);
         }
@@ -54,7 +74,7 @@ impl Cache {
     /// Write provided data to cache file.
     #[instrument(skip_all, err)]
     pub(crate) fn write(&self, file_name: &str, data: &[u8]) -> Result<()> {
-        let path = self.cache_dir.join(file_name);
+        let path = self.dir.join(file_name);
         let mut file = fs::File::create(path)?;
         file.write_all(data)?;
         Ok(())
@@ -0,0 +1,94 @@
+//! This module provides some helper functions to generate the items' social
+//! cards (images displayed when sharing an item's link on social networks).
+
+use super::settings::SocialCards;
+use anyhow::{format_err, Result};
+use base64::{engine::general_purpose::STANDARD as b64, Engine as _};
+use lazy_static::lazy_static;
+use resvg::tiny_skia;
+use usvg::{fontdb, TreeParsing, TreeTextToPath};
+
+/// Default social card background color.
+const DEFAULT_BACKGROUND_COLOR: &str = "rgba(255, 255, 255, 1)";
+
+/// Default social card height (in pixels).
+const DEFAULT_HEIGHT: u32 = 630;
+
+/// Default social card width (in pixels).
+const DEFAULT_WIDTH: u32 = 1200;
+
+lazy_static! {
+    /// Fonts database used to render the social cards text.
+    static ref FONTS: fontdb::Database = {
+        let mut fonts = fontdb::Database::new();
+        fonts.load_system_fonts();
+        fonts
+    };
+}
+
+/// Get the dimensions and background color of the social cards, using the
+/// default values for the ones not provided in the configuration.
+pub(crate) fn get_social_card_settings(cfg: &SocialCards) -> (u32, u32, &str) {
+    (
+        cfg.width.unwrap_or(DEFAULT_WIDTH),
+        cfg.height.unwrap_or(DEFAULT_HEIGHT),
+        cfg.background_color.as_deref().unwrap_or(DEFAULT_BACKGROUND_COLOR),
+    )
+}
+
+/// Render the social card of an item in PNG format, including its logo, name
+/// and category.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub(crate) fn render_social_card(
+    cfg: &SocialCards,
+    name: &str,
+    category: &str,
+    logo_svg: &[u8],
+) -> Result<Vec<u8>> {
+    let (width, height, background_color) = get_social_card_settings(cfg);
+
+    // Prepare card layout (logo in the top area, name and category below)
+    let (w, h) = (width as f32, height as f32);
+    let logo_height = h * 0.5;
+    let name_font_size = h * 0.09;
+    let category_font_size = h * 0.05;
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">
+            <rect width="{width}" height="{height}" fill="{background_color}"/>
+            <image x="{logo_x}" y="{logo_y}" width="{logo_width}" height="{logo_height}" preserveAspectRatio="xMidYMid meet" href="data:image/svg+xml;base64,{logo}"/>
+            <text x="{center}" y="{name_y}" font-family="sans-serif" font-size="{name_font_size}" font-weight="bold" text-anchor="middle" fill="rgba(0, 0, 0, 1)">{name}</text>
+            <text x="{center}" y="{category_y}" font-family="sans-serif" font-size="{category_font_size}" text-anchor="middle" fill="rgba(90, 90, 90, 1)">{category}</text>
+        </svg>"#,
+        logo_x = w * 0.1,
+        logo_y = h * 0.1,
+        logo_width = w * 0.8,
+        logo = b64.encode(logo_svg),
+        center = w / 2.0,
+        name_y = h * 0.75,
+        category_y = h * 0.75 + name_font_size * 1.2,
+        name = xml_escape(name),
+        category = xml_escape(category),
+    );
+
+    // Render card and encode it as PNG
+    let mut tree = usvg::Tree::from_data(svg.as_bytes(), &usvg::Options::default())?;
+    tree.convert_text(&FONTS);
+    let tree = resvg::Tree::from_usvg(&tree);
+    let mut pixmap =
+        tiny_skia::Pixmap::new(width, height).ok_or_else(|| format_err!("invalid social card size"))?;
+    tree.render(tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap.encode_png()?)
+}
+
+/// Escape the XML special characters in the text provided.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,72 @@
+//! This module defines the `DataCollector` trait, implemented by the
+//! collectors used to enrich the landscape items with data from external
+//! services, as well as the registry of collectors run during the build.
+//!
+//! The built-in collectors (Crunchbase and GitHub) are always run first, and
+//! concurrently, as they don't depend on each other. Additional collectors can be registered in the build configuration when
+//! using landscape2 as a library:
+//!
+//! ```ignore
+//! let mut config = landscape2::BuildConfig::default();
+//! config.collectors.register(MyRegistryCollector::new());
+//! ```
+
+use super::Item;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::{fmt, sync::Arc};
+use tracing::{debug, instrument};
+
+/// Trait that defines the operations a data collector must support.
+#[async_trait]
+pub trait DataCollector {
+    /// Name of the collector (used in logs and error messages).
+    fn name(&self) -> &str;
+
+    /// Collect data for the items provided, updating them with it.
+    ///
+    /// # Errors
+    ///
+    /// Returning an error makes the build fail. Collectors that cannot get
+    /// the data for some of the items should only log it.
+    async fn collect(&self, items: &mut [Item]) -> Result<()>;
+}
+
+/// Type alias to represent a DataCollector trait object.
+type DynDataCollector = Arc<dyn DataCollector + Send + Sync>;
+
+/// Registry of data collectors. Collectors are run one after another in the
+/// order they were registered, so they can use the data added by the previous
+/// ones.
+#[derive(Clone, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct DataCollectors {
+    collectors: Vec<DynDataCollector>,
+}
+
+impl DataCollectors {
+    /// Register the data collector provided.
+    pub fn register(&mut self, collector: impl DataCollector + Send + Sync + 'static) -> &mut Self {
+        self.collectors.push(Arc::new(collector));
+        self
+    }
+
+    /// Run all the data collectors registered on the items provided.
+    #[instrument(skip_all, err)]
+    pub(crate) async fn collect(&self, items: &mut [Item]) -> Result<()> {
+        for collector in &self.collectors {
+            debug!(collector = collector.name(), "running data collector");
+            collector
+                .collect(items)
+                .await
+                .context(format!("error running {} data collector", collector.name()))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DataCollectors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.collectors.iter().map(|collector| collector.name())).finish()
+    }
+}
@@ -1,11 +1,14 @@
 //! This module defines some types used to represent the information collected
 //! from Crunchbase for each of the landscape items (when applicable), as well
 //! as the functionality used to collect that information.
+//!
+//! Details about the latest funding round of each organization are only
+//! collected when enabled in the settings file (`crunchbase.funding_rounds`).
 
-use super::{cache::Cache, LandscapeData};
+use super::{cache::Cache, collectors::DataCollector, http::HttpClient, settings::Crunchbase, Item};
 use anyhow::{format_err, Result};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use futures::stream::{self, StreamExt};
 use lazy_static::lazy_static;
 use leaky_bucket::RateLimiter;
@@ -20,21 +23,79 @@ use tracing::{debug, instrument, warn};
 /// File used to cache data collected from Crunchbase.
 const CRUNCHBASE_CACHE_FILE: &str = "crunchbase.json";
 
-/// How long the Crunchbase data in the cache is valid (in days).
-const CRUNCHBASE_CACHE_TTL: i64 = 7;
-
 /// Environment variable containing the Crunchbase API key.
 const CRUNCHBASE_API_KEY: &str = "CRUNCHBASE_API_KEY";
 
 /// Interval for the rate limiter used when sending requests to the CB API.
 const CRUNCHBASE_RATE_LIMITER_INTERVAL: Duration = Duration::from_millis(300);
 
+/// Data collector used to add the information collected from Crunchbase to
+/// the landscape items orgs.
+pub(crate) struct CrunchbaseCollector {
+    cache: Cache,
+    funding_rounds: bool,
+    http: HttpClient,
+}
+
+impl CrunchbaseCollector {
+    /// Create a new CrunchbaseCollector instance.
+    pub(crate) fn new(cache: &Cache, http: &HttpClient, cfg: Option<&Crunchbase>) -> Self {
+        Self {
+            cache: cache.clone(),
+            funding_rounds: cfg.and_then(|cfg| cfg.funding_rounds).unwrap_or(false),
+            http: http.clone(),
+        }
+    }
+
+    /// Collect the Crunchbase data of the items orgs provided (the items are
+    /// not updated, so that this collector can run concurrently with others).
+    pub(crate) async fn fetch(&self, items: &[Item]) -> Result<CrunchbaseData> {
+        collect_crunchbase_data(&self.cache, &self.http, self.funding_rounds, items).await
+    }
+
+    /// Add the Crunchbase data provided to the items orgs.
+    pub(crate) fn apply(&self, items: &mut [Item], crunchbase_data: &CrunchbaseData) {
+        for item in items {
+            if let Some(crunchbase_url) = item.crunchbase_url.as_ref() {
+                if let Some(org_crunchbase_data) = crunchbase_data.get(crunchbase_url) {
+                    let mut org_crunchbase_data = org_crunchbase_data.clone();
+
+                    // The cache may be shared with landscapes collecting the
+                    // funding rounds, so we only keep them when enabled
+                    if !self.funding_rounds {
+                        org_crunchbase_data.latest_funding_round = None;
+                    }
+                    item.crunchbase_data = Some(org_crunchbase_data);
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DataCollector for CrunchbaseCollector {
+    /// [DataCollector::name]
+    fn name(&self) -> &'static str {
+        "crunchbase"
+    }
+
+    /// [DataCollector::collect]
+    async fn collect(&self, items: &mut [Item]) -> Result<()> {
+        let crunchbase_data = self.fetch(items).await?;
+        self.apply(items, &crunchbase_data);
+        Ok(())
+    }
+}
+
 /// Collect Crunchbase data for each of the items orgs in the landscape,
-/// reusing cached data whenever possible.
+/// reusing cached data whenever possible. Cached organizations collected
+/// without the funding rounds details are not reused when they are requested.
 #[instrument(skip_all, err)]
-pub(crate) async fn collect_crunchbase_data(
+async fn collect_crunchbase_data(
     cache: &Cache,
-    landscape_data: &LandscapeData,
+    http: &HttpClient,
+    funding_rounds: bool,
+    items: &[Item],
 ) -> Result<CrunchbaseData> {
     debug!("collecting organizations information from crunchbase (this may take a while)");
 
@@ -44,7 +105,7 @@ pub(crate) async fn collect_crunchbase_data(
         if let Ok(crunchbase_data) = serde_json::from_slice(&json_data) {
             cached_data = Some(crunchbase_data);
         }
-    };
+    }
 
     // Setup Crunchbase API client if an api key was provided
     let api_key = match env::var(CRUNCHBASE_API_KEY) {
@@ -52,7 +113,7 @@ pub(crate) async fn collect_crunchbase_data(
         Ok(_) | Err(_) => None,
     };
     let cb: Option<DynCB> = if let Some(api_key) = api_key {
-        Some(Arc::new(CBApi::new(&api_key)?))
+        Some(Arc::new(CBApi::new(&api_key, http)?))
     } else {
         warn!("crunchbase api key not provided: no information will be collected from crunchbase");
         None
@@ -60,9 +121,9 @@ pub(crate) async fn collect_crunchbase_data(
 
     // Collect items Crunchbase urls
     let mut urls = vec![];
-    for item in &landscape_data.items {
+    for item in items {
         if let Some(url) = &item.crunchbase_url {
-            urls.push(url);
+            urls.push(url.clone());
         }
     }
     urls.sort();
@@ -72,12 +133,10 @@ pub(crate) async fn collect_crunchbase_data(
     let limiter = RateLimiter::builder().initial(1).interval(CRUNCHBASE_RATE_LIMITER_INTERVAL).build();
     let crunchbase_data: CrunchbaseData = stream::iter(urls)
         .map(|url| async {
-            let url = url.clone();
-
             // Use cached data when available if it hasn't expired yet
-            if let Some(cached_org) = cached_data.as_ref().and_then(|cache| {
-                cache.get(&url).and_then(|org| {
-                    if org.generated_at + chrono::Duration::days(CRUNCHBASE_CACHE_TTL) > Utc::now() {
+            if let Some(cached_org) = cached_data.as_ref().and_then(|cached_data| {
+                cached_data.get(&url).and_then(|org| {
+                    if cache.is_fresh(org.generated_at) && (!funding_rounds || org.funding_rounds_collected) {
                         Some(org)
                     } else {
                         None
@@ -89,7 +148,7 @@ pub(crate) async fn collect_crunchbase_data(
             // Otherwise we pull it from Crunchbase if a key was provided
             else if let Some(cb) = cb.clone() {
                 limiter.acquire_one().await;
-                (url.clone(), Organization::new(cb, &url).await)
+                (url.clone(), Organization::new(cb, &url, funding_rounds).await)
             } else {
                 (url.clone(), Err(format_err!("no api key provided")))
             }
@@ -107,11 +166,12 @@ pub(crate) async fn collect_crunchbase_data(
         })
         .collect();
 
-    // Write data (in json format) to cache
-    cache.write(
-        CRUNCHBASE_CACHE_FILE,
-        &serde_json::to_vec_pretty(&crunchbase_data)?,
-    )?;
+    // Write data (in json format) to cache, keeping the fresh data collected
+    // for other landscapes sharing the cache
+    let mut cache_data = cached_data.unwrap_or_default();
+    cache_data.retain(|_, org| cache.is_fresh(org.generated_at));
+    cache_data.extend(crunchbase_data.clone());
+    cache.write(CRUNCHBASE_CACHE_FILE, &serde_json::to_vec_pretty(&cache_data)?)?;
 
     debug!("done!");
     Ok(crunchbase_data)
@@ -125,9 +185,14 @@ type CrunchbaseUrl = String;
 
 /// Organization information collected from Crunchbase.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct Organization {
+pub struct Organization {
     pub generated_at: DateTime<Utc>,
 
+    /// Whether the funding rounds were requested when collecting the data
+    /// (only used to decide if the cached data can be reused).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub funding_rounds_collected: bool,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub city: Option<String>,
 
@@ -152,6 +217,9 @@ pub(crate) struct Organization {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kind: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_funding_round: Option<FundingRound>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub linkedin_url: Option<String>,
 
@@ -180,10 +248,10 @@ pub(crate) struct Organization {
 impl Organization {
     /// Create a new Organization instance from information obtained from the
     /// Crunchbase API.
-    async fn new(cb: DynCB, cb_url: &str) -> Result<Self> {
+    async fn new(cb: DynCB, cb_url: &str, funding_rounds: bool) -> Result<Self> {
         // Collect some information from Crunchbase
         let permalink = get_permalink(cb_url)?;
-        let cb_org = cb.get_organization(&permalink).await?;
+        let cb_org = cb.get_organization(&permalink, funding_rounds).await?;
 
         // Prepare organization instance using the information collected
         let (num_employees_min, num_employees_max) = match cb_org.properties.num_employees_enum {
@@ -203,19 +271,21 @@ impl Organization {
         };
         Ok(Organization {
             generated_at: Utc::now(),
-            city: get_location_value(&cb_org.cards.headquarters_address, "city"),
+            funding_rounds_collected: funding_rounds,
+            city: get_location_value(cb_org.cards.headquarters_address.as_ref(), "city"),
             company_type: cb_org.properties.company_type,
-            country: get_location_value(&cb_org.cards.headquarters_address, "country"),
+            country: get_location_value(cb_org.cards.headquarters_address.as_ref(), "country"),
             description: cb_org.properties.short_description,
             funding: cb_org.properties.funding_total.as_ref().and_then(|f| f.value_usd),
             homepage_url: cb_org.properties.website.and_then(|v| v.value),
             categories: cb_org.properties.categories.and_then(|c| c.into_iter().map(|c| c.value).collect()),
             kind: cb_org.properties.funding_total.map(|_| "funding".to_string()),
+            latest_funding_round: get_latest_funding_round(cb_org.cards.raised_funding_rounds.as_ref()),
             linkedin_url: cb_org.properties.linkedin.and_then(|v| v.value),
             name: cb_org.properties.name,
             num_employees_max,
             num_employees_min,
-            region: get_location_value(&cb_org.cards.headquarters_address, "region"),
+            region: get_location_value(cb_org.cards.headquarters_address.as_ref(), "region"),
             stock_exchange: cb_org.properties.stock_exchange_symbol,
             ticker: cb_org.properties.stock_symbol.and_then(|v| v.value),
             twitter_url: cb_org.properties.twitter.and_then(|v| v.value),
@@ -223,6 +293,19 @@ impl Organization {
     }
 }
 
+/// Details of a funding round raised by an organization.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FundingRound {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub announced_on: Option<NaiveDate>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
 /// Crunchbase API base url.
 const CRUNCHBASE_API_URL: &str = "https://api.crunchbase.com/api/v4";
 
@@ -233,35 +316,44 @@ type DynCB = Arc<dyn CB + Send + Sync>;
 #[async_trait]
 #[cfg_attr(test, automock)]
 trait CB {
-    /// Get organization information.
-    async fn get_organization(&self, permalink: &str) -> Result<CBOrganizationEntity>;
+    /// Get organization information, including the funding rounds it has
+    /// raised when requested.
+    async fn get_organization(&self, permalink: &str, funding_rounds: bool) -> Result<CBOrganizationEntity>;
 }
 
 /// CB implementation backed by the Crunchbase API.
 struct CBApi {
-    http_client: reqwest::Client,
+    headers: header::HeaderMap,
+    http: HttpClient,
 }
 
 impl CBApi {
     /// Create a new CBApi instance.
-    fn new(key: &str) -> Result<Self> {
-        // Setup HTTP client ready to make requests to the Crunchbase API
-        let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    fn new(key: &str, http: &HttpClient) -> Result<Self> {
+        // Setup headers used to make requests to the Crunchbase API
         let mut headers = header::HeaderMap::new();
-        headers.insert("X-cb-user-key", header::HeaderValue::from_str(key).unwrap());
-        let http_client =
-            reqwest::Client::builder().user_agent(user_agent).default_headers(headers).build()?;
+        headers.insert("X-cb-user-key", header::HeaderValue::from_str(key)?);
 
-        Ok(Self { http_client })
+        Ok(Self {
+            headers,
+            http: http.clone(),
+        })
     }
 }
 
+// Clippy 1.76 reports the code generated by instrument for async trait
+// methods (the lint is unknown to previous versions)
+#[allow(unknown_lints, clippy::blocks_in_conditions)]
 #[async_trait]
 impl CB for CBApi {
     /// [CB::get_organization]
     #[instrument(fields(?permalink), skip_all, err)]
-    async fn get_organization(&self, permalink: &str) -> Result<CBOrganizationEntity> {
-        let cards = &["headquarters_address"].join(",");
+    async fn get_organization(&self, permalink: &str, funding_rounds: bool) -> Result<CBOrganizationEntity> {
+        let mut cards = vec!["headquarters_address"];
+        if funding_rounds {
+            cards.push("raised_funding_rounds");
+        }
+        let cards = &cards.join(",");
         let fields = &[
             "num_employees_enum",
             "linkedin",
@@ -279,7 +371,8 @@ impl CB for CBApi {
         let url = format!(
             "{CRUNCHBASE_API_URL}/entities/organizations/{permalink}?card_ids={cards}&field_ids={fields}"
         );
-        let response = self.http_client.get(url).send().await?;
+        let request = self.http.client().get(url).headers(self.headers.clone());
+        let response = self.http.send(request).await?;
         if response.status() != StatusCode::OK {
             return Err(format_err!("unexpected status code: {:?}", response.status()));
         }
@@ -327,6 +420,7 @@ struct CBValue {
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 struct CBCards {
     headquarters_address: Option<Vec<CBAddress>>,
+    raised_funding_rounds: Option<Vec<CBFundingRound>>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
@@ -334,14 +428,34 @@ struct CBAddress {
     location_identifiers: Option<Vec<CBLocationIdentifier>>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct CBFundingRound {
+    announced_on: Option<NaiveDate>,
+    investment_type: Option<String>,
+    money_raised: Option<CBFundingTotal>,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 struct CBLocationIdentifier {
     location_type: Option<String>,
     value: Option<String>,
 }
 
+/// Return the most recent funding round (by announcement date) if available.
+fn get_latest_funding_round(funding_rounds: Option<&Vec<CBFundingRound>>) -> Option<FundingRound> {
+    funding_rounds
+        .as_ref()?
+        .iter()
+        .max_by_key(|round| round.announced_on)
+        .map(|round| FundingRound {
+            amount: round.money_raised.as_ref().and_then(|m| m.value_usd),
+            announced_on: round.announced_on,
+            kind: round.investment_type.clone(),
+        })
+}
+
 /// Return the location value for the location type provided if available.
-fn get_location_value(headquarters_address: &Option<Vec<CBAddress>>, location_type: &str) -> Option<String> {
+fn get_location_value(headquarters_address: Option<&Vec<CBAddress>>, location_type: &str) -> Option<String> {
     headquarters_address
         .as_ref()
         .and_then(|addresses| addresses.iter().next())
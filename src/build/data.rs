@@ -8,24 +8,76 @@
 //! allows parsing the legacy format and convert it to the new one.
 
 use super::{
-    crunchbase::{CrunchbaseData, Organization, CRUNCHBASE_URL},
-    github::{self, GithubData},
+    crunchbase::{Organization, CRUNCHBASE_URL},
+    github,
+    packages::{Package, PackageRegistry, PackagesData},
+    remote::{fetch_data_url, DataRepo},
     settings::LandscapeSettings,
 };
 use crate::DataSource;
-use anyhow::{format_err, Result};
-use chrono::NaiveDate;
+use anyhow::{format_err, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
 use lazy_static::lazy_static;
 use regex::Regex;
-use reqwest::StatusCode;
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::Path};
-use tracing::{debug, instrument};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::{debug, instrument, warn};
 use url::Url;
 
 /// Format used for dates across the landscape data file.
 pub const DATE_FORMAT: &str = "%Y-%m-%d";
 
+/// Names of the data file in overlay directories (in order of preference).
+const OVERLAY_DATA_FILES: [&str; 4] = [
+    "landscape.yml",
+    "landscape.yaml",
+    "landscape.json",
+    "landscape.toml",
+];
+
+/// Format of the landscape data and settings files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[allow(clippy::module_name_repetitions)]
+pub enum DataFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl DataFormat {
+    /// Get the format of the file provided (a path or a url). The format
+    /// provided explicitly takes precedence, otherwise it's detected from the
+    /// file extension (YAML is used by default).
+    pub(crate) fn detect(format: Option<DataFormat>, file: &str) -> Self {
+        if let Some(format) = format {
+            return format;
+        }
+        let path = file.split(['?', '#']).next().unwrap_or_default();
+        let ext = Path::new(path).extension().and_then(|ext| ext.to_str()).map(str::to_lowercase);
+        match ext.as_deref() {
+            Some("json") => DataFormat::Json,
+            Some("toml") => DataFormat::Toml,
+            _ => DataFormat::Yaml,
+        }
+    }
+
+    /// Deserialize the raw data provided using this format.
+    pub(crate) fn parse<T: DeserializeOwned>(self, raw_data: &str) -> Result<T> {
+        let value = match self {
+            DataFormat::Json => serde_json::from_str(raw_data)?,
+            DataFormat::Toml => toml::from_str(raw_data)?,
+            DataFormat::Yaml => serde_yaml::from_str(raw_data)?,
+        };
+        Ok(value)
+    }
+}
+
 /// Landscape data.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub(crate) struct LandscapeData {
@@ -34,62 +86,64 @@ pub(crate) struct LandscapeData {
 }
 
 impl LandscapeData {
-    /// Create a new landscape data instance from the source provided.
+    /// Create a new landscape data instance from the source provided, using
+    /// the format given (it's detected from the file extension otherwise).
+    /// Remote sources are cached in the cache directory provided (or the
+    /// user's one).
     #[instrument(skip_all, err)]
-    pub(crate) async fn new(src: &DataSource) -> Result<Self> {
+    pub(crate) async fn new(
+        src: &DataSource,
+        format: Option<DataFormat>,
+        cache_dir: Option<&PathBuf>,
+    ) -> Result<Self> {
         // Try from file
         if let Some(file) = &src.data_file {
             debug!(?file, "getting landscape data from file");
-            return LandscapeData::new_from_file(file);
-        };
+            return LandscapeData::new_from_file(file, format);
+        }
 
         // Try from url
         if let Some(url) = &src.data_url {
             debug!(?url, "getting landscape data from url");
-            return LandscapeData::new_from_url(url).await;
-        };
+            return LandscapeData::new_from_url(url, format, cache_dir).await;
+        }
+
+        // Try from git repository
+        if let Some(repo) = &src.data_repo {
+            debug!(?repo, "getting landscape data from git repository");
+            let file = DataRepo::parse(repo)?.fetch(cache_dir).await?;
+            return LandscapeData::new_from_file(&file, format);
+        }
 
-        Err(format_err!("data file or url not provided"))
+        Err(format_err!("data file, url or repository not provided"))
     }
 
     /// Create a new landscape data instance from the file provided.
-    fn new_from_file(file: &Path) -> Result<Self> {
+    fn new_from_file(file: &Path, format: Option<DataFormat>) -> Result<Self> {
         let raw_data = fs::read_to_string(file)?;
-        let legacy_data: legacy::LandscapeData = serde_yaml::from_str(&raw_data)?;
-        legacy_data.validate()?;
-
-        Ok(LandscapeData::from(legacy_data))
+        let format = DataFormat::detect(format, &file.to_string_lossy());
+        LandscapeData::new_from_raw_data(&raw_data, format)
     }
 
     /// Create a new landscape data instance from the url provided.
-    async fn new_from_url(url: &str) -> Result<Self> {
-        let resp = reqwest::get(url).await?;
-        if resp.status() != StatusCode::OK {
-            return Err(format_err!(
-                "unexpected status code getting landscape data file: {}",
-                resp.status()
-            ));
-        }
-        let raw_data = resp.text().await?;
-        let legacy_data: legacy::LandscapeData = serde_yaml::from_str(&raw_data)?;
+    async fn new_from_url(
+        url: &str,
+        format: Option<DataFormat>,
+        cache_dir: Option<&PathBuf>,
+    ) -> Result<Self> {
+        let raw_data = fetch_data_url(url, cache_dir).await?;
+        LandscapeData::new_from_raw_data(&raw_data, DataFormat::detect(format, url))
+    }
+
+    /// Create a new landscape data instance from the raw data provided, in
+    /// the format given.
+    pub(crate) fn new_from_raw_data(raw_data: &str, format: DataFormat) -> Result<Self> {
+        let legacy_data: legacy::LandscapeData = format.parse(raw_data)?;
         legacy_data.validate()?;
 
         Ok(LandscapeData::from(legacy_data))
     }
 
-    /// Add items Crunchbase data.
-    #[instrument(skip_all, err)]
-    pub(crate) fn add_crunchbase_data(&mut self, crunchbase_data: CrunchbaseData) -> Result<()> {
-        for item in &mut self.items {
-            if let Some(crunchbase_url) = item.crunchbase_url.as_ref() {
-                if let Some(org_crunchbase_data) = crunchbase_data.get(crunchbase_url) {
-                    item.crunchbase_data = Some(org_crunchbase_data.clone());
-                }
-            }
-        }
-        Ok(())
-    }
-
     /// Add featured items information to the landscape data based on the
     /// settings provided (i.e. graduated and incubating projects must be
     /// featured and the former displayed first).
@@ -130,34 +184,37 @@ impl LandscapeData {
         Ok(())
     }
 
-    /// Add items repositories GitHub data.
-    #[instrument(skip_all, err)]
-    pub(crate) fn add_github_data(&mut self, github_data: GithubData) -> Result<()> {
+    /// Add download stats collected from the packages registries to the
+    /// landscape items packages.
+    #[instrument(skip_all)]
+    pub(crate) fn add_packages_data(&mut self, packages_data: &PackagesData) {
         for item in &mut self.items {
-            // Add GH data to each of the items repositories
-            if item.repositories.is_some() {
-                let mut repositories = vec![];
-                for mut repo in item.repositories.clone().unwrap_or_default() {
-                    if let Some(repo_github_data) = github_data.get(&repo.url) {
-                        repo.github_data = Some(repo_github_data.clone());
-                    }
-                    repositories.push(repo);
+            for package in item.packages.iter_mut().flatten() {
+                if let Some(downloads) = packages_data.get(&package.key()) {
+                    package.downloads = Some(downloads.clone());
                 }
-                item.repositories = Some(repositories);
             }
+        }
+    }
 
-            // Set item's oss field
-            if item
-                .primary_repository()
-                .and_then(|repo| repo.github_data.as_ref())
-                .and_then(|gh_data| gh_data.license.as_ref())
-                .is_some()
-            {
-                item.oss = Some(true);
+    /// Pin the time the data was collected from external services at to the
+    /// timestamp provided (used to generate reproducible outputs).
+    pub(crate) fn pin_collected_at(&mut self, ts: DateTime<Utc>) {
+        for item in &mut self.items {
+            if let Some(crunchbase_data) = item.crunchbase_data.as_mut() {
+                crunchbase_data.generated_at = ts;
+            }
+            for repo in item.repositories.iter_mut().flatten() {
+                if let Some(github_data) = repo.github_data.as_mut() {
+                    github_data.generated_at = ts;
+                }
+            }
+            for package in item.packages.iter_mut().flatten() {
+                if let Some(downloads) = package.downloads.as_mut() {
+                    downloads.generated_at = ts;
+                }
             }
         }
-
-        Ok(())
     }
 
     /// Add items member subcategory.
@@ -236,6 +293,120 @@ impl LandscapeData {
             }
         }
     }
+
+    /// Filter the landscape categories (and their items), keeping only the ones
+    /// provided (when any) and removing the ones excluded. The categories and
+    /// items removed are returned. An error is returned when any of the
+    /// categories provided is not defined in the landscape.
+    #[instrument(skip(self), err)]
+    pub(crate) fn filter_categories(
+        &mut self,
+        only: &[CategoryName],
+        exclude: &[CategoryName],
+    ) -> Result<FilteredOut> {
+        if only.is_empty() && exclude.is_empty() {
+            return Ok(FilteredOut::default());
+        }
+
+        // Check the categories provided are defined in the landscape
+        for name in only.iter().chain(exclude) {
+            if !self.categories.iter().any(|category| &category.name == name) {
+                let available: Vec<&str> = self.categories.iter().map(|c| c.name.as_str()).collect();
+                return Err(format_err!(
+                    "category {name} not found in landscape data (available categories: {})",
+                    available.join(", ")
+                ));
+            }
+        }
+
+        // Remove the categories (and items) filtered out
+        let keep = |name: &CategoryName| (only.is_empty() || only.contains(name)) && !exclude.contains(name);
+        let filtered_out = FilteredOut {
+            categories: self.categories.iter().filter(|c| !keep(&c.name)).map(|c| c.name.clone()).collect(),
+            items: self.items.iter().filter(|i| !keep(&i.category)).map(|i| i.id.clone()).collect(),
+        };
+        self.categories.retain(|category| keep(&category.name));
+        self.items.retain(|item| keep(&item.category));
+        for item in &mut self.items {
+            if let Some(additional_categories) = item.additional_categories.as_mut() {
+                additional_categories.retain(|ac| keep(&ac.category));
+                if additional_categories.is_empty() {
+                    item.additional_categories = None;
+                }
+            }
+        }
+        if self.categories.is_empty() {
+            return Err(format_err!(
+                "no categories left after applying the categories filters"
+            ));
+        }
+        debug!(
+            categories = self.categories.len(),
+            items = self.items.len(),
+            "categories filters applied"
+        );
+
+        Ok(filtered_out)
+    }
+
+    /// Merge the landscape data overlay provided (a data file, or a directory
+    /// containing a landscape.{yml,yaml,json,toml} file) into this landscape
+    /// data. The format of the overlay is detected from its extension. Categories
+    /// and subcategories not defined yet are appended in the order they are
+    /// defined in the overlay. Items are matched by id: existing items are
+    /// replaced by the overlay definition as a whole (lists included), and new
+    /// ones are appended.
+    #[instrument(skip(self), err)]
+    pub(crate) fn merge_overlay(&mut self, path: &Path) -> Result<()> {
+        let file = if path.is_dir() {
+            OVERLAY_DATA_FILES
+                .iter()
+                .map(|name| path.join(name))
+                .find(|file| file.is_file())
+                .ok_or_else(|| {
+                    format_err!(
+                        "overlay directory {} doesn't contain a data file ({})",
+                        path.display(),
+                        OVERLAY_DATA_FILES.join(", ")
+                    )
+                })?
+        } else {
+            path.to_path_buf()
+        };
+        let overlay = LandscapeData::new_from_file(&file, None)
+            .context(format!("error reading overlay {}", file.display()))?;
+
+        // Categories and subcategories
+        for overlay_category in overlay.categories {
+            let Some(category) = self.categories.iter_mut().find(|c| c.name == overlay_category.name) else {
+                self.categories.push(overlay_category);
+                continue;
+            };
+            for subcategory in overlay_category.subcategories {
+                if !category.subcategories.contains(&subcategory) {
+                    category.subcategories.push(subcategory);
+                }
+            }
+        }
+
+        // Items
+        for overlay_item in overlay.items {
+            let Some(item) = self.items.iter_mut().find(|i| i.id == overlay_item.id) else {
+                self.items.push(overlay_item);
+                continue;
+            };
+            if *item != overlay_item {
+                warn!(
+                    id = %overlay_item.id,
+                    ?file,
+                    "item defined differently in overlay, overriding previous definition"
+                );
+            }
+            *item = overlay_item;
+        }
+
+        Ok(())
+    }
 }
 
 impl From<legacy::LandscapeData> for LandscapeData {
@@ -299,6 +470,21 @@ impl From<legacy::LandscapeData> for LandscapeData {
                         item.repositories = Some(repositories);
                     }
 
+                    // Additional categories
+                    if let Some(second_path) = legacy_item.second_path {
+                        let additional_categories: Vec<AdditionalCategory> = second_path
+                            .iter()
+                            .filter_map(|path| legacy::parse_category_path(path))
+                            .map(|(category, subcategory)| AdditionalCategory {
+                                category,
+                                subcategory,
+                            })
+                            .collect();
+                        if !additional_categories.is_empty() {
+                            item.additional_categories = Some(additional_categories);
+                        }
+                    }
+
                     // Additional information in extra field
                     if let Some(extra) = legacy_item.extra {
                         item.accepted_at = extra.accepted;
@@ -322,6 +508,25 @@ impl From<legacy::LandscapeData> for LandscapeData {
                         item.tag = extra.tag;
                         item.youtube_url = extra.youtube_url;
 
+                        // Packages published in packages registries
+                        let packages: Vec<Package> = [
+                            (PackageRegistry::Crates, extra.crate_name),
+                            (PackageRegistry::Docker, extra.docker_image),
+                            (PackageRegistry::Npm, extra.npm_package),
+                        ]
+                        .into_iter()
+                        .filter_map(|(registry, name)| {
+                            name.map(|name| Package {
+                                name,
+                                registry,
+                                downloads: None,
+                            })
+                        })
+                        .collect();
+                        if !packages.is_empty() {
+                            item.packages = Some(packages);
+                        }
+
                         // Summary information
                         let mut summary = ItemSummary {
                             business_use_case: extra.summary_business_use_case,
@@ -360,8 +565,16 @@ impl From<legacy::LandscapeData> for LandscapeData {
     }
 }
 
+/// Categories and items removed from the landscape data by the categories
+/// filters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct FilteredOut {
+    pub categories: Vec<CategoryName>,
+    pub items: Vec<String>,
+}
+
 /// Landscape category.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Category {
     pub name: CategoryName,
     pub subcategories: Vec<SubCategoryName>,
@@ -375,7 +588,7 @@ pub(crate) type SubCategoryName = String;
 
 /// Landscape item (project, product, member, etc).
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct Item {
+pub struct Item {
     pub category: String,
     pub homepage_url: String,
     pub id: String,
@@ -386,6 +599,9 @@ pub(crate) struct Item {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub accepted_at: Option<NaiveDate>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_categories: Option<Vec<AdditionalCategory>>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub archived_at: Option<NaiveDate>,
 
@@ -464,6 +680,9 @@ pub(crate) struct Item {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub oss: Option<bool>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packages: Option<Vec<Package>>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub repositories: Option<Vec<Repository>>,
 
@@ -523,33 +742,28 @@ impl Item {
 
     /// Generate and set the item's id.
     fn set_id(&mut self) {
-        lazy_static! {
-            static ref VALID_CHARS: Regex =
-                Regex::new(r"[a-z0-9\-\ ]").expect("exprs in VALID_CHARS to be valid");
-        }
-
         // Normalize category, subcategory and item name
-        let normalize = |value: &str| {
-            value
-                .to_lowercase()
-                .replace(' ', "-")
-                .chars()
-                .filter(|c| VALID_CHARS.is_match(&c.to_string()))
-                .collect::<String>()
-                .replace("--", "-")
-        };
-        let category = normalize(&self.category);
-        let subcategory = normalize(&self.subcategory);
-        let item = normalize(&self.name);
+        let category = normalize_name(&self.category);
+        let subcategory = normalize_name(&self.subcategory);
+        let item = normalize_name(&self.name);
 
         // Build and set id
         self.id = format!("{category}--{subcategory}--{item}");
     }
 }
 
-/// Landscape item audit information.
+/// Landscape item additional category. Items can be listed in some additional
+/// categories (and subcategories) besides the one they belong to. The item's
+/// id (and permalink) is always based on its primary category.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct ItemAudit {
+pub struct AdditionalCategory {
+    pub category: CategoryName,
+    pub subcategory: SubCategoryName,
+}
+
+/// Landscape item audit information.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ItemAudit {
     pub date: NaiveDate,
     #[serde(rename = "type")]
     pub kind: String,
@@ -559,7 +773,7 @@ pub(crate) struct ItemAudit {
 
 /// Landscape item featured information.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct ItemFeatured {
+pub struct ItemFeatured {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
 
@@ -569,7 +783,7 @@ pub(crate) struct ItemFeatured {
 
 /// Landscape item summary.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct ItemSummary {
+pub struct ItemSummary {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub business_use_case: Option<String>,
 
@@ -597,7 +811,7 @@ pub(crate) struct ItemSummary {
 
 /// Repository information.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct Repository {
+pub struct Repository {
     pub url: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -610,7 +824,7 @@ pub(crate) struct Repository {
     pub primary: Option<bool>,
 }
 
-mod legacy {
+pub(crate) mod legacy {
     //! This module defines some types used to parse the landscape data file in
     //! legacy format and convert it to the new one.
 
@@ -619,10 +833,11 @@ mod legacy {
     use chrono::NaiveDate;
     use lazy_static::lazy_static;
     use regex::Regex;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
 
     /// Landscape data (legacy format).
-    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
     pub(crate) struct LandscapeData {
         pub landscape: Vec<Category>,
     }
@@ -630,6 +845,14 @@ mod legacy {
     impl LandscapeData {
         /// Validate landscape data.
         pub(crate) fn validate(&self) -> Result<()> {
+            // Categories and subcategories available, used to check the
+            // additional categories of the items
+            let subcategories_available: Vec<(&str, &str)> = self
+                .landscape
+                .iter()
+                .flat_map(|c| c.subcategories.iter().map(|sc| (c.name.as_str(), sc.name.as_str())))
+                .collect();
+
             for (category_index, category) in self.landscape.iter().enumerate() {
                 // Check category name
                 if category.name.is_empty() {
@@ -692,6 +915,30 @@ mod legacy {
                             }
                         }
 
+                        // Check additional categories
+                        for path in item.second_path.iter().flatten() {
+                            let Some((second_category, second_subcategory)) = parse_category_path(path)
+                            else {
+                                return Err(format_err!(
+                                    "invalid second_path {path} (expected format: category / subcategory)"
+                                ))
+                                .context(ctx);
+                            };
+                            let second_path = (second_category.as_str(), second_subcategory.as_str());
+                            if second_path == (category.name.as_str(), subcategory.name.as_str()) {
+                                return Err(format_err!(
+                                    "invalid second_path {path}: it matches the item's category"
+                                ))
+                                .context(ctx);
+                            }
+                            if !subcategories_available.contains(&second_path) {
+                                return Err(format_err!(
+                                    "invalid second_path {path}: category (or subcategory) not found"
+                                ))
+                                .context(ctx);
+                            }
+                        }
+
                         // Check urls
                         validate_urls(item).context(ctx)?;
                     }
@@ -703,21 +950,21 @@ mod legacy {
     }
 
     /// Landscape category.
-    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
     pub(crate) struct Category {
         pub name: String,
         pub subcategories: Vec<SubCategory>,
     }
 
     /// Landscape subcategory.
-    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
     pub(crate) struct SubCategory {
         pub name: String,
         pub items: Vec<Item>,
     }
 
     /// Landscape item (project, product, member, etc).
-    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
     pub(crate) struct Item {
         pub name: String,
         pub homepage_url: String,
@@ -731,20 +978,21 @@ mod legacy {
         pub joined: Option<NaiveDate>,
         pub project: Option<String>,
         pub repo_url: Option<String>,
+        pub second_path: Option<Vec<String>>,
         pub twitter: Option<String>,
         pub url_for_bestpractices: Option<String>,
         pub unnamed_organization: Option<bool>,
     }
 
     /// Landscape item repository.
-    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
     pub(crate) struct Repository {
         pub repo_url: String,
         pub branch: Option<String>,
     }
 
     /// Extra information for a landscape item.
-    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
     pub(crate) struct ItemExtra {
         pub accepted: Option<NaiveDate>,
         pub archived: Option<NaiveDate>,
@@ -755,13 +1003,16 @@ mod legacy {
         pub blog_url: Option<String>,
         pub chat_channel: Option<String>,
         pub clomonitor_name: Option<String>,
+        pub crate_name: Option<String>,
         pub dev_stats_url: Option<String>,
         pub discord_url: Option<String>,
+        pub docker_image: Option<String>,
         pub docker_url: Option<String>,
         pub github_discussions_url: Option<String>,
         pub graduated: Option<NaiveDate>,
         pub incubating: Option<NaiveDate>,
         pub mailing_list_url: Option<String>,
+        pub npm_package: Option<String>,
         pub slack_url: Option<String>,
         pub specification: Option<bool>,
         pub stack_overflow_url: Option<String>,
@@ -777,6 +1028,17 @@ mod legacy {
         pub youtube_url: Option<String>,
     }
 
+    /// Parse the category path provided (i.e. `Category / Subcategory`),
+    /// returning the category and subcategory names.
+    pub(crate) fn parse_category_path(path: &str) -> Option<(String, String)> {
+        let (category, subcategory) = path.split_once(" / ")?;
+        let (category, subcategory) = (category.trim(), subcategory.trim());
+        if category.is_empty() || subcategory.is_empty() {
+            return None;
+        }
+        Some((category.to_string(), subcategory.to_string()))
+    }
+
     /// Validate the urls of the item provided.
     fn validate_urls(item: &Item) -> Result<()> {
         // Check urls in item
@@ -789,14 +1051,14 @@ mod legacy {
             ("twitter", &item.twitter),
         ];
         for (name, url) in urls {
-            validate_url(name, url)?;
+            validate_url(name, url.as_ref())?;
         }
 
         // Check additional repositories
         if let Some(additional_repos) = &item.additional_repos {
             for r in additional_repos {
                 let repo_url = Some(r.repo_url.clone());
-                validate_url("additional_repository", &repo_url)?;
+                validate_url("additional_repository", repo_url.as_ref())?;
             }
         }
 
@@ -816,17 +1078,17 @@ mod legacy {
                 ("youtube", &extra.youtube_url),
             ];
             for (name, url) in urls {
-                validate_url(name, url)?;
+                validate_url(name, url.as_ref())?;
             }
 
             // Check audits urls
             if let Some(audits) = &extra.audits {
                 for a in audits {
                     let audit_url = Some(a.url.clone());
-                    validate_url("audit", &audit_url)?;
+                    validate_url("audit", audit_url.as_ref())?;
                 }
             }
-        };
+        }
 
         Ok(())
     }
@@ -837,8 +1099,25 @@ mod legacy {
     }
 }
 
+/// Normalize the name provided so that it can be used as part of an id or a
+/// file name.
+pub(crate) fn normalize_name(value: &str) -> String {
+    lazy_static! {
+        static ref VALID_CHARS: Regex =
+            Regex::new(r"[a-z0-9\-\ ]").expect("exprs in VALID_CHARS to be valid");
+    }
+
+    value
+        .to_lowercase()
+        .replace(' ', "-")
+        .chars()
+        .filter(|c| VALID_CHARS.is_match(&c.to_string()))
+        .collect::<String>()
+        .replace("--", "-")
+}
+
 /// Validate the url provided.
-pub(crate) fn validate_url(kind: &str, url: &Option<String>) -> Result<()> {
+pub(crate) fn validate_url(kind: &str, url: Option<&String>) -> Result<()> {
     if let Some(url) = url {
         let invalid_url = |reason: &str| Err(format_err!("invalid {kind} url: {reason}"));
 
@@ -861,10 +1140,8 @@ pub(crate) fn validate_url(kind: &str, url: &Option<String>) -> Result<()> {
             Ok(())
         };
         match kind {
-            "crunchbase" => {
-                if !CRUNCHBASE_URL.is_match(url.as_str()) {
-                    return invalid_url(&format!("expecting: {}", CRUNCHBASE_URL.as_str()));
-                }
+            "crunchbase" if !CRUNCHBASE_URL.is_match(url.as_str()) => {
+                return invalid_url(&format!("expecting: {}", CRUNCHBASE_URL.as_str()));
             }
             "facebook" => return check_domain("facebook.com"),
             "flickr" => return check_domain("flickr.com"),
@@ -881,3 +1158,121 @@ pub(crate) fn validate_url(kind: &str, url: &Option<String>) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const DATA_YAML: &str = r"
+landscape:
+  - category:
+    name: App Definition
+    subcategories:
+      - subcategory:
+        name: Database
+        items:
+          - item:
+            name: Item
+            homepage_url: https://item.example.com
+            logo: item.svg
+";
+
+    const DATA_JSON: &str = r#"{
+  "landscape": [
+    {
+      "name": "App Definition",
+      "subcategories": [
+        {
+          "name": "Database",
+          "items": [
+            {
+              "name": "Item",
+              "homepage_url": "https://item.example.com",
+              "logo": "item.svg"
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}"#;
+
+    const DATA_TOML: &str = r#"
+[[landscape]]
+name = "App Definition"
+
+[[landscape.subcategories]]
+name = "Database"
+
+[[landscape.subcategories.items]]
+name = "Item"
+homepage_url = "https://item.example.com"
+logo = "item.svg"
+"#;
+
+    #[test]
+    fn detect_format() {
+        assert_eq!(DataFormat::detect(None, "landscape.json"), DataFormat::Json);
+        assert_eq!(DataFormat::detect(None, "landscape.TOML"), DataFormat::Toml);
+        assert_eq!(DataFormat::detect(None, "landscape.yml"), DataFormat::Yaml);
+        assert_eq!(DataFormat::detect(None, "landscape"), DataFormat::Yaml);
+        assert_eq!(
+            DataFormat::detect(None, "https://example.com/landscape.json?ref=main#sha256=0"),
+            DataFormat::Json
+        );
+        assert_eq!(
+            DataFormat::detect(Some(DataFormat::Toml), "landscape.json"),
+            DataFormat::Toml
+        );
+    }
+
+    #[test]
+    fn parse_data_in_all_formats() {
+        let yaml = LandscapeData::new_from_raw_data(DATA_YAML, DataFormat::Yaml).unwrap();
+        let json = LandscapeData::new_from_raw_data(DATA_JSON, DataFormat::Json).unwrap();
+        let toml = LandscapeData::new_from_raw_data(DATA_TOML, DataFormat::Toml).unwrap();
+
+        assert_eq!(yaml.items.len(), 1);
+        assert_eq!(yaml.items[0].name, "Item");
+        assert_eq!(json, yaml);
+        assert_eq!(toml, yaml);
+    }
+
+    #[test]
+    fn parse_data_in_wrong_format_fails() {
+        assert!(LandscapeData::new_from_raw_data(DATA_YAML, DataFormat::Json).is_err());
+        assert!(LandscapeData::new_from_raw_data(DATA_JSON, DataFormat::Toml).is_err());
+    }
+
+    #[test]
+    fn merge_overlay_directory_in_any_format() {
+        for (file_name, overlay) in [("landscape.json", DATA_JSON), ("landscape.toml", DATA_TOML)] {
+            let dir = env::temp_dir().join(format!(
+                "landscape2-data-tests-{}-{file_name}",
+                std::process::id()
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(file_name), overlay.replace("\"Item\"", "\"Other\"")).unwrap();
+
+            let mut landscape_data = LandscapeData::new_from_raw_data(DATA_YAML, DataFormat::Yaml).unwrap();
+            landscape_data.merge_overlay(&dir).unwrap();
+            fs::remove_dir_all(&dir).unwrap();
+
+            let names: Vec<&str> = landscape_data.items.iter().map(|i| i.name.as_str()).collect();
+            assert_eq!(names, ["Item", "Other"], "overlay {file_name}");
+        }
+    }
+
+    #[test]
+    fn merge_overlay_directory_without_data_file_fails() {
+        let dir = env::temp_dir().join(format!("landscape2-data-tests-{}-empty", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut landscape_data = LandscapeData::new_from_raw_data(DATA_YAML, DataFormat::Yaml).unwrap();
+        let result = landscape_data.merge_overlay(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err());
+    }
+}
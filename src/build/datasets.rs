@@ -5,8 +5,11 @@
 //! rendering it), whereas others will be written to the output directory so
 //! that they can be fetched when needed.
 
-use self::{base::Base, full::Full};
-use super::{guide::LandscapeGuide, settings::LandscapeSettings, stats::Stats, LandscapeData};
+use self::base::Base;
+pub(crate) use self::full::Full;
+use super::{
+    aliases::AliasRedirect, guide::LandscapeGuide, settings::LandscapeSettings, stats::Stats, LandscapeData,
+};
 use anyhow::{Ok, Result};
 
 /// Datasets collection.
@@ -27,11 +30,12 @@ impl Datasets {
     pub(crate) fn new(
         landscape_data: &LandscapeData,
         settings: &LandscapeSettings,
-        guide: &Option<LandscapeGuide>,
-        qr_code: &Option<String>,
+        guide: Option<&LandscapeGuide>,
+        qr_code: Option<&String>,
+        aliases: &[AliasRedirect],
     ) -> Result<Self> {
         let datasets = Datasets {
-            base: Base::new(landscape_data, settings, guide, qr_code),
+            base: Base::new(landscape_data, settings, guide, qr_code, aliases),
             full: Full::new(landscape_data.clone()),
             stats: Stats::new(landscape_data, settings),
         };
@@ -46,12 +50,13 @@ impl Datasets {
 /// the initial page and power the features available on it.
 mod base {
     use crate::build::{
-        data::{Category, CategoryName, ItemFeatured, LandscapeData},
+        aliases::{AliasRedirect, AliasTarget},
+        data::{AdditionalCategory, Category, CategoryName, ItemFeatured, LandscapeData},
         guide::LandscapeGuide,
         settings::{Colors, GridItemsSize, Group, Images, LandscapeSettings, SocialNetworks},
     };
     use serde::{Deserialize, Serialize};
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
 
     /// Base dataset information.
     #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
@@ -62,6 +67,9 @@ mod base {
         #[serde(skip_serializing_if = "Vec::is_empty")]
         pub categories: Vec<Category>,
 
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        pub categories_aliases: BTreeMap<String, AliasTarget>,
+
         #[serde(skip_serializing_if = "Vec::is_empty")]
         pub categories_overridden: Vec<CategoryName>,
 
@@ -92,17 +100,22 @@ mod base {
         pub(crate) fn new(
             landscape_data: &LandscapeData,
             settings: &LandscapeSettings,
-            guide: &Option<LandscapeGuide>,
-            qr_code: &Option<String>,
+            guide: Option<&LandscapeGuide>,
+            qr_code: Option<&String>,
+            aliases: &[AliasRedirect],
         ) -> Self {
             let mut base = Base {
                 foundation: settings.foundation.clone(),
                 images: settings.images.clone(),
                 categories: landscape_data.categories.clone(),
+                categories_aliases: aliases
+                    .iter()
+                    .map(|alias| (alias.slug.clone(), alias.target.clone()))
+                    .collect(),
                 colors: settings.colors.clone(),
                 grid_items_size: settings.grid_items_size.clone(),
                 groups: settings.groups.clone().unwrap_or_default(),
-                qr_code: qr_code.clone(),
+                qr_code: qr_code.cloned(),
                 social_networks: settings.social_networks.clone(),
                 ..Default::default()
             };
@@ -120,6 +133,7 @@ mod base {
             // Prepare items from landscape data
             for item in &landscape_data.items {
                 base.items.push(Item {
+                    additional_categories: item.additional_categories.clone(),
                     category: item.category.clone(),
                     featured: item.featured.clone(),
                     id: item.id.clone(),
@@ -137,11 +151,11 @@ mod base {
                 if let Some(categories) = &guide.categories {
                     for category in categories {
                         let subcategories = if let Some(subcategories) = &category.subcategories {
-                            subcategories.iter().map(|s| s.subcategory.clone()).collect()
+                            subcategories.iter().map(|s| s.name.clone()).collect()
                         } else {
                             Vec::new()
                         };
-                        base.guide_summary.insert(category.category.clone(), subcategories);
+                        base.guide_summary.insert(category.name.clone(), subcategories);
                     }
                 }
             }
@@ -159,6 +173,9 @@ mod base {
         pub logo: String,
         pub subcategory: String,
 
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub additional_categories: Option<Vec<AdditionalCategory>>,
+
         #[serde(skip_serializing_if = "Option::is_none")]
         pub featured: Option<ItemFeatured>,
 
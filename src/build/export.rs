@@ -1,14 +1,28 @@
-//! This module defines the functionality to generate the `items.csv`
-//! file from the information available in the landscape.
+//! This module defines the functionality to generate the `items.csv`,
+//! `items.json` and `items.ndjson` files from the information available in the
+//! landscape.
+//!
+//! Items are converted and written to the files one at a time, so that the
+//! memory used doesn't grow with the size of the landscape.
 
 use super::{data, LandscapeData};
 use crate::build::data::DATE_FORMAT;
 use anyhow::Result;
 use chrono::NaiveDate;
+use clap::ValueEnum;
 use serde::Serialize;
-use std::fs::File;
+use std::io::{BufWriter, Write};
 
-/// Item information used for each record in the CSV file.
+/// Format of the items export files generated during the build.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+#[allow(clippy::module_name_repetitions)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+/// Item information used for each record in the export files.
 ///
 /// The order in which the values in Item are defined is the order in which
 /// they will be appear in the CSV file as headers.
@@ -97,31 +111,31 @@ impl From<&data::Item> for Item {
 
         // Crunchbase values
         if let Some(organization) = &di.crunchbase_data {
-            item.crunchbase_city = organization.city.clone();
-            item.crunchbase_country = organization.country.clone();
-            item.crunchbase_description = organization.description.clone();
-            item.crunchbase_homepage = organization.homepage_url.clone();
-            item.crunchbase_kind = organization.kind.clone();
-            item.crunchbase_linkedin = organization.linkedin_url.clone();
+            item.crunchbase_city.clone_from(&organization.city);
+            item.crunchbase_country.clone_from(&organization.country);
+            item.crunchbase_description.clone_from(&organization.description);
+            item.crunchbase_homepage.clone_from(&organization.homepage_url);
+            item.crunchbase_kind.clone_from(&organization.kind);
+            item.crunchbase_linkedin.clone_from(&organization.linkedin_url);
             item.crunchbase_max_employees = organization.num_employees_max;
             item.crunchbase_min_employees = organization.num_employees_min;
-            item.crunchbase_region = organization.region.clone();
-            item.crunchbase_ticker = organization.ticker.clone();
-            item.crunchbase_twitter = organization.twitter_url.clone();
+            item.crunchbase_region.clone_from(&organization.region);
+            item.crunchbase_ticker.clone_from(&organization.ticker);
+            item.crunchbase_twitter.clone_from(&organization.twitter_url);
             item.funding = organization.funding;
-            item.organization = organization.name.clone();
+            item.organization.clone_from(&organization.name);
         }
 
         // Twitter
         if di.twitter_url.is_some() {
-            item.twitter = di.twitter_url.clone();
+            item.twitter.clone_from(&di.twitter_url);
         } else if item.crunchbase_twitter.is_some() {
-            item.twitter = item.crunchbase_twitter.clone();
+            item.twitter.clone_from(&item.crunchbase_twitter);
         }
 
         // Relation
         if let Some(maturity) = &di.maturity {
-            item.relation = Some(maturity.to_string());
+            item.relation = Some(maturity.clone());
         } else if di.member_subcategory.is_some() {
             item.relation = Some(String::from("member"));
         }
@@ -134,8 +148,8 @@ impl From<&data::Item> for Item {
         // GitHub values
         if let Some(repo) = di.primary_repository() {
             if let Some(gh_data) = &repo.github_data {
-                item.github_contributors_count = Some(gh_data.contributors.count);
-                item.github_contributors_link = Some(gh_data.contributors.url.clone());
+                item.github_contributors_count = gh_data.contributors.as_ref().map(|c| c.count);
+                item.github_contributors_link = gh_data.contributors.as_ref().map(|c| c.url.clone());
                 item.github_description = Some(gh_data.description.clone());
                 item.github_latest_commit_link = Some(gh_data.latest_commit.url.clone());
                 item.github_repo = Some(gh_data.url.clone());
@@ -194,11 +208,130 @@ impl From<&data::Item> for Item {
 }
 
 /// Generate CSV file with some information about each item.
-pub(crate) fn generate_items_csv(mut w: csv::Writer<File>, landscape_data: &LandscapeData) -> Result<()> {
-    let mut items: Vec<Item> = landscape_data.items.iter().map(Item::from).collect();
-    items.sort_by_key(|i| i.name.to_lowercase());
-    items.iter().try_for_each(|i| w.serialize(i))?;
+pub(crate) fn generate_items_csv<W: Write>(
+    mut w: csv::Writer<W>,
+    landscape_data: &LandscapeData,
+) -> Result<()> {
+    for item in sorted_items(landscape_data) {
+        w.serialize(Item::from(item))?;
+    }
+    w.flush()?;
+
+    Ok(())
+}
+
+/// Generate JSON file with some information about each item (as an array).
+pub(crate) fn generate_items_json(w: impl Write, landscape_data: &LandscapeData) -> Result<()> {
+    let mut w = BufWriter::new(w);
+    w.write_all(b"[")?;
+    for (i, item) in sorted_items(landscape_data).into_iter().enumerate() {
+        if i > 0 {
+            w.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut w, &Item::from(item))?;
+    }
+    w.write_all(b"]")?;
     w.flush()?;
 
     Ok(())
 }
+
+/// Generate newline delimited JSON file with some information about each item
+/// (one item per line).
+pub(crate) fn generate_items_ndjson(w: impl Write, landscape_data: &LandscapeData) -> Result<()> {
+    let mut w = BufWriter::new(w);
+    for item in sorted_items(landscape_data) {
+        serde_json::to_writer(&mut w, &Item::from(item))?;
+        w.write_all(b"\n")?;
+    }
+    w.flush()?;
+
+    Ok(())
+}
+
+/// Return the landscape items sorted by name (the items are converted when
+/// they are written, so only references to them are collected).
+fn sorted_items(landscape_data: &LandscapeData) -> Vec<&data::Item> {
+    let mut items: Vec<&data::Item> = landscape_data.items.iter().collect();
+    items.sort_by_cached_key(|i| i.name.to_lowercase());
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, io, rc::Rc};
+
+    /// Number of items in the synthetic landscape used to check the export
+    /// files are streamed.
+    const ITEMS: usize = 20_000;
+
+    /// Maximum size of a single write to the underlying file (the buffer used
+    /// by the writers is much smaller than the whole export file).
+    const MAX_WRITE_SIZE: usize = 64 * 1024;
+
+    /// Writer that only keeps track of the writes made to it.
+    #[derive(Default)]
+    struct TrackingWriter {
+        bytes: Rc<Cell<usize>>,
+        max_write: Rc<Cell<usize>>,
+    }
+
+    impl Write for TrackingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.bytes.set(self.bytes.get() + buf.len());
+            self.max_write.set(self.max_write.get().max(buf.len()));
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Prepare a large synthetic landscape.
+    fn synthetic_landscape() -> LandscapeData {
+        let items = (0..ITEMS)
+            .map(|i| data::Item {
+                category: format!("Category {}", i % 10),
+                subcategory: format!("Subcategory {}", i % 100),
+                name: format!("Item {i}"),
+                homepage_url: format!("https://item{i}.example.com"),
+                description: Some("Some description of the item. ".repeat(10)),
+                ..data::Item::default()
+            })
+            .collect();
+        LandscapeData {
+            categories: vec![],
+            items,
+        }
+    }
+
+    /// Check the export file generated by the function provided is written to
+    /// the underlying writer progressively, in small chunks (instead of being
+    /// prepared in memory and written at once).
+    fn check_streamed(generate: impl FnOnce(&mut TrackingWriter) -> Result<()>) {
+        let mut w = TrackingWriter::default();
+        generate(&mut w).unwrap();
+        assert!(w.bytes.get() > ITEMS * 300);
+        assert!(w.max_write.get() <= MAX_WRITE_SIZE);
+    }
+
+    #[test]
+    fn generate_items_csv_streams_items() {
+        let landscape_data = synthetic_landscape();
+        check_streamed(|w| generate_items_csv(csv::Writer::from_writer(w), &landscape_data));
+    }
+
+    #[test]
+    fn generate_items_json_streams_items() {
+        let landscape_data = synthetic_landscape();
+        check_streamed(|w| generate_items_json(w, &landscape_data));
+    }
+
+    #[test]
+    fn generate_items_ndjson_streams_items() {
+        let landscape_data = synthetic_landscape();
+        check_streamed(|w| generate_items_ndjson(w, &landscape_data));
+    }
+}
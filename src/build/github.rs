@@ -1,38 +1,127 @@
 //! This module defines some types used to represent the information collected
 //! from GitHub for each of the landscape items repositories (when applicable),
-//! as well as the functionality used to collect that information.
-
-use super::{cache::Cache, LandscapeData};
+//! as well as the functionality used to collect that information. The
+//! contributors and commit activity of the repositories require some extra
+//! requests, so they are only collected when enabled in the settings file
+//! (`github.contributors_activity`).
+
+use super::{
+    cache::Cache,
+    collectors::DataCollector,
+    http::{HttpClient, Response},
+    settings::Github,
+    Item,
+};
 use anyhow::{format_err, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use deadpool::unmanaged::{Object, Pool};
 use futures::stream::{self, StreamExt};
-use lazy_static::lazy_static;
 #[cfg(test)]
 use mockall::automock;
 use octorust::auth::Credentials;
 use octorust::types::{FullRepository, ParticipationStats};
 use regex::Regex;
-use reqwest::header;
+use reqwest::{header, Method, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
-use tracing::{debug, instrument, warn};
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{debug, info, instrument, warn};
+use url::Url;
 
 /// File used to cache data collected from GitHub.
 const GITHUB_CACHE_FILE: &str = "github.json";
 
-/// How long the GitHub data in the cache is valid (in days).
-const GITHUB_CACHE_TTL: i64 = 7;
+/// Environment variable containing the GitHub API base url (used to collect
+/// data from GitHub Enterprise or other self-hosted instances).
+const GITHUB_API_BASE_URL: &str = "GITHUB_API_BASE_URL";
 
 /// Environment variable containing a comma separated list of GitHub tokens.
 const GITHUB_TOKENS: &str = "GITHUB_TOKENS";
 
+/// Data collector used to add the information collected from GitHub to the
+/// landscape items repositories.
+pub(crate) struct GithubCollector {
+    cache: Cache,
+    contributors_activity: bool,
+    http: HttpClient,
+}
+
+impl GithubCollector {
+    /// Create a new GithubCollector instance.
+    pub(crate) fn new(cache: &Cache, http: &HttpClient, cfg: Option<&Github>) -> Self {
+        Self {
+            cache: cache.clone(),
+            contributors_activity: cfg.and_then(|cfg| cfg.contributors_activity).unwrap_or(false),
+            http: http.clone(),
+        }
+    }
+
+    /// Collect the GitHub data of the items repositories provided (the items
+    /// are not updated, so that this collector can run concurrently with
+    /// others).
+    pub(crate) async fn fetch(&self, items: &[Item]) -> Result<GithubData> {
+        collect_github_data(&self.cache, &self.http, self.contributors_activity, items).await
+    }
+
+    /// Add the GitHub data provided to the items repositories.
+    pub(crate) fn apply(&self, items: &mut [Item], github_data: &GithubData) {
+        for item in items {
+            // Add GH data to each of the items repositories
+            for repo in item.repositories.iter_mut().flatten() {
+                if let Some(repo_github_data) = github_data.get(&repo.url) {
+                    let mut repo_github_data = repo_github_data.clone();
+
+                    // The cache may be shared with landscapes collecting the
+                    // contributors activity, so we only keep it when enabled
+                    if !self.contributors_activity {
+                        repo_github_data.contributors = None;
+                        repo_github_data.participation_stats = vec![];
+                    }
+                    repo.github_data = Some(repo_github_data);
+                }
+            }
+
+            // Set item's oss field
+            if item
+                .primary_repository()
+                .and_then(|repo| repo.github_data.as_ref())
+                .and_then(|gh_data| gh_data.license.as_ref())
+                .is_some()
+            {
+                item.oss = Some(true);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DataCollector for GithubCollector {
+    /// [DataCollector::name]
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    /// [DataCollector::collect]
+    async fn collect(&self, items: &mut [Item]) -> Result<()> {
+        let github_data = self.fetch(items).await?;
+        self.apply(items, &github_data);
+        Ok(())
+    }
+}
+
 /// Collect GitHub data for each of the items repositories in the landscape,
 /// reusing cached data whenever possible.
 #[instrument(skip_all, err)]
-pub(crate) async fn collect_github_data(cache: &Cache, landscape_data: &LandscapeData) -> Result<GithubData> {
+async fn collect_github_data(
+    cache: &Cache,
+    http: &HttpClient,
+    contributors_activity: bool,
+    items: &[Item],
+) -> Result<GithubData> {
     debug!("collecting repositories information from github (this may take a while)");
 
     // Read cached data (if available)
@@ -41,7 +130,11 @@ pub(crate) async fn collect_github_data(cache: &Cache, landscape_data: &Landscap
         if let Ok(github_data) = serde_json::from_slice(&json_data) {
             cached_data = Some(github_data);
         }
-    };
+    }
+
+    // Get GitHub API base url and the host used by its repositories
+    let api_url = get_api_url()?;
+    let repo_url = get_repo_url_regex(&api_url)?;
 
     // Setup GitHub API clients pool if any tokens have been provided
     let tokens: Option<Vec<String>> = match env::var(GITHUB_TOKENS) {
@@ -51,7 +144,7 @@ pub(crate) async fn collect_github_data(cache: &Cache, landscape_data: &Landscap
     let gh_pool: Option<Pool<DynGH>> = if let Some(tokens) = &tokens {
         let mut gh_clients: Vec<DynGH> = vec![];
         for token in tokens {
-            let gh = Box::new(GHApi::new(token)?);
+            let gh = Box::new(GHApi::new(&api_url, token, http)?);
             gh_clients.push(gh);
         }
         Some(Pool::from(gh_clients))
@@ -62,11 +155,11 @@ pub(crate) async fn collect_github_data(cache: &Cache, landscape_data: &Landscap
 
     // Collect urls of the repositories to process
     let mut urls = vec![];
-    for item in &landscape_data.items {
+    for item in items {
         if let Some(repositories) = &item.repositories {
             for repo in repositories {
-                if GITHUB_REPO_URL.is_match(&repo.url) {
-                    urls.push(&repo.url);
+                if repo_url.is_match(&repo.url) {
+                    urls.push(repo.url.clone());
                 }
             }
         }
@@ -82,12 +175,13 @@ pub(crate) async fn collect_github_data(cache: &Cache, landscape_data: &Landscap
     };
     let github_data: GithubData = stream::iter(urls)
         .map(|url| async {
-            let url = url.clone();
-
-            // Use cached data when available if it hasn't expired yet
-            if let Some(cached_repo) = cached_data.as_ref().and_then(|cache| {
-                cache.get(&url).and_then(|repo| {
-                    if repo.generated_at + chrono::Duration::days(GITHUB_CACHE_TTL) > Utc::now() {
+            // Use cached data when available if it hasn't expired yet (and
+            // it includes the contributors activity, when enabled)
+            if let Some(cached_repo) = cached_data.as_ref().and_then(|cached_data| {
+                cached_data.get(&url).and_then(|repo| {
+                    if cache.is_fresh(repo.generated_at)
+                        && (!contributors_activity || repo.contributors.is_some())
+                    {
                         Some(repo)
                     } else {
                         None
@@ -99,7 +193,10 @@ pub(crate) async fn collect_github_data(cache: &Cache, landscape_data: &Landscap
             // Otherwise we pull it from GitHub if any tokens were provided
             else if let Some(gh_pool) = &gh_pool {
                 let gh = gh_pool.get().await.expect("token -when available-");
-                (url.clone(), Repository::new(gh, &url).await)
+                (
+                    url.clone(),
+                    Repository::new(gh, &url, contributors_activity).await,
+                )
             } else {
                 (url.clone(), Err(format_err!("no tokens provided")))
             }
@@ -117,8 +214,12 @@ pub(crate) async fn collect_github_data(cache: &Cache, landscape_data: &Landscap
         })
         .collect();
 
-    // Write data (in json format) to cache
-    cache.write(GITHUB_CACHE_FILE, &serde_json::to_vec_pretty(&github_data)?)?;
+    // Write data (in json format) to cache, keeping the fresh data collected
+    // for other landscapes sharing the cache
+    let mut cache_data = cached_data.unwrap_or_default();
+    cache_data.retain(|_, repo| cache.is_fresh(repo.generated_at));
+    cache_data.extend(github_data.clone());
+    cache.write(GITHUB_CACHE_FILE, &serde_json::to_vec_pretty(&cache_data)?)?;
 
     debug!("done!");
     Ok(github_data)
@@ -132,15 +233,16 @@ pub(crate) type RepositoryUrl = String;
 
 /// Repository information collected from GitHub.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct Repository {
-    pub contributors: Contributors,
+pub struct Repository {
     pub description: String,
     pub generated_at: DateTime<Utc>,
     pub latest_commit: Commit,
-    pub participation_stats: Vec<i64>,
     pub stars: i64,
     pub url: String,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contributors: Option<Contributors>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub first_commit: Option<Commit>,
 
@@ -152,28 +254,36 @@ pub(crate) struct Repository {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub license: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub participation_stats: Vec<i64>,
 }
 
 impl Repository {
     /// Create a new Repository instance from information available on GitHub.
-    async fn new(gh: Object<DynGH>, repo_url: &str) -> Result<Self> {
+    /// The contributors and commit activity are only collected when requested.
+    async fn new(gh: Object<DynGH>, repo_url: &str, contributors_activity: bool) -> Result<Self> {
         // Collect some information from GitHub
         let (owner, repo) = get_owner_and_repo(repo_url)?;
         let gh_repo = gh.get_repository(&owner, &repo).await?;
-        let contributors_count = gh.get_contributors_count(&owner, &repo).await?;
         let first_commit = gh.get_first_commit(&owner, &repo, &gh_repo.default_branch).await?;
         let languages = gh.get_languages(&owner, &repo).await?;
         let latest_commit = gh.get_latest_commit(&owner, &repo, &gh_repo.default_branch).await?;
         let latest_release = gh.get_latest_release(&owner, &repo).await?;
-        let participation_stats = gh.get_participation_stats(&owner, &repo).await?.all;
+        let mut contributors = None;
+        let mut participation_stats = vec![];
+        if contributors_activity {
+            contributors = Some(Contributors {
+                count: gh.get_contributors_count(&owner, &repo).await?,
+                url: format!("{}/graphs/contributors", repo_url.trim_end_matches('/')),
+            });
+            participation_stats = gh.get_participation_stats(&owner, &repo).await?.all;
+        }
 
         // Prepare repository instance using the information collected
         Ok(Repository {
             generated_at: Utc::now(),
-            contributors: Contributors {
-                count: contributors_count,
-                url: format!("https://github.com/{owner}/{repo}/graphs/contributors"),
-            },
+            contributors,
             description: gh_repo.description,
             first_commit,
             languages,
@@ -195,7 +305,7 @@ impl Repository {
 
 /// Commit information.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct Commit {
+pub struct Commit {
     pub ts: Option<DateTime<Utc>>,
     pub url: String,
 }
@@ -215,14 +325,14 @@ impl From<octorust::types::CommitDataType> for Commit {
 
 /// Contributors information.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct Contributors {
+pub struct Contributors {
     pub count: usize,
     pub url: String,
 }
 
 /// Release information.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
-pub(crate) struct Release {
+pub struct Release {
     pub ts: Option<DateTime<Utc>>,
     pub url: String,
 }
@@ -236,15 +346,28 @@ impl From<octorust::types::Release> for Release {
     }
 }
 
-/// GitHub API base url.
+/// Default GitHub API base url.
 const GITHUB_API_URL: &str = "https://api.github.com";
 
+/// Maximum number of times a request will be retried when hitting GitHub's
+/// rate limits.
+const GITHUB_MAX_RETRIES: u32 = 5;
+
+/// Maximum time we'll wait for GitHub's rate limit to be reset.
+const GITHUB_MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(3600);
+
+/// Delay used to backoff when hitting a rate limit and GitHub doesn't tell us
+/// how long to wait (it's doubled on each retry).
+const GITHUB_RETRY_BASE_DELAY: Duration = Duration::from_secs(15);
+
 /// Type alias to represent a GH trait object.
 type DynGH = Box<dyn GH + Send + Sync>;
 
 /// Trait that defines some operations a GH implementation must support.
 #[async_trait]
 #[cfg_attr(test, automock)]
+// The mock has a field per method, all of them starting with get
+#[allow(unknown_lints, clippy::struct_field_names)]
 trait GH {
     /// Get number of repository contributors.
     async fn get_contributors_count(&self, owner: &str, repo: &str) -> Result<usize>;
@@ -270,19 +393,25 @@ trait GH {
 
 /// GH implementation backed by the GitHub API.
 struct GHApi {
+    api_url: String,
     gh_client: octorust::Client,
-    http_client: reqwest::Client,
+    headers: header::HeaderMap,
+    http: HttpClient,
 }
 
 impl GHApi {
     /// Create a new GHApi instance.
-    fn new(token: &str) -> Result<Self> {
+    fn new(api_url: &str, token: &str, http: &HttpClient) -> Result<Self> {
         // Setup octorust GitHub API client
         let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-        let gh_client = octorust::Client::new(user_agent.clone(), Credentials::Token(token.to_string()))?;
+        let mut gh_client = octorust::Client::new(user_agent, Credentials::Token(token.to_string()))?;
+        if api_url != GITHUB_API_URL {
+            gh_client.with_host_override(api_url);
+        }
 
-        // Setup HTTP client ready to make requests to the GitHub API
-        // (for some operations that cannot be done with the octorust client)
+        // Setup headers used to make requests to the GitHub API using the
+        // shared HTTP client (for some operations that cannot be done with the
+        // octorust client)
         let mut headers = header::HeaderMap::new();
         headers.insert(
             header::ACCEPT,
@@ -296,24 +425,88 @@ impl GHApi {
             "X-GitHub-Api-Version",
             header::HeaderValue::from_str("2022-11-28").unwrap(),
         );
-        let http_client =
-            reqwest::Client::builder().user_agent(user_agent).default_headers(headers).build()?;
 
         Ok(Self {
+            api_url: api_url.to_string(),
             gh_client,
-            http_client,
+            headers,
+            http: http.clone(),
         })
     }
+
+    /// Prepare a request to the GitHub API url provided.
+    fn request(&self, method: Method, url: String) -> reqwest::RequestBuilder {
+        self.http.client().request(method, url).headers(self.headers.clone())
+    }
+
+    /// Get how long we should wait before retrying a request that hit a rate
+    /// limit. The rate limit endpoint is not subject to rate limiting, so we
+    /// use it to find out when the primary rate limit will be reset. If it
+    /// hasn't been exhausted, we have hit a secondary rate limit and we just
+    /// backoff.
+    async fn get_rate_limit_wait(&self, retries: u32) -> Duration {
+        let url = format!("{}/rate_limit", self.api_url);
+        if let Ok(response) = self.http.send(self.request(Method::GET, url)).await {
+            if let Some(wait) = get_rate_limit_reset_wait(response.headers()) {
+                return wait;
+            }
+        }
+        backoff(retries)
+    }
+
+    /// Send the request provided, waiting and retrying it when hitting
+    /// GitHub's rate limits.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Response> {
+        let mut retries = 0;
+        loop {
+            let request = request.try_clone().ok_or_else(|| format_err!("error cloning github request"))?;
+            let response = self.http.send(request).await?;
+            let Some(wait) = get_response_rate_limit_wait(&response, retries) else {
+                return Ok(response);
+            };
+            if retries == GITHUB_MAX_RETRIES {
+                return Err(format_err!("github rate limit exceeded (max retries reached)"));
+            }
+            wait_for_rate_limit(wait).await;
+            retries += 1;
+        }
+    }
+
+    /// Run the octorust operation provided, waiting and retrying it when
+    /// hitting GitHub's rate limits.
+    async fn with_retries<T, F, Fut>(&self, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut + Send,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send,
+    {
+        let mut retries = 0;
+        loop {
+            match self.http.limit(op()).await {
+                Err(err) if retries < GITHUB_MAX_RETRIES && is_rate_limit_error(&err) => {
+                    wait_for_rate_limit(self.get_rate_limit_wait(retries).await).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
 }
 
+// Clippy 1.76 reports the code generated by instrument for async trait
+// methods (the lint is unknown to previous versions)
+#[allow(unknown_lints, clippy::blocks_in_conditions)]
 #[async_trait]
 impl GH for GHApi {
     /// [GH::get_contributors_count]
     #[instrument(fields(?owner, ?repo), skip_all, err)]
     async fn get_contributors_count(&self, owner: &str, repo: &str) -> Result<usize> {
         let mut count = 1;
-        let url = format!("{GITHUB_API_URL}/repos/{owner}/{repo}/contributors?per_page=1&anon=true");
-        let response = self.http_client.head(url).send().await?;
+        let url = format!(
+            "{}/repos/{owner}/{repo}/contributors?per_page=1&anon=true",
+            self.api_url
+        );
+        let response = self.send(self.request(Method::HEAD, url)).await?;
         if let Some(link_header) = response.headers().get("link") {
             let rels = parse_link_header::parse_with_rel(link_header.to_str()?)?;
             if let Some(last_page_url) = rels.get("last") {
@@ -330,8 +523,11 @@ impl GH for GHApi {
     async fn get_first_commit(&self, owner: &str, repo: &str, ref_: &str) -> Result<Option<Commit>> {
         // Get last commits page
         let mut last_page = 1;
-        let url = format!("{GITHUB_API_URL}/repos/{owner}/{repo}/commits?sha={ref_}&per_page=1");
-        let response = self.http_client.head(url).send().await?;
+        let url = format!(
+            "{}/repos/{owner}/{repo}/commits?sha={ref_}&per_page=1",
+            self.api_url
+        );
+        let response = self.send(self.request(Method::HEAD, url)).await?;
         if let Some(link_header) = response.headers().get("link") {
             let rels = parse_link_header::parse_with_rel(link_header.to_str()?)?;
             if let Some(last_page_url) = rels.get("last") {
@@ -343,9 +539,12 @@ impl GH for GHApi {
 
         // Get first repository commit and return it if found
         if let Some(commit) = self
-            .gh_client
-            .repos()
-            .list_commits(owner, repo, ref_, "", "", None, None, 1, last_page)
+            .with_retries(|| async move {
+                self.gh_client
+                    .repos()
+                    .list_commits(owner, repo, ref_, "", "", None, None, 1, last_page)
+                    .await
+            })
             .await?
             .pop()
         {
@@ -357,22 +556,28 @@ impl GH for GHApi {
     /// [GH::get_languages]
     #[instrument(fields(?owner, ?repo), skip_all, err)]
     async fn get_languages(&self, owner: &str, repo: &str) -> Result<Option<HashMap<String, i64>>> {
-        let url = format!("{GITHUB_API_URL}/repos/{owner}/{repo}/languages");
-        let languages: HashMap<String, i64> = self.http_client.get(url).send().await?.json().await?;
+        let url = format!("{}/repos/{owner}/{repo}/languages", self.api_url);
+        let languages: HashMap<String, i64> = self.send(self.request(Method::GET, url)).await?.json().await?;
         Ok(Some(languages))
     }
 
     /// [GH::get_latest_commit]
     #[instrument(fields(?owner, ?repo, ?ref_), skip_all, err)]
     async fn get_latest_commit(&self, owner: &str, repo: &str, ref_: &str) -> Result<Commit> {
-        let commit: Commit = self.gh_client.repos().get_commit(owner, repo, 1, 1, ref_).await?.into();
+        let commit: Commit = self
+            .with_retries(|| async move { self.gh_client.repos().get_commit(owner, repo, 1, 1, ref_).await })
+            .await?
+            .into();
         Ok(commit)
     }
 
     /// [GH::get_latest_release]
     #[instrument(fields(?owner, ?repo), skip_all, err)]
     async fn get_latest_release(&self, owner: &str, repo: &str) -> Result<Option<Release>> {
-        match self.gh_client.repos().get_latest_release(owner, repo).await {
+        match self
+            .with_retries(|| async move { self.gh_client.repos().get_latest_release(owner, repo).await })
+            .await
+        {
             Ok(release) => Ok(Some(release.into())),
             Err(err) => {
                 if err.to_string().to_lowercase().contains("not found") {
@@ -386,25 +591,119 @@ impl GH for GHApi {
     /// [GH::get_participation_stats]
     #[instrument(fields(?owner, ?repo), skip_all, err)]
     async fn get_participation_stats(&self, owner: &str, repo: &str) -> Result<ParticipationStats> {
-        self.gh_client.repos().get_participation_stats(owner, repo).await
+        self.with_retries(|| async move { self.gh_client.repos().get_participation_stats(owner, repo).await })
+            .await
     }
 
     /// [GH::get_repository]
     #[instrument(fields(?owner, ?repo), skip_all, err)]
     async fn get_repository(&self, owner: &str, repo: &str) -> Result<FullRepository> {
-        self.gh_client.repos().get(owner, repo).await
+        self.with_retries(|| async move { self.gh_client.repos().get(owner, repo).await }).await
+    }
+}
+
+/// Get the delay used to backoff on the retry provided.
+fn backoff(retries: u32) -> Duration {
+    GITHUB_RETRY_BASE_DELAY * 2_u32.pow(retries)
+}
+
+/// Get the GitHub API base url from the environment (when provided), checking
+/// that it's valid. The public GitHub API is used by default.
+pub(crate) fn get_api_url() -> Result<String> {
+    let api_url = match env::var(GITHUB_API_BASE_URL) {
+        Ok(api_url) if !api_url.is_empty() => api_url,
+        Ok(_) | Err(_) => return Ok(GITHUB_API_URL.to_string()),
+    };
+    let invalid = |reason: &str| format_err!("invalid {GITHUB_API_BASE_URL} ({api_url}): {reason}");
+    let url = Url::parse(&api_url).map_err(|err| invalid(&err.to_string()))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(invalid("scheme must be http or https"));
+    }
+    if url.host_str().is_none() {
+        return Err(invalid("host not found"));
     }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(invalid("query and fragment are not allowed"));
+    }
+
+    Ok(api_url.trim_end_matches('/').to_string())
 }
 
-lazy_static! {
-    /// GitHub repository url regular expression.
-    pub(crate) static ref GITHUB_REPO_URL: Regex =
-        Regex::new("^https://github.com/(?P<owner>[^/]+)/(?P<repo>[^/]+)/?$")
-            .expect("exprs in GITHUB_REPO_URL to be valid");
+/// Get the value of the header provided parsed as the type requested.
+fn get_header_value<T: FromStr>(headers: &header::HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
 }
 
 /// Extract the owner and repository from the repository url provided.
 fn get_owner_and_repo(repo_url: &str) -> Result<(String, String)> {
-    let c = GITHUB_REPO_URL.captures(repo_url).ok_or_else(|| format_err!("invalid repository url"))?;
-    Ok((c["owner"].to_string(), c["repo"].to_string()))
+    let url = Url::parse(repo_url)?;
+    let segments: Vec<&str> = url.path_segments().into_iter().flatten().filter(|s| !s.is_empty()).collect();
+    let [owner, repo] = segments[..] else {
+        return Err(format_err!("invalid repository url"));
+    };
+    Ok((owner.to_string(), repo.to_string()))
+}
+
+/// Get the regular expression used to match the urls of the repositories
+/// hosted in the GitHub instance of the API base url provided. The web host
+/// of the public GitHub API and GitHub Enterprise Cloud (api.<host>) is the
+/// API host without the api subdomain, whereas on GitHub Enterprise Server
+/// the API is served from the same host (<host>/api/v3).
+fn get_repo_url_regex(api_url: &str) -> Result<Regex> {
+    let url = Url::parse(api_url)?;
+    let host = url.host_str().ok_or_else(|| format_err!("invalid github api url"))?;
+    let web_host = host.strip_prefix("api.").unwrap_or(host);
+    let scheme = if api_url == GITHUB_API_URL {
+        "https"
+    } else {
+        "https?"
+    };
+    let repo_url = Regex::new(&format!(
+        "^{scheme}://{}/(?P<owner>[^/]+)/(?P<repo>[^/]+)/?$",
+        regex::escape(web_host)
+    ))?;
+
+    Ok(repo_url)
+}
+
+/// Get how long we should wait for the primary rate limit to be reset, if it
+/// has been exhausted, from the rate limit headers provided.
+fn get_rate_limit_reset_wait(headers: &header::HeaderMap) -> Option<Duration> {
+    if get_header_value::<u64>(headers, "x-ratelimit-remaining") != Some(0) {
+        return None;
+    }
+    let reset = get_header_value::<i64>(headers, "x-ratelimit-reset")?;
+    let secs = u64::try_from(reset - Utc::now().timestamp()).unwrap_or_default();
+    Some(Duration::from_secs(secs.max(1)))
+}
+
+/// Check if the response provided hit a rate limit, returning how long we
+/// should wait before retrying the request.
+fn get_response_rate_limit_wait(response: &reqwest::Response, retries: u32) -> Option<Duration> {
+    let status = response.status();
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    if let Some(secs) = get_header_value::<u64>(response.headers(), "retry-after") {
+        return Some(Duration::from_secs(secs));
+    }
+    if let Some(wait) = get_rate_limit_reset_wait(response.headers()) {
+        return Some(wait);
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Some(backoff(retries));
+    }
+    None
+}
+
+/// Check if the error provided was caused by hitting a rate limit.
+fn is_rate_limit_error(err: &anyhow::Error) -> bool {
+    err.to_string().to_lowercase().contains("rate limit")
+}
+
+/// Wait for the rate limit reset (up to GITHUB_MAX_RATE_LIMIT_WAIT).
+async fn wait_for_rate_limit(wait: Duration) {
+    let wait = wait.min(GITHUB_MAX_RATE_LIMIT_WAIT);
+    info!("waiting {}s for github rate limit reset", wait.as_secs());
+    tokio::time::sleep(wait).await;
 }
@@ -1,18 +1,53 @@
 //! This module defines the types used to represent the landscape guide content
 //! that must be provided from a YAML file (guide.yml).
+//!
+//! The guide content is converted from markdown to HTML. Each heading gets a
+//! stable anchor (its slugified title, prefixed by the id of the section it
+//! belongs to), and a nested table of contents is generated from the guide
+//! sections and their headings. Internal links (to guide sections or headings
+//! and to landscape categories, subcategories or items) are validated.
 
+use super::{data::FilteredOut, LandscapeData};
 use crate::GuideSource;
 use anyhow::{format_err, Context, Result};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
 use reqwest::StatusCode;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use std::{collections::HashSet, fs, path::Path};
 use tracing::{debug, instrument};
+use url::form_urlencoded;
+
+lazy_static! {
+    /// Heading element regular expression.
+    static ref HEADING: Regex =
+        Regex::new(r"<h(?P<level>[1-6])>(?P<content>.*?)</h[1-6]>").expect("exprs in HEADING to be valid");
+
+    /// Internal link regular expression (links to anchors in the guide or to
+    /// landscape permalinks).
+    static ref INTERNAL_LINK: Regex =
+        Regex::new(r#"<a href="(?P<href>(?:\./|/)?[?#][^"]*)""#).expect("exprs in INTERNAL_LINK to be valid");
+
+    /// Internal link element regular expression (including its content).
+    static ref INTERNAL_LINK_ELEMENT: Regex =
+        Regex::new(r#"(?s)<a href="(?P<href>(?:\./|/)?[?#][^"]*)"[^>]*>(?P<content>.*?)</a>"#)
+            .expect("exprs in INTERNAL_LINK_ELEMENT to be valid");
+
+    /// HTML tag regular expression.
+    static ref TAG: Regex = Regex::new(r"<[^>]+>").expect("exprs in TAG to be valid");
+}
 
 /// Landscape guide content.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct LandscapeGuide {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub categories: Option<Vec<Category>>,
+
+    /// Table of contents (generated from the guide content).
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(skip)]
+    pub toc: Vec<TocEntry>,
 }
 
 impl LandscapeGuide {
@@ -23,13 +58,13 @@ impl LandscapeGuide {
         if let Some(file) = &src.guide_file {
             debug!(?file, "getting landscape guide from file");
             return Ok(Some(LandscapeGuide::new_from_file(file)?));
-        };
+        }
 
         // Try from url
         if let Some(url) = &src.guide_url {
             debug!(?url, "getting landscape guide from url");
             return Ok(Some(LandscapeGuide::new_from_url(url).await?));
-        };
+        }
 
         Ok(None)
     }
@@ -63,41 +98,203 @@ impl LandscapeGuide {
         let mut guide: LandscapeGuide = serde_yaml::from_str(s)?;
         guide.validate().context("the landscape guide file provided is not valid")?;
 
-        // Convert content fields from markdown to HTML
+        // Convert content fields from markdown to HTML, adding anchors to the
+        // headings and preparing the table of contents. Sections ids are
+        // reserved first, so that headings anchors never collide with them.
         let options = markdown::Options::default();
+        let mut ids: HashSet<String> = HashSet::new();
+        for c in guide.categories.iter().flatten() {
+            ids.insert(slugify(&c.name));
+            for sc in c.subcategories.iter().flatten() {
+                ids.insert(slugify(&format!("{} {}", c.name, sc.name)));
+            }
+        }
+        let mut toc = vec![];
         if let Some(categories) = guide.categories.as_mut() {
             for c in &mut *categories {
+                let category_id = slugify(&c.name);
+                let mut entries = vec![];
                 if let Some(content) = &c.content {
                     let html = markdown::to_html_with_options(content, &options)
                         .map_err(|err| format_err!("{err}"))?;
+                    let (html, headings) = add_headings_anchors(&html, &category_id, &mut ids);
                     c.content = Some(html);
+                    entries.extend(headings);
                 }
 
                 if let Some(subcategories) = c.subcategories.as_mut() {
                     for sc in &mut *subcategories {
-                        sc.content = markdown::to_html_with_options(&sc.content, &options)
+                        let subcategory_id = slugify(&format!("{} {}", c.name, sc.name));
+                        let html = markdown::to_html_with_options(&sc.content, &options)
                             .map_err(|err| format_err!("{err}"))?;
+                        let (html, headings) = add_headings_anchors(&html, &subcategory_id, &mut ids);
+                        sc.content = html;
+                        entries.push(TocEntry {
+                            id: subcategory_id,
+                            title: sc.name.clone(),
+                            entries: headings,
+                        });
                     }
                 }
+
+                toc.push(TocEntry {
+                    id: category_id,
+                    title: c.name.clone(),
+                    entries,
+                });
+            }
+        }
+        guide.toc = toc;
+
+        // Check the links to anchors in the guide point to existing sections
+        // or headings
+        let mut broken_links = vec![];
+        for (section, href) in guide.internal_links() {
+            if let Some(anchor) = href.strip_prefix('#') {
+                if !ids.contains(anchor) {
+                    broken_links.push(format!("{href} (in {section})"));
+                }
             }
         }
+        if !broken_links.is_empty() {
+            return Err(format_err!(
+                "the landscape guide file provided contains broken links: {}",
+                broken_links.join(", ")
+            ));
+        }
 
         Ok(guide)
     }
 
+    /// Check that the links to landscape categories, subcategories and items
+    /// in the guide point to existing ones in the landscape data provided.
+    #[instrument(skip_all, err)]
+    pub(crate) fn check_landscape_links(&self, landscape_data: &LandscapeData) -> Result<()> {
+        let mut broken_links = vec![];
+        for (section, href) in self.internal_links() {
+            let Some((_, query)) = href.split_once('?') else {
+                continue;
+            };
+            let query = query.split('#').next().unwrap_or_default();
+            let params: Vec<(String, String)> =
+                form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+            let param = |name: &str| params.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+
+            let valid = match (param("category"), param("subcategory"), param("item")) {
+                (_, _, Some(item_id)) => landscape_data.items.iter().any(|item| item.id == item_id),
+                (Some(category), subcategory, None) => landscape_data.categories.iter().any(|c| {
+                    c.name == category
+                        && subcategory.map_or(true, |sc| c.subcategories.iter().any(|s| s == sc))
+                }),
+                _ => true,
+            };
+            if !valid {
+                broken_links.push(format!("{href} (in {section})"));
+            }
+        }
+        if !broken_links.is_empty() {
+            return Err(format_err!(
+                "the landscape guide contains broken links to the landscape: {}",
+                broken_links.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Remove the sections of the categories filtered out of the landscape, as
+    /// well as their table of contents entries. The links to the sections
+    /// removed, or to the categories and items filtered out, are replaced by
+    /// their content.
+    pub(crate) fn remove_filtered_out(&mut self, filtered_out: &FilteredOut) {
+        let Some(categories) = self.categories.as_mut() else {
+            return;
+        };
+        if filtered_out.categories.is_empty() {
+            return;
+        }
+
+        // Remove sections and their table of contents entries, keeping track
+        // of the anchors removed
+        categories.retain(|c| !filtered_out.categories.contains(&c.name));
+        let mut removed_ids = HashSet::new();
+        self.toc.retain(|entry| {
+            if filtered_out.categories.contains(&entry.title) {
+                collect_toc_ids(entry, &mut removed_ids);
+                return false;
+            }
+            true
+        });
+
+        // Unlink the links pointing to content no longer available
+        let is_removed = |href: &str| {
+            if let Some(anchor) = href.strip_prefix('#') {
+                return removed_ids.contains(anchor);
+            }
+            let Some((_, query)) = href.split_once('?') else {
+                return false;
+            };
+            let query = query.split('#').next().unwrap_or_default();
+            form_urlencoded::parse(query.as_bytes()).any(|(name, value)| match name.as_ref() {
+                "category" => filtered_out.categories.iter().any(|c| c == value.as_ref()),
+                "item" => filtered_out.items.iter().any(|i| i == value.as_ref()),
+                _ => false,
+            })
+        };
+        let unlink = |html: &str| {
+            INTERNAL_LINK_ELEMENT
+                .replace_all(html, |c: &Captures| {
+                    if is_removed(&c["href"].replace("&amp;", "&")) {
+                        c["content"].to_string()
+                    } else {
+                        c[0].to_string()
+                    }
+                })
+                .into_owned()
+        };
+        for c in categories {
+            if let Some(content) = &c.content {
+                c.content = Some(unlink(content));
+            }
+            for sc in c.subcategories.iter_mut().flatten() {
+                sc.content = unlink(&sc.content);
+            }
+        }
+    }
+
+    /// Return the internal links found in the guide content, along with the
+    /// section where they were found.
+    fn internal_links(&self) -> Vec<(String, String)> {
+        let mut links = vec![];
+        let mut add_links = |section: String, html: &str| {
+            for c in INTERNAL_LINK.captures_iter(html) {
+                links.push((section.clone(), c["href"].replace("&amp;", "&")));
+            }
+        };
+        for c in self.categories.iter().flatten() {
+            if let Some(content) = &c.content {
+                add_links(c.name.clone(), content);
+            }
+            for sc in c.subcategories.iter().flatten() {
+                add_links(format!("{} / {}", c.name, sc.name), &sc.content);
+            }
+        }
+        links
+    }
+
     /// Validate landscape guide.
     fn validate(&self) -> Result<()> {
         if let Some(categories) = &self.categories {
             for (i, categories) in categories.iter().enumerate() {
-                let category_id = if categories.category.is_empty() {
+                let category_id = if categories.name.is_empty() {
                     format!("{i}")
                 } else {
-                    categories.category.clone()
+                    categories.name.clone()
                 };
                 let mut ctx = format!("category [{category_id}] is not valid");
 
                 // Category
-                if categories.category.is_empty() {
+                if categories.name.is_empty() {
                     return Err(format_err!("category cannot be empty")).context(ctx);
                 }
 
@@ -120,15 +317,15 @@ impl LandscapeGuide {
                 // Subcategories
                 if let Some(subcategories) = &categories.subcategories {
                     for (i, subcategory) in subcategories.iter().enumerate() {
-                        let subcategory_id = if subcategory.subcategory.is_empty() {
+                        let subcategory_id = if subcategory.name.is_empty() {
                             format!("{i}")
                         } else {
-                            subcategory.subcategory.clone()
+                            subcategory.name.clone()
                         };
                         ctx = format!("subcategory [{subcategory_id}] in {ctx}");
 
                         // Subcategory
-                        if subcategory.subcategory.is_empty() {
+                        if subcategory.name.is_empty() {
                             return Err(format_err!("subcategory cannot be empty")).context(ctx);
                         }
 
@@ -155,9 +352,10 @@ impl LandscapeGuide {
 }
 
 /// Guide category.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Category {
-    pub category: String,
+    #[serde(rename = "category")]
+    pub name: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
@@ -170,11 +368,161 @@ pub(crate) struct Category {
 }
 
 /// Guide subcategory.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Subcategory {
-    pub subcategory: String,
+    #[serde(rename = "subcategory")]
+    pub name: String,
     pub content: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub keywords: Option<Vec<String>>,
 }
+
+/// Guide table of contents entry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TocEntry {
+    pub id: String,
+    pub title: String,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<TocEntry>,
+}
+
+/// Add an anchor to each of the headings in the HTML content provided, using
+/// the id of the section it belongs to as prefix. The ids provided are used to
+/// make sure anchors are unique (new ones are added). The headings found are
+/// returned as table of contents entries, nested by their level.
+fn add_headings_anchors(html: &str, section_id: &str, ids: &mut HashSet<String>) -> (String, Vec<TocEntry>) {
+    let mut headings = vec![];
+    let html = HEADING
+        .replace_all(html, |c: &Captures| {
+            let title = decode_entities(&TAG.replace_all(&c["content"], ""));
+            let base_id = format!("{section_id}-{}", slugify(&title));
+            let mut id = base_id.clone();
+            let mut n = 1;
+            while ids.contains(&id) {
+                n += 1;
+                id = format!("{base_id}-{n}");
+            }
+            ids.insert(id.clone());
+
+            // Anchors use the same prefix as the guide sections, so that the
+            // web application can scroll to them
+            let level: usize = c["level"].parse().unwrap_or_default();
+            let heading = format!(r#"<h{level} id="section_{id}">{}</h{level}>"#, &c["content"]);
+            headings.push((
+                level,
+                TocEntry {
+                    id,
+                    title,
+                    entries: vec![],
+                },
+            ));
+            heading
+        })
+        .into_owned();
+
+    (html, nest_headings(headings))
+}
+
+/// Collect the ids of the table of contents entry provided and its children.
+fn collect_toc_ids(entry: &TocEntry, ids: &mut HashSet<String>) {
+    ids.insert(entry.id.clone());
+    for child in &entry.entries {
+        collect_toc_ids(child, ids);
+    }
+}
+
+/// Decode the basic HTML entities escaped in the markdown output.
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&amp;", "&")
+}
+
+/// Nest the headings provided (along with their level), so that each one
+/// includes the headings of a lower level that follow it.
+fn nest_headings(headings: Vec<(usize, TocEntry)>) -> Vec<TocEntry> {
+    let mut entries = vec![];
+    let mut stack: Vec<(usize, TocEntry)> = vec![];
+    let attach =
+        |stack: &mut Vec<(usize, TocEntry)>, entries: &mut Vec<TocEntry>, entry: TocEntry| match stack
+            .last_mut()
+        {
+            Some((_, parent)) => parent.entries.push(entry),
+            None => entries.push(entry),
+        };
+
+    for (level, entry) in headings {
+        while stack.last().is_some_and(|(last_level, _)| *last_level >= level) {
+            if let Some((_, last)) = stack.pop() {
+                attach(&mut stack, &mut entries, last);
+            }
+        }
+        stack.push((level, entry));
+    }
+    while let Some((_, last)) = stack.pop() {
+        attach(&mut stack, &mut entries, last);
+    }
+
+    entries
+}
+
+/// Convert the text provided into a slug, the same way the web application
+/// does it to prepare the guide sections ids.
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '_' || c == '-') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUIDE: &str = r"
+categories:
+  - category: Introduction
+    content: |
+      See [observability](#observability), [its tools](#observability-tools),
+      [the tracing tools](/?category=Observability&subcategory=Tracing),
+      [Jaeger](/?item=observability--tracing--jaeger) and
+      [Kubernetes](/?item=orchestration--scheduling--kubernetes).
+  - category: Observability
+    content: |
+      ## Tools
+  - category: Orchestration
+    content: Orchestration content.
+";
+
+    #[test]
+    fn remove_filtered_out_sections_and_links() {
+        let mut guide = LandscapeGuide::new_from_yaml(GUIDE).unwrap();
+        guide.remove_filtered_out(&FilteredOut {
+            categories: vec!["Observability".to_string()],
+            items: vec!["observability--tracing--jaeger".to_string()],
+        });
+
+        let categories: Vec<&str> = guide.categories.iter().flatten().map(|c| c.name.as_str()).collect();
+        assert_eq!(categories, ["Introduction", "Orchestration"]);
+        let toc: Vec<&str> = guide.toc.iter().map(|entry| entry.title.as_str()).collect();
+        assert_eq!(toc, ["Introduction", "Orchestration"]);
+
+        let intro = guide.categories.as_ref().unwrap()[0].content.as_deref().unwrap();
+        assert!(intro.contains("See observability, its tools,\nthe tracing tools,\nJaeger and"));
+        assert!(intro.contains(r#"<a href="/?item=orchestration--scheduling--kubernetes">Kubernetes</a>"#));
+    }
+
+    #[test]
+    fn remove_filtered_out_nothing_filtered() {
+        let mut guide = LandscapeGuide::new_from_yaml(GUIDE).unwrap();
+        let expected = guide.clone();
+        guide.remove_filtered_out(&FilteredOut::default());
+
+        assert_eq!(guide, expected);
+    }
+}
@@ -0,0 +1,111 @@
+//! This module defines the HTTP client shared by the collectors used to get
+//! data from external services (GitHub, Crunchbase, CLOMonitor and the
+//! packages registries), also used to fetch the projects status list.
+//!
+//! A single pooled client is reused across all the collectors (and across all
+//! the landscapes when building several of them), and the number of requests
+//! in flight at any given time is limited by a semaphore shared by all of
+//! them, so that large landscapes don't open too many simultaneous
+//! connections (tripping rate limits or exhausting the file descriptors). The
+//! permit used to send a request is held until its response body has been
+//! read, as the connection is in use until then.
+
+use anyhow::{format_err, Result};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use std::{future::Future, ops::Deref, sync::Arc};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Default maximum number of requests to external services in flight.
+pub(crate) const DEFAULT_COLLECT_CONCURRENCY: usize = 8;
+
+/// HTTP client shared by the collectors.
+#[derive(Debug, Clone)]
+pub(crate) struct HttpClient {
+    client: reqwest::Client,
+    permits: Arc<Semaphore>,
+}
+
+impl HttpClient {
+    /// Create a new HttpClient instance, allowing up to the number of
+    /// requests in flight provided.
+    pub(crate) fn new(concurrency: usize) -> Result<Self> {
+        if concurrency == 0 {
+            return Err(format_err!("collect concurrency must be greater than zero"));
+        }
+
+        let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        let client = reqwest::Client::builder()
+            .user_agent(user_agent)
+            .pool_max_idle_per_host(concurrency)
+            .build()?;
+
+        Ok(Self {
+            client,
+            permits: Arc::new(Semaphore::new(concurrency)),
+        })
+    }
+
+    /// Get the underlying client, used to prepare the requests.
+    pub(crate) fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Run the operation provided once a permit is available, so that the
+    /// concurrency limit is never exceeded. The operation must read the
+    /// responses bodies before completing (i.e. API clients operations).
+    pub(crate) async fn limit<F: Future>(&self, op: F) -> F::Output {
+        let _permit = self.permits.acquire().await.expect("semaphore not to be closed");
+        op.await
+    }
+
+    /// Send the request provided once a permit is available. The permit is
+    /// held by the response returned until its body is read (or it's dropped).
+    pub(crate) async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let permit = self.permits.clone().acquire_owned().await.expect("semaphore not to be closed");
+        let response = request.send().await?;
+
+        Ok(Response {
+            response,
+            _permit: permit,
+        })
+    }
+}
+
+/// Response to a request sent using the shared HTTP client, holding the
+/// permit used to send it until its body is read.
+#[derive(Debug)]
+pub(crate) struct Response {
+    response: reqwest::Response,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Response {
+    /// Get the response body as bytes.
+    pub(crate) async fn bytes(self) -> Result<Vec<u8>> {
+        Ok(self.response.bytes().await?.to_vec())
+    }
+
+    /// Deserialize the response body as JSON.
+    pub(crate) async fn json<T: DeserializeOwned>(self) -> Result<T> {
+        Ok(self.response.json().await?)
+    }
+
+    /// Get the response body as text.
+    pub(crate) async fn text(self) -> Result<String> {
+        Ok(self.response.text().await?)
+    }
+
+    /// Get the response status code.
+    pub(crate) fn status(&self) -> StatusCode {
+        self.response.status()
+    }
+}
+
+impl Deref for Response {
+    type Target = reqwest::Response;
+
+    fn deref(&self) -> &Self::Target {
+        &self.response
+    }
+}
@@ -0,0 +1,98 @@
+//! This module defines the functionality used to support incremental builds,
+//! which allow skipping some of the build steps when their inputs haven't
+//! changed since the previous build.
+//!
+//! Only the most expensive steps that produce the same output for the same
+//! inputs are skipped: copying the web assets (when the landscape2 version
+//! hasn't changed) and taking the landscape screenshot (when the data, the
+//! settings and the landscape2 version haven't changed). The rest of the
+//! outputs depend on data collected from external services at build time, so
+//! they are always generated.
+
+use super::{LandscapeData, LandscapeGuide, LandscapeSettings};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fs, path::Path};
+use tracing::{debug, instrument};
+
+/// File where the build manifest will be written to in the output directory.
+/// Files starting with a dot are not deployed.
+const BUILD_MANIFEST_FILE: &str = ".build-manifest.json";
+
+/// Name of the build inputs tracked in the build manifest.
+pub(crate) const DATA_INPUT: &str = "data";
+pub(crate) const GUIDE_INPUT: &str = "guide";
+pub(crate) const LANDSCAPE2_INPUT: &str = "landscape2";
+pub(crate) const SETTINGS_INPUT: &str = "settings";
+
+/// Build manifest, containing a digest of each of the inputs used in a build.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct BuildManifest {
+    pub inputs: BTreeMap<String, String>,
+}
+
+impl BuildManifest {
+    /// Create a new build manifest from the inputs provided. The landscape
+    /// data provided is expected to include the items' logos paths (they are
+    /// based on the logos content, so any logo change will be detected).
+    pub(crate) fn new(
+        landscape_data: &LandscapeData,
+        settings: &LandscapeSettings,
+        guide: Option<&LandscapeGuide>,
+    ) -> Result<Self> {
+        let mut inputs = BTreeMap::new();
+        inputs.insert(DATA_INPUT.to_string(), digest(landscape_data)?);
+        inputs.insert(GUIDE_INPUT.to_string(), digest(&guide)?);
+        inputs.insert(
+            LANDSCAPE2_INPUT.to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        );
+        inputs.insert(SETTINGS_INPUT.to_string(), digest(settings)?);
+
+        Ok(Self { inputs })
+    }
+
+    /// Read the build manifest from the output directory provided (if
+    /// available).
+    #[instrument(skip_all)]
+    pub(crate) fn read(output_dir: &Path) -> Option<Self> {
+        let data = fs::read(output_dir.join(BUILD_MANIFEST_FILE)).ok()?;
+        match serde_json::from_slice(&data) {
+            Ok(manifest) => Some(manifest),
+            Err(err) => {
+                debug!(?err, "invalid build manifest found, ignoring it");
+                None
+            }
+        }
+    }
+
+    /// Write the build manifest to the output directory provided.
+    #[instrument(skip_all, err)]
+    pub(crate) fn write(&self, output_dir: &Path) -> Result<()> {
+        fs::write(
+            output_dir.join(BUILD_MANIFEST_FILE),
+            serde_json::to_vec_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// Return the names of the inputs that have changed compared to the
+    /// previous build manifest provided. When there is no previous manifest,
+    /// all inputs are considered changed.
+    pub(crate) fn changed_inputs(&self, previous: Option<&BuildManifest>) -> Vec<&str> {
+        self.inputs
+            .iter()
+            .filter(|(name, digest)| previous.and_then(|p| p.inputs.get(*name)) != Some(*digest))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+/// Calculate the digest of the value provided. The value is converted to a
+/// JSON value first, to make sure maps keys are sorted and the digest is
+/// stable across builds.
+fn digest<T: Serialize>(value: &T) -> Result<String> {
+    let json_data = serde_json::to_vec(&serde_json::to_value(value)?)?;
+    Ok(hex::encode(Sha256::digest(json_data)))
+}
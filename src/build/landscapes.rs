@@ -0,0 +1,379 @@
+//! This module defines the functionality used to build multiple landscapes in
+//! a single invocation of the build CLI subcommand (build --landscapes-file).
+//!
+//! The landscapes file is a YAML file listing the landscapes to build, with
+//! the following format (paths are relative to the landscapes file):
+//!
+//! ```yaml
+//! landscapes:
+//!   - name: cncf
+//!     data_file: cncf/landscape.yml
+//!     settings_file: cncf/settings.yml
+//!     guide_file: cncf/guide.yml
+//!     logos_path: cncf/hosted_logos
+//! ```
+//!
+//! Each landscape is built to a directory named after it in the output
+//! directory, using the other build options provided. Landscapes are built
+//! sequentially in the same process, sharing the cache directory, so the
+//! data collected from external services for a landscape is reused by the
+//! others. When the build metrics are requested, each landscape writes them
+//! to its own file (named after it).
+//!
+//! Landscapes can share the same logos path. In that case, the logos used by
+//! any of them are not considered orphaned (so they are never pruned).
+
+use super::{build_with_http_client, http::HttpClient, BuildConfig, LandscapeData};
+use crate::{metrics::metrics_file_for, DataSource, GuideSource, LogosSource, SettingsSource};
+use anyhow::{format_err, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+use tracing::{error, info, instrument, warn};
+
+/// Landscapes to build, as defined in the landscapes file.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct LandscapesFile {
+    landscapes: Vec<Landscape>,
+}
+
+/// Landscape entry in the landscapes file.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Landscape {
+    name: String,
+
+    #[serde(flatten)]
+    data_source: DataSource,
+
+    #[serde(flatten)]
+    guide_source: GuideSource,
+
+    #[serde(flatten)]
+    logos_source: LogosSource,
+
+    #[serde(flatten)]
+    settings_source: SettingsSource,
+}
+
+impl Landscape {
+    /// Resolve the local paths of the landscape sources, which are relative
+    /// to the directory of the landscapes file.
+    fn resolve_paths(&mut self, base_dir: &Path) {
+        let resolve = |path: &mut Option<PathBuf>| {
+            if let Some(path) = path.as_mut().filter(|path| path.is_relative()) {
+                *path = base_dir.join(&*path);
+            }
+        };
+        resolve(&mut self.data_source.data_file);
+        resolve(&mut self.guide_source.guide_file);
+        resolve(&mut self.logos_source.logos_path);
+        resolve(&mut self.settings_source.settings_file);
+    }
+
+    /// Check the landscape provides the sources required to build it (only
+    /// one of each kind of source can be provided).
+    fn validate(&self) -> Result<()> {
+        let sources = [
+            (
+                "data file, url or repository",
+                true,
+                usize::from(self.data_source.data_file.is_some())
+                    + usize::from(self.data_source.data_url.is_some())
+                    + usize::from(self.data_source.data_repo.is_some()),
+            ),
+            (
+                "guide file or url",
+                false,
+                usize::from(self.guide_source.guide_file.is_some())
+                    + usize::from(self.guide_source.guide_url.is_some()),
+            ),
+            (
+                "logos path or url",
+                true,
+                usize::from(self.logos_source.logos_path.is_some())
+                    + usize::from(self.logos_source.logos_url.is_some()),
+            ),
+            (
+                "settings file or url",
+                true,
+                usize::from(self.settings_source.settings_file.is_some())
+                    + usize::from(self.settings_source.settings_url.is_some()),
+            ),
+        ];
+        for (source, required, provided) in sources {
+            if required && provided == 0 {
+                return Err(format_err!("landscape {}: {source} not provided", self.name));
+            }
+            if provided > 1 {
+                return Err(format_err!(
+                    "landscape {}: only one {source} can be provided",
+                    self.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Build all the landscapes listed in the landscapes file provided, reporting
+/// the result of each of them. Landscapes failing to build don't prevent the
+/// others from being built, but an error is returned at the end.
+#[instrument(skip_all, err)]
+pub(crate) async fn build_landscapes(landscapes_file: &Path, config: &BuildConfig) -> Result<()> {
+    let start = Instant::now();
+    let landscapes = read_landscapes_file(landscapes_file)?;
+    info!("building {} landscapes..", landscapes.len());
+
+    // Get the logos referenced by the landscapes sharing their logos path
+    let shared_logos = get_shared_logos(&landscapes, config).await;
+
+    // Build landscapes (the HTTP client is shared by all of them)
+    let http = HttpClient::new(config.collect_concurrency)?;
+    let mut failed = vec![];
+    for landscape in &landscapes {
+        let landscape_start = Instant::now();
+
+        // The logos referenced by the other landscapes sharing the logos path
+        // are not orphaned (pruning is disabled when they are unknown)
+        let mut other_landscapes_logos = vec![];
+        let mut prune_orphaned_logos = config.prune_orphaned_logos;
+        for other in landscapes.iter().filter(|other| other.name != landscape.name) {
+            if !shares_logos_path(landscape, other) {
+                continue;
+            }
+            if let Some(Some(logos)) = shared_logos.get(&other.name) {
+                other_landscapes_logos.extend(logos.iter().cloned());
+            } else if prune_orphaned_logos {
+                warn!(
+                    landscape = landscape.name,
+                    other = other.name,
+                    "logos used by a landscape sharing the logos path are unknown, orphaned logos won't be pruned"
+                );
+                prune_orphaned_logos = false;
+            }
+        }
+
+        let landscape_config = BuildConfig {
+            data_source: landscape.data_source.clone(),
+            guide_source: landscape.guide_source.clone(),
+            logos_source: landscape.logos_source.clone(),
+            metrics_file: config.metrics_file.as_ref().map(|file| metrics_file_for(file, &landscape.name)),
+            other_landscapes_logos,
+            prune_orphaned_logos,
+            settings_source: landscape.settings_source.clone(),
+            output_dir: config.output_dir.join(&landscape.name),
+            ..config.clone()
+        };
+        let duration = || landscape_start.elapsed().as_secs_f64();
+        match build_with_http_client(&landscape_config, &http).await {
+            Ok(_) => info!(
+                landscape = landscape.name,
+                "landscape built (took: {:.3}s)",
+                duration()
+            ),
+            Err(err) => {
+                error!(
+                    landscape = landscape.name,
+                    ?err,
+                    "error building landscape (took: {:.3}s)",
+                    duration()
+                );
+                failed.push(landscape.name.clone());
+            }
+        }
+    }
+
+    let duration = start.elapsed().as_secs_f64();
+    if !failed.is_empty() {
+        return Err(format_err!(
+            "{} landscapes could not be built: {} (took: {:.3}s)",
+            failed.len(),
+            failed.join(", "),
+            duration
+        ));
+    }
+    info!("all landscapes built! (took: {:.3}s)", duration);
+
+    Ok(())
+}
+
+/// Get the logos referenced by the landscapes that share their logos path with
+/// other landscapes, keyed by the landscape name. The logos referenced by the
+/// landscapes whose data cannot be read are unknown (None).
+async fn get_shared_logos(
+    landscapes: &[Landscape],
+    config: &BuildConfig,
+) -> HashMap<String, Option<Vec<String>>> {
+    let mut shared_logos = HashMap::new();
+    for landscape in landscapes {
+        if !landscapes
+            .iter()
+            .any(|other| other.name != landscape.name && shares_logos_path(landscape, other))
+        {
+            continue;
+        }
+
+        let mut landscape_data = match LandscapeData::new(
+            &landscape.data_source,
+            config.data_format,
+            config.cache_dir.as_ref(),
+        )
+        .await
+        {
+            Ok(landscape_data) => Some(landscape_data),
+            Err(err) => {
+                warn!(landscape = landscape.name, ?err, "error reading landscape data");
+                None
+            }
+        };
+        for overlay in &config.overlays {
+            if let Some(Err(err)) = landscape_data.as_mut().map(|data| data.merge_overlay(overlay)) {
+                warn!(
+                    landscape = landscape.name,
+                    ?err,
+                    "error merging landscape data overlay"
+                );
+                landscape_data = None;
+            }
+        }
+        let logos = landscape_data.map(|data| data.items.into_iter().map(|item| item.logo).collect());
+        shared_logos.insert(landscape.name.clone(), logos);
+    }
+    shared_logos
+}
+
+/// Read the landscapes listed in the landscapes file provided, checking they
+/// are valid.
+fn read_landscapes_file(landscapes_file: &Path) -> Result<Vec<Landscape>> {
+    let raw_data = fs::read_to_string(landscapes_file).context("error reading landscapes file")?;
+    let LandscapesFile { mut landscapes } =
+        serde_yaml::from_str(&raw_data).context("error parsing landscapes file")?;
+    if landscapes.is_empty() {
+        return Err(format_err!("no landscapes found in landscapes file"));
+    }
+
+    // Landscapes names are used as the name of their output directory
+    let mut names = HashSet::new();
+    for landscape in &landscapes {
+        let name = &landscape.name;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format_err!(
+                "invalid landscape name: {name} (only alphanumeric characters, - and _ are allowed)"
+            ));
+        }
+        if !names.insert(name) {
+            return Err(format_err!("duplicate landscape name: {name}"));
+        }
+
+        // Check the required sources are provided before building any of
+        // the landscapes
+        landscape.validate()?;
+    }
+
+    let base_dir = landscapes_file.parent().unwrap_or(Path::new(""));
+    for landscape in &mut landscapes {
+        landscape.resolve_paths(base_dir);
+    }
+
+    Ok(landscapes)
+}
+
+/// Check if the landscapes provided share the same (local) logos path.
+fn shares_logos_path(landscape: &Landscape, other: &Landscape) -> bool {
+    let canonical_logos_path = |landscape: &Landscape| {
+        let logos_path = landscape.logos_source.logos_path.as_ref()?;
+        Some(fs::canonicalize(logos_path).unwrap_or(logos_path.clone()))
+    };
+    match (canonical_logos_path(landscape), canonical_logos_path(other)) {
+        (Some(logos_path), Some(other_logos_path)) => logos_path == other_logos_path,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// Write the landscapes file provided to a temporary directory, returning
+    /// its path.
+    fn write_landscapes_file(name: &str, content: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("landscape2-landscapes-tests-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_landscapes_file_resolves_paths() {
+        let path = write_landscapes_file(
+            "valid.yml",
+            r"
+landscapes:
+  - name: cncf
+    data_file: cncf/landscape.yml
+    logos_path: cncf/logos
+    settings_url: https://example.com/settings.yml
+",
+        );
+        let landscapes = read_landscapes_file(&path).unwrap();
+        let base_dir = path.parent().unwrap();
+
+        assert_eq!(landscapes.len(), 1);
+        assert_eq!(
+            landscapes[0].data_source.data_file,
+            Some(base_dir.join("cncf/landscape.yml"))
+        );
+        assert_eq!(
+            landscapes[0].logos_source.logos_path,
+            Some(base_dir.join("cncf/logos"))
+        );
+    }
+
+    #[test]
+    fn read_landscapes_file_missing_required_source() {
+        let path = write_landscapes_file(
+            "missing.yml",
+            r"
+landscapes:
+  - name: cncf
+    data_file: cncf/landscape.yml
+    logos_path: cncf/logos
+    settings_file: cncf/settings.yml
+  - name: lfai
+    data_file: lfai/landscape.yml
+    settings_file: lfai/settings.yml
+",
+        );
+        let err = read_landscapes_file(&path).unwrap_err();
+
+        assert_eq!(err.to_string(), "landscape lfai: logos path or url not provided");
+    }
+
+    #[test]
+    fn read_landscapes_file_multiple_sources_of_same_kind() {
+        let path = write_landscapes_file(
+            "multiple.yml",
+            r"
+landscapes:
+  - name: cncf
+    data_file: cncf/landscape.yml
+    data_url: https://example.com/landscape.yml
+    logos_path: cncf/logos
+    settings_file: cncf/settings.yml
+",
+        );
+        let err = read_landscapes_file(&path).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "landscape cncf: only one data file, url or repository can be provided"
+        );
+    }
+}
@@ -3,16 +3,53 @@
 
 use super::cache::Cache;
 use crate::LogosSource;
-use anyhow::{format_err, Result};
+use anyhow::{format_err, Context, Result};
 use lazy_static::lazy_static;
-use regex::bytes::Regex;
+use regex::bytes::{Captures, Regex};
 use reqwest::StatusCode;
+use resvg::tiny_skia;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::fs;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+use tracing::{debug, instrument};
 use usvg::{NodeExt, Rect, TreeParsing};
+use walkdir::WalkDir;
 
 lazy_static! {
+    /// Regular expression used to find the logo field of the items in the
+    /// landscape data file.
+    static ref DATA_FILE_LOGO: Regex =
+        Regex::new(r#"(?m)^(?P<prefix>[ \t]*(?:-[ \t]+)?logo:[ \t]*)(?P<quote>['"]?)(?P<logo>[^'"\s#]+)(?P<suffix>['"]?[ \t]*(?:#[^\r\n]*)?\r?)$"#)
+            .expect("exprs in DATA_FILE_LOGO to be valid");
+
+    /// Regular expression used to remove comments from the SVG logos.
+    static ref SVG_COMMENT: Regex = Regex::new(r"(?s)<!--.*?-->").expect("exprs in SVG_COMMENT to be valid");
+
+    /// Regular expression used to remove event handlers attributes (i.e.
+    /// onload) from the SVG logos.
+    static ref SVG_EVENT_HANDLER: Regex = Regex::new(r#"(?i)\s+on[a-z]+\s*=\s*("[^"]*"|'[^']*')"#)
+        .expect("exprs in SVG_EVENT_HANDLER to be valid");
+
+    /// Regular expression used to find href and xlink:href attributes in the
+    /// SVG logos.
+    static ref SVG_HREF: Regex = Regex::new(r#"(?i)\s+(?:xlink:)?href\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .expect("exprs in SVG_HREF to be valid");
+
+    /// Regular expression used to remove metadata elements from the SVG logos.
+    static ref SVG_METADATA: Regex = Regex::new(r"(?is)<metadata\b.*?</metadata\s*>|<metadata\b[^>]*/>")
+        .expect("exprs in SVG_METADATA to be valid");
+
+    /// Regular expression used to remove script elements from the SVG logos.
+    static ref SVG_SCRIPT: Regex = Regex::new(r"(?is)<script\b[^>]*/>|<script\b.*?</script\s*>")
+        .expect("exprs in SVG_SCRIPT to be valid");
+
+    /// Regular expression used to collapse the whitespace between tags.
+    static ref SVG_WHITESPACE: Regex = Regex::new(r">\s+<").expect("exprs in SVG_WHITESPACE to be valid");
+
     /// Regular expression used to remove the SVG logos' title.
     static ref SVG_TITLE: Regex = Regex::new("<title>.*</title>",).expect("exprs in SVG_TITLE to be valid");
 
@@ -27,20 +64,164 @@ pub(crate) struct Logo {
     pub digest: String,
 }
 
+/// Logos with identical content (same digest) referenced by the items using
+/// different file names.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DuplicateLogos {
+    /// Digest of the logos content.
+    pub digest: String,
+
+    /// File name suggested to be used by all the items (the one used by most
+    /// of them).
+    pub canonical: String,
+
+    /// Names of the items using each of the logos file names.
+    pub logos: BTreeMap<String, Vec<String>>,
+}
+
+impl DuplicateLogos {
+    /// Find the logos with identical content referenced using different file
+    /// names. Each entry provided contains the logo digest, the logo file name
+    /// and the name of the item using it.
+    pub(crate) fn find(entries: &[(String, String, String)]) -> Vec<Self> {
+        let mut logos_by_digest: HashMap<&str, BTreeMap<String, Vec<String>>> = HashMap::new();
+        for (digest, logo, item_name) in entries {
+            logos_by_digest
+                .entry(digest)
+                .or_default()
+                .entry(logo.clone())
+                .or_default()
+                .push(item_name.clone());
+        }
+
+        let mut duplicates: Vec<Self> = logos_by_digest
+            .into_iter()
+            .filter(|(_, logos)| logos.len() > 1)
+            .map(|(digest, logos)| {
+                // Logos are sorted by name, so ties are resolved alphabetically
+                let mut canonical = String::new();
+                let mut canonical_items = 0;
+                for (logo, items) in &logos {
+                    if items.len() > canonical_items {
+                        canonical.clone_from(logo);
+                        canonical_items = items.len();
+                    }
+                }
+                Self {
+                    digest: digest.to_string(),
+                    canonical,
+                    logos,
+                }
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.canonical.cmp(&b.canonical));
+
+        duplicates
+    }
+}
+
+/// Find the logos files in the logos path provided that are not referenced by
+/// any of the logos given (i.e. the logos of items removed from the landscape),
+/// returning their file names relative to the logos path sorted
+/// alphabetically. Only image files are considered (hidden files are ignored
+/// as well), so that other files in the logos path are never reported.
+#[instrument(skip_all, err)]
+pub(crate) fn find_orphaned_logos<'a>(
+    logos_path: &Path,
+    referenced: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<String>> {
+    let referenced: HashSet<&str> =
+        referenced.into_iter().map(|logo| logo.trim_start_matches("./")).collect();
+
+    let mut orphaned = vec![];
+    for entry in WalkDir::new(logos_path) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let is_image = mime_guess::from_path(entry.path())
+            .first()
+            .is_some_and(|mime_type| mime_type.type_() == mime_guess::mime::IMAGE);
+        if !is_image {
+            continue;
+        }
+        let logo = entry
+            .path()
+            .strip_prefix(logos_path)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if !referenced.contains(logo.as_str()) {
+            orphaned.push(logo);
+        }
+    }
+    orphaned.sort();
+
+    Ok(orphaned)
+}
+
+/// Rewrite the logos references in the landscape data file provided, so that
+/// all the items using duplicate logos use the canonical file name. The number
+/// of references rewritten is returned.
+#[instrument(skip_all, err)]
+pub(crate) fn rewrite_duplicate_logos(data_file: &Path, duplicates: &[DuplicateLogos]) -> Result<usize> {
+    let replacements: HashMap<&[u8], &[u8]> = duplicates
+        .iter()
+        .flat_map(|duplicate| {
+            duplicate
+                .logos
+                .keys()
+                .filter(|logo| **logo != duplicate.canonical)
+                .map(|logo| (logo.as_bytes(), duplicate.canonical.as_bytes()))
+        })
+        .collect();
+    if replacements.is_empty() {
+        return Ok(0);
+    }
+
+    let data = fs::read(data_file).context("error reading landscape data file")?;
+    let mut rewritten = 0;
+    let new_data = DATA_FILE_LOGO.replace_all(&data, |c: &Captures| match replacements.get(&c["logo"]) {
+        Some(canonical) => {
+            rewritten += 1;
+            [&c["prefix"], &c["quote"], *canonical, &c["suffix"]].concat()
+        }
+        None => c[0].to_vec(),
+    });
+    if rewritten > 0 {
+        fs::write(data_file, new_data).context("error writing landscape data file")?;
+    }
+
+    debug!(rewritten, "duplicate logos references rewritten");
+    Ok(rewritten)
+}
+
 /// Get SVG logo from the source provided and apply some modifications to it.
 pub(crate) async fn prepare_logo(
     cache: &Cache,
     http_client: reqwest::Client,
     logos_source: &LogosSource,
     file_name: &str,
+    optimize: bool,
 ) -> Result<Logo> {
     // Get SVG logo from the source provided
     let mut svg_data = get_svg(http_client.clone(), logos_source, file_name).await?;
 
+    // Remove any potentially unsafe content (scripts, event handlers and
+    // external references). As this is done before computing the digest,
+    // logos sanitized will get a new file name.
+    svg_data = sanitize_svg(&svg_data);
+
     // Remove title if present (some identical logos are using a different
     // title, so we do this before computing the digest)
     svg_data = SVG_TITLE.replace(&svg_data, b"").into_owned();
 
+    // Remove comments and metadata and collapse whitespace
+    if optimize {
+        svg_data = optimize_svg(&svg_data);
+    }
+
     // Calculate digest
     let digest = hex::encode(Sha256::digest(&svg_data));
 
@@ -85,7 +266,7 @@ async fn get_svg(
     // Try from path
     if let Some(path) = &logos_source.logos_path {
         return fs::read(path.join(file_name)).map_err(Into::into);
-    };
+    }
 
     // Try from url
     if let Some(logos_url) = &logos_source.logos_url {
@@ -99,7 +280,7 @@ async fn get_svg(
             ));
         }
         return Ok(resp.bytes().await?.to_vec());
-    };
+    }
 
     Err(format_err!("logos path or url not provided"))
 }
@@ -112,3 +293,55 @@ fn get_svg_bounding_box(svg_data: &[u8]) -> Result<Option<Rect>> {
 
     Ok(bounding_box)
 }
+
+/// Optimize the SVG data provided, removing comments and metadata elements and
+/// collapsing the whitespace between tags.
+fn optimize_svg(svg_data: &[u8]) -> Vec<u8> {
+    let svg_data = SVG_COMMENT.replace_all(svg_data, b"");
+    let svg_data = SVG_METADATA.replace_all(&svg_data, b"");
+    SVG_WHITESPACE.replace_all(&svg_data, b"><").into_owned()
+}
+
+/// Render the SVG data provided as a PNG image, scaled so that its longest side
+/// matches the size provided (in pixels).
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub(crate) fn render_png(svg_data: &[u8], size: u32) -> Result<Vec<u8>> {
+    let opt = usvg::Options::default();
+    let tree = resvg::Tree::from_usvg(&usvg::Tree::from_data(svg_data, &opt)?);
+
+    // Prepare canvas keeping the logo aspect ratio
+    let scale = size as f32 / tree.size.width().max(tree.size.height());
+    let width = (tree.size.width() * scale).ceil() as u32;
+    let height = (tree.size.height() * scale).ceil() as u32;
+    let mut pixmap =
+        tiny_skia::Pixmap::new(width, height).ok_or_else(|| format_err!("invalid logo png size"))?;
+
+    // Render logo and encode it as PNG
+    tree.render(
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    Ok(pixmap.encode_png()?)
+}
+
+/// Sanitize the SVG data provided, removing script elements, event handlers
+/// attributes and references to external resources (only references to
+/// fragments in the same document and embedded images are allowed).
+fn sanitize_svg(svg_data: &[u8]) -> Vec<u8> {
+    let svg_data = SVG_SCRIPT.replace_all(svg_data, b"");
+    let svg_data = SVG_EVENT_HANDLER.replace_all(&svg_data, b"");
+    SVG_HREF
+        .replace_all(&svg_data, |caps: &Captures| {
+            let value = caps.get(1).or_else(|| caps.get(2)).map_or(&b""[..], |m| m.as_bytes());
+            if value.starts_with(b"#") || value.starts_with(b"data:image/") {
+                caps[0].to_vec()
+            } else {
+                vec![]
+            }
+        })
+        .into_owned()
+}
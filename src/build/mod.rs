@@ -1,38 +1,73 @@
 //! This module defines the functionality of the build CLI subcommand.
 
 use self::{
+    aliases::{get_aliases_redirects, AliasRedirect, AliasRedirectPage, ALIASES_PATH},
+    analytics::{render_snippet as render_analytics_snippet, validate_snippet as validate_analytics_snippet},
     cache::Cache,
-    crunchbase::collect_crunchbase_data,
+    cards::{get_social_card_settings, render_social_card},
+    crunchbase::CrunchbaseCollector,
     datasets::Datasets,
-    export::generate_items_csv,
-    github::collect_github_data,
-    logos::prepare_logo,
+    export::{generate_items_csv, generate_items_json, generate_items_ndjson},
+    github::{get_api_url as get_github_api_url, GithubCollector},
+    incremental::{BuildManifest, DATA_INPUT, LANDSCAPE2_INPUT, SETTINGS_INPUT},
+    logos::{prepare_logo, render_png, rewrite_duplicate_logos, DuplicateLogos},
+    packages::collect_packages_data,
     projects::{generate_projects_csv, Project, ProjectsMd},
-    settings::{Images, QrCode},
+    qr_codes::render_qr_code,
+    remote::DataRepo,
+    search::SearchIndex,
+    settings::{Images, QrCode, SocialCards},
+    sitemap::{generate_sitemaps, permalink},
+    stats::Stats,
+};
+use crate::{
+    metrics::{now_timestamp, Metrics},
+    serve::run_server,
+    BuildArgs, DataSource, GuideSource, LogosSource, ServeArgs, SettingsSource,
 };
-use crate::{serve, BuildArgs, GuideSource, LogosSource, ServeArgs};
 use anyhow::{format_err, Context, Result};
 use askama::Template;
+use askama_escape::JsonEscapeBuffer;
 use base64::{engine::general_purpose::STANDARD as b64, Engine as _};
-pub(crate) use data::LandscapeData;
+pub(crate) use cache::DEFAULT_CACHE_TTL;
+use chrono::{DateTime, NaiveDate, Utc};
+pub use collectors::{DataCollector, DataCollectors};
+pub(crate) use data::{legacy, LandscapeData};
+use data::{normalize_name, CategoryName, FilteredOut};
+pub use data::{DataFormat, Item};
+pub(crate) use datasets::Full;
+pub use export::ExportFormat;
 use futures::stream::{self, StreamExt};
 pub(crate) use guide::LandscapeGuide;
 use headless_chrome::{
     browser,
     protocol::cdp::Page::{self, CaptureScreenshotFormatOption},
     types::PrintToPdfOptions,
-    Browser, LaunchOptions,
+    Browser, LaunchOptions, Tab,
 };
+pub(crate) use http::{HttpClient, DEFAULT_COLLECT_CONCURRENCY};
+pub(crate) use landscapes::build_landscapes;
+use lazy_static::lazy_static;
+pub(crate) use logos::find_orphaned_logos;
+pub(crate) use packages::PackageRegistry;
+pub(crate) use projects_status::ProjectsStatus;
+pub use qr_codes::QrCodeEcLevel;
 use qrcode::render::svg;
+use regex::{Captures, Regex};
 use reqwest::StatusCode;
 use rust_embed::RustEmbed;
+use serde::Serialize;
 pub(crate) use settings::LandscapeSettings;
+use sha2::{Digest, Sha256};
+pub(crate) use stats::parse_since_date;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    env,
     ffi::OsStr,
     fs::{self, File},
     io::Write,
     path::{Path, PathBuf},
+    process::Command,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -40,24 +75,40 @@ use tokio::sync::Mutex;
 use tracing::{debug, error, info, instrument, warn};
 use url::Url;
 
+mod aliases;
+mod analytics;
 mod cache;
+mod cards;
 mod clomonitor;
+mod collectors;
 mod crunchbase;
 mod data;
 mod datasets;
 mod export;
 mod github;
 mod guide;
+mod http;
+mod incremental;
+mod landscapes;
 mod logos;
+mod packages;
 mod projects;
+mod projects_status;
+mod qr_codes;
+mod remote;
+mod search;
 mod settings;
+mod sitemap;
 mod stats;
 
+/// Path where the items social cards will be written to in the output directory.
+pub(crate) const CARDS_PATH: &str = "cards";
+
 /// Maximum number of CLOMonitor reports summaries to fetch concurrently.
 const CLOMONITOR_MAX_CONCURRENCY: usize = 10;
 
 /// Path where the datasets will be written to in the output directory.
-const DATASETS_PATH: &str = "data";
+pub(crate) const DATASETS_PATH: &str = "data";
 
 /// Path where some documents will be written to in the output directory.
 const DOCS_PATH: &str = "docs";
@@ -71,32 +122,414 @@ const LOGOS_PATH: &str = "logos";
 /// Maximum number of logos to prepare concurrently.
 const PREPARE_LOGOS_MAX_CONCURRENCY: usize = 20;
 
+/// Path where the items QR codes will be written to in the output directory.
+const QR_CODES_PATH: &str = "qr-codes";
+
+lazy_static! {
+    /// Root-relative url regular expression (used in src and href attributes).
+    /// The base element is matched as well, so that it's not rewritten.
+    static ref ROOT_RELATIVE_URL: Regex =
+        Regex::new(r#"(?P<base><base\s+)?(?P<attr>src|href)="/(?P<path>(?:[^/"][^"]*)?)""#).expect("exprs in ROOT_RELATIVE_URL to be valid");
+}
+
 /// Embed web application assets into binary.
 /// (these assets will be built automatically from the build script)
 #[derive(RustEmbed)]
 #[folder = "web/dist"]
 struct WebAssets;
 
+/// Landscape website build configuration.
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
+pub struct BuildConfig {
+    /// File where the redirects from the categories and subcategories aliases
+    /// will be written to (in the format of the S3 deploy redirects file),
+    /// instead of writing redirect pages to the output directory.
+    pub aliases_redirects_file: Option<PathBuf>,
+
+    /// Report the items logos that are missing (or are not supported) as
+    /// warnings instead of making the build fail.
+    pub allow_missing_logos: bool,
+
+    /// File containing the analytics snippet to inject into the index
+    /// document (it replaces the analytics configured in the settings).
+    pub analytics_snippet: Option<PathBuf>,
+
+    /// Base path the landscape website will be served from (the root of the
+    /// domain is used when not provided).
+    pub base_href: Option<String>,
+
+    /// Write a build-info.json file to the output directory.
+    pub build_info: bool,
+
+    /// Cache directory (the user's cache directory is used when not provided).
+    pub cache_dir: Option<PathBuf>,
+
+    /// Number of days the data collected from external services is reused.
+    pub cache_ttl: u32,
+
+    /// Maximum number of requests to external services in flight at the same
+    /// time (shared by all the collectors).
+    pub collect_concurrency: usize,
+
+    /// Additional data collectors used to enrich the landscape items (they run
+    /// after the built-in ones).
+    pub collectors: DataCollectors,
+
+    /// Format of the landscape data file (detected from its extension when
+    /// not provided).
+    pub data_format: Option<DataFormat>,
+
+    /// Landscape data location.
+    pub data_source: DataSource,
+
+    /// Generate reproducible outputs (maps keys sorted in the JSON files, and
+    /// build and data collection timestamps pinned to SOURCE_DATE_EPOCH).
+    pub deterministic: bool,
+
+    /// Do not inject any analytics snippet into the index document.
+    pub disable_analytics: bool,
+
+    /// Categories excluded from the landscape (along with their items).
+    pub exclude_categories: Vec<CategoryName>,
+
+    /// Make the build fail when any data quality warnings are found.
+    pub fail_on_warning: bool,
+
+    /// Formats of the items export files to generate.
+    pub formats: Vec<ExportFormat>,
+
+    /// Landscape guide location.
+    pub guide_source: GuideSource,
+
+    /// Reuse the web assets and the landscape screenshot of the previous
+    /// build to the same output directory when their inputs haven't changed.
+    pub incremental: bool,
+
+    /// Generate a QR code for each item, linking to its permalink.
+    pub items_qr_codes: bool,
+
+    /// File where the report of the logos with identical content referenced
+    /// using different file names will be written to (in JSON format).
+    pub logos_duplicates_report: Option<PathBuf>,
+
+    /// Size of the PNG version of the logos (not generated when not provided).
+    pub logos_png_size: Option<u32>,
+
+    /// Landscape logos location.
+    pub logos_source: LogosSource,
+
+    /// File where the build metrics will be written to (in the Prometheus text
+    /// format).
+    pub metrics_file: Option<PathBuf>,
+
+    /// Categories included in the landscape (all when empty).
+    pub only_categories: Vec<CategoryName>,
+
+    /// Optimize the SVG logos (they are always sanitized).
+    pub optimize_logos: bool,
+
+    /// Logos referenced by other landscapes sharing the logos path (i.e. when
+    /// building multiple landscapes), which are not considered orphaned.
+    pub other_landscapes_logos: Vec<String>,
+
+    /// Authoritative projects status list (local file or url) the items
+    /// maturity (and TAG) are checked against.
+    pub projects_status: Option<String>,
+
+    /// Remove the logos files in the logos path not referenced by any item.
+    pub prune_orphaned_logos: bool,
+
+    /// Output directory to write files to.
+    pub output_dir: PathBuf,
+
+    /// Landscape data overlays, merged in order on top of the data source.
+    pub overlays: Vec<PathBuf>,
+
+    /// Error correction level of the items QR codes.
+    pub qr_codes_ec_level: QrCodeEcLevel,
+
+    /// Size (in pixels) of each module of the items QR codes.
+    pub qr_codes_module_size: u32,
+
+    /// Ignore the data collected from external services available in the
+    /// cache and collect it again.
+    pub refresh: bool,
+
+    /// Rewrite the references to duplicate logos in the landscape data file,
+    /// so that all the items use the canonical file name.
+    pub rewrite_duplicate_logos: bool,
+
+    /// Categories to take a screenshot of, in addition to the whole landscape.
+    pub screenshot_categories: Vec<CategoryName>,
+
+    /// Width of the landscape screenshots (overrides the one in the settings).
+    pub screenshot_width: Option<u32>,
+
+    /// Generate a precomputed search index file.
+    pub search_index: bool,
+
+    /// Format of the landscape settings file (detected from its extension
+    /// when not provided).
+    pub settings_format: Option<DataFormat>,
+
+    /// Landscape settings overlay, merged on top of the settings source.
+    pub settings_overlay: Option<PathBuf>,
+
+    /// Landscape settings location.
+    pub settings_source: SettingsSource,
+
+    /// Generate an additional stats file scoped to the items added, and the
+    /// events that happened, since this date.
+    pub stats_since: Option<NaiveDate>,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            aliases_redirects_file: None,
+            allow_missing_logos: false,
+            analytics_snippet: None,
+            base_href: None,
+            build_info: false,
+            cache_dir: None,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            collect_concurrency: DEFAULT_COLLECT_CONCURRENCY,
+            collectors: DataCollectors::default(),
+            data_format: None,
+            data_source: DataSource::default(),
+            deterministic: false,
+            disable_analytics: false,
+            exclude_categories: vec![],
+            fail_on_warning: false,
+            formats: vec![ExportFormat::Csv],
+            guide_source: GuideSource::default(),
+            incremental: false,
+            items_qr_codes: false,
+            logos_duplicates_report: None,
+            logos_png_size: None,
+            logos_source: LogosSource::default(),
+            metrics_file: None,
+            only_categories: vec![],
+            optimize_logos: true,
+            other_landscapes_logos: vec![],
+            output_dir: PathBuf::default(),
+            overlays: vec![],
+            projects_status: None,
+            prune_orphaned_logos: false,
+            qr_codes_ec_level: QrCodeEcLevel::default(),
+            qr_codes_module_size: 8,
+            refresh: false,
+            rewrite_duplicate_logos: false,
+            screenshot_categories: vec![],
+            screenshot_width: None,
+            search_index: false,
+            settings_format: None,
+            settings_overlay: None,
+            settings_source: SettingsSource::default(),
+            stats_since: None,
+        }
+    }
+}
+
+impl From<&BuildArgs> for BuildConfig {
+    fn from(args: &BuildArgs) -> Self {
+        Self {
+            aliases_redirects_file: args.aliases_redirects_file.clone(),
+            allow_missing_logos: args.allow_missing_logos,
+            analytics_snippet: args.analytics_snippet.clone(),
+            base_href: args.base_href.clone(),
+            build_info: args.build_info,
+            cache_dir: args.cache_dir.clone(),
+            cache_ttl: args.cache_ttl,
+            collect_concurrency: args.collect_concurrency,
+            collectors: DataCollectors::default(),
+            data_format: args.data_format,
+            data_source: args.data_source.clone(),
+            deterministic: args.deterministic,
+            disable_analytics: args.no_analytics,
+            exclude_categories: args.exclude.clone(),
+            fail_on_warning: args.fail_on_warning,
+            formats: args.formats.clone(),
+            guide_source: args.guide_source.clone(),
+            incremental: args.incremental,
+            items_qr_codes: args.items_qr_codes,
+            logos_duplicates_report: args.logos_duplicates_report.clone(),
+            logos_png_size: args.logos_png_size,
+            logos_source: args.logos_source.clone(),
+            metrics_file: args.metrics_file.clone(),
+            only_categories: args.only.clone(),
+            optimize_logos: !args.no_logo_optimize,
+            other_landscapes_logos: vec![],
+            output_dir: args.output_dir.clone(),
+            overlays: args.overlay.clone(),
+            projects_status: args.projects_status.clone(),
+            prune_orphaned_logos: args.prune_orphaned_logos,
+            qr_codes_ec_level: args.qr_codes_ec_level,
+            qr_codes_module_size: args.qr_codes_module_size,
+            refresh: args.refresh,
+            rewrite_duplicate_logos: args.rewrite_duplicate_logos,
+            screenshot_categories: args.screenshot_category.clone(),
+            screenshot_width: args.screenshot_width,
+            search_index: args.search_index,
+            settings_format: args.settings_format,
+            settings_overlay: args.settings_overlay.clone(),
+            settings_source: args.settings_source.clone(),
+            stats_since: args.stats_since,
+        }
+    }
+}
+
+/// Some information about the landscape website built.
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct BuildOutput {
+    /// Build duration (in seconds).
+    pub duration: f64,
+
+    /// Number of items in the landscape.
+    pub items: usize,
+
+    /// Output directory where the landscape website files were written to.
+    pub output_dir: PathBuf,
+
+    /// Number of data quality warnings found (i.e. missing or orphaned logos).
+    pub warnings: usize,
+}
+
 /// Build landscape website.
+///
+/// # Errors
+///
+/// This function will return an error if any of the landscape sources is not
+/// valid or if some of the build steps fail.
+#[instrument(skip_all)]
+pub async fn build(config: &BuildConfig) -> Result<BuildOutput> {
+    // Setup HTTP client shared by the collectors
+    let http = HttpClient::new(config.collect_concurrency)?;
+
+    build_with_http_client(config, &http).await
+}
+
+/// Build landscape website using the HTTP client provided (it can be shared
+/// by several builds, so that the concurrency limit applies to all of them).
 #[instrument(skip_all)]
-pub(crate) async fn build(args: &BuildArgs) -> Result<()> {
+pub(crate) async fn build_with_http_client(config: &BuildConfig, http: &HttpClient) -> Result<BuildOutput> {
     info!("building landscape website..");
     let start = Instant::now();
 
+    // Build landscape website, writing the build metrics (when requested)
+    // even if the build failed
+    let mut phases = vec![];
+    let result = build_website(config, http, start, &mut phases).await;
+    if let Some(metrics_file) = &config.metrics_file {
+        if let Err(err) = write_metrics(metrics_file, start, &phases, &result) {
+            error!(?err, "error writing build metrics");
+        }
+    }
+
+    result
+}
+
+/// Build landscape website, tracking the duration of each of the build phases
+/// in the list provided.
+#[allow(clippy::too_many_lines)]
+async fn build_website(
+    config: &BuildConfig,
+    http: &HttpClient,
+    start: Instant,
+    phases: &mut Vec<(&'static str, f64)>,
+) -> Result<BuildOutput> {
     // Check required web assets are present
     check_web_assets()?;
 
+    // Check the base href is valid (when provided)
+    let base_href = config.base_href.as_deref().map(normalize_base_href).transpose()?;
+
+    // Check the GitHub API base url is valid (when provided), so that we fail
+    // early instead of after having prepared the logos
+    get_github_api_url()?;
+
     // Setup output directory, creating it when needed
-    setup_output_dir(&args.output_dir)?;
+    setup_output_dir(&config.output_dir)?;
 
     // Setup cache
-    let cache = Cache::new(&args.cache_dir)?;
+    let cache = Cache::new(config.cache_dir.as_ref(), config.cache_ttl, config.refresh)?;
+
+    // Read the manifest of the previous build (incremental builds only)
+    let prev_manifest = if config.incremental {
+        BuildManifest::read(&config.output_dir)
+    } else {
+        None
+    };
 
     // Get landscape data from the source provided
-    let mut landscape_data = LandscapeData::new(&args.data_source).await?;
+    let mut landscape_data =
+        LandscapeData::new(&config.data_source, config.data_format, config.cache_dir.as_ref()).await?;
+
+    // Merge landscape data overlays (later overlays take precedence)
+    for overlay in &config.overlays {
+        landscape_data.merge_overlay(overlay)?;
+    }
+
+    // Report the logos not referenced by any item (before the categories are
+    // filtered, so that the logos of the items filtered out are not included)
+    let mut warnings = check_orphaned_logos(config, &landscape_data)?;
+
+    // Filter landscape categories (when requested), so that the website
+    // (stats and search index included) only includes the categories selected
+    let filtered_out =
+        landscape_data.filter_categories(&config.only_categories, &config.exclude_categories)?;
+
+    // Check that the items logos are available (when they are local), so that
+    // the landscape is not built with broken logos
+    warnings += check_items_logos(config, &landscape_data)?;
+
+    // Check the items maturity against the projects status list (when provided)
+    if let Some(src) = &config.projects_status {
+        warnings += check_projects_status(src, http, &landscape_data).await?;
+    }
 
     // Get landscape settings from the source provided
-    let mut settings = LandscapeSettings::new(&args.settings_source).await?;
+    let mut settings = LandscapeSettings::new(
+        &config.settings_source,
+        config.settings_overlay.as_deref(),
+        config.settings_format,
+    )
+    .await?;
+    if !config.only_categories.is_empty() || !config.exclude_categories.is_empty() {
+        let categories: Vec<CategoryName> =
+            landscape_data.categories.iter().map(|c| c.name.clone()).collect();
+        settings.retain_categories(&categories);
+    }
+
+    // Prepare the analytics snippet to inject into the index document
+    let analytics_snippet = get_analytics_snippet(config, &settings)?;
+
+    // Check the duplicate logos references can be rewritten (when requested)
+    if config.rewrite_duplicate_logos && config.data_source.data_file.is_none() {
+        return Err(format_err!(
+            "rewriting the duplicate logos references requires a local data file"
+        ));
+    }
+
+    // Check the items QR codes can be generated (when requested), as they
+    // link to the items permalinks
+    if config.items_qr_codes {
+        if settings.url.is_none() {
+            return Err(format_err!(
+                "the landscape url must be set in the settings to generate the items qr codes"
+            ));
+        }
+        if config.qr_codes_module_size == 0 {
+            return Err(format_err!("qr codes module size must be at least 1"));
+        }
+    }
+
+    // Generate the redirects from the categories and subcategories aliases
+    // (before the other build steps, so that the build fails early when they
+    // are not valid)
+    let aliases = generate_aliases_redirects(config, &landscape_data, &settings)?;
 
     // Add some extra information to the landscape based on the settings
     landscape_data.add_featured_items_data(&settings)?;
@@ -104,58 +537,391 @@ pub(crate) async fn build(args: &BuildArgs) -> Result<()> {
     landscape_data.add_tags(&settings);
 
     // Get settings images and update their urls to the local copy
-    settings.images = get_settings_images(&settings, &args.output_dir).await?;
+    settings.images = get_settings_images(&settings, &config.output_dir).await?;
 
     // Prepare guide and copy it to the output directory
-    let guide = prepare_guide(&args.guide_source, &args.output_dir).await?;
+    let guide = prepare_guide(config, &landscape_data, &filtered_out).await?;
+    track_phase(phases, "sources", start);
 
     // Prepare items logos and copy them to the output directory
-    prepare_items_logos(&cache, &args.logos_source, &mut landscape_data, &args.output_dir).await?;
+    let phase_start = Instant::now();
+    let duplicate_logos = prepare_items_logos(
+        &cache,
+        &config.logos_source,
+        config.optimize_logos,
+        config.logos_png_size,
+        &mut landscape_data,
+        &config.output_dir,
+    )
+    .await?;
+
+    // Report the logos with identical content referenced using different file
+    // names, rewriting their references in the data file (when requested)
+    if !duplicate_logos.is_empty() {
+        info!(
+            count = duplicate_logos.len(),
+            "identical logos referenced using different file names found"
+        );
+    }
+    if let Some(report_file) = &config.logos_duplicates_report {
+        fs::write(
+            report_file,
+            to_json(&duplicate_logos, true, config.deterministic)?,
+        )
+        .context("error writing logos duplicates report")?;
+    }
+    if config.rewrite_duplicate_logos {
+        if let Some(data_file) = &config.data_source.data_file {
+            let rewritten = rewrite_duplicate_logos(data_file, &duplicate_logos)?;
+            info!(rewritten, "duplicate logos references rewritten in data file");
+        }
+    }
 
-    // Collect CLOMonitor reports summaries and copy them to the output directory
-    collect_clomonitor_reports(&cache, &mut landscape_data, &settings, &args.output_dir).await?;
+    track_phase(phases, "logos", phase_start);
+
+    // Detect which inputs have changed since the previous build
+    let manifest = BuildManifest::new(&landscape_data, &settings, guide.as_ref())?;
+    let changed_inputs = manifest.changed_inputs(prev_manifest.as_ref());
+    if config.incremental {
+        if prev_manifest.is_some() {
+            info!(?changed_inputs, "incremental build");
+        } else {
+            info!("incremental build: no previous build manifest found, running a full build");
+        }
+    }
 
-    // Collect data from external services
-    let (crunchbase_data, github_data) = tokio::try_join!(
-        collect_crunchbase_data(&cache, &landscape_data),
-        collect_github_data(&cache, &landscape_data)
+    // Collect CLOMonitor reports summaries and copy them to the output directory
+    let phase_start = Instant::now();
+    collect_clomonitor_reports(&cache, http, &mut landscape_data, &settings, &config.output_dir).await?;
+
+    // Collect data from external services concurrently using the built-in
+    // collectors, and add it to the landscape data once all of them are done
+    let crunchbase = CrunchbaseCollector::new(&cache, http, settings.crunchbase.as_ref());
+    let github = GithubCollector::new(&cache, http, settings.github.as_ref());
+    let (crunchbase_data, github_data, packages_data) = tokio::try_join!(
+        async {
+            crunchbase
+                .fetch(&landscape_data.items)
+                .await
+                .context("error running crunchbase data collector")
+        },
+        async { github.fetch(&landscape_data.items).await.context("error running github data collector") },
+        async {
+            match &settings.package_stats {
+                Some(cfg) => collect_packages_data(&cache, http, cfg, &landscape_data).await.map(Some),
+                None => Ok(None),
+            }
+        },
     )?;
+    crunchbase.apply(&mut landscape_data.items, &crunchbase_data);
+    github.apply(&mut landscape_data.items, &github_data);
+    if let Some(packages_data) = packages_data {
+        landscape_data.add_packages_data(&packages_data);
+    }
 
-    // Add data collected from external services to the landscape data
-    landscape_data.add_crunchbase_data(crunchbase_data)?;
-    landscape_data.add_github_data(github_data)?;
+    // Run the additional collectors registered in the config (they can use
+    // the data added by the built-in ones)
+    config.collectors.collect(&mut landscape_data.items).await?;
+
+    // In deterministic mode the time the data was collected at is pinned to
+    // the timestamp in SOURCE_DATE_EPOCH (or the Unix epoch when not provided)
+    if config.deterministic {
+        landscape_data.pin_collected_at(source_date_epoch()?.unwrap_or_default());
+    }
+    track_phase(phases, "collectors", phase_start);
 
     // Generate QR code
+    let phase_start = Instant::now();
     let mut qr_code = None;
     if let Some(cfg) = &settings.qr_code {
-        qr_code = Some(generate_qr_code(cfg, &args.output_dir)?);
+        qr_code = Some(generate_qr_code(cfg, &config.output_dir)?);
     }
 
     // Generate datasets for web application
-    let datasets = generate_datasets(&landscape_data, &settings, &guide, &qr_code, &args.output_dir)?;
+    let datasets = generate_datasets(
+        config,
+        &landscape_data,
+        &settings,
+        guide.as_ref(),
+        qr_code.as_ref(),
+        &aliases,
+    )?;
+
+    // Generate stats scoped to a period of time (when requested)
+    if let Some(since) = config.stats_since {
+        generate_scoped_stats_file(config, &landscape_data, &settings, since)?;
+    }
+
+    // Generate precomputed search index (when requested)
+    if config.search_index {
+        generate_search_index_file(config, &landscape_data)?;
+    }
 
     // Render index file and write it to the output directory
-    render_index(&datasets, &args.output_dir)?;
+    render_index(
+        config,
+        &datasets,
+        base_href.as_deref(),
+        analytics_snippet.as_deref(),
+    )?;
 
-    // Copy web assets files to the output directory
-    copy_web_assets(&args.output_dir)?;
+    // Copy web assets files to the output directory (they only change when
+    // a different landscape2 version is used)
+    if changed_inputs.contains(&LANDSCAPE2_INPUT) {
+        copy_web_assets(&config.output_dir)?;
+    } else {
+        debug!("web assets unchanged, skipping copy");
+    }
 
-    // Generate items.csv file
-    generate_items_csv_file(&landscape_data, &args.output_dir)?;
+    // Generate items export files (items.csv, items.json, items.ndjson)
+    generate_items_files(&landscape_data, &config.formats, &config.output_dir)?;
 
     // Generate projects.* files
-    generate_projects_files(&landscape_data, &args.output_dir)?;
+    generate_projects_files(&landscape_data, &config.output_dir)?;
+
+    // Generate sitemap files (only when the landscape url is known)
+    if let Some(url) = &settings.url {
+        generate_sitemap_files(url, &landscape_data, guide.is_some(), &config.output_dir)?;
+    }
 
-    // Prepare landscape screenshot (in PNG and PDF formats)
-    if let Some(width) = &settings.screenshot_width {
-        prepare_screenshot(*width, &args.output_dir).await?;
+    // Generate items QR codes (when requested)
+    if config.items_qr_codes {
+        generate_items_qr_codes(config, &landscape_data, &settings)?;
     }
 
+    // Generate items social cards
+    if let Some(cfg) = &settings.social_cards {
+        generate_social_cards(&cache, cfg, &landscape_data, &config.output_dir)?;
+    }
+    track_phase(phases, "outputs", phase_start);
+
+    // Prepare landscape screenshots (in PNG and PDF formats)
+    if let Some(width) = config.screenshot_width.or(settings.screenshot_width) {
+        let phase_start = Instant::now();
+        let screenshot_inputs = [DATA_INPUT, LANDSCAPE2_INPUT, SETTINGS_INPUT];
+        let mut screenshot_files = vec!["landscape.png".to_string(), "landscape.pdf".to_string()];
+        for category in &config.screenshot_categories {
+            screenshot_files.push(category_screenshot_file(category));
+        }
+        let screenshot_exists =
+            screenshot_files.iter().all(|file| config.output_dir.join(DOCS_PATH).join(file).exists());
+        if screenshot_exists && !screenshot_inputs.iter().any(|input| changed_inputs.contains(input)) {
+            debug!("screenshot inputs unchanged, skipping it");
+        } else {
+            prepare_screenshot(
+                width,
+                &config.screenshot_categories,
+                &landscape_data,
+                &config.output_dir,
+            )
+            .await?;
+        }
+        track_phase(phases, "screenshot", phase_start);
+    }
+
+    // Generate build-info.json file
+    if config.build_info {
+        generate_build_info_file(config)?;
+    }
+
+    // Write build manifest (used by the next incremental build)
+    if config.incremental {
+        manifest.write(&config.output_dir)?;
+    }
+
+    // Make the build fail when warnings were found (when requested)
     let duration = start.elapsed().as_secs_f64();
+    info!(errors = 0, warnings, "build summary");
+    if config.fail_on_warning && warnings > 0 {
+        return Err(format_err!(
+            "{warnings} warnings found (and --fail-on-warning was provided)"
+        ));
+    }
     info!("landscape website built! (took: {:.3}s)", duration);
-    display_success_msg(&args.output_dir.to_string_lossy());
+    display_success_msg(&config.output_dir.to_string_lossy());
+
+    Ok(BuildOutput {
+        duration,
+        items: landscape_data.items.len(),
+        output_dir: config.output_dir.clone(),
+        warnings,
+    })
+}
 
-    Ok(())
+/// Track the duration of the build phase provided, which started at the
+/// instant given.
+fn track_phase(phases: &mut Vec<(&'static str, f64)>, name: &'static str, start: Instant) {
+    phases.push((name, start.elapsed().as_secs_f64()));
+}
+
+/// Write the build metrics (duration of the build and its phases, number of
+/// items and warnings and the result) to the metrics file provided.
+#[allow(clippy::cast_precision_loss)]
+fn write_metrics(
+    metrics_file: &Path,
+    start: Instant,
+    phases: &[(&'static str, f64)],
+    result: &Result<BuildOutput>,
+) -> Result<()> {
+    let mut metrics = Metrics::default();
+    metrics
+        .gauge(
+            "build_success",
+            "Whether the last build succeeded (1) or not (0).",
+            if result.is_ok() { 1.0 } else { 0.0 },
+        )
+        .gauge(
+            "build_last_run_timestamp_seconds",
+            "Timestamp of the last build.",
+            now_timestamp(),
+        )
+        .gauge(
+            "build_duration_seconds",
+            "Duration of the last build.",
+            start.elapsed().as_secs_f64(),
+        );
+    for (phase, duration) in phases {
+        metrics.gauge_with_labels(
+            "build_phase_duration_seconds",
+            "Duration of each of the phases of the last build.",
+            &[("phase", *phase)],
+            *duration,
+        );
+    }
+    if let Ok(output) = result {
+        metrics
+            .gauge(
+                "build_items",
+                "Number of items in the landscape built.",
+                output.items as f64,
+            )
+            .gauge(
+                "build_warnings",
+                "Number of data quality warnings found in the last build.",
+                output.warnings as f64,
+            );
+    }
+    metrics.write(metrics_file)
+}
+
+/// Capture a screenshot in PNG format of the whole page loaded in the tab
+/// provided.
+fn capture_png_screenshot(tab: &Tab) -> Result<Vec<u8>> {
+    let png_b64_data = tab
+        .call_method(Page::CaptureScreenshot {
+            format: Some(CaptureScreenshotFormatOption::Png),
+            quality: None,
+            clip: None,
+            from_surface: None,
+            capture_beyond_viewport: Some(true),
+        })?
+        .data;
+
+    Ok(b64.decode(png_b64_data)?)
+}
+
+/// Return the name of the screenshot file of the category provided.
+fn category_screenshot_file(category: &str) -> String {
+    format!("landscape-{}.png", normalize_name(category))
+}
+
+/// Check that the logos referenced by the items exist in the logos path and
+/// are SVG files (only when the logos are available locally). The problems
+/// found make the build fail, unless they are allowed to be reported as
+/// warnings (returning the number of warnings reported).
+#[instrument(skip_all, err)]
+fn check_items_logos(config: &BuildConfig, landscape_data: &LandscapeData) -> Result<usize> {
+    let Some(logos_path) = &config.logos_source.logos_path else {
+        return Ok(0);
+    };
+    debug!("checking items logos are available");
+
+    let mut problems = vec![];
+    for item in &landscape_data.items {
+        let path = logos_path.join(&item.logo);
+        let problem = if !path.is_file() {
+            "logo not found"
+        } else if !Path::new(&item.logo).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg")) {
+            "logo is not an SVG file"
+        } else {
+            continue;
+        };
+        problems.push(format!("item {}: {problem} ({})", item.id, path.display()));
+    }
+    if problems.is_empty() {
+        return Ok(0);
+    }
+
+    if config.allow_missing_logos {
+        for problem in &problems {
+            warn!("{problem}");
+        }
+        return Ok(problems.len());
+    }
+    for problem in &problems {
+        error!("{problem}");
+    }
+    Err(format_err!(
+        "{} items logos are missing or not supported (--allow-missing-logos can be used to build the landscape anyway)",
+        problems.len()
+    ))
+}
+
+/// Check that the maturity (and TAG) declared by the items match the ones in
+/// the projects status list provided, reporting the mismatches found as
+/// warnings (returning the number of warnings reported).
+#[instrument(skip_all, err)]
+async fn check_projects_status(
+    src: &str,
+    http: &HttpClient,
+    landscape_data: &LandscapeData,
+) -> Result<usize> {
+    let mismatches = ProjectsStatus::new(src, http).await?.find_mismatches(landscape_data);
+    for mismatch in &mismatches {
+        warn!(item = mismatch.item_id, "{mismatch}");
+    }
+
+    Ok(mismatches.len())
+}
+
+/// Check if there are logos files in the logos path (only when the logos are
+/// available locally) that are not referenced by any item (or by the other
+/// landscapes sharing the logos path), reporting them as warnings (returning
+/// the number of warnings reported). They are removed when pruning them has
+/// been requested.
+#[instrument(skip_all, err)]
+fn check_orphaned_logos(config: &BuildConfig, landscape_data: &LandscapeData) -> Result<usize> {
+    let Some(logos_path) = &config.logos_source.logos_path else {
+        return Ok(0);
+    };
+    debug!("checking orphaned logos");
+
+    let referenced = landscape_data
+        .items
+        .iter()
+        .map(|item| item.logo.as_str())
+        .chain(config.other_landscapes_logos.iter().map(String::as_str));
+    let orphaned = find_orphaned_logos(logos_path, referenced)?;
+    if orphaned.is_empty() {
+        return Ok(0);
+    }
+
+    if config.prune_orphaned_logos {
+        for logo in &orphaned {
+            fs::remove_file(logos_path.join(logo)).context(format!("error removing orphaned logo {logo}"))?;
+            debug!(logo, "orphaned logo removed");
+        }
+        info!(count = orphaned.len(), "orphaned logos removed");
+        return Ok(0);
+    }
+    for logo in &orphaned {
+        warn!(logo, "logo not referenced by any item");
+    }
+    warn!(
+        "{} logos are not referenced by any item (--prune-orphaned-logos can be used to remove them)",
+        orphaned.len()
+    );
+    Ok(orphaned.len())
 }
 
 /// Check web assets are present, to make sure the web app has been built.
@@ -177,6 +943,7 @@ fn check_web_assets() -> Result<()> {
 #[instrument(skip_all, err)]
 async fn collect_clomonitor_reports(
     cache: &Cache,
+    http: &HttpClient,
     landscape_data: &mut LandscapeData,
     settings: &LandscapeSettings,
     output_dir: &Path,
@@ -184,7 +951,6 @@ async fn collect_clomonitor_reports(
     debug!("collecting clomonitor reports");
 
     // Fetch CLOMonitor reports summaries and copy them to the output directory
-    let http_client = reqwest::Client::new();
     let foundation = &settings.foundation.to_lowercase();
     let reports_summaries: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
     stream::iter(landscape_data.items.iter())
@@ -195,9 +961,8 @@ async fn collect_clomonitor_reports(
             };
 
             // Fetch report summary
-            let http_client = http_client.clone();
             let report_summary =
-                match clomonitor::fetch_report_summary(cache, http_client, foundation, project_name).await {
+                match clomonitor::fetch_report_summary(cache, http, foundation, project_name).await {
                     Ok(Some(report_summary)) => report_summary,
                     Ok(None) => return,
                     Err(err) => {
@@ -218,7 +983,7 @@ async fn collect_clomonitor_reports(
             if let Err(err) = file.write_all(&report_summary) {
                 error!(?err, ?file_name, "error writing report summary to file");
                 return;
-            };
+            }
 
             // Track report summary to include it later in the item
             let mut reports_summaries = reports_summaries.lock().await;
@@ -278,46 +1043,93 @@ You can see it in action by running the following command:
     );
 }
 
+/// Generate the build-info.json file, which contains some information about
+/// the build (like the landscape2 version used or when it was built).
+#[instrument(skip_all, err)]
+fn generate_build_info_file(config: &BuildConfig) -> Result<()> {
+    debug!("generating build info file");
+
+    // In deterministic mode the build timestamp is pinned to the one provided
+    // in SOURCE_DATE_EPOCH (https://reproducible-builds.org/specs/source-date-epoch/)
+    let built_at = if config.deterministic {
+        source_date_epoch()?
+    } else {
+        Some(Utc::now())
+    };
+    let build_info = BuildInfo {
+        built_at,
+        git_sha: get_git_short_sha(&config.data_source, config.cache_dir.as_ref()),
+        landscape2_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    let mut file = File::create(config.output_dir.join("build-info.json"))?;
+    file.write_all(&to_json(&build_info, true, config.deterministic)?)?;
+
+    Ok(())
+}
+
 /// Generate datasets from the landscape data and settings, as well as from the
 /// data collected from external services (GitHub, Crunchbase, etc). Some of
 /// the datasets will be embedded in the index document, and the rest will be
 /// written to the DATASETS_PATH in the output directory.
 #[instrument(skip_all, err)]
 fn generate_datasets(
+    config: &BuildConfig,
     landscape_data: &LandscapeData,
     settings: &LandscapeSettings,
-    guide: &Option<LandscapeGuide>,
-    qr_code: &Option<String>,
-    output_dir: &Path,
+    guide: Option<&LandscapeGuide>,
+    qr_code: Option<&String>,
+    aliases: &[AliasRedirect],
 ) -> Result<Datasets> {
     debug!("generating datasets");
 
-    let datasets = Datasets::new(landscape_data, settings, guide, qr_code)?;
-    let datasets_path = output_dir.join(DATASETS_PATH);
+    let datasets = Datasets::new(landscape_data, settings, guide, qr_code, aliases)?;
+    let datasets_path = config.output_dir.join(DATASETS_PATH);
 
     // Base
     let mut base_file = File::create(datasets_path.join("base.json"))?;
-    base_file.write_all(&serde_json::to_vec(&datasets.base)?)?;
+    base_file.write_all(&to_json(&datasets.base, false, config.deterministic)?)?;
 
     // Full
     let mut full_file = File::create(datasets_path.join("full.json"))?;
-    full_file.write_all(&serde_json::to_vec(&datasets.full)?)?;
+    full_file.write_all(&to_json(&datasets.full, false, config.deterministic)?)?;
 
     // Stats
     let mut stats_file = File::create(datasets_path.join("stats.json"))?;
-    stats_file.write_all(&serde_json::to_vec(&datasets.stats)?)?;
+    stats_file.write_all(&to_json(&datasets.stats, false, config.deterministic)?)?;
 
     Ok(datasets)
 }
 
-/// Generate the items.csv file from the landscape data.
+/// Generate the items export files from the landscape data, one for each of
+/// the formats requested.
 #[instrument(skip_all, err)]
-fn generate_items_csv_file(landscape_data: &LandscapeData, output_dir: &Path) -> Result<()> {
-    debug!("generating items csv file");
-
+fn generate_items_files(
+    landscape_data: &LandscapeData,
+    formats: &[ExportFormat],
+    output_dir: &Path,
+) -> Result<()> {
     let docs_path = output_dir.join(DOCS_PATH);
-    let w = csv::Writer::from_path(docs_path.join("items.csv"))?;
-    generate_items_csv(w, landscape_data)?;
+
+    // items.csv
+    if formats.contains(&ExportFormat::Csv) {
+        debug!("generating items csv file");
+        let w = csv::Writer::from_path(docs_path.join("items.csv"))?;
+        generate_items_csv(w, landscape_data)?;
+    }
+
+    // items.json
+    if formats.contains(&ExportFormat::Json) {
+        debug!("generating items json file");
+        let w = File::create(docs_path.join("items.json"))?;
+        generate_items_json(w, landscape_data)?;
+    }
+
+    // items.ndjson
+    if formats.contains(&ExportFormat::Ndjson) {
+        debug!("generating items ndjson file");
+        let w = File::create(docs_path.join("items.ndjson"))?;
+        generate_items_ndjson(w, landscape_data)?;
+    }
 
     Ok(())
 }
@@ -342,6 +1154,191 @@ fn generate_projects_files(landscape_data: &LandscapeData, output_dir: &Path) ->
     Ok(())
 }
 
+/// Generate a stats file scoped to the items added, and the events that
+/// happened, since the date provided. The file is written to the DATASETS_PATH
+/// in the output directory, named stats-since-<date>.json.
+#[instrument(skip_all, err)]
+fn generate_scoped_stats_file(
+    config: &BuildConfig,
+    landscape_data: &LandscapeData,
+    settings: &LandscapeSettings,
+    since: NaiveDate,
+) -> Result<()> {
+    debug!(%since, "generating scoped stats file");
+
+    let stats = Stats::new_since(landscape_data, settings, since);
+    let file_name = format!("stats-since-{}.json", since.format("%Y-%m-%d"));
+    let mut file = File::create(config.output_dir.join(DATASETS_PATH).join(file_name))?;
+    file.write_all(&to_json(&stats, false, config.deterministic)?)?;
+
+    Ok(())
+}
+
+/// Generate a precomputed search index from the landscape data, so that the
+/// web application doesn't need to build it in the browser. The index is
+/// written to the DATASETS_PATH in the output directory.
+#[instrument(skip_all, err)]
+fn generate_search_index_file(config: &BuildConfig, landscape_data: &LandscapeData) -> Result<()> {
+    debug!("generating search index file");
+
+    let search_index = SearchIndex::new(landscape_data);
+    let mut file = File::create(config.output_dir.join(DATASETS_PATH).join("search-index.json"))?;
+    file.write_all(&to_json(&search_index, false, config.deterministic)?)?;
+
+    Ok(())
+}
+
+/// Generate a QR code (in SVG format) for each of the items, linking to its
+/// permalink, and write them to the QR_CODES_PATH in the output directory
+/// (named <item id>.svg).
+#[instrument(skip_all, err)]
+fn generate_items_qr_codes(
+    config: &BuildConfig,
+    landscape_data: &LandscapeData,
+    settings: &LandscapeSettings,
+) -> Result<()> {
+    debug!("generating items qr codes");
+
+    let Some(base_url) = &settings.url else {
+        return Err(format_err!("landscape url not provided in settings"));
+    };
+    let base_url = base_url.trim_end_matches('/');
+    let qr_codes_path = config.output_dir.join(QR_CODES_PATH);
+    if !qr_codes_path.exists() {
+        fs::create_dir(&qr_codes_path)?;
+    }
+
+    for item in &landscape_data.items {
+        let item_url = permalink(base_url, &[("item", &item.id)]);
+        let svg = render_qr_code(&item_url, config.qr_codes_ec_level, config.qr_codes_module_size)
+            .context(format!("error generating qr code for item {}", item.name))?;
+        File::create(qr_codes_path.join(format!("{}.svg", item.id)))?.write_all(svg.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Generate the sitemap files from the landscape data.
+#[instrument(skip_all, err)]
+fn generate_sitemap_files(
+    url: &str,
+    landscape_data: &LandscapeData,
+    guide_available: bool,
+    output_dir: &Path,
+) -> Result<()> {
+    debug!("generating sitemap files");
+
+    for (file_name, content) in generate_sitemaps(url, landscape_data, guide_available) {
+        File::create(output_dir.join(file_name))?.write_all(content.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Generate the redirects from the previous slugs of the categories and
+/// subcategories (aliases) to their current permalinks, returning them (they
+/// are included in the base dataset). Redirect pages are written to the output
+/// directory, unless an aliases redirects file has been requested (to create
+/// them as 301 redirects when deploying to S3).
+#[instrument(skip_all, err)]
+fn generate_aliases_redirects(
+    config: &BuildConfig,
+    landscape_data: &LandscapeData,
+    settings: &LandscapeSettings,
+) -> Result<Vec<AliasRedirect>> {
+    // Remove the redirect pages written by previous builds, as some aliases
+    // may have been removed
+    let aliases_path = config.output_dir.join(ALIASES_PATH);
+    if aliases_path.exists() {
+        fs::remove_dir_all(&aliases_path)?;
+    }
+    let Some(aliases) = &settings.aliases else {
+        return Ok(vec![]);
+    };
+    debug!("generating aliases redirects");
+
+    let categories_filtered = !config.only_categories.is_empty() || !config.exclude_categories.is_empty();
+    let redirects = get_aliases_redirects(aliases, landscape_data, categories_filtered)?;
+
+    // Write the redirects file (using the format of the S3 deploy redirects
+    // file), when requested
+    if let Some(redirects_file) = &config.aliases_redirects_file {
+        let redirects_map: BTreeMap<String, String> = redirects
+            .iter()
+            .map(|redirect| (format!("/{}", redirect.path()), redirect.url("")))
+            .collect();
+        fs::write(redirects_file, serde_yaml::to_string(&redirects_map)?)
+            .context("error writing aliases redirects file")?;
+        return Ok(redirects);
+    }
+
+    // Write the redirect pages
+    for redirect in &redirects {
+        let path = config.output_dir.join(redirect.path());
+        fs::create_dir_all(&path)?;
+        let url = redirect.relative_url();
+        let page = AliasRedirectPage {
+            foundation: &settings.foundation,
+            name: &redirect.name(),
+            url: &url,
+        }
+        .render()?;
+        File::create(path.join("index.html"))?.write_all(page.as_bytes())?;
+    }
+
+    Ok(redirects)
+}
+
+/// Generate the items social cards (PNG images). They are used as the Open
+/// Graph image of the items permalinks when the landscape is served using the
+/// serve subcommand. Cards are cached using a digest of the card content, so
+/// unchanged items are not rendered again.
+#[instrument(skip_all, err)]
+fn generate_social_cards(
+    cache: &Cache,
+    cfg: &SocialCards,
+    landscape_data: &LandscapeData,
+    output_dir: &Path,
+) -> Result<()> {
+    debug!("generating social cards");
+
+    let cards_path = output_dir.join(CARDS_PATH);
+    if !cards_path.exists() {
+        fs::create_dir(&cards_path)?;
+    }
+    let (width, height, background_color) = get_social_card_settings(cfg);
+
+    for item in &landscape_data.items {
+        if item.logo.is_empty() {
+            continue;
+        }
+
+        // Render card (reusing the cached version when available)
+        let logo_svg = fs::read(output_dir.join(&item.logo))?;
+        let digest = hex::encode(Sha256::digest(
+            format!(
+                "{}|{}|{}|{width}x{height}|{background_color}",
+                item.name,
+                item.category,
+                hex::encode(Sha256::digest(&logo_svg))
+            )
+            .as_bytes(),
+        ));
+        let card_cache_file = format!("card_{digest}.png");
+        let card = if let Ok(Some((_, card))) = cache.read(&card_cache_file) {
+            card
+        } else {
+            let card = render_social_card(cfg, &item.name, &item.category, &logo_svg)
+                .context(format!("error rendering social card for item {}", item.name))?;
+            cache.write(&card_cache_file, &card)?;
+            card
+        };
+        File::create(cards_path.join(format!("{}.png", item.id)))?.write_all(&card)?;
+    }
+
+    Ok(())
+}
+
 /// Generate QR code and copy it to output directory.
 #[instrument(skip_all, err)]
 fn generate_qr_code(cfg: &QrCode, output_dir: &Path) -> Result<String> {
@@ -363,11 +1360,37 @@ fn generate_qr_code(cfg: &QrCode, output_dir: &Path) -> Result<String> {
     Ok(svg_path.to_string_lossy().into_owned())
 }
 
+/// Get the git short SHA of the repository containing the landscape data file
+/// (only when a local data file or a git repository is used). This is done on
+/// a best effort basis, so any error getting it will be ignored.
+fn get_git_short_sha(data_source: &DataSource, cache_dir: Option<&PathBuf>) -> Option<String> {
+    let repo_path = if let Some(repo) = &data_source.data_repo {
+        DataRepo::parse(repo).ok()?.checkout_dir(cache_dir).ok()?
+    } else {
+        match data_source.data_file.as_ref()?.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        debug!("landscape data file is not in a git repository, git sha will be omitted");
+        return None;
+    }
+    let sha = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!sha.is_empty()).then_some(sha)
+}
+
 /// Get settings images and copy them to the output directory.
 #[instrument(skip_all, err)]
 async fn get_settings_images(settings: &LandscapeSettings, output_dir: &Path) -> Result<Images> {
     // Helper function to process the image provided
-    async fn process_image(url: &Option<String>, output_dir: &Path) -> Result<Option<String>> {
+    async fn process_image(url: Option<&String>, output_dir: &Path) -> Result<Option<String>> {
         let Some(url) = url else {
             return Ok(None);
         };
@@ -396,9 +1419,9 @@ async fn get_settings_images(settings: &LandscapeSettings, output_dir: &Path) ->
     debug!("getting settings images");
 
     let (favicon, footer_logo, header_logo) = tokio::try_join!(
-        process_image(&settings.images.favicon, output_dir),
-        process_image(&settings.images.footer_logo, output_dir),
-        process_image(&settings.images.header_logo, output_dir),
+        process_image(settings.images.favicon.as_ref(), output_dir),
+        process_image(settings.images.footer_logo.as_ref(), output_dir),
+        process_image(settings.images.header_logo.as_ref(), output_dir),
     )?;
     let images = Images {
         favicon,
@@ -410,29 +1433,56 @@ async fn get_settings_images(settings: &LandscapeSettings, output_dir: &Path) ->
     Ok(images)
 }
 
-/// Prepare guide and copy it to the output directory.
+/// Normalize the base href provided, making sure it is an absolute path that
+/// starts and ends with a slash (i.e. landscapes/cncf -> /landscapes/cncf/).
+fn normalize_base_href(base_href: &str) -> Result<String> {
+    let path = base_href.trim().trim_matches('/');
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || "-._~/%".contains(c);
+    if !path.chars().all(valid_char) || path.contains("//") {
+        return Err(format_err!(
+            "invalid base href: {base_href} (it must be a path, like /landscapes/cncf/)"
+        ));
+    }
+    if path.is_empty() {
+        return Ok("/".to_string());
+    }
+    Ok(format!("/{path}/"))
+}
+
+/// Prepare guide and copy it to the output directory. The sections of the
+/// categories filtered out are removed (along with the links to them). The
+/// build fails when the guide contains broken links to the landscape.
 #[instrument(skip_all, err)]
-async fn prepare_guide(guide_source: &GuideSource, output_dir: &Path) -> Result<Option<LandscapeGuide>> {
+async fn prepare_guide(
+    config: &BuildConfig,
+    landscape_data: &LandscapeData,
+    filtered_out: &FilteredOut,
+) -> Result<Option<LandscapeGuide>> {
     debug!("preparing guide");
 
-    let Some(guide) = LandscapeGuide::new(guide_source).await? else {
+    let Some(mut guide) = LandscapeGuide::new(&config.guide_source).await? else {
         return Ok(None);
     };
-    let path = output_dir.join(DATASETS_PATH).join("guide.json");
-    File::create(path)?.write_all(&serde_json::to_vec(&guide)?)?;
+    guide.remove_filtered_out(filtered_out);
+    guide.check_landscape_links(landscape_data)?;
+    let path = config.output_dir.join(DATASETS_PATH).join("guide.json");
+    File::create(path)?.write_all(&to_json(&guide, false, config.deterministic)?)?;
 
     Ok(Some(guide))
 }
 
 /// Prepare items logos and copy them to the output directory, updating the
-/// logo reference on each landscape item.
+/// logo reference on each landscape item. The logos with identical content
+/// referenced using different file names are returned.
 #[instrument(skip_all, err)]
 async fn prepare_items_logos(
     cache: &Cache,
     logos_source: &LogosSource,
+    optimize_logos: bool,
+    logos_png_size: Option<u32>,
     landscape_data: &mut LandscapeData,
     output_dir: &Path,
-) -> Result<()> {
+) -> Result<Vec<DuplicateLogos>> {
     debug!("preparing logos");
 
     // Get logos from the source and copy them to the output directory
@@ -450,7 +1500,7 @@ async fn prepare_items_logos(
             let logos_source = logos_source.clone();
             let file_name = item.logo.clone();
             let logo = match tokio::spawn(async move {
-                prepare_logo(&cache, http_client, &logos_source, &file_name).await
+                prepare_logo(&cache, http_client, &logos_source, &file_name, optimize_logos).await
             })
             .await
             {
@@ -476,33 +1526,70 @@ async fn prepare_items_logos(
             };
             if let Err(err) = file.write_all(&logo.svg_data) {
                 error!(?err, ?file_name, "error writing logo to file in output dir");
-            };
+            }
 
-            (item.id.clone(), Some(format!("{LOGOS_PATH}/{file_name}")))
+            // Generate a PNG version of the logo if requested (the size is
+            // part of the filename, so changing it produces new files)
+            if let Some(size) = logos_png_size {
+                let png_file_name = format!("{}-{size}.png", logo.digest);
+                match render_png(&logo.svg_data, size) {
+                    Ok(png_data) => {
+                        if let Err(err) =
+                            fs::write(output_dir.join(LOGOS_PATH).join(&png_file_name), png_data)
+                        {
+                            error!(
+                                ?err,
+                                ?png_file_name,
+                                "error writing logo png to file in output dir"
+                            );
+                        }
+                    }
+                    Err(err) => error!(?err, ?png_file_name, "error rendering logo png"),
+                }
+            }
+
+            (item.id.clone(), Some(logo.digest))
         })
         .buffer_unordered(concurrency)
         .collect()
         .await;
 
     // Update logo field in landscape items to logo digest path
+    let mut logos_entries = vec![];
     for item in &mut landscape_data.items {
-        item.logo = if let Some(Some(logo)) = logos.get(&item.id) {
-            logo.clone()
+        item.logo = if let Some(Some(digest)) = logos.get(&item.id) {
+            logos_entries.push((digest.clone(), item.logo.clone(), item.name.clone()));
+            format!("{LOGOS_PATH}/{digest}.svg")
         } else {
             String::new()
         }
     }
 
     debug!("done!");
-    Ok(())
+    Ok(DuplicateLogos::find(&logos_entries))
 }
 
-/// Prepare landscape screenshot (in PNG and PDF formats).
+/// Prepare landscape screenshot (in PNG and PDF formats), as well as the
+/// screenshots of the categories views provided (in PNG format).
 #[instrument(skip_all, err)]
 #[allow(clippy::cast_precision_loss, clippy::items_after_statements)]
-async fn prepare_screenshot(width: u32, output_dir: &Path) -> Result<()> {
+async fn prepare_screenshot(
+    width: u32,
+    categories: &[CategoryName],
+    landscape_data: &LandscapeData,
+    output_dir: &Path,
+) -> Result<()> {
     debug!("preparing screenshot");
 
+    // Check the categories provided exist in the landscape
+    for category in categories {
+        if !landscape_data.categories.iter().any(|c| &c.name == category) {
+            return Err(format_err!(
+                "screenshot category {category} not found in landscape"
+            ));
+        }
+    }
+
     // Check if Chrome/Chromium is available
     if browser::default_executable().is_err() {
         warn!("chrome/chromium not found, no screenshot will be taken");
@@ -518,8 +1605,9 @@ async fn prepare_screenshot(width: u32, output_dir: &Path) -> Result<()> {
             graceful_shutdown: false,
             landscape_dir,
             silent: true,
+            watch: None,
         };
-        serve(&args).await
+        run_server(&args, None).await
     });
 
     // Setup headless browser and navigate to screenshot url
@@ -542,18 +1630,8 @@ async fn prepare_screenshot(width: u32, output_dir: &Path) -> Result<()> {
     tab.navigate_to(&screenshot_url)?.wait_until_navigated()?;
 
     // Take screenshot in PNG format and save it to a file
-    let png_b64_data = tab
-        .call_method(Page::CaptureScreenshot {
-            format: Some(CaptureScreenshotFormatOption::Png),
-            quality: None,
-            clip: None,
-            from_surface: None,
-            capture_beyond_viewport: Some(true),
-        })?
-        .data;
-    let png_data = b64.decode(png_b64_data)?;
     let png_path = output_dir.join(DOCS_PATH).join("landscape.png");
-    File::create(&png_path)?.write_all(&png_data)?;
+    File::create(&png_path)?.write_all(&capture_png_screenshot(&tab)?)?;
 
     // Take screenshot in PDF format and save it to a file
     // We use the dimensions of the screenshot in PNG format to calculate the
@@ -573,6 +1651,15 @@ async fn prepare_screenshot(width: u32, output_dir: &Path) -> Result<()> {
     let pdf_path = output_dir.join(DOCS_PATH).join("landscape.pdf");
     File::create(pdf_path)?.write_all(&pdf_data)?;
 
+    // Take screenshots of the categories views (using their permalinks)
+    for category in categories {
+        let category_url = Url::parse_with_params(&format!("http://{SVR_ADDR}/"), &[("category", category)])?;
+        tab.navigate_to(category_url.as_str())?.wait_until_navigated()?;
+        let png_path = output_dir.join(DOCS_PATH).join(category_screenshot_file(category));
+        File::create(&png_path)?.write_all(&capture_png_screenshot(&tab)?)?;
+        debug!(?category, "category screenshot taken");
+    }
+
     // Stop server
     server.abort();
 
@@ -580,20 +1667,80 @@ async fn prepare_screenshot(width: u32, output_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Some information about the build.
+#[derive(Debug, Clone, Serialize)]
+struct BuildInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    built_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git_sha: Option<String>,
+    landscape2_version: String,
+}
+
+/// Get the analytics snippet to inject into the index document. The snippet
+/// file provided takes precedence over the analytics configured in the
+/// settings, and no snippet is injected when analytics have been disabled.
+fn get_analytics_snippet(config: &BuildConfig, settings: &LandscapeSettings) -> Result<Option<String>> {
+    if config.disable_analytics {
+        debug!("analytics disabled, no snippet will be injected");
+        return Ok(None);
+    }
+
+    // Snippet file provided
+    if let Some(file) = &config.analytics_snippet {
+        let snippet = fs::read_to_string(file).context("error reading analytics snippet file")?;
+        validate_analytics_snippet(&snippet)?;
+        return Ok(Some(snippet.trim().to_string()));
+    }
+
+    // Analytics configured in the settings (already validated)
+    let snippet = settings.analytics.as_ref().map(render_analytics_snippet);
+    Ok(snippet.filter(|snippet| !snippet.is_empty()))
+}
+
 /// Template for the index document.
 #[derive(Debug, Clone, Template)]
 #[template(path = "index.html", escape = "none")]
 struct Index<'a> {
+    analytics: Option<&'a str>,
+    base_href: Option<&'a str>,
+    base_ds: String,
     datasets: &'a Datasets,
+    stats_ds: String,
 }
 
 /// Render index file and write it to the output directory.
 #[instrument(skip_all, err)]
-fn render_index(datasets: &Datasets, output_dir: &Path) -> Result<()> {
+fn render_index(
+    config: &BuildConfig,
+    datasets: &Datasets,
+    base_href: Option<&str>,
+    analytics: Option<&str>,
+) -> Result<()> {
     debug!("rendering index.html file");
 
-    let index = Index { datasets }.render()?;
-    File::create(output_dir.join("index.html"))?.write_all(index.as_bytes())?;
+    let mut index = Index {
+        analytics,
+        base_href,
+        base_ds: to_embedded_json(&datasets.base, config.deterministic)?,
+        datasets,
+        stats_ds: to_embedded_json(&datasets.stats, config.deterministic)?,
+    }
+    .render()?;
+
+    // Root-relative urls are not resolved using the base element, so they are
+    // rewritten to be relative to the base href
+    if let Some(base_href) = base_href {
+        index = ROOT_RELATIVE_URL
+            .replace_all(&index, |c: &Captures| {
+                if c.name("base").is_some() {
+                    return c[0].to_string();
+                }
+                format!(r#"{}="{base_href}{}""#, &c["attr"], &c["path"])
+            })
+            .into_owned();
+    }
+    File::create(config.output_dir.join("index.html"))?.write_all(index.as_bytes())?;
 
     Ok(())
 }
@@ -632,6 +1779,44 @@ fn setup_output_dir(output_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Serialize the value provided to JSON (pretty printed when requested). In
+/// deterministic mode the value is converted to a JSON value first, to make
+/// sure maps keys are sorted and the output is stable across builds.
+fn to_json<T: Serialize>(value: &T, pretty: bool, deterministic: bool) -> Result<Vec<u8>> {
+    let json_data = match (deterministic, pretty) {
+        (false, false) => serde_json::to_vec(value)?,
+        (false, true) => serde_json::to_vec_pretty(value)?,
+        (true, false) => serde_json::to_vec(&serde_json::to_value(value)?)?,
+        (true, true) => serde_json::to_vec_pretty(&serde_json::to_value(value)?)?,
+    };
+    Ok(json_data)
+}
+
+/// Serialize the value provided to JSON, escaped so that it can be embedded
+/// in the index document (maps keys are sorted in deterministic mode).
+fn to_embedded_json<T: Serialize>(value: &T, deterministic: bool) -> Result<String> {
+    let mut writer = JsonEscapeBuffer::new();
+    if deterministic {
+        serde_json::to_writer(&mut writer, &serde_json::to_value(value)?)?;
+    } else {
+        serde_json::to_writer(&mut writer, value)?;
+    }
+    Ok(writer.finish())
+}
+
+/// Return the timestamp provided in the SOURCE_DATE_EPOCH environment variable
+/// (if any), used to pin the build and data collection timestamps in
+/// deterministic mode.
+fn source_date_epoch() -> Result<Option<DateTime<Utc>>> {
+    let Ok(value) = env::var("SOURCE_DATE_EPOCH") else {
+        return Ok(None);
+    };
+    let secs: i64 = value.trim().parse().context("invalid SOURCE_DATE_EPOCH")?;
+    let built_at =
+        DateTime::from_timestamp(secs, 0).ok_or_else(|| format_err!("invalid SOURCE_DATE_EPOCH"))?;
+    Ok(Some(built_at))
+}
+
 mod filters {
     use askama_escape::JsonEscapeBuffer;
     use serde::Serialize;
@@ -646,3 +1831,78 @@ mod filters {
         Ok(writer.finish())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str = r"
+landscape:
+  - category:
+    name: App Definition
+    subcategories:
+      - subcategory:
+        name: Database
+        items:
+          - item:
+            name: Item
+            homepage_url: https://item.example.com
+            logo: item.svg
+";
+
+    const SETTINGS: &str = r"
+foundation: CNCF
+url: https://example.com/landscapes/cncf/
+images: {}
+";
+
+    const LOGO: &str =
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1 1"><rect width="1" height="1"/></svg>"#;
+
+    #[tokio::test]
+    async fn build_with_base_href() {
+        // Prepare landscape sources in a temporary directory
+        let dir = std::env::temp_dir().join(format!("landscape2-build-tests-{}", std::process::id()));
+        let logos_path = dir.join("logos");
+        fs::create_dir_all(&logos_path).unwrap();
+        fs::write(dir.join("landscape.yml"), DATA).unwrap();
+        fs::write(dir.join("settings.yml"), SETTINGS).unwrap();
+        fs::write(logos_path.join("item.svg"), LOGO).unwrap();
+
+        // Build landscape using a base href
+        let output_dir = dir.join("build");
+        let config = BuildConfig {
+            base_href: Some("landscapes/cncf".to_string()),
+            cache_dir: Some(dir.join("cache")),
+            data_source: DataSource {
+                data_file: Some(dir.join("landscape.yml")),
+                ..DataSource::default()
+            },
+            formats: vec![],
+            logos_source: LogosSource {
+                logos_path: Some(logos_path),
+                ..LogosSource::default()
+            },
+            output_dir: output_dir.clone(),
+            settings_source: SettingsSource {
+                settings_file: Some(dir.join("settings.yml")),
+                ..SettingsSource::default()
+            },
+            ..BuildConfig::default()
+        };
+        build(&config).await.unwrap();
+
+        // Check the base element has been added and the root-relative urls
+        // have been rewritten to be relative to the base href
+        let index = fs::read_to_string(output_dir.join("index.html")).unwrap();
+        assert!(index.contains(r#"<base href="/landscapes/cncf/" />"#));
+        let urls: Vec<Captures> = ROOT_RELATIVE_URL.captures_iter(&index).collect();
+        assert!(urls.iter().any(|c| &c["attr"] == "src"));
+        assert!(urls
+            .iter()
+            .filter(|c| c.name("base").is_none())
+            .all(|c| c["path"].starts_with("landscapes/cncf/")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,330 @@
+//! This module defines some types used to represent the download stats
+//! collected from the packages registries (npm, crates.io and Docker Hub) for
+//! each of the landscape items packages (when applicable), as well as the
+//! functionality used to collect that information.
+//!
+//! Packages are provided in the `extra` section of the items in the landscape
+//! data file (`npm_package`, `crate_name` and `docker_image`), and stats are
+//! only collected when enabled in the settings file (`package_stats`).
+
+use super::{cache::Cache, http::HttpClient, settings::PackageStats, LandscapeData};
+use anyhow::{format_err, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use leaky_bucket::RateLimiter;
+#[cfg(test)]
+use mockall::automock;
+use reqwest::{header, StatusCode};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+use tracing::{debug, info, instrument};
+
+/// File used to cache data collected from the packages registries.
+const PACKAGES_CACHE_FILE: &str = "packages.json";
+
+/// Maximum number of times a request will be retried when hitting a registry
+/// rate limit.
+const PACKAGES_MAX_RETRIES: u32 = 5;
+
+/// Interval for the rate limiter used when sending requests to the registries
+/// (crates.io asks crawlers to send one request per second at most).
+const PACKAGES_RATE_LIMITER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Delay used to backoff when hitting a rate limit and the registry doesn't
+/// tell us how long to wait (it's doubled on each retry).
+const PACKAGES_RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+
+/// Collect the download stats of each of the items packages in the landscape,
+/// reusing cached data whenever possible.
+#[instrument(skip_all, err)]
+pub(crate) async fn collect_packages_data(
+    cache: &Cache,
+    http: &HttpClient,
+    cfg: &PackageStats,
+    landscape_data: &LandscapeData,
+) -> Result<PackagesData> {
+    debug!("collecting packages download stats from registries (this may take a while)");
+
+    // Read cached data (if available)
+    let mut cached_data: Option<PackagesData> = None;
+    if let Ok(Some((_, json_data))) = cache.read(PACKAGES_CACHE_FILE) {
+        if let Ok(packages_data) = serde_json::from_slice(&json_data) {
+            cached_data = Some(packages_data);
+        }
+    }
+
+    // Setup packages registries API client
+    let pr: DynPR = Arc::new(PRApi::new(http));
+
+    // Collect items packages (only from the registries enabled)
+    let mut packages = vec![];
+    for item in &landscape_data.items {
+        for package in item.packages.iter().flatten() {
+            if cfg.registries.as_ref().map_or(true, |registries| registries.contains(&package.registry)) {
+                packages.push(package);
+            }
+        }
+    }
+    packages.sort_by_key(|package| package.key());
+    packages.dedup_by_key(|package| package.key());
+
+    // Collect download stats from the registries, reusing cached data when
+    // available
+    let limiter = RateLimiter::builder().initial(1).interval(PACKAGES_RATE_LIMITER_INTERVAL).build();
+    let packages_data: PackagesData = stream::iter(packages)
+        .map(|package| async {
+            let key = package.key();
+
+            // Use cached data when available if it hasn't expired yet
+            if let Some(cached_downloads) = cached_data.as_ref().and_then(|cached_data| {
+                cached_data.get(&key).and_then(|downloads| {
+                    if cache.is_fresh(downloads.generated_at) {
+                        Some(downloads)
+                    } else {
+                        None
+                    }
+                })
+            }) {
+                (key, Ok(cached_downloads.clone()))
+            }
+            // Otherwise we pull it from the registry
+            else {
+                limiter.acquire_one().await;
+                (
+                    key,
+                    PackageDownloads::new(pr.clone(), package.registry, &package.name).await,
+                )
+            }
+        })
+        .buffer_unordered(1)
+        .collect::<HashMap<PackageKey, Result<PackageDownloads>>>()
+        .await
+        .into_iter()
+        .filter_map(|(key, result)| {
+            if let Ok(downloads) = result {
+                Some((key, downloads))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // Write data (in json format) to cache, keeping the fresh data collected
+    // for other landscapes sharing the cache
+    let mut cache_data = cached_data.unwrap_or_default();
+    cache_data.retain(|_, downloads| cache.is_fresh(downloads.generated_at));
+    cache_data.extend(packages_data.clone());
+    cache.write(PACKAGES_CACHE_FILE, &serde_json::to_vec_pretty(&cache_data)?)?;
+
+    debug!("done!");
+    Ok(packages_data)
+}
+
+/// Type alias to represent some packages' download stats.
+pub(crate) type PackagesData = HashMap<PackageKey, PackageDownloads>;
+
+/// Type alias to represent a package key (`<registry>:<name>`).
+pub(crate) type PackageKey = String;
+
+/// Package published by an item in a packages registry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub registry: PackageRegistry,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<PackageDownloads>,
+}
+
+impl Package {
+    /// Return the key used to identify the package.
+    pub(crate) fn key(&self) -> PackageKey {
+        format!("{}:{}", self.registry, self.name)
+    }
+}
+
+/// Packages registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageRegistry {
+    Crates,
+    Docker,
+    Npm,
+}
+
+impl fmt::Display for PackageRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let registry = match self {
+            PackageRegistry::Crates => "crates",
+            PackageRegistry::Docker => "docker",
+            PackageRegistry::Npm => "npm",
+        };
+        write!(f, "{registry}")
+    }
+}
+
+/// Package download stats collected from its registry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackageDownloads {
+    pub generated_at: DateTime<Utc>,
+
+    /// Recent downloads (last month in npm, last 90 days in crates.io; not
+    /// available in Docker Hub).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recent: Option<u64>,
+
+    /// All-time downloads (pulls in Docker Hub; not available in npm).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+impl PackageDownloads {
+    /// Create a new PackageDownloads instance from information obtained from
+    /// the registry of the package provided.
+    async fn new(pr: DynPR, registry: PackageRegistry, name: &str) -> Result<Self> {
+        let (recent, total) = match registry {
+            PackageRegistry::Crates => {
+                let krate = pr.get_crate(name).await?.krate;
+                (krate.recent_downloads, Some(krate.downloads))
+            }
+            PackageRegistry::Docker => {
+                let repository = pr.get_docker_repository(name).await?;
+                (None, Some(repository.pull_count))
+            }
+            PackageRegistry::Npm => {
+                let downloads = pr.get_npm_downloads(name).await?;
+                (Some(downloads.downloads), None)
+            }
+        };
+
+        Ok(PackageDownloads {
+            generated_at: Utc::now(),
+            recent,
+            total,
+        })
+    }
+}
+
+/// crates.io API base url.
+const CRATES_API_URL: &str = "https://crates.io/api/v1";
+
+/// Docker Hub API base url.
+const DOCKER_HUB_API_URL: &str = "https://hub.docker.com/v2";
+
+/// npm downloads API base url.
+const NPM_DOWNLOADS_API_URL: &str = "https://api.npmjs.org/downloads";
+
+/// Type alias to represent a PR trait object.
+type DynPR = Arc<dyn PR + Send + Sync>;
+
+/// Trait that defines some operations a PR (packages registries)
+/// implementation must support.
+#[async_trait]
+#[cfg_attr(test, automock)]
+// The mock has a field per method, all of them starting with get
+#[allow(unknown_lints, clippy::struct_field_names)]
+trait PR {
+    /// Get crate information from crates.io.
+    async fn get_crate(&self, name: &str) -> Result<CratesIoCrateResponse>;
+
+    /// Get repository information from Docker Hub.
+    async fn get_docker_repository(&self, image: &str) -> Result<DockerHubRepository>;
+
+    /// Get package downloads during the last month from npm.
+    async fn get_npm_downloads(&self, name: &str) -> Result<NpmDownloads>;
+}
+
+/// PR implementation backed by the registries APIs.
+struct PRApi {
+    http: HttpClient,
+}
+
+impl PRApi {
+    /// Create a new PRApi instance (the shared HTTP client sets the user
+    /// agent required by crates.io).
+    fn new(http: &HttpClient) -> Self {
+        Self { http: http.clone() }
+    }
+
+    /// Get the JSON document available at the url provided, waiting and
+    /// retrying the request when hitting the registry rate limits.
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let mut retries = 0;
+        loop {
+            let response = self.http.send(self.http.client().get(url)).await?;
+            match response.status() {
+                StatusCode::OK => return response.json().await,
+                StatusCode::TOO_MANY_REQUESTS if retries < PACKAGES_MAX_RETRIES => {
+                    let wait = response
+                        .headers()
+                        .get(header::RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.parse().ok())
+                        .map_or(
+                            PACKAGES_RETRY_BASE_DELAY * 2_u32.pow(retries),
+                            Duration::from_secs,
+                        );
+                    info!("waiting {}s for registry rate limit reset", wait.as_secs());
+                    tokio::time::sleep(wait).await;
+                    retries += 1;
+                }
+                status => return Err(format_err!("unexpected status code: {status:?}")),
+            }
+        }
+    }
+}
+
+// Clippy 1.76 reports the code generated by instrument for async trait
+// methods (the lint is unknown to previous versions)
+#[allow(unknown_lints, clippy::blocks_in_conditions)]
+#[async_trait]
+impl PR for PRApi {
+    /// [PR::get_crate]
+    #[instrument(fields(?name), skip_all, err)]
+    async fn get_crate(&self, name: &str) -> Result<CratesIoCrateResponse> {
+        self.get_json(&format!("{CRATES_API_URL}/crates/{name}")).await
+    }
+
+    /// [PR::get_docker_repository]
+    #[instrument(fields(?image), skip_all, err)]
+    async fn get_docker_repository(&self, image: &str) -> Result<DockerHubRepository> {
+        // Official images live in the library namespace
+        let image = image.split(':').next().unwrap_or_default();
+        let repository = if image.contains('/') {
+            image.to_string()
+        } else {
+            format!("library/{image}")
+        };
+        self.get_json(&format!("{DOCKER_HUB_API_URL}/repositories/{repository}/")).await
+    }
+
+    /// [PR::get_npm_downloads]
+    #[instrument(fields(?name), skip_all, err)]
+    async fn get_npm_downloads(&self, name: &str) -> Result<NpmDownloads> {
+        self.get_json(&format!("{NPM_DOWNLOADS_API_URL}/point/last-month/{name}")).await
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct CratesIoCrateResponse {
+    #[serde(rename = "crate")]
+    krate: CratesIoCrate,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct CratesIoCrate {
+    downloads: u64,
+    recent_downloads: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct DockerHubRepository {
+    pull_count: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct NpmDownloads {
+    downloads: u64,
+}
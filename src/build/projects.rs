@@ -41,14 +41,10 @@ impl From<&LandscapeData> for Vec<Project> {
             .cloned()
             .filter_map(|item| {
                 // Prepare maturity
-                let Some(maturity) = item.maturity else {
-                    return None;
-                };
+                let maturity = item.maturity?;
 
                 // Prepare tag
-                let Some(tag) = item.tag else {
-                    return None;
-                };
+                let tag = item.tag?;
 
                 // Prepare sandbox date
                 let sandbox_at = if item.accepted_at == item.incubating_at {
@@ -68,12 +64,12 @@ impl From<&LandscapeData> for Vec<Project> {
                     graduated_at: fmt_date(&item.graduated_at),
                     homepage_url: item.homepage_url,
                     incubating_at: fmt_date(&item.incubating_at),
-                    maturity: maturity.to_string(),
+                    maturity: maturity.clone(),
                     name: item.name.to_lowercase(),
                     num_security_audits: num_security_audits.unwrap_or_default().to_string(),
                     last_security_audit: fmt_date(&last_security_audit),
                     sandbox_at: fmt_date(&sandbox_at),
-                    tag: tag.to_string(),
+                    tag: tag.clone(),
                 };
                 Some(project)
             })
@@ -0,0 +1,177 @@
+//! This module defines the functionality used to check that the maturity (and
+//! TAG) declared by the landscape projects match the ones in an authoritative
+//! projects status list (i.e. the one maintained by the foundation's TOC), so
+//! that stale maturity labels are detected automatically.
+//!
+//! The projects status list is a YAML (or JSON, or TOML) file, available
+//! locally or from a url, with the following format:
+//!
+//! ```yaml
+//! projects:
+//!   - name: Kubernetes
+//!     maturity: graduated
+//!     # TAG that owns the project (optional, not checked when omitted).
+//!     tag: runtime
+//! ```
+//!
+//! Projects are matched by their normalized name.
+
+use super::{data::normalize_name, http::HttpClient, DataFormat, LandscapeData};
+use anyhow::{format_err, Context, Result};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, fs};
+use tracing::instrument;
+
+/// Authoritative list of projects status.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ProjectsStatus {
+    pub projects: Vec<ProjectStatus>,
+}
+
+impl ProjectsStatus {
+    /// Create a new projects status instance from the source provided (a
+    /// local file or a url, fetched using the HTTP client provided).
+    #[instrument(skip(http), err)]
+    pub(crate) async fn new(src: &str, http: &HttpClient) -> Result<Self> {
+        let raw_data = if src.starts_with("http://") || src.starts_with("https://") {
+            let resp = http.send(http.client().get(src)).await?;
+            if resp.status() != StatusCode::OK {
+                return Err(format_err!(
+                    "unexpected status code getting projects status list: {}",
+                    resp.status()
+                ));
+            }
+            resp.text().await?
+        } else {
+            fs::read_to_string(src).context("error reading projects status list")?
+        };
+        let projects_status: ProjectsStatus = DataFormat::detect(None, src)
+            .parse(&raw_data)
+            .context("the projects status list provided is not valid")?;
+        projects_status.validate()?;
+
+        Ok(projects_status)
+    }
+
+    /// Validate projects status list.
+    fn validate(&self) -> Result<()> {
+        for (i, project) in self.projects.iter().enumerate() {
+            if project.name.is_empty() {
+                return Err(format_err!("project [{i}] name cannot be empty"));
+            }
+            if project.maturity.is_empty() {
+                return Err(format_err!("project [{}] maturity cannot be empty", project.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the landscape items whose declared maturity (or TAG) disagrees
+    /// with the projects status list. Items declaring a maturity that are not
+    /// in the list are reported as well.
+    pub(crate) fn find_mismatches(&self, landscape_data: &LandscapeData) -> Vec<StatusMismatch> {
+        let projects: HashMap<String, &ProjectStatus> =
+            self.projects.iter().map(|p| (normalize_name(&p.name), p)).collect();
+
+        let mut mismatches = vec![];
+        for item in &landscape_data.items {
+            let new_mismatch = |field, declared: Option<&String>, expected: Option<&String>| StatusMismatch {
+                item_id: item.id.clone(),
+                item_name: item.name.clone(),
+                field,
+                declared: declared.cloned(),
+                expected: expected.cloned(),
+            };
+
+            // Items not in the list are only checked when they declare a
+            // maturity (they are considered projects)
+            let Some(project) = projects.get(&normalize_name(&item.name)) else {
+                if item.maturity.is_some() {
+                    mismatches.push(new_mismatch(
+                        MismatchField::Maturity,
+                        item.maturity.as_ref(),
+                        None,
+                    ));
+                }
+                continue;
+            };
+
+            // Maturity
+            let declared = item.maturity.as_ref();
+            if declared.map(|m| m.to_lowercase()) != Some(project.maturity.to_lowercase()) {
+                mismatches.push(new_mismatch(
+                    MismatchField::Maturity,
+                    declared,
+                    Some(&project.maturity),
+                ));
+            }
+
+            // TAG (only when provided in the list)
+            if let Some(tag) = &project.tag {
+                let declared = item.tag.as_ref();
+                if declared.map(|t| t.to_lowercase()) != Some(tag.to_lowercase()) {
+                    mismatches.push(new_mismatch(MismatchField::Tag, declared, Some(tag)));
+                }
+            }
+        }
+
+        mismatches
+    }
+}
+
+/// Project status information.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ProjectStatus {
+    pub name: String,
+    pub maturity: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+/// Item whose declared maturity (or TAG) disagrees with the projects status
+/// list.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StatusMismatch {
+    pub item_id: String,
+    pub item_name: String,
+    pub field: MismatchField,
+    pub declared: Option<String>,
+    pub expected: Option<String>,
+}
+
+impl fmt::Display for StatusMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let declared = self.declared.as_deref().unwrap_or("none");
+        match &self.expected {
+            Some(expected) => write!(
+                f,
+                "item {} declares {} {declared}, but it is {expected} in the projects status list",
+                self.item_id, self.field
+            ),
+            None => write!(
+                f,
+                "item {} declares {} {declared}, but it is not in the projects status list",
+                self.item_id, self.field
+            ),
+        }
+    }
+}
+
+/// Field of the item that disagrees with the projects status list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MismatchField {
+    Maturity,
+    Tag,
+}
+
+impl fmt::Display for MismatchField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MismatchField::Maturity => write!(f, "maturity"),
+            MismatchField::Tag => write!(f, "tag"),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! This module defines the functionality to generate a QR code for each of the
+//! landscape items, linking to their permalink (i.e. to be used in printed
+//! landscape posters).
+
+use anyhow::Result;
+use clap::ValueEnum;
+use qrcode::{render::svg, EcLevel, QrCode};
+
+/// Error correction level of the items QR codes. Higher levels allow reading
+/// damaged (or partially covered) codes, at the cost of a denser code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum QrCodeEcLevel {
+    /// Up to 7% of the code can be recovered.
+    Low,
+
+    /// Up to 15% of the code can be recovered.
+    #[default]
+    Medium,
+
+    /// Up to 25% of the code can be recovered.
+    Quartile,
+
+    /// Up to 30% of the code can be recovered.
+    High,
+}
+
+impl From<QrCodeEcLevel> for EcLevel {
+    fn from(ec_level: QrCodeEcLevel) -> Self {
+        match ec_level {
+            QrCodeEcLevel::Low => EcLevel::L,
+            QrCodeEcLevel::Medium => EcLevel::M,
+            QrCodeEcLevel::Quartile => EcLevel::Q,
+            QrCodeEcLevel::High => EcLevel::H,
+        }
+    }
+}
+
+/// Render a QR code (in SVG format) encoding the url provided, using the error
+/// correction level and the module size (in pixels) given.
+pub(crate) fn render_qr_code(url: &str, ec_level: QrCodeEcLevel, module_size: u32) -> Result<String> {
+    let code = QrCode::with_error_correction_level(url.as_bytes(), ec_level.into())?;
+    let svg = code
+        .render()
+        .module_dimensions(module_size, module_size)
+        .dark_color(svg::Color("#000000"))
+        .light_color(svg::Color("#ffffff"))
+        .build();
+
+    Ok(svg)
+}
@@ -0,0 +1,232 @@
+//! This module defines the functionality used to fetch the landscape data from
+//! remote sources (a git repository or a url). A copy of the data fetched is
+//! cached locally, so that it can still be used when the remote source is not
+//! available (i.e. when building offline).
+//!
+//! Git repositories are provided using the following format, where both the
+//! ref (branch, tag or commit) and the path of the data file in the repository
+//! are optional (HEAD and landscape.yml are used by default):
+//!
+//! ```text
+//! <repository url>[#[<ref>][:<data file path>]]
+//! ```
+//!
+//! The integrity of the data fetched from a url can be verified by including
+//! its expected SHA-256 digest in the url fragment (`#sha256=<digest>`).
+
+use super::cache::CACHE_PATH;
+use anyhow::{format_err, Context, Result};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use tokio::process::Command;
+use tracing::{debug, instrument, warn};
+use url::Url;
+
+/// Default path of the data file in the landscape data repositories.
+const DEFAULT_DATA_FILE: &str = "landscape.yml";
+
+/// Name of the file where the ref of the repository checked out is recorded.
+const REF_FILE: &str = "landscape2-ref";
+
+/// Path where the remote sources are cached inside the cache directory.
+const SOURCES_PATH: &str = "sources";
+
+/// Git repository containing the landscape data file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DataRepo {
+    pub url: String,
+    pub git_ref: Option<String>,
+    pub data_file: PathBuf,
+}
+
+impl DataRepo {
+    /// Create a new data repository instance from the reference provided
+    /// (`<repository url>[#[<ref>][:<data file path>]]`).
+    pub(crate) fn parse(s: &str) -> Result<Self> {
+        let (url, fragment) = s.split_once('#').unwrap_or((s, ""));
+        let (git_ref, data_file) = fragment.split_once(':').unwrap_or((fragment, ""));
+        if url.is_empty() {
+            return Err(format_err!("data repository url not provided"));
+        }
+
+        // The data file must be inside the repository
+        let data_file = PathBuf::from(if data_file.is_empty() {
+            DEFAULT_DATA_FILE
+        } else {
+            data_file
+        });
+        if data_file.is_absolute() || data_file.components().any(|c| c == Component::ParentDir) {
+            return Err(format_err!(
+                "invalid data file path in repository: {}",
+                data_file.display()
+            ));
+        }
+
+        Ok(Self {
+            url: url.to_string(),
+            git_ref: (!git_ref.is_empty()).then(|| git_ref.to_string()),
+            data_file,
+        })
+    }
+
+    /// Return the local directory where the repository is checked out
+    /// (inside the cache directory provided, or the user's one).
+    pub(crate) fn checkout_dir(&self, cache_dir: Option<&PathBuf>) -> Result<PathBuf> {
+        let digest = hex::encode(Sha256::digest(self.url.as_bytes()));
+        Ok(sources_dir(cache_dir)?.join(format!("repo-{}", &digest[..16])))
+    }
+
+    /// Fetch the ref of the repository requested, checking it out in the
+    /// cache, and return the path of the data file. When the repository
+    /// cannot be fetched, the copy available in the cache (for the same ref)
+    /// is used instead.
+    #[instrument(skip_all, err)]
+    pub(crate) async fn fetch(&self, cache_dir: Option<&PathBuf>) -> Result<PathBuf> {
+        let checkout_dir = self.checkout_dir(cache_dir)?;
+        let git_ref = self.git_ref.as_deref().unwrap_or("HEAD");
+
+        if let Err(err) = self.update_checkout(&checkout_dir, git_ref).await {
+            let cached_ref = fs::read_to_string(checkout_dir.join(".git").join(REF_FILE)).ok();
+            if cached_ref.as_deref() != Some(git_ref) {
+                return Err(err.context("error fetching landscape data repository"));
+            }
+            warn!(
+                ?err,
+                "error fetching landscape data repository, using cached copy"
+            );
+        }
+
+        let data_file = checkout_dir.join(&self.data_file);
+        if !data_file.exists() {
+            return Err(format_err!(
+                "data file not found in repository: {}",
+                self.data_file.display()
+            ));
+        }
+        Ok(data_file)
+    }
+
+    /// Fetch the ref provided (shallow) and check it out in the directory
+    /// given, verifying the commit checked out when a commit was requested.
+    async fn update_checkout(&self, checkout_dir: &Path, git_ref: &str) -> Result<()> {
+        if !checkout_dir.join(".git").exists() {
+            fs::create_dir_all(checkout_dir)?;
+            git(checkout_dir, &["init", "--quiet"]).await?;
+        }
+        debug!(url = self.url, git_ref, "fetching landscape data repository");
+        git(
+            checkout_dir,
+            &["fetch", "--quiet", "--depth", "1", &self.url, git_ref],
+        )
+        .await?;
+        git(
+            checkout_dir,
+            &["checkout", "--quiet", "--force", "--detach", "FETCH_HEAD"],
+        )
+        .await?;
+
+        // Git verifies the objects fetched, so we only need to make sure we got
+        // the commit requested (when a full commit hash was provided)
+        let commit = git(checkout_dir, &["rev-parse", "HEAD"]).await?;
+        if is_commit_hash(git_ref) && !commit.eq_ignore_ascii_case(git_ref) {
+            return Err(format_err!(
+                "commit checked out ({commit}) does not match the one requested ({git_ref})"
+            ));
+        }
+        debug!(commit, "landscape data repository checked out");
+
+        fs::write(checkout_dir.join(".git").join(REF_FILE), git_ref)?;
+        Ok(())
+    }
+}
+
+/// Fetch the landscape data from the url provided, caching a copy of it in the
+/// cache directory given (or the user's one). When the url cannot be reached,
+/// the cached copy is used instead. If the url fragment includes a SHA-256
+/// digest, the data is verified against it.
+#[instrument(skip_all, err)]
+pub(crate) async fn fetch_data_url(url: &str, cache_dir: Option<&PathBuf>) -> Result<String> {
+    let parsed_url = Url::parse(url).context("invalid data url")?;
+    let expected_digest = parsed_url
+        .fragment()
+        .and_then(|fragment| fragment.strip_prefix("sha256="))
+        .map(str::to_lowercase);
+    let digest = hex::encode(Sha256::digest(url.as_bytes()));
+    let cached_file = sources_dir(cache_dir)?.join(format!("url-{}.yml", &digest[..16]));
+
+    // Fetch data from url, falling back to the cached copy if it's unreachable
+    let raw_data = match reqwest::get(url).await {
+        Ok(resp) => {
+            if resp.status() != StatusCode::OK {
+                return Err(format_err!(
+                    "unexpected status code getting landscape data file: {}",
+                    resp.status()
+                ));
+            }
+            resp.text().await?
+        }
+        Err(err) if cached_file.exists() => {
+            warn!(?err, "error fetching landscape data file, using cached copy");
+            fs::read_to_string(&cached_file)?
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    // Verify data integrity (when the expected digest is known)
+    if let Some(expected_digest) = expected_digest {
+        let digest = hex::encode(Sha256::digest(raw_data.as_bytes()));
+        if digest != expected_digest {
+            return Err(format_err!(
+                "landscape data file digest ({digest}) does not match the one expected ({expected_digest})"
+            ));
+        }
+    }
+
+    fs::write(&cached_file, &raw_data)?;
+    Ok(raw_data)
+}
+
+/// Run the git command provided in the directory given, returning its output.
+async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .await
+        .context("error running git (is it installed?)")?;
+    if !output.status.success() {
+        return Err(format_err!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Check if the git ref provided is a full commit hash.
+fn is_commit_hash(git_ref: &str) -> bool {
+    git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Return the directory where the remote sources are cached inside the cache
+/// directory provided (the user's cache directory is used when not provided),
+/// creating it when needed.
+fn sources_dir(cache_dir: Option<&PathBuf>) -> Result<PathBuf> {
+    let Some(cache_dir) = cache_dir.cloned().or_else(dirs::cache_dir) else {
+        return Err(format_err!(
+            "error setting up sources cache: no cache directory provided and user's cache directory could not be found"
+        ));
+    };
+    let sources_dir = cache_dir.join(CACHE_PATH).join(SOURCES_PATH);
+    if !sources_dir.exists() {
+        fs::create_dir_all(&sources_dir)?;
+    }
+    Ok(sources_dir)
+}
@@ -0,0 +1,96 @@
+//! This module defines the functionality to generate a precomputed search
+//! index from the landscape data, so that the web application can load it
+//! directly instead of building it in the browser.
+//!
+//! The index is written to `data/search-index.json` and uses the following
+//! format (the version is increased on any incompatible change):
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "items": ["<item id>", ...],
+//!   "terms": {
+//!     "<term>": [<item position>, ...],
+//!     ...
+//!   }
+//! }
+//! ```
+//!
+//! Terms are lowercase alphanumeric tokens taken from the item name, its
+//! keywords (summary tags), category, subcategory and aliases (Crunchbase
+//! organization name and repositories names). They are sorted alphabetically,
+//! so prefix matches can be found using a binary search. Each term points to
+//! the (sorted) positions of the items it matches in the items list.
+
+use super::LandscapeData;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Current version of the search index format.
+const SEARCH_INDEX_VERSION: u32 = 1;
+
+/// Precomputed search index.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct SearchIndex {
+    pub version: u32,
+    pub items: Vec<String>,
+    pub terms: BTreeMap<String, Vec<usize>>,
+}
+
+impl SearchIndex {
+    /// Create a new search index from the landscape data provided.
+    pub(crate) fn new(landscape_data: &LandscapeData) -> Self {
+        let mut items = vec![];
+        let mut terms: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+
+        for (pos, item) in landscape_data.items.iter().enumerate() {
+            items.push(item.id.clone());
+
+            // Collect the values the item can be found by
+            let mut values = vec![
+                item.name.as_str(),
+                item.category.as_str(),
+                item.subcategory.as_str(),
+            ];
+            for ac in item.additional_categories.iter().flatten() {
+                values.extend([ac.category.as_str(), ac.subcategory.as_str()]);
+            }
+            if let Some(tags) = item.summary.as_ref().and_then(|summary| summary.tags.as_ref()) {
+                values.extend(tags.iter().map(String::as_str));
+            }
+            if let Some(name) = item.crunchbase_data.as_ref().and_then(|org| org.name.as_ref()) {
+                values.push(name);
+            }
+            for repo in item.repositories.iter().flatten() {
+                if let Some(repo_name) = repo.url.trim_end_matches('/').rsplit('/').next() {
+                    values.push(repo_name);
+                }
+            }
+
+            // Register item in each of its terms
+            for term in values.into_iter().flat_map(tokenize) {
+                terms.entry(term).or_default().insert(pos);
+            }
+        }
+
+        Self {
+            version: SEARCH_INDEX_VERSION,
+            items,
+            terms: terms
+                .into_iter()
+                .map(|(term, positions)| (term, positions.into_iter().collect()))
+                .collect(),
+        }
+    }
+}
+
+/// Split the value provided into lowercase alphanumeric terms (terms shorter
+/// than two characters are ignored).
+fn tokenize(value: &str) -> Vec<String> {
+    value
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| term.chars().count() >= 2)
+        .map(ToString::to_string)
+        .collect()
+}
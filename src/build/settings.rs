@@ -7,31 +7,52 @@
 //! NOTE: the landscape settings file uses a new format that is not backwards
 //! compatible with the legacy settings file used by existing landscapes.
 
-use super::data::{validate_url, Category, CategoryName, SubCategoryName};
+use super::{
+    analytics::validate_snippet,
+    data::{validate_url, Category, CategoryName, DataFormat, SubCategoryName},
+    packages::PackageRegistry,
+};
 use crate::SettingsSource;
 use anyhow::{format_err, Context, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
 use reqwest::StatusCode;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
 use tracing::{debug, instrument};
 
 /// Landscape settings.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct LandscapeSettings {
     pub foundation: String,
     pub images: Images,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<BTreeMap<String, String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analytics: Option<Analytics>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub categories: Option<Vec<Category>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub colors: Option<Colors>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crunchbase: Option<Crunchbase>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub featured_items: Option<Vec<FeaturedItemRule>>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub github: Option<Github>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grid_items_size: Option<GridItemsSize>,
 
@@ -41,49 +62,64 @@ pub(crate) struct LandscapeSettings {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub members_category: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_stats: Option<PackageStats>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub qr_code: Option<QrCode>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub screenshot_width: Option<u32>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub social_cards: Option<SocialCards>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub social_networks: Option<SocialNetworks>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<HashMap<TagName, Vec<TagRule>>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
 }
 
 impl LandscapeSettings {
-    /// Create a new landscape settings instance from the source provided.
+    /// Create a new landscape settings instance from the source provided,
+    /// merging the settings overlay file given (if any) on top of it. The
+    /// settings are parsed using the format provided (it's detected from the
+    /// file extension otherwise).
     #[instrument(skip_all, err)]
-    pub(crate) async fn new(src: &SettingsSource) -> Result<Self> {
+    pub(crate) async fn new(
+        src: &SettingsSource,
+        overlay: Option<&Path>,
+        format: Option<DataFormat>,
+    ) -> Result<Self> {
         // Try from file
         if let Some(file) = &src.settings_file {
             debug!(?file, "getting landscape settings from file");
-            return LandscapeSettings::new_from_file(file);
-        };
+            let format = DataFormat::detect(format, &file.to_string_lossy());
+            return LandscapeSettings::new_from_file(file, overlay, format);
+        }
 
         // Try from url
         if let Some(url) = &src.settings_url {
             debug!(?url, "getting landscape settings from url");
-            return LandscapeSettings::new_from_url(url).await;
-        };
+            let format = DataFormat::detect(format, url);
+            return LandscapeSettings::new_from_url(url, overlay, format).await;
+        }
 
         Err(format_err!("settings file or url not provided"))
     }
 
     /// Create a new landscape settings instance from the file provided.
-    fn new_from_file(file: &Path) -> Result<Self> {
+    fn new_from_file(file: &Path, overlay: Option<&Path>, format: DataFormat) -> Result<Self> {
         let raw_data = fs::read_to_string(file)?;
-        let settings: LandscapeSettings = serde_yaml::from_str(&raw_data)?;
-        settings.validate().context("the landscape settings file provided is not valid")?;
-
-        Ok(settings)
+        LandscapeSettings::new_from_raw_data(&raw_data, overlay, format)
     }
 
     /// Create a new landscape settings instance from the url provided.
-    async fn new_from_url(url: &str) -> Result<Self> {
+    async fn new_from_url(url: &str, overlay: Option<&Path>, format: DataFormat) -> Result<Self> {
         let resp = reqwest::get(url).await?;
         if resp.status() != StatusCode::OK {
             return Err(format_err!(
@@ -92,12 +128,62 @@ impl LandscapeSettings {
             ));
         }
         let raw_data = resp.text().await?;
-        let settings: LandscapeSettings = serde_yaml::from_str(&raw_data)?;
+        LandscapeSettings::new_from_raw_data(&raw_data, overlay, format)
+    }
+
+    /// Create a new landscape settings instance from the raw data provided,
+    /// merging the settings overlay file given (if any) on top of it. The
+    /// overlay is merged before the settings are parsed: maps are merged
+    /// deeply, and any other value defined in the overlay (lists included)
+    /// replaces the one in the settings (null values remove them). The format
+    /// of the overlay is detected from its extension.
+    fn new_from_raw_data(raw_data: &str, overlay: Option<&Path>, format: DataFormat) -> Result<Self> {
+        let settings: LandscapeSettings = match overlay {
+            None => format.parse(raw_data)?,
+            Some(overlay) => {
+                let mut value: serde_yaml::Value = format.parse(raw_data)?;
+                let overlay_raw_data = fs::read_to_string(overlay)
+                    .context(format!("error reading settings overlay {}", overlay.display()))?;
+                let overlay_format = DataFormat::detect(None, &overlay.to_string_lossy());
+                let overlay_value =
+                    overlay_format.parse(&overlay_raw_data).context("error parsing settings overlay")?;
+                merge_yaml_values(&mut value, overlay_value);
+                let settings: LandscapeSettings =
+                    serde_yaml::from_value(value).context("error applying settings overlay")?;
+                debug!(
+                    ?overlay,
+                    "effective landscape settings:\n{}",
+                    serde_yaml::to_string(&settings)?
+                );
+                settings
+            }
+        };
         settings.validate().context("the landscape settings file provided is not valid")?;
 
         Ok(settings)
     }
 
+    /// Remove the references to categories not included in the list provided
+    /// (i.e. when the landscape categories have been filtered). Groups left
+    /// without categories are removed as well.
+    pub(crate) fn retain_categories(&mut self, categories: &[CategoryName]) {
+        if let Some(settings_categories) = self.categories.as_mut() {
+            settings_categories.retain(|category| categories.contains(&category.name));
+        }
+        if let Some(groups) = self.groups.as_mut() {
+            for group in &mut *groups {
+                group.categories.retain(|category| categories.contains(category));
+            }
+            groups.retain(|group| !group.categories.is_empty());
+        }
+        if self.groups.as_ref().is_some_and(Vec::is_empty) {
+            self.groups = None;
+        }
+        if self.members_category.as_ref().is_some_and(|name| !categories.contains(name)) {
+            self.members_category = None;
+        }
+    }
+
     /// Validate landscape settings.
     fn validate(&self) -> Result<()> {
         // Check foundation is not empty
@@ -119,13 +205,74 @@ impl LandscapeSettings {
             }
         }
 
+        self.validate_aliases()?;
+        self.validate_analytics()?;
         self.validate_categories()?;
         self.validate_colors()?;
         self.validate_featured_items()?;
         self.validate_groups()?;
         self.validate_images()?;
+        self.validate_social_cards()?;
         self.validate_social_networks()?;
         self.validate_tags()?;
+        validate_url("landscape", self.url.as_ref())?;
+
+        Ok(())
+    }
+
+    /// Check aliases are valid (they are checked against the categories in the
+    /// landscape data when building the landscape).
+    fn validate_aliases(&self) -> Result<()> {
+        if let Some(aliases) = &self.aliases {
+            for (from, to) in aliases {
+                for slug in [from, to] {
+                    if !SLUG.is_match(slug) {
+                        return Err(format_err!(
+                            "alias [{from}]: {slug} is not a valid category (or category/subcategory) slug"
+                        ));
+                    }
+                }
+                if from == to {
+                    return Err(format_err!("alias [{from}] cannot point to itself"));
+                }
+                if from.contains('/') != to.contains('/') {
+                    return Err(format_err!(
+                        "alias [{from}]: categories and subcategories cannot be aliased to each other"
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check analytics configuration is valid.
+    fn validate_analytics(&self) -> Result<()> {
+        if let Some(analytics) = &self.analytics {
+            // Google Analytics
+            if let Some(ga) = &analytics.google {
+                if !GA_MEASUREMENT_ID.is_match(&ga.measurement_id) {
+                    return Err(format_err!(
+                        "google analytics measurement id is not valid (expected format: G-XXXXXXXXXX)"
+                    ));
+                }
+            }
+
+            // Plausible
+            if let Some(plausible) = &analytics.plausible {
+                if !PLAUSIBLE_DOMAIN.is_match(&plausible.domain) {
+                    return Err(format_err!(
+                        "plausible domain is not valid (expected format: landscape.example.org)"
+                    ));
+                }
+                validate_url("plausible_script", plausible.script_url.as_ref())?;
+            }
+
+            // Custom snippet
+            if let Some(snippet) = &analytics.snippet {
+                validate_snippet(snippet)?;
+            }
+        }
 
         Ok(())
     }
@@ -260,7 +407,27 @@ impl LandscapeSettings {
             ("open_graph", &self.images.open_graph),
         ];
         for (name, url) in urls {
-            validate_url(name, url)?;
+            validate_url(name, url.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Check social cards configuration is valid.
+    fn validate_social_cards(&self) -> Result<()> {
+        if let Some(social_cards) = &self.social_cards {
+            if social_cards.width == Some(0) || social_cards.height == Some(0) {
+                return Err(format_err!(
+                    "social cards width and height must be greater than 0"
+                ));
+            }
+            if let Some(background_color) = &social_cards.background_color {
+                if !RGBA.is_match(background_color) {
+                    return Err(format_err!(
+                        r#"social cards background color is not valid (expected format: "rgba(0, 107, 204, 1)")"#
+                    ));
+                }
+            }
         }
 
         Ok(())
@@ -282,7 +449,7 @@ impl LandscapeSettings {
                 ("youtube", &social_networks.youtube),
             ];
             for (name, url) in urls {
-                validate_url(name, url)?;
+                validate_url(name, url.as_ref())?;
             }
         }
 
@@ -314,14 +481,39 @@ impl LandscapeSettings {
 }
 
 lazy_static! {
+    /// Google Analytics measurement id regular expression.
+    static ref GA_MEASUREMENT_ID: Regex = Regex::new(r"^G-[A-Z0-9]+$").expect("exprs in GA_MEASUREMENT_ID to be valid");
+
+    /// Plausible domain (or comma separated list of domains) regular expression.
+    static ref PLAUSIBLE_DOMAIN: Regex =
+        Regex::new(r"^[a-z0-9\-]+(\.[a-z0-9\-]+)+(,[a-z0-9\-]+(\.[a-z0-9\-]+)+)*$")
+            .expect("exprs in PLAUSIBLE_DOMAIN to be valid");
+
     /// RGBA regular expression.
     pub(crate) static ref RGBA: Regex =
         Regex::new(r"rgba?\(((25[0-5]|2[0-4]\d|1\d{1,2}|\d\d?)\s*,\s*?){2}(25[0-5]|2[0-4]\d|1\d{1,2}|\d\d?)\s*,?\s*([01]\.?\d*?)\)")
             .expect("exprs in RGBA to be valid");
+
+    /// Category (or category/subcategory) slug regular expression.
+    static ref SLUG: Regex = Regex::new(r"^[a-z0-9\-]+(/[a-z0-9\-]+)?$").expect("exprs in SLUG to be valid");
+}
+
+/// Analytics configuration. The tracking snippet of the providers configured
+/// (and the custom snippet, if any) is injected into the index document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Analytics {
+    #[serde(rename = "google_analytics", skip_serializing_if = "Option::is_none")]
+    pub google: Option<GoogleAnalytics>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plausible: Option<Plausible>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
 }
 
 /// Colors used across the landscape UI.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Colors {
     pub color1: String,
     pub color2: String,
@@ -331,17 +523,24 @@ pub(crate) struct Colors {
     pub color6: String,
 }
 
+/// Crunchbase data collection configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Crunchbase {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub funding_rounds: Option<bool>,
+}
+
 /// Featured item rule information. A featured item is specially highlighted in
 /// the web application, usually making it larger with some special styling.
 /// These rules are used to decide which items should be featured.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct FeaturedItemRule {
     pub field: String,
     pub options: Vec<FeaturedItemRuleOption>,
 }
 
 /// Featured item rule option.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct FeaturedItemRuleOption {
     pub value: String,
 
@@ -352,8 +551,15 @@ pub(crate) struct FeaturedItemRuleOption {
     pub order: Option<usize>,
 }
 
+/// GitHub data collection configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Github {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contributors_activity: Option<bool>,
+}
+
 /// Grid items size.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub(crate) enum GridItemsSize {
     Small,
@@ -361,16 +567,22 @@ pub(crate) enum GridItemsSize {
     Large,
 }
 
+/// Google Analytics configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct GoogleAnalytics {
+    pub measurement_id: String,
+}
+
 /// Landscape group. A group provides a mechanism to organize sets of
 /// categories in the web application.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Group {
     pub name: String,
     pub categories: Vec<CategoryName>,
 }
 
 /// Images urls.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Images {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub favicon: Option<String>,
@@ -385,14 +597,45 @@ pub(crate) struct Images {
     pub open_graph: Option<String>,
 }
 
+/// Packages download stats configuration. Stats are collected from all the
+/// supported registries when no registries are provided.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct PackageStats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registries: Option<Vec<PackageRegistry>>,
+}
+
+/// Plausible configuration. The script url can be provided when Plausible is
+/// self-hosted (or to use one of its script extensions).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Plausible {
+    pub domain: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_url: Option<String>,
+}
+
 /// QR code configuration.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct QrCode {
     pub url: String,
 }
 
+/// Social cards configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SocialCards {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+}
+
 /// Social networks urls.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct SocialNetworks {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub facebook: Option<String>,
@@ -429,10 +672,81 @@ pub(crate) struct SocialNetworks {
 pub(crate) type TagName = String;
 
 /// TAG rule used to set the TAG that owns a project automatically.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct TagRule {
     pub category: CategoryName,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subcategories: Option<Vec<SubCategoryName>>,
 }
+
+/// Merge the overlay YAML value provided into the base one. Maps are merged
+/// deeply, and any other value in the overlay replaces the base one.
+fn merge_yaml_values(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_yaml_values(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const SETTINGS_YAML: &str = r"
+foundation: CNCF
+url: https://landscape.example.com
+images:
+  favicon: https://landscape.example.com/favicon.png
+";
+
+    const SETTINGS_JSON: &str = r#"{
+  "foundation": "CNCF",
+  "url": "https://landscape.example.com",
+  "images": {
+    "favicon": "https://landscape.example.com/favicon.png"
+  }
+}"#;
+
+    const SETTINGS_TOML: &str = r#"
+foundation = "CNCF"
+url = "https://landscape.example.com"
+
+[images]
+favicon = "https://landscape.example.com/favicon.png"
+"#;
+
+    #[test]
+    fn parse_settings_in_all_formats() {
+        let yaml = LandscapeSettings::new_from_raw_data(SETTINGS_YAML, None, DataFormat::Yaml).unwrap();
+        let json = LandscapeSettings::new_from_raw_data(SETTINGS_JSON, None, DataFormat::Json).unwrap();
+        let toml = LandscapeSettings::new_from_raw_data(SETTINGS_TOML, None, DataFormat::Toml).unwrap();
+
+        assert_eq!(yaml.foundation, "CNCF");
+        assert_eq!(json, yaml);
+        assert_eq!(toml, yaml);
+    }
+
+    #[test]
+    fn merge_overlay_in_another_format() {
+        let overlay = env::temp_dir().join(format!("landscape2-settings-tests-{}.json", std::process::id()));
+        fs::write(&overlay, r#"{"url": "https://staging.example.com"}"#).unwrap();
+
+        let settings =
+            LandscapeSettings::new_from_raw_data(SETTINGS_TOML, Some(&overlay), DataFormat::Toml).unwrap();
+        fs::remove_file(&overlay).unwrap();
+
+        assert_eq!(settings.foundation, "CNCF");
+        assert_eq!(settings.url.as_deref(), Some("https://staging.example.com"));
+    }
+}
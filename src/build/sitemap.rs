@@ -0,0 +1,97 @@
+//! This module defines the functionality to generate the sitemap files from
+//! the information available in the landscape.
+
+use super::LandscapeData;
+use std::fmt::Write as _;
+use url::form_urlencoded;
+
+/// Maximum number of urls allowed in a sitemap file.
+const MAX_URLS_PER_SITEMAP: usize = 50_000;
+
+/// Name of the sitemap file (or sitemap index file, when the urls need to be
+/// split into multiple sitemap files).
+const SITEMAP_FILE: &str = "sitemap.xml";
+
+/// Generate the sitemap files for the landscape published at the base url
+/// provided, returning their names and content. The sitemap includes the
+/// landscape views, as well as each category, subcategory and item permalink.
+/// When there are more urls than the ones allowed in a single sitemap, they
+/// are split into multiple files referenced from a sitemap index.
+pub(crate) fn generate_sitemaps(
+    base_url: &str,
+    landscape_data: &LandscapeData,
+    guide_available: bool,
+) -> Vec<(String, String)> {
+    let base_url = base_url.trim_end_matches('/');
+
+    // Collect urls
+    let mut urls = vec![format!("{base_url}/")];
+    if guide_available {
+        urls.push(format!("{base_url}/guide"));
+    }
+    urls.push(format!("{base_url}/stats"));
+    for category in &landscape_data.categories {
+        urls.push(permalink(base_url, &[("category", &category.name)]));
+        for subcategory in &category.subcategories {
+            urls.push(permalink(
+                base_url,
+                &[("category", &category.name), ("subcategory", subcategory)],
+            ));
+        }
+    }
+    for item in &landscape_data.items {
+        urls.push(permalink(base_url, &[("item", &item.id)]));
+    }
+
+    // Prepare sitemap files
+    if urls.len() <= MAX_URLS_PER_SITEMAP {
+        return vec![(SITEMAP_FILE.to_string(), render_urlset(&urls))];
+    }
+    let mut files = vec![];
+    let mut sitemaps_urls = vec![];
+    for (i, chunk) in urls.chunks(MAX_URLS_PER_SITEMAP).enumerate() {
+        let file_name = format!("sitemap-{}.xml", i + 1);
+        sitemaps_urls.push(format!("{base_url}/{file_name}"));
+        files.push((file_name, render_urlset(chunk)));
+    }
+    files.push((SITEMAP_FILE.to_string(), render_sitemap_index(&sitemaps_urls)));
+
+    files
+}
+
+/// Prepare a permalink using the query parameters provided.
+pub(crate) fn permalink(base_url: &str, params: &[(&str, &str)]) -> String {
+    let query = form_urlencoded::Serializer::new(String::new()).extend_pairs(params).finish();
+    format!("{base_url}/?{query}")
+}
+
+/// Render a sitemap index referencing the sitemaps urls provided.
+fn render_sitemap_index(sitemaps_urls: &[String]) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str(r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+    for url in sitemaps_urls {
+        let _ = write!(xml, "<sitemap><loc>{}</loc></sitemap>", xml_escape(url));
+    }
+    xml.push_str("</sitemapindex>");
+    xml
+}
+
+/// Render a sitemap including the urls provided.
+fn render_urlset(urls: &[String]) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+    for url in urls {
+        let _ = write!(xml, "<url><loc>{}</loc></url>", xml_escape(url));
+    }
+    xml.push_str("</urlset>");
+    xml
+}
+
+/// Escape the XML special characters in the text provided.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -6,6 +6,8 @@ use super::{
     settings::{LandscapeSettings, TagName},
     LandscapeData,
 };
+use anyhow::{format_err, Result};
+use chrono::{DateTime, Days, Months, NaiveDate, Utc};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -42,6 +44,78 @@ impl Stats {
             repositories: RepositoriesStats::new(landscape_data),
         }
     }
+
+    /// Create a new Stats instance scoped to the items added, and the events
+    /// (promotions, audits, etc) that happened, since the date provided.
+    pub(crate) fn new_since(
+        landscape_data: &LandscapeData,
+        settings: &LandscapeSettings,
+        since: NaiveDate,
+    ) -> Self {
+        let mut scoped_data = landscape_data.clone();
+        scoped_data.items.retain_mut(|item| {
+            // Discard the events that happened before the date provided
+            let is_recent = |date: &NaiveDate| *date >= since;
+            for date in [
+                &mut item.accepted_at,
+                &mut item.archived_at,
+                &mut item.graduated_at,
+                &mut item.incubating_at,
+                &mut item.joined_at,
+                &mut item.latest_annual_review_at,
+            ] {
+                *date = date.filter(is_recent);
+            }
+            if let Some(audits) = &mut item.audits {
+                audits.retain(|audit| is_recent(&audit.date));
+            }
+
+            // Keep only the items with some activity since the date provided
+            item.accepted_at.is_some()
+                || item.archived_at.is_some()
+                || item.audits.as_ref().is_some_and(|audits| !audits.is_empty())
+                || item.graduated_at.is_some()
+                || item.incubating_at.is_some()
+                || item.joined_at.is_some()
+                || item.latest_annual_review_at.is_some()
+        });
+
+        Self::new(&scoped_data, settings)
+    }
+}
+
+/// Parse the date provided, used to scope the stats. Dates can be provided in
+/// ISO-8601 format (i.e. 2023-10-01 or 2023-10-01T00:00:00Z) or relative to
+/// the current date using the d (days), w (weeks), m (months) or y (years)
+/// units (i.e. 90d).
+pub(crate) fn parse_since_date(value: &str) -> Result<NaiveDate> {
+    let value = value.trim();
+
+    // ISO-8601 date or date time
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date);
+    }
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.date_naive());
+    }
+
+    // Relative date
+    let invalid_date = || format_err!("invalid date: {value} (expected format: 2023-10-01 or 90d)");
+    let Some(unit_start) = value.char_indices().last().map(|(i, _)| i) else {
+        return Err(invalid_date());
+    };
+    let (amount, unit) = value.split_at(unit_start);
+    let amount: u32 = amount.parse().map_err(|_| invalid_date())?;
+    let today = Utc::now().date_naive();
+    let date = match unit {
+        "d" => today.checked_sub_days(Days::new(u64::from(amount))),
+        "w" => today.checked_sub_days(Days::new(u64::from(amount) * 7)),
+        "m" => today.checked_sub_months(Months::new(amount)),
+        "y" => today.checked_sub_months(Months::new(amount.saturating_mul(12))),
+        _ => return Err(invalid_date()),
+    };
+
+    date.ok_or_else(invalid_date)
 }
 
 /// Some stats about the foundation's members.
@@ -162,18 +236,22 @@ impl ProjectsStats {
                     increment(&mut stats.accepted_at, &year_month, 1);
                 }
 
-                // Number of projects per category and subcategory
-                if let Some(category_stats) = stats.category.get_mut(&item.category) {
-                    category_stats.projects += 1;
-                    increment(&mut category_stats.subcategories, &item.subcategory, 1);
-                } else {
-                    stats.category.insert(
-                        item.category.clone(),
-                        CategoryProjectsStats {
-                            projects: 1,
-                            subcategories: HashMap::from([(item.subcategory.clone(), 1)]),
-                        },
-                    );
+                // Number of projects per category and subcategory (projects
+                // are also counted in their additional categories, but only
+                // once per category and subcategory)
+                let additional_categories = item.additional_categories.iter().flatten();
+                let mut categories = vec![(&item.category, &item.subcategory)];
+                categories.extend(additional_categories.map(|ac| (&ac.category, &ac.subcategory)));
+                categories.sort();
+                categories.dedup();
+                let mut categories_seen = vec![];
+                for (category, subcategory) in categories {
+                    let category_stats = stats.category.entry(category.clone()).or_default();
+                    if !categories_seen.contains(&category) {
+                        category_stats.projects += 1;
+                        categories_seen.push(category);
+                    }
+                    increment(&mut category_stats.subcategories, subcategory, 1);
                 }
 
                 // Number of projects per maturity
@@ -264,7 +342,9 @@ impl RepositoriesStats {
                 for repo in repos {
                     if let Some(gh_data) = &repo.github_data {
                         // Contributors
-                        stats.contributors += gh_data.contributors.count as u64;
+                        if let Some(contributors) = &gh_data.contributors {
+                            stats.contributors += contributors.count as u64;
+                        }
 
                         // Languages
                         if let Some(languages) = &gh_data.languages {
@@ -287,7 +367,7 @@ impl RepositoriesStats {
 
                         // Participation stats
                         if stats.participation_stats.is_empty() {
-                            stats.participation_stats = gh_data.participation_stats.clone();
+                            stats.participation_stats.clone_from(&gh_data.participation_stats);
                         } else {
                             stats.participation_stats = stats
                                 .participation_stats
@@ -365,3 +445,70 @@ fn calculate_running_total(map: &HashMap<YearMonth, u64>) -> HashMap<YearMonth,
 
     rt
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::{data::AdditionalCategory, Item};
+
+    #[test]
+    fn parse_since_date_formats() {
+        let today = Utc::now().date_naive();
+        assert_eq!(
+            parse_since_date("2023-10-01").unwrap(),
+            NaiveDate::from_ymd_opt(2023, 10, 1).unwrap()
+        );
+        assert_eq!(
+            parse_since_date("2023-10-01T12:00:00Z").unwrap(),
+            NaiveDate::from_ymd_opt(2023, 10, 1).unwrap()
+        );
+        assert_eq!(parse_since_date("90d").unwrap(), today - Days::new(90));
+        assert_eq!(parse_since_date("2w").unwrap(), today - Days::new(14));
+    }
+
+    #[test]
+    fn parse_since_date_invalid() {
+        for value in ["", "d", "90", "90x", "-1d", "90日", "日", "2023-13-01"] {
+            assert!(parse_since_date(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn projects_stats_count_additional_categories_once() {
+        let additional_category = |category: &str, subcategory: &str| AdditionalCategory {
+            category: category.to_string(),
+            subcategory: subcategory.to_string(),
+        };
+        let landscape_data = LandscapeData {
+            items: vec![Item {
+                category: "App Definition".to_string(),
+                subcategory: "Database".to_string(),
+                maturity: Some("graduated".to_string()),
+                additional_categories: Some(vec![
+                    additional_category("App Definition", "Streaming"),
+                    additional_category("App Definition", "Database"),
+                    additional_category("Security", "Runtime"),
+                ]),
+                ..Item::default()
+            }],
+            ..LandscapeData::default()
+        };
+
+        let stats = ProjectsStats::new(&landscape_data).unwrap();
+        assert_eq!(stats.projects, 1);
+        assert_eq!(
+            stats.category["App Definition"],
+            CategoryProjectsStats {
+                projects: 1,
+                subcategories: HashMap::from([("Database".to_string(), 1), ("Streaming".to_string(), 1)]),
+            }
+        );
+        assert_eq!(
+            stats.category["Security"],
+            CategoryProjectsStats {
+                projects: 1,
+                subcategories: HashMap::from([("Runtime".to_string(), 1)]),
+            }
+        );
+    }
+}
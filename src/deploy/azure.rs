@@ -0,0 +1,385 @@
+//! This module defines the functionality of the deploy CLI subcommand for the
+//! Azure Blob Storage provider.
+
+use super::{check_landscape_dir, get_object_key, FileStatus, Key, Summary, INDEX_DOCUMENT, LOGOS_PREFIX};
+use crate::AzureArgs;
+use anyhow::{format_err, Context, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use mime_guess::mime;
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use std::{collections::HashMap, env, fmt::Write as _, fs, path::Path, time::Instant};
+use tracing::{debug, info, instrument};
+use url::Url;
+use walkdir::WalkDir;
+
+/// Container used by Azure Static Websites to serve the website files.
+const CONTAINER: &str = "$web";
+
+/// Version of the Azure Blob Storage REST API used.
+const API_VERSION: &str = "2021-08-06";
+
+/// Environment variable containing the name of the storage account.
+const STORAGE_ACCOUNT_ENV_VAR: &str = "AZURE_STORAGE_ACCOUNT";
+
+/// Environment variable containing the shared access signature token used to
+/// authenticate the requests to the Azure Blob Storage API.
+const STORAGE_SAS_TOKEN_ENV_VAR: &str = "AZURE_STORAGE_SAS_TOKEN";
+
+/// Deploy landscape website to Azure Blob Storage.
+#[instrument(skip_all)]
+pub(crate) async fn deploy(args: &AzureArgs) -> Result<()> {
+    info!("deploying landscape website..");
+    let start = Instant::now();
+
+    // Check arguments, required environment variables and landscape directory
+    check_args(args)?;
+    check_env_vars()?;
+    check_landscape_dir(&args.landscape_dir)?;
+    info!(concurrency = args.concurrency, "deploy settings");
+    if args.dry_run {
+        info!("dry run mode enabled: no changes will be made to the container");
+    }
+
+    // Setup Azure Blob Storage client
+    let client = Client::new()?;
+
+    // Get blobs already deployed
+    let mut summary = Summary::default();
+    let phase_start = Instant::now();
+    let deployed_blobs = get_deployed_blobs(&client).await?;
+    summary.track_phase("listing", phase_start);
+
+    // Upload landscape website files (except index document)
+    let phase_start = Instant::now();
+    for (key, status, bytes) in upload_files(&client, args, &deployed_blobs).await? {
+        summary.track_file(key, status, bytes);
+    }
+    summary.track_phase("upload", phase_start);
+
+    // Upload index document if all the other files were uploaded successfully
+    let phase_start = Instant::now();
+    let (index_document_status, bytes) = upload_index_document(&client, args, &deployed_blobs).await?;
+    summary.track_file(INDEX_DOCUMENT.to_string(), index_document_status, bytes);
+    summary.track_phase("index", phase_start);
+
+    summary.duration = start.elapsed().as_secs_f64();
+    summary.log(args.dry_run);
+
+    Ok(())
+}
+
+/// Check that the arguments provided are valid.
+#[instrument(skip_all, err)]
+fn check_args(args: &AzureArgs) -> Result<()> {
+    if args.concurrency == 0 {
+        return Err(format_err!("concurrency must be greater than 0"));
+    }
+
+    Ok(())
+}
+
+/// Check that the required environment variables have been provided.
+#[instrument(skip_all, err)]
+fn check_env_vars() -> Result<()> {
+    let required_env_vars = [STORAGE_ACCOUNT_ENV_VAR, STORAGE_SAS_TOKEN_ENV_VAR];
+
+    for var in required_env_vars {
+        if !env::var(var).is_ok_and(|value| !value.is_empty()) {
+            return Err(format_err!("required environment variable {var} not provided"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Get blobs already deployed, returning their key and some information about
+/// them (like their last modification date).
+#[instrument(skip_all, err)]
+async fn get_deployed_blobs(client: &Client) -> Result<HashMap<Key, DeployedBlob>> {
+    let mut deployed_blobs = HashMap::new();
+
+    // List all blobs in the container, collecting their name and last
+    // modification timestamp
+    let mut marker: Option<String> = None;
+    loop {
+        let mut url = client.container_url()?;
+        url.query_pairs_mut().append_pair("restype", "container").append_pair("comp", "list");
+        if let Some(marker) = &marker {
+            url.query_pairs_mut().append_pair("marker", marker);
+        }
+        let response = client.http.get(url).send().await?;
+        if response.status() != StatusCode::OK {
+            return Err(format_err!(
+                "error listing blobs: unexpected status code: {:?}",
+                response.status()
+            ));
+        }
+        let output: ListBlobsOutput = quick_xml::de::from_str(&response.text().await?)?;
+        for blob in output.blobs.blob {
+            let modified_at = DateTime::parse_from_rfc2822(&blob.properties.last_modified)?.into();
+            deployed_blobs.insert(blob.name, DeployedBlob { modified_at });
+        }
+        match output.next_marker {
+            Some(next_marker) if !next_marker.is_empty() => marker = Some(next_marker),
+            _ => break,
+        }
+    }
+
+    Ok(deployed_blobs)
+}
+
+/// Get the status of the file provided compared to its deployed copy (if any).
+/// Given that logos filenames are based on their content, we don't need to
+/// upload again existing ones.
+fn get_file_status(
+    key: &Key,
+    file_name: &Path,
+    deployed_blobs: &HashMap<Key, DeployedBlob>,
+) -> Result<FileStatus> {
+    let Some(deployed_blob) = deployed_blobs.get(key) else {
+        return Ok(FileStatus::New);
+    };
+
+    // Skip already deployed logos
+    if key.starts_with(LOGOS_PREFIX) {
+        return Ok(FileStatus::Logo);
+    }
+
+    // Skip blobs when the remote copy is up to date
+    let local_ts: DateTime<Utc> = fs::metadata(file_name)?.modified()?.into();
+    if deployed_blob.modified_at >= local_ts {
+        return Ok(FileStatus::UpToDate);
+    }
+
+    Ok(FileStatus::Modified)
+}
+
+/// Upload the file provided to the container using the key and content type
+/// provided, returning the number of bytes transferred.
+async fn put_blob(client: &Client, key: &str, file_name: &Path, content_type: &str) -> Result<u64> {
+    let body = fs::read(file_name)?;
+    let bytes = body.len() as u64;
+    let response = client
+        .http
+        .put(client.blob_url(key)?)
+        .header("x-ms-blob-type", "BlockBlob")
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body)
+        .send()
+        .await?;
+    if response.status() != StatusCode::CREATED {
+        return Err(format_err!("unexpected status code: {:?}", response.status()));
+    }
+
+    Ok(bytes)
+}
+
+/// Upload landscape website files to the container, returning the status of
+/// each of the files processed and the number of bytes transferred.
+#[instrument(skip_all, err)]
+async fn upload_files(
+    client: &Client,
+    args: &AzureArgs,
+    deployed_blobs: &HashMap<Key, DeployedBlob>,
+) -> Result<Vec<(Key, FileStatus, u64)>> {
+    // Upload files in the landscape directory to the container
+    let landscape_dir = &args.landscape_dir;
+    let results: Vec<Result<Option<(Key, FileStatus, u64)>>> = stream::iter(WalkDir::new(landscape_dir))
+        .map(|entry| async {
+            // Check if the entry is a regular file
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                return Ok(None);
+            }
+
+            // Prepare blob key
+            let file_name = entry.path();
+            let key = get_object_key(landscape_dir, file_name);
+
+            // We'll upload the index document at the end when all the other
+            // files have been uploaded successfully
+            if key == INDEX_DOCUMENT {
+                return Ok(None);
+            }
+
+            // Skip files that start with a dot
+            if key.starts_with('.') {
+                return Ok(None);
+            }
+
+            // Skip blobs that don't need to be uploaded again
+            let status = get_file_status(&key, file_name, deployed_blobs)?;
+            if !status.needs_upload() {
+                if args.dry_run {
+                    info!(?key, reason = %status, "file would be skipped");
+                }
+                return Ok(Some((key, status, 0)));
+            }
+
+            // Prepare blob's content type
+            let content_type = mime_guess::from_path(&key)
+                .first()
+                .ok_or(format_err!("cannot detect content type of key: {})", &key))?;
+
+            // In dry run mode we only log the files that would be uploaded
+            if args.dry_run {
+                info!(?key, reason = %status, "file would be uploaded");
+                let bytes = fs::metadata(file_name)?.len();
+                return Ok(Some((key, status, bytes)));
+            }
+
+            // Upload file
+            let bytes = put_blob(client, &key, file_name, content_type.essence_str())
+                .await
+                .context(format_err!("error uploading file {}", key))?;
+
+            debug!(?key, "file uploaded");
+            Ok(Some((key, status, bytes)))
+        })
+        .buffer_unordered(args.concurrency)
+        .collect()
+        .await;
+
+    // Process results
+    let mut errors_found = false;
+    let mut errors = String::new();
+    let mut files_status = vec![];
+    for result in results {
+        match result {
+            Ok(Some(file_status)) => files_status.push(file_status),
+            Ok(None) => {}
+            Err(err) => {
+                errors_found = true;
+                let _ = writeln!(errors, "- {err:?}");
+            }
+        }
+    }
+    if errors_found {
+        return Err(format_err!("{errors}"));
+    }
+
+    Ok(files_status)
+}
+
+/// Upload landscape website index document to the container, returning its
+/// status and the number of bytes transferred.
+#[instrument(skip_all, err)]
+async fn upload_index_document(
+    client: &Client,
+    args: &AzureArgs,
+    deployed_blobs: &HashMap<Key, DeployedBlob>,
+) -> Result<(FileStatus, u64)> {
+    // Prepare blob's key and content type
+    let file_name = args.landscape_dir.join(INDEX_DOCUMENT);
+    let key = INDEX_DOCUMENT.to_string();
+    let content_type = mime::TEXT_HTML.essence_str();
+
+    // Check if the remote copy is up to date
+    let status = get_file_status(&key, &file_name, deployed_blobs)?;
+    if !status.needs_upload() {
+        if args.dry_run {
+            info!(?key, reason = %status, "file would be skipped");
+        }
+        return Ok((status, 0));
+    }
+
+    // In dry run mode we only log the index document would be uploaded
+    if args.dry_run {
+        info!(?key, reason = %status, "file would be uploaded");
+        return Ok((status, fs::metadata(&file_name)?.len()));
+    }
+
+    // Upload index document
+    let bytes = put_blob(client, &key, &file_name, content_type)
+        .await
+        .context(format_err!("error uploading file {}", key))?;
+
+    debug!(?key, "file uploaded");
+    Ok((status, bytes))
+}
+
+/// Azure Blob Storage client, authenticated using a shared access signature.
+struct Client {
+    http: reqwest::Client,
+    account: String,
+    sas_token: String,
+}
+
+impl Client {
+    /// Create a new Client instance.
+    fn new() -> Result<Self> {
+        // Setup HTTP client ready to make requests to the Azure Blob Storage
+        // API (credentials are read from the environment)
+        let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        let mut headers = header::HeaderMap::new();
+        headers.insert("x-ms-version", header::HeaderValue::from_static(API_VERSION));
+        let http_client =
+            reqwest::Client::builder().user_agent(user_agent).default_headers(headers).build()?;
+        let account = env::var(STORAGE_ACCOUNT_ENV_VAR)?;
+        let sas_token = env::var(STORAGE_SAS_TOKEN_ENV_VAR)?.trim_start_matches('?').to_string();
+
+        Ok(Self {
+            http: http_client,
+            account,
+            sas_token,
+        })
+    }
+
+    /// Get the url of the blob provided.
+    fn blob_url(&self, key: &str) -> Result<Url> {
+        let mut url = self.container_url()?;
+        url.path_segments_mut()
+            .map_err(|()| format_err!("invalid container url"))?
+            .extend(key.split('/'));
+        Ok(url)
+    }
+
+    /// Get the url of the website container, including the shared access
+    /// signature token.
+    fn container_url(&self) -> Result<Url> {
+        let mut url = Url::parse(&format!("https://{}.blob.core.windows.net", self.account))?;
+        url.path_segments_mut().map_err(|()| format_err!("invalid account url"))?.push(CONTAINER);
+        url.set_query(Some(&self.sas_token));
+        Ok(url)
+    }
+}
+
+/// Some information about a deployed blob.
+#[derive(Debug, Clone)]
+struct DeployedBlob {
+    modified_at: DateTime<Utc>,
+}
+
+/// Blobs list returned by the Azure Blob Storage API.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListBlobsOutput {
+    #[serde(default)]
+    blobs: Blobs,
+    next_marker: Option<String>,
+}
+
+/// Blobs included in a blobs list.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Blobs {
+    #[serde(default)]
+    blob: Vec<Blob>,
+}
+
+/// Blob information included in a blobs list.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Blob {
+    name: String,
+    properties: BlobProperties,
+}
+
+/// Blob properties included in a blobs list.
+#[derive(Debug, Clone, Deserialize)]
+struct BlobProperties {
+    #[serde(rename = "Last-Modified")]
+    last_modified: String,
+}
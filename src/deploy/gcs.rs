@@ -0,0 +1,424 @@
+//! This module defines the functionality of the deploy CLI subcommand for the
+//! Google Cloud Storage provider.
+
+use super::{
+    check_landscape_dir, get_local_keys, get_object_key, FileStatus, Key, Summary, INDEX_DOCUMENT,
+    LOGOS_PREFIX,
+};
+use crate::GcsArgs;
+use anyhow::{format_err, Context, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use mime_guess::mime;
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use std::{collections::HashMap, env, fmt::Write as _, fs, path::Path, time::Instant};
+use tracing::{debug, info, instrument};
+use url::Url;
+use walkdir::WalkDir;
+
+/// Google Cloud Storage JSON API base url.
+const GCS_API_URL: &str = "https://storage.googleapis.com/storage/v1";
+
+/// Google Cloud Storage JSON API upload base url.
+const GCS_UPLOAD_API_URL: &str = "https://storage.googleapis.com/upload/storage/v1";
+
+/// Environment variable containing the OAuth 2.0 access token used to
+/// authenticate the requests to the Google Cloud Storage API.
+const GCS_ACCESS_TOKEN_ENV_VAR: &str = "GOOGLE_OAUTH_ACCESS_TOKEN";
+
+/// Deploy landscape website to Google Cloud Storage.
+#[instrument(skip_all)]
+pub(crate) async fn deploy(args: &GcsArgs) -> Result<()> {
+    info!("deploying landscape website..");
+    let start = Instant::now();
+
+    // Check arguments, required environment variables and landscape directory
+    check_args(args)?;
+    check_env_vars()?;
+    check_landscape_dir(&args.landscape_dir)?;
+    info!(concurrency = args.concurrency, "deploy settings");
+    if args.dry_run {
+        info!("dry run mode enabled: no changes will be made to the bucket");
+    }
+
+    // Setup HTTP client ready to make requests to the GCS API
+    let access_token = env::var(GCS_ACCESS_TOKEN_ENV_VAR).expect("var to be set");
+    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    let mut headers = header::HeaderMap::new();
+    headers.insert(
+        header::AUTHORIZATION,
+        header::HeaderValue::from_str(&format!("Bearer {access_token}"))?,
+    );
+    let http_client = reqwest::Client::builder().user_agent(user_agent).default_headers(headers).build()?;
+
+    // Get objects already deployed
+    let mut summary = Summary::default();
+    let phase_start = Instant::now();
+    let deployed_objects = get_deployed_objects(&http_client, args).await?;
+    summary.track_phase("listing", phase_start);
+
+    // Upload landscape website files (except index document)
+    let phase_start = Instant::now();
+    for (key, status, bytes) in upload_files(&http_client, args, &deployed_objects).await? {
+        summary.track_file(key, status, bytes);
+    }
+    summary.track_phase("upload", phase_start);
+
+    // Upload index document if all the other files were uploaded successfully
+    let phase_start = Instant::now();
+    let (index_document_status, bytes) = upload_index_document(&http_client, args, &deployed_objects).await?;
+    summary.track_file(INDEX_DOCUMENT.to_string(), index_document_status, bytes);
+    summary.track_phase("index", phase_start);
+
+    // Delete objects that don't exist in the landscape directory anymore
+    if args.prune {
+        let phase_start = Instant::now();
+        summary.deleted = delete_stale_objects(&http_client, args, &deployed_objects).await?;
+        summary.track_phase("prune", phase_start);
+    }
+
+    summary.duration = start.elapsed().as_secs_f64();
+    summary.log(args.dry_run);
+
+    Ok(())
+}
+
+/// Check that the arguments provided are valid.
+#[instrument(skip_all, err)]
+fn check_args(args: &GcsArgs) -> Result<()> {
+    if args.concurrency == 0 {
+        return Err(format_err!("concurrency must be greater than 0"));
+    }
+
+    Ok(())
+}
+
+/// Check that the required environment variables have been provided.
+#[instrument(skip_all, err)]
+fn check_env_vars() -> Result<()> {
+    if !env::var(GCS_ACCESS_TOKEN_ENV_VAR).is_ok_and(|value| !value.is_empty()) {
+        return Err(format_err!(
+            "required environment variable {GCS_ACCESS_TOKEN_ENV_VAR} not provided"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Delete objects deployed previously that are not present in the landscape
+/// directory anymore, returning the keys of the objects deleted. The index
+/// document is never deleted.
+#[instrument(skip_all, err)]
+async fn delete_stale_objects(
+    http_client: &reqwest::Client,
+    args: &GcsArgs,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+) -> Result<Vec<Key>> {
+    // Collect the keys of the objects that should be deleted
+    let local_keys = get_local_keys(&args.landscape_dir)?;
+    let mut stale_keys: Vec<Key> = deployed_objects
+        .keys()
+        .filter(|key| *key != INDEX_DOCUMENT && !local_keys.contains(*key))
+        .cloned()
+        .collect();
+    stale_keys.sort();
+    if stale_keys.is_empty() {
+        info!("no stale objects found");
+        return Ok(stale_keys);
+    }
+
+    // In dry run mode we only log the objects that would be deleted
+    if args.dry_run {
+        for key in &stale_keys {
+            info!(?key, reason = "stale", "object would be deleted");
+        }
+        return Ok(stale_keys);
+    }
+
+    // Delete stale objects (the JSON API doesn't support deleting multiple
+    // objects in a single request, so we delete them concurrently)
+    let results: Vec<Result<()>> = stream::iter(&stale_keys)
+        .map(|key| async move {
+            let url = get_objects_url(GCS_API_URL, &args.bucket, Some(key))?;
+            let response = http_client.delete(url).send().await?;
+            if response.status() != StatusCode::NO_CONTENT && response.status() != StatusCode::NOT_FOUND {
+                return Err(format_err!(
+                    "error deleting object {key}: unexpected status code: {:?}",
+                    response.status()
+                ));
+            }
+            debug!(?key, "object deleted");
+            Ok(())
+        })
+        .buffer_unordered(args.concurrency)
+        .collect()
+        .await;
+    let mut errors = String::new();
+    for result in results {
+        if let Err(err) = result {
+            let _ = writeln!(errors, "- {err:?}");
+        }
+    }
+    if !errors.is_empty() {
+        return Err(format_err!("{errors}"));
+    }
+
+    info!("{} stale objects deleted", stale_keys.len());
+    Ok(stale_keys)
+}
+
+/// Get objects already deployed, returning their key and some information
+/// about them (like their last update date).
+#[instrument(skip_all, err)]
+async fn get_deployed_objects(
+    http_client: &reqwest::Client,
+    args: &GcsArgs,
+) -> Result<HashMap<Key, DeployedObject>> {
+    let mut deployed_objects = HashMap::new();
+
+    // List all objects in the bucket provided, collecting their name and last
+    // update timestamp
+    let url = get_objects_url(GCS_API_URL, &args.bucket, None)?;
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut query = vec![("fields", "items(name,updated),nextPageToken")];
+        if let Some(token) = &page_token {
+            query.push(("pageToken", token));
+        }
+        let response = http_client.get(url.clone()).query(&query).send().await?;
+        if response.status() != StatusCode::OK {
+            return Err(format_err!(
+                "error listing objects: unexpected status code: {:?}",
+                response.status()
+            ));
+        }
+        let output: ListObjectsOutput = response.json().await?;
+        for object in output.items.unwrap_or_default() {
+            deployed_objects.insert(
+                object.name,
+                DeployedObject {
+                    updated_at: object.updated,
+                },
+            );
+        }
+        if output.next_page_token.is_none() {
+            break;
+        }
+        page_token = output.next_page_token;
+    }
+
+    Ok(deployed_objects)
+}
+
+/// Get the url of the objects resource in the bucket provided (or the url of
+/// the object provided, when a key is given). Path segments are escaped as
+/// required by the GCS API (i.e. slashes in the object name).
+fn get_objects_url(base_url: &str, bucket: &str, key: Option<&str>) -> Result<Url> {
+    let mut url = Url::parse(base_url)?;
+    {
+        let mut segments = url.path_segments_mut().map_err(|()| format_err!("invalid base url"))?;
+        segments.extend(["b", bucket, "o"]);
+        if let Some(key) = key {
+            segments.push(key);
+        }
+    }
+    Ok(url)
+}
+
+/// Get the status of the file provided compared to its deployed copy (if any).
+/// Given that logos filenames are based on their content, we don't need to
+/// upload again existing ones.
+fn get_file_status(
+    key: &Key,
+    file_name: &Path,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+) -> Result<FileStatus> {
+    let Some(deployed_object) = deployed_objects.get(key) else {
+        return Ok(FileStatus::New);
+    };
+
+    // Skip already deployed logos
+    if key.starts_with(LOGOS_PREFIX) {
+        return Ok(FileStatus::Logo);
+    }
+
+    // Skip objects when the remote copy is up to date
+    let local_ts: DateTime<Utc> = fs::metadata(file_name)?.modified()?.into();
+    if deployed_object.updated_at >= local_ts {
+        return Ok(FileStatus::UpToDate);
+    }
+
+    Ok(FileStatus::Modified)
+}
+
+/// Upload the file provided to the GCS bucket using the key and content type
+/// provided, returning the number of bytes transferred.
+async fn put_object(
+    http_client: &reqwest::Client,
+    args: &GcsArgs,
+    key: &str,
+    file_name: &Path,
+    content_type: &str,
+) -> Result<u64> {
+    let body = fs::read(file_name)?;
+    let bytes = body.len() as u64;
+    let url = get_objects_url(GCS_UPLOAD_API_URL, &args.bucket, None)?;
+    let response = http_client
+        .post(url)
+        .query(&[("uploadType", "media"), ("name", key)])
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body)
+        .send()
+        .await?;
+    if response.status() != StatusCode::OK {
+        return Err(format_err!("unexpected status code: {:?}", response.status()));
+    }
+
+    Ok(bytes)
+}
+
+/// Upload landscape website files to GCS bucket, returning the status of each
+/// of the files processed and the number of bytes transferred.
+#[instrument(skip_all, err)]
+async fn upload_files(
+    http_client: &reqwest::Client,
+    args: &GcsArgs,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+) -> Result<Vec<(Key, FileStatus, u64)>> {
+    // Upload files in the landscape directory to the bucket provided
+    let landscape_dir = &args.landscape_dir;
+    let results: Vec<Result<Option<(Key, FileStatus, u64)>>> = stream::iter(WalkDir::new(landscape_dir))
+        .map(|entry| async {
+            // Check if the entry is a regular file
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                return Ok(None);
+            }
+
+            // Prepare object key
+            let file_name = entry.path();
+            let key = get_object_key(landscape_dir, file_name);
+
+            // We'll upload the index document at the end when all the other
+            // files have been uploaded successfully
+            if key == INDEX_DOCUMENT {
+                return Ok(None);
+            }
+
+            // Skip files that start with a dot
+            if key.starts_with('.') {
+                return Ok(None);
+            }
+
+            // Skip objects that don't need to be uploaded again
+            let status = get_file_status(&key, file_name, deployed_objects)?;
+            if !status.needs_upload() {
+                if args.dry_run {
+                    info!(?key, reason = %status, "file would be skipped");
+                }
+                return Ok(Some((key, status, 0)));
+            }
+
+            // Prepare object's content type
+            let content_type = mime_guess::from_path(&key)
+                .first()
+                .ok_or(format_err!("cannot detect content type of key: {})", &key))?;
+
+            // In dry run mode we only log the files that would be uploaded
+            if args.dry_run {
+                info!(?key, reason = %status, "file would be uploaded");
+                let bytes = fs::metadata(file_name)?.len();
+                return Ok(Some((key, status, bytes)));
+            }
+
+            // Upload file
+            let bytes = put_object(http_client, args, &key, file_name, content_type.essence_str())
+                .await
+                .context(format_err!("error uploading file {}", key))?;
+
+            debug!(?key, "file uploaded");
+            Ok(Some((key, status, bytes)))
+        })
+        .buffer_unordered(args.concurrency)
+        .collect()
+        .await;
+
+    // Process results
+    let mut errors_found = false;
+    let mut errors = String::new();
+    let mut files_status = vec![];
+    for result in results {
+        match result {
+            Ok(Some(file_status)) => files_status.push(file_status),
+            Ok(None) => {}
+            Err(err) => {
+                errors_found = true;
+                let _ = writeln!(errors, "- {err:?}");
+            }
+        }
+    }
+    if errors_found {
+        return Err(format_err!("{errors}"));
+    }
+
+    Ok(files_status)
+}
+
+/// Upload landscape website index document to GCS bucket, returning its
+/// status and the number of bytes transferred.
+#[instrument(skip_all, err)]
+async fn upload_index_document(
+    http_client: &reqwest::Client,
+    args: &GcsArgs,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+) -> Result<(FileStatus, u64)> {
+    // Prepare object's key and content type
+    let file_name = args.landscape_dir.join(INDEX_DOCUMENT);
+    let key = INDEX_DOCUMENT.to_string();
+    let content_type = mime::TEXT_HTML.essence_str();
+
+    // Check if the remote copy is up to date
+    let status = get_file_status(&key, &file_name, deployed_objects)?;
+    if !status.needs_upload() {
+        if args.dry_run {
+            info!(?key, reason = %status, "file would be skipped");
+        }
+        return Ok((status, 0));
+    }
+
+    // In dry run mode we only log the index document would be uploaded
+    if args.dry_run {
+        info!(?key, reason = %status, "file would be uploaded");
+        return Ok((status, fs::metadata(&file_name)?.len()));
+    }
+
+    // Upload index document
+    let bytes = put_object(http_client, args, &key, &file_name, content_type)
+        .await
+        .context(format_err!("error uploading file {}", key))?;
+
+    debug!(?key, "file uploaded");
+    Ok((status, bytes))
+}
+
+/// Some information about a deployed object.
+#[derive(Debug, Clone)]
+struct DeployedObject {
+    updated_at: DateTime<Utc>,
+}
+
+/// Objects list returned by the GCS API.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListObjectsOutput {
+    items: Option<Vec<ObjectMetadata>>,
+    next_page_token: Option<String>,
+}
+
+/// Object metadata returned by the GCS API.
+#[derive(Debug, Clone, Deserialize)]
+struct ObjectMetadata {
+    name: String,
+    updated: DateTime<Utc>,
+}
@@ -0,0 +1,293 @@
+//! This module defines the functionality of the deploy CLI subcommand for the
+//! local filesystem provider (useful to publish the landscape website to a
+//! web server document root, like a NFS mount).
+
+use super::{
+    check_landscape_dir, get_local_keys, get_object_key, FileStatus, Key, Summary, INDEX_DOCUMENT,
+    LOGOS_PREFIX,
+};
+use crate::LocalArgs;
+use anyhow::{format_err, Context, Result};
+use futures::stream::{self, StreamExt};
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
+use tokio::task;
+use tracing::{debug, info, instrument};
+use walkdir::WalkDir;
+
+/// Deploy landscape website to a local directory.
+#[instrument(skip_all)]
+pub(crate) async fn deploy(args: &LocalArgs) -> Result<()> {
+    info!("deploying landscape website..");
+    let start = Instant::now();
+
+    // Check arguments and landscape directory
+    check_args(args)?;
+    check_landscape_dir(&args.landscape_dir)?;
+    info!(concurrency = args.concurrency, "deploy settings");
+    if args.dry_run {
+        info!("dry run mode enabled: no changes will be made to the destination directory");
+    }
+
+    // Get files already deployed
+    let mut summary = Summary::default();
+    let phase_start = Instant::now();
+    let deployed_files = get_deployed_files(&args.dest_dir)?;
+    summary.track_phase("listing", phase_start);
+
+    // Copy landscape website files (except index document)
+    let phase_start = Instant::now();
+    for (key, status, bytes) in copy_files(args, &deployed_files).await? {
+        summary.track_file(key, status, bytes);
+    }
+    summary.track_phase("upload", phase_start);
+
+    // Copy index document if all the other files were copied successfully
+    let phase_start = Instant::now();
+    let (index_document_status, bytes) = copy_index_document(args, &deployed_files).await?;
+    summary.track_file(INDEX_DOCUMENT.to_string(), index_document_status, bytes);
+    summary.track_phase("index", phase_start);
+
+    // Delete files that don't exist in the landscape directory anymore
+    if args.prune {
+        let phase_start = Instant::now();
+        summary.deleted = delete_stale_files(args, &deployed_files)?;
+        summary.track_phase("prune", phase_start);
+    }
+
+    summary.duration = start.elapsed().as_secs_f64();
+    summary.log(args.dry_run);
+
+    Ok(())
+}
+
+/// Check that the arguments provided are valid.
+#[instrument(skip_all, err)]
+fn check_args(args: &LocalArgs) -> Result<()> {
+    if args.concurrency == 0 {
+        return Err(format_err!("concurrency must be greater than 0"));
+    }
+    if args.dest_dir.exists() && !args.dest_dir.is_dir() {
+        return Err(format_err!(
+            "destination {} is not a directory",
+            args.dest_dir.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Copy the file provided to the destination directory, returning the number
+/// of bytes copied.
+async fn copy_file(file_name: &Path, dst_file: PathBuf) -> Result<u64> {
+    let file_name = file_name.to_owned();
+    task::spawn_blocking(move || -> Result<u64> {
+        if let Some(parent) = dst_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(fs::copy(file_name, dst_file)?)
+    })
+    .await?
+}
+
+/// Copy landscape website files to the destination directory, returning the
+/// status of each of the files processed and the number of bytes copied.
+#[instrument(skip_all, err)]
+async fn copy_files(
+    args: &LocalArgs,
+    deployed_files: &HashMap<Key, SystemTime>,
+) -> Result<Vec<(Key, FileStatus, u64)>> {
+    // Copy files in the landscape directory to the destination directory
+    let landscape_dir = &args.landscape_dir;
+    let results: Vec<Result<Option<(Key, FileStatus, u64)>>> = stream::iter(WalkDir::new(landscape_dir))
+        .map(|entry| async {
+            // Check if the entry is a regular file
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                return Ok(None);
+            }
+
+            // Prepare file key
+            let file_name = entry.path();
+            let key = get_object_key(landscape_dir, file_name);
+
+            // We'll copy the index document at the end when all the other
+            // files have been copied successfully
+            if key == INDEX_DOCUMENT {
+                return Ok(None);
+            }
+
+            // Skip files that start with a dot
+            if key.starts_with('.') {
+                return Ok(None);
+            }
+
+            // Skip files that don't need to be copied again
+            let status = get_file_status(&key, file_name, deployed_files)?;
+            if !status.needs_upload() {
+                if args.dry_run {
+                    info!(?key, reason = %status, "file would be skipped");
+                }
+                return Ok(Some((key, status, 0)));
+            }
+
+            // In dry run mode we only log the files that would be copied
+            if args.dry_run {
+                info!(?key, reason = %status, "file would be copied");
+                let bytes = fs::metadata(file_name)?.len();
+                return Ok(Some((key, status, bytes)));
+            }
+
+            // Copy file
+            let bytes = copy_file(file_name, args.dest_dir.join(&key))
+                .await
+                .context(format_err!("error copying file {}", key))?;
+
+            debug!(?key, "file copied");
+            Ok(Some((key, status, bytes)))
+        })
+        .buffer_unordered(args.concurrency)
+        .collect()
+        .await;
+
+    // Process results
+    let mut errors_found = false;
+    let mut errors = String::new();
+    let mut files_status = vec![];
+    for result in results {
+        match result {
+            Ok(Some(file_status)) => files_status.push(file_status),
+            Ok(None) => {}
+            Err(err) => {
+                errors_found = true;
+                let _ = writeln!(errors, "- {err:?}");
+            }
+        }
+    }
+    if errors_found {
+        return Err(format_err!("{errors}"));
+    }
+
+    Ok(files_status)
+}
+
+/// Copy landscape website index document to the destination directory,
+/// returning its status and the number of bytes copied.
+#[instrument(skip_all, err)]
+async fn copy_index_document(
+    args: &LocalArgs,
+    deployed_files: &HashMap<Key, SystemTime>,
+) -> Result<(FileStatus, u64)> {
+    let file_name = args.landscape_dir.join(INDEX_DOCUMENT);
+    let key = INDEX_DOCUMENT.to_string();
+
+    // Check if the deployed copy is up to date
+    let status = get_file_status(&key, &file_name, deployed_files)?;
+    if !status.needs_upload() {
+        if args.dry_run {
+            info!(?key, reason = %status, "file would be skipped");
+        }
+        return Ok((status, 0));
+    }
+
+    // In dry run mode we only log the index document would be copied
+    if args.dry_run {
+        info!(?key, reason = %status, "file would be copied");
+        return Ok((status, fs::metadata(&file_name)?.len()));
+    }
+
+    // Copy index document
+    let bytes = copy_file(&file_name, args.dest_dir.join(&key))
+        .await
+        .context(format_err!("error copying file {}", key))?;
+
+    debug!(?key, "file copied");
+    Ok((status, bytes))
+}
+
+/// Delete files deployed previously that are not present in the landscape
+/// directory anymore, returning the keys of the files deleted. The index
+/// document is never deleted.
+#[instrument(skip_all, err)]
+fn delete_stale_files(args: &LocalArgs, deployed_files: &HashMap<Key, SystemTime>) -> Result<Vec<Key>> {
+    // Collect the keys of the files that should be deleted
+    let local_keys = get_local_keys(&args.landscape_dir)?;
+    let mut stale_keys: Vec<Key> = deployed_files
+        .keys()
+        .filter(|key| *key != INDEX_DOCUMENT && !local_keys.contains(*key))
+        .cloned()
+        .collect();
+    stale_keys.sort();
+    if stale_keys.is_empty() {
+        info!("no stale files found");
+        return Ok(stale_keys);
+    }
+
+    // In dry run mode we only log the files that would be deleted
+    if args.dry_run {
+        for key in &stale_keys {
+            info!(?key, reason = "stale", "file would be deleted");
+        }
+        return Ok(stale_keys);
+    }
+
+    // Delete stale files
+    for key in &stale_keys {
+        fs::remove_file(args.dest_dir.join(key)).context(format!("error deleting file {key}"))?;
+        debug!(?key, "file deleted");
+    }
+
+    info!("{} stale files deleted", stale_keys.len());
+    Ok(stale_keys)
+}
+
+/// Get files already deployed to the destination directory, returning their
+/// key and last modification time.
+#[instrument(skip_all, err)]
+fn get_deployed_files(dest_dir: &Path) -> Result<HashMap<Key, SystemTime>> {
+    let mut deployed_files = HashMap::new();
+
+    if !dest_dir.exists() {
+        return Ok(deployed_files);
+    }
+    for entry in WalkDir::new(dest_dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let key = get_object_key(dest_dir, entry.path());
+            deployed_files.insert(key, entry.metadata()?.modified()?);
+        }
+    }
+
+    Ok(deployed_files)
+}
+
+/// Get the status of the file provided compared to its deployed copy (if any).
+/// Given that logos filenames are based on their content, we don't need to
+/// copy again existing ones.
+fn get_file_status(
+    key: &Key,
+    file_name: &Path,
+    deployed_files: &HashMap<Key, SystemTime>,
+) -> Result<FileStatus> {
+    let Some(deployed_ts) = deployed_files.get(key) else {
+        return Ok(FileStatus::New);
+    };
+
+    // Skip already deployed logos
+    if key.starts_with(LOGOS_PREFIX) {
+        return Ok(FileStatus::Logo);
+    }
+
+    // Skip files when the deployed copy is up to date
+    let local_ts = fs::metadata(file_name)?.modified()?;
+    if *deployed_ts >= local_ts {
+        return Ok(FileStatus::UpToDate);
+    }
+
+    Ok(FileStatus::Modified)
+}
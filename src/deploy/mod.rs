@@ -1,3 +1,583 @@
 //! This module defines the functionality of the deploy CLI subcommand.
 
+use crate::metrics::{now_timestamp, Metrics};
+use anyhow::{format_err, Context, Result};
+use flate2::{Compression, GzBuilder};
+use indicatif::{HumanBytes, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use regex::Regex;
+use reqwest::header;
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fmt::{self, Write as _},
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, instrument, warn};
+use walkdir::WalkDir;
+
+pub(crate) mod azure;
+pub(crate) mod gcs;
+pub(crate) mod local;
+pub(crate) mod netlify;
 pub(crate) mod s3;
+
+/// Name of the file in the landscape directory containing the ignore rules.
+pub(crate) const IGNORE_FILE: &str = ".landscapeignore";
+
+/// File name of the index document.
+pub(crate) const INDEX_DOCUMENT: &str = "index.html";
+
+/// Prefix used in the logos objects keys.
+pub(crate) const LOGOS_PREFIX: &str = "logos/";
+
+/// Timeout of the requests sent to notify the deploy webhook.
+const NOTIFY_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Type alias to represent an object key.
+pub(crate) type Key = String;
+
+/// Check that the landscape directory provided looks like a landscape website
+/// ready to be deployed (build subcommand output).
+pub(crate) fn check_landscape_dir(landscape_dir: &Path) -> Result<()> {
+    if !landscape_dir.exists() {
+        return Err(format_err!(
+            "landscape directory {} does not exist",
+            landscape_dir.display()
+        ));
+    }
+    if !landscape_dir.is_dir() {
+        return Err(format_err!(
+            "landscape directory {} is not a directory",
+            landscape_dir.display()
+        ));
+    }
+
+    // Source landscape files are not part of the build output, so this is
+    // likely the wrong path
+    for source_file in ["landscape.yml", "settings.yml"] {
+        if landscape_dir.join(source_file).exists() {
+            warn!(
+                "landscape directory contains a {source_file} file: it looks like a source landscape \
+                 (please make sure you are deploying the build subcommand output)"
+            );
+        }
+    }
+
+    if !landscape_dir.join(INDEX_DOCUMENT).is_file() {
+        return Err(format_err!(
+            "index document not found in landscape directory {} (please make sure you are \
+             deploying the build subcommand output)",
+            landscape_dir.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Convert the glob pattern provided into a regular expression. The `*` and
+/// `?` wildcards don't match the path separator, whereas `**` matches any
+/// sequence of characters (including path separators).
+pub(crate) fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let mut expr = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        // `**/` also matches zero directories
+                        chars.next();
+                        expr.push_str("(?:.*/)?");
+                    } else {
+                        expr.push_str(".*");
+                    }
+                } else {
+                    expr.push_str("[^/]*");
+                }
+            }
+            '?' => expr.push_str("[^/]"),
+            c => expr.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expr.push('$');
+
+    Ok(Regex::new(&expr)?)
+}
+
+/// Get the keys of the files in the landscape directory.
+pub(crate) fn get_local_keys(landscape_dir: &Path) -> Result<HashSet<Key>> {
+    let mut keys = HashSet::new();
+
+    for entry in WalkDir::new(landscape_dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            keys.insert(get_object_key(landscape_dir, entry.path()));
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Get the object key of the file provided (its path relative to the
+/// landscape directory).
+pub(crate) fn get_object_key(landscape_dir: &Path, file_name: &Path) -> Key {
+    file_name
+        .display()
+        .to_string()
+        .trim_start_matches(landscape_dir.display().to_string().as_str())
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// Notify the webhook provided about the result of a deploy. Errors sending
+/// the notification are only logged, so that they don't make the deploy fail.
+pub(crate) async fn notify_webhook(url: &str, notification: &Notification) {
+    let result = async {
+        let http_client = reqwest::Client::builder().timeout(NOTIFY_WEBHOOK_TIMEOUT).build()?;
+        let resp = http_client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(notification)?)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(format_err!(
+                "unexpected status code notifying deploy webhook: {}",
+                resp.status()
+            ));
+        }
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => debug!("deploy webhook notified"),
+        Err(err) => warn!(?err, "error notifying deploy webhook"),
+    }
+}
+
+/// Parse the duration provided. Durations can be provided in seconds (i.e.
+/// 3600) or using the s (seconds), m (minutes), h (hours) or d (days) units
+/// (i.e. 1h).
+pub(crate) fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let invalid_duration = || format_err!("invalid duration: {value} (expected format: 3600 or 1h)");
+
+    let (amount, multiplier) = match value.chars().last() {
+        Some('s') => (&value[..value.len() - 1], 1),
+        Some('m') => (&value[..value.len() - 1], 60),
+        Some('h') => (&value[..value.len() - 1], 60 * 60),
+        Some('d') => (&value[..value.len() - 1], 24 * 60 * 60),
+        _ => (value, 1),
+    };
+    let amount: u64 = amount.parse().map_err(|_| invalid_duration())?;
+
+    amount.checked_mul(multiplier).map(Duration::from_secs).ok_or_else(invalid_duration)
+}
+
+/// Write a compressed tarball (tar.gz) to the path provided including the
+/// files of the landscape directory with the keys given. The archive is
+/// deterministic: entries are sorted by key and use fixed metadata (owner,
+/// permissions and modification time), so the same files always produce the
+/// same archive.
+#[instrument(skip_all, err)]
+pub(crate) fn write_archive(landscape_dir: &Path, keys: &[Key], path: &Path) -> Result<()> {
+    let file = fs::File::create(path).context(format!("error creating archive {}", path.display()))?;
+    let encoder = GzBuilder::new().mtime(0).write(file, Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+
+    let mut keys = keys.to_vec();
+    keys.sort();
+    for key in &keys {
+        let data = fs::read(landscape_dir.join(key))?;
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+        builder.append_data(&mut header, key, data.as_slice())?;
+    }
+    builder.into_inner()?.finish()?;
+
+    info!(files = keys.len(), path = %path.display(), "archive written");
+    Ok(())
+}
+
+/// Set of rules used to decide which files in the landscape directory should
+/// not be deployed. Rules use the gitignore syntax: patterns that don't contain
+/// a slash match at any depth, a trailing slash only matches directories and a
+/// leading `!` negates the pattern (the last rule matching wins).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Create a new IgnoreRules instance from the ignore file in the landscape
+    /// directory (if any), followed by the extra patterns provided.
+    pub(crate) fn new(landscape_dir: &Path, extra_patterns: &[String]) -> Result<Self> {
+        let mut patterns = vec![];
+        let ignore_file = landscape_dir.join(IGNORE_FILE);
+        if ignore_file.exists() {
+            let content = fs::read_to_string(&ignore_file).context("error reading ignore file")?;
+            patterns.extend(content.lines().map(ToString::to_string));
+        }
+        patterns.extend(extra_patterns.iter().cloned());
+
+        let mut rules = vec![];
+        for pattern in &patterns {
+            if let Some(rule) =
+                IgnoreRule::new(pattern).context(format!("invalid ignore pattern: {pattern}"))?
+            {
+                rules.push(rule);
+            }
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Check if the key provided is ignored.
+    pub(crate) fn is_ignored(&self, key: &str) -> bool {
+        self.rules.iter().rev().find(|rule| rule.is_match(key)).is_some_and(|rule| !rule.negated)
+    }
+}
+
+/// Ignore rule.
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// Expressions matching the keys covered by the rule.
+    patterns: Vec<Regex>,
+    /// Whether the rule negates a previous match or not.
+    negated: bool,
+}
+
+impl IgnoreRule {
+    /// Create a new IgnoreRule instance from the pattern provided. Blank lines
+    /// and comments don't produce any rule.
+    fn new(pattern: &str) -> Result<Option<Self>> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return Ok(None);
+        }
+
+        // Prepare glob
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let glob = if pattern.contains('/') {
+            pattern.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{pattern}")
+        };
+
+        // Files inside a matching directory are matched as well
+        let mut patterns = vec![glob_to_regex(&format!("{glob}/**"))?];
+        if !dir_only {
+            patterns.push(glob_to_regex(&glob)?);
+        }
+
+        Ok(Some(Self { patterns, negated }))
+    }
+
+    /// Check if the key provided matches the rule.
+    fn is_match(&self, key: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(key))
+    }
+}
+
+/// Status of a file in the landscape directory compared to its deployed copy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FileStatus {
+    /// File hasn't been deployed yet.
+    New,
+    /// File has been modified since it was deployed.
+    Modified,
+    /// Deployed copy of the file is up to date.
+    UpToDate,
+    /// File is a logo already deployed (logos filenames are based on their
+    /// content, so they never change).
+    Logo,
+}
+
+impl FileStatus {
+    /// Check if the file needs to be uploaded.
+    pub(crate) fn needs_upload(self) -> bool {
+        matches!(self, FileStatus::New | FileStatus::Modified)
+    }
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            FileStatus::New => "new",
+            FileStatus::Modified => "modified",
+            FileStatus::UpToDate => "skipped-up-to-date",
+            FileStatus::Logo => "skipped-logo",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// Summary of the changes made (or that would be made) during a deploy.
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct Summary {
+    /// Keys of the files uploaded.
+    pub uploaded: Vec<Key>,
+    /// Number of logos skipped (already deployed).
+    pub skipped_logos: usize,
+    /// Number of files skipped because their deployed copy was up to date.
+    pub skipped_up_to_date: usize,
+    /// Keys of the objects deleted.
+    pub deleted: Vec<Key>,
+    /// Total number of bytes transferred.
+    pub bytes_transferred: u64,
+    /// Duration of each of the deploy phases.
+    pub phases: Vec<Phase>,
+    /// Total duration of the deploy (in seconds).
+    pub duration: f64,
+}
+
+impl Summary {
+    /// Track the file provided in the summary.
+    pub(crate) fn track_file(&mut self, key: Key, status: FileStatus, bytes: u64) {
+        match status {
+            FileStatus::New | FileStatus::Modified => {
+                self.uploaded.push(key);
+                self.bytes_transferred += bytes;
+            }
+            FileStatus::UpToDate => self.skipped_up_to_date += 1,
+            FileStatus::Logo => self.skipped_logos += 1,
+        }
+    }
+
+    /// Track the duration of the phase provided, which started at the instant
+    /// given.
+    pub(crate) fn track_phase(&mut self, name: &'static str, start: Instant) {
+        self.phases.push(Phase {
+            name,
+            duration: start.elapsed().as_secs_f64(),
+        });
+    }
+
+    /// Log the summary of the deploy.
+    pub(crate) fn log(&self, dry_run: bool) {
+        // Prepare table with the summary details
+        let mut rows = vec![
+            ("files uploaded".to_string(), self.uploaded.len().to_string()),
+            ("files skipped (logo)".to_string(), self.skipped_logos.to_string()),
+            (
+                "files skipped (up-to-date)".to_string(),
+                self.skipped_up_to_date.to_string(),
+            ),
+            ("objects deleted".to_string(), self.deleted.len().to_string()),
+            (
+                "bytes transferred".to_string(),
+                self.bytes_transferred.to_string(),
+            ),
+        ];
+        for phase in &self.phases {
+            rows.push((
+                format!("{} duration", phase.name),
+                format!("{:.3}s", phase.duration),
+            ));
+        }
+        rows.push(("total duration".to_string(), format!("{:.3}s", self.duration)));
+        let mut table = String::new();
+        for (name, value) in rows {
+            let _ = write!(table, "\n  {name:<28}{value:>16}");
+        }
+
+        if dry_run {
+            info!("dry run completed:{table}");
+        } else {
+            info!("landscape website deployed!{table}");
+        }
+    }
+
+    /// Write the deploy metrics, based on the summary, to the metrics file
+    /// provided (in the Prometheus text format).
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn write_metrics(&self, metrics_file: &Path, bucket: &str, success: bool) -> Result<()> {
+        let labels = [("bucket", bucket)];
+        let mut metrics = Metrics::default();
+        metrics
+            .gauge_with_labels(
+                "deploy_success",
+                "Whether the last deploy succeeded (1) or not (0).",
+                &labels,
+                if success { 1.0 } else { 0.0 },
+            )
+            .gauge_with_labels(
+                "deploy_last_run_timestamp_seconds",
+                "Timestamp of the last deploy.",
+                &labels,
+                now_timestamp(),
+            )
+            .gauge_with_labels(
+                "deploy_duration_seconds",
+                "Duration of the last deploy.",
+                &labels,
+                self.duration,
+            );
+        for phase in &self.phases {
+            metrics.gauge_with_labels(
+                "deploy_phase_duration_seconds",
+                "Duration of each of the phases of the last deploy.",
+                &[("bucket", bucket), ("phase", phase.name)],
+                phase.duration,
+            );
+        }
+        let files = [
+            ("uploaded", self.uploaded.len()),
+            ("skipped-logo", self.skipped_logos),
+            ("skipped-up-to-date", self.skipped_up_to_date),
+        ];
+        for (status, count) in files {
+            metrics.gauge_with_labels(
+                "deploy_files",
+                "Number of files processed in the last deploy, by status.",
+                &[("bucket", bucket), ("status", status)],
+                count as f64,
+            );
+        }
+        metrics
+            .gauge_with_labels(
+                "deploy_objects_deleted",
+                "Number of objects deleted in the last deploy.",
+                &labels,
+                self.deleted.len() as f64,
+            )
+            .gauge_with_labels(
+                "deploy_bytes_transferred",
+                "Number of bytes transferred in the last deploy.",
+                &labels,
+                self.bytes_transferred as f64,
+            );
+        metrics.write(metrics_file)
+    }
+
+    /// Write the summary in JSON format to the file provided.
+    pub(crate) fn write_json(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?).context("error writing summary json file")?;
+        Ok(())
+    }
+}
+
+/// Duration of a deploy phase.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Phase {
+    /// Name of the phase.
+    pub name: &'static str,
+    /// Duration of the phase (in seconds).
+    pub duration: f64,
+}
+
+/// Notification sent to the deploy webhook once the deploy has completed (or
+/// failed).
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Notification {
+    /// Message describing the result of the deploy (used by services like
+    /// Slack to display the notification).
+    pub text: String,
+    /// Whether the deploy succeeded or not.
+    pub success: bool,
+    /// Bucket (or destination) the landscape website was deployed to.
+    pub bucket: String,
+    /// Duration of the deploy (in seconds).
+    pub duration: f64,
+    /// Number of files uploaded.
+    pub uploaded: usize,
+    /// Number of files skipped (logos and up-to-date files).
+    pub skipped: usize,
+    /// Error message (when the deploy failed).
+    pub error: Option<String>,
+}
+
+impl Notification {
+    /// Create a new notification from the deploy summary and error provided.
+    pub(crate) fn new(bucket: &str, summary: &Summary, error: Option<&anyhow::Error>) -> Self {
+        let uploaded = summary.uploaded.len();
+        let skipped = summary.skipped_logos + summary.skipped_up_to_date;
+        let error = error.map(|err| format!("{err:#}"));
+        let text = match &error {
+            None => format!(
+                "Landscape website deployed to {bucket} in {:.1}s ({uploaded} files uploaded, {skipped} skipped)",
+                summary.duration
+            ),
+            Some(err) => format!(
+                "Landscape website deploy to {bucket} failed after {:.1}s ({uploaded} files uploaded, {skipped} skipped): {err}",
+                summary.duration
+            ),
+        };
+
+        Self {
+            text,
+            success: error.is_none(),
+            bucket: bucket.to_string(),
+            duration: summary.duration,
+            uploaded,
+            skipped,
+            error,
+        }
+    }
+}
+
+/// Progress bar displayed while uploading the landscape website files in
+/// interactive deploys.
+pub(crate) struct UploadProgress {
+    bar: Option<ProgressBar>,
+    bytes: u64,
+}
+
+impl UploadProgress {
+    /// Create a new UploadProgress instance for the number of files provided.
+    /// When it's not enabled (i.e. stdout is not a terminal) nothing is
+    /// displayed.
+    pub(crate) fn new(files: u64, enabled: bool) -> Self {
+        let bar = enabled.then(|| {
+            let bar = ProgressBar::with_draw_target(Some(files), ProgressDrawTarget::stdout());
+            bar.set_style(
+                ProgressStyle::with_template("[{elapsed_precise}] {bar:40} {pos}/{len} files ({msg})")
+                    .expect("progress bar template to be valid"),
+            );
+            bar.set_message(HumanBytes(0).to_string());
+            bar
+        });
+
+        Self { bar, bytes: 0 }
+    }
+
+    /// Abandon the progress bar, leaving it as it is (i.e. when some files
+    /// could not be uploaded).
+    pub(crate) fn abandon(&self) {
+        if let Some(bar) = &self.bar {
+            bar.abandon();
+        }
+    }
+
+    /// Clear the progress bar once all the files have been processed.
+    pub(crate) fn finish(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+
+    /// Track a file processed, including the number of bytes transferred.
+    pub(crate) fn inc(&mut self, bytes: u64) {
+        if let Some(bar) = &self.bar {
+            self.bytes += bytes;
+            bar.set_message(HumanBytes(self.bytes).to_string());
+            bar.inc(1);
+        }
+    }
+}
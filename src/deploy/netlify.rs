@@ -0,0 +1,362 @@
+//! This module defines the functionality of the deploy CLI subcommand for the
+//! Netlify provider.
+//!
+//! Netlify deploys are created using the file digest API: a manifest with the
+//! SHA1 digest of every file in the landscape directory is posted when
+//! creating the deploy, and Netlify replies with the digests of the files it
+//! doesn't have yet. Only those files are uploaded.
+
+use super::{check_landscape_dir, get_object_key, FileStatus, Key, Summary, LOGOS_PREFIX};
+use crate::NetlifyArgs;
+use anyhow::{format_err, Context, Result};
+use futures::stream::{self, StreamExt};
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::{
+    collections::{BTreeMap, HashSet},
+    env,
+    fmt::Write as _,
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+use tracing::{debug, info, instrument};
+use url::Url;
+use walkdir::WalkDir;
+
+/// Netlify API base url.
+const API_URL: &str = "https://api.netlify.com/api/v1";
+
+/// Environment variable containing the token used to authenticate the
+/// requests to the Netlify API.
+const AUTH_TOKEN_ENV_VAR: &str = "NETLIFY_AUTH_TOKEN";
+
+/// Time between checks of the deploy state while waiting for it to be ready.
+const DEPLOY_READY_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maximum time to wait for the deploy to be ready.
+const DEPLOY_READY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Environment variable containing the id of the site to deploy to.
+const SITE_ID_ENV_VAR: &str = "NETLIFY_SITE_ID";
+
+/// Deploy landscape website to Netlify.
+#[instrument(skip_all)]
+pub(crate) async fn deploy(args: &NetlifyArgs) -> Result<()> {
+    info!("deploying landscape website..");
+    let start = Instant::now();
+
+    // Check arguments, required environment variables and landscape directory
+    check_args(args)?;
+    check_env_vars()?;
+    check_landscape_dir(&args.landscape_dir)?;
+    info!(
+        concurrency = args.concurrency,
+        draft = args.draft,
+        "deploy settings"
+    );
+    if args.dry_run {
+        info!("dry run mode enabled: no deploy will be created");
+    }
+
+    // Compute the digests of the landscape website files
+    let mut summary = Summary::default();
+    let phase_start = Instant::now();
+    let files = get_local_files(args)?;
+    summary.track_phase("digest", phase_start);
+
+    // In dry run mode we only log the files that would be included in the
+    // deploy (Netlify decides which ones need to be uploaded when the deploy
+    // is created)
+    if args.dry_run {
+        for (key, file) in &files {
+            info!(?key, digest = %file.digest, "file would be included in deploy");
+            summary.track_file(key.clone(), FileStatus::New, fs::metadata(&file.path)?.len());
+        }
+        summary.duration = start.elapsed().as_secs_f64();
+        summary.log(args.dry_run);
+        return Ok(());
+    }
+
+    // Setup Netlify client
+    let client = Client::new()?;
+
+    // Create deploy
+    let phase_start = Instant::now();
+    let deploy = client.create_deploy(args, &files).await?;
+    debug!(deploy_id = %deploy.id, required = deploy.required.len(), "deploy created");
+    summary.track_phase("create", phase_start);
+
+    // Upload the files required by Netlify
+    let phase_start = Instant::now();
+    for (key, status, bytes) in upload_files(&client, args, &deploy, &files).await? {
+        summary.track_file(key, status, bytes);
+    }
+    summary.track_phase("upload", phase_start);
+
+    // Wait for the deploy to be ready
+    let phase_start = Instant::now();
+    let deploy = client.wait_for_deploy(&deploy.id).await?;
+    summary.track_phase("processing", phase_start);
+
+    summary.duration = start.elapsed().as_secs_f64();
+    summary.log(args.dry_run);
+    if let Some(url) = deploy.ssl_url.or(deploy.url) {
+        info!("deploy available at {url}");
+    }
+
+    Ok(())
+}
+
+/// Check that the arguments provided are valid.
+#[instrument(skip_all, err)]
+fn check_args(args: &NetlifyArgs) -> Result<()> {
+    if args.concurrency == 0 {
+        return Err(format_err!("concurrency must be greater than 0"));
+    }
+
+    Ok(())
+}
+
+/// Check that the required environment variables have been provided.
+#[instrument(skip_all, err)]
+fn check_env_vars() -> Result<()> {
+    let required_env_vars = [AUTH_TOKEN_ENV_VAR, SITE_ID_ENV_VAR];
+
+    for var in required_env_vars {
+        if !env::var(var).is_ok_and(|value| !value.is_empty()) {
+            return Err(format_err!("required environment variable {var} not provided"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the files in the landscape directory that will be part of the deploy,
+/// returning their key and some information about them (like their digest).
+#[instrument(skip_all, err)]
+fn get_local_files(args: &NetlifyArgs) -> Result<BTreeMap<Key, LocalFile>> {
+    let mut files = BTreeMap::new();
+
+    for entry in WalkDir::new(&args.landscape_dir) {
+        // Check if the entry is a regular file
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        // Skip files that start with a dot
+        let key = get_object_key(&args.landscape_dir, entry.path());
+        if key.starts_with('.') {
+            continue;
+        }
+
+        // Compute file digest
+        let content = fs::read(entry.path()).context(format!("error reading file {key}"))?;
+        let digest = hex::encode(Sha1::digest(&content));
+        files.insert(
+            key,
+            LocalFile {
+                digest,
+                path: entry.path().to_owned(),
+            },
+        );
+    }
+
+    Ok(files)
+}
+
+/// Upload the landscape website files Netlify requires to the deploy provided,
+/// returning the status of each of the files processed and the number of
+/// bytes transferred. Files with the same content are only uploaded once.
+#[instrument(skip_all, err)]
+async fn upload_files(
+    client: &Client,
+    args: &NetlifyArgs,
+    deploy: &Deploy,
+    files: &BTreeMap<Key, LocalFile>,
+) -> Result<Vec<(Key, FileStatus, u64)>> {
+    // Prepare the status of each of the files
+    let required: HashSet<&String> = deploy.required.iter().collect();
+    let mut digests_seen = HashSet::new();
+    let mut files_status = vec![];
+    let mut files_to_upload = vec![];
+    for (key, file) in files {
+        if required.contains(&file.digest) && digests_seen.insert(&file.digest) {
+            files_to_upload.push((key, file));
+        } else if key.starts_with(LOGOS_PREFIX) {
+            files_status.push((key.clone(), FileStatus::Logo, 0));
+        } else {
+            files_status.push((key.clone(), FileStatus::UpToDate, 0));
+        }
+    }
+
+    // Upload files
+    let results: Vec<Result<(Key, FileStatus, u64)>> = stream::iter(files_to_upload)
+        .map(|(key, file)| async move {
+            let bytes = client
+                .put_file(&deploy.id, key, file)
+                .await
+                .context(format_err!("error uploading file {}", key))?;
+
+            debug!(?key, "file uploaded");
+            Ok((key.clone(), FileStatus::New, bytes))
+        })
+        .buffer_unordered(args.concurrency)
+        .collect()
+        .await;
+
+    // Process results
+    let mut errors_found = false;
+    let mut errors = String::new();
+    for result in results {
+        match result {
+            Ok(file_status) => files_status.push(file_status),
+            Err(err) => {
+                errors_found = true;
+                let _ = writeln!(errors, "- {err:?}");
+            }
+        }
+    }
+    if errors_found {
+        return Err(format_err!("{errors}"));
+    }
+
+    Ok(files_status)
+}
+
+/// Netlify API client, authenticated using a personal access token.
+struct Client {
+    http_client: reqwest::Client,
+    site_id: String,
+}
+
+impl Client {
+    /// Create a new Client instance.
+    fn new() -> Result<Self> {
+        // Setup HTTP client ready to make requests to the Netlify API
+        // (credentials are read from the environment)
+        let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        let mut headers = header::HeaderMap::new();
+        let auth_token = env::var(AUTH_TOKEN_ENV_VAR)?;
+        headers.insert(
+            header::AUTHORIZATION,
+            header::HeaderValue::from_str(&format!("Bearer {auth_token}"))?,
+        );
+        let http_client =
+            reqwest::Client::builder().user_agent(user_agent).default_headers(headers).build()?;
+        let site_id = env::var(SITE_ID_ENV_VAR)?;
+
+        Ok(Self { http_client, site_id })
+    }
+
+    /// Create a new deploy including the files provided.
+    #[instrument(skip_all, err)]
+    async fn create_deploy(&self, args: &NetlifyArgs, files: &BTreeMap<Key, LocalFile>) -> Result<Deploy> {
+        let body = CreateDeployInput {
+            draft: args.draft,
+            files: files.iter().map(|(key, file)| (format!("/{key}"), file.digest.clone())).collect(),
+            title: args.title.clone(),
+        };
+        let url = format!("{API_URL}/sites/{}/deploys", self.site_id);
+        let response = self.http_client.post(url).json(&body).send().await?;
+        if !response.status().is_success() {
+            return Err(format_err!(
+                "error creating deploy: unexpected status code: {:?}",
+                response.status()
+            ));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Get the deploy provided.
+    async fn get_deploy(&self, deploy_id: &str) -> Result<Deploy> {
+        let url = format!("{API_URL}/deploys/{deploy_id}");
+        let response = self.http_client.get(url).send().await?;
+        if response.status() != StatusCode::OK {
+            return Err(format_err!(
+                "error getting deploy: unexpected status code: {:?}",
+                response.status()
+            ));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Upload the file provided to the deploy given, returning the number of
+    /// bytes transferred.
+    async fn put_file(&self, deploy_id: &str, key: &str, file: &LocalFile) -> Result<u64> {
+        let mut url = Url::parse(&format!("{API_URL}/deploys/{deploy_id}/files"))?;
+        url.path_segments_mut()
+            .map_err(|()| format_err!("invalid deploy url"))?
+            .extend(key.split('/'));
+        let body = fs::read(&file.path)?;
+        let bytes = body.len() as u64;
+        let response = self
+            .http_client
+            .put(url)
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .body(body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(format_err!("unexpected status code: {:?}", response.status()));
+        }
+
+        Ok(bytes)
+    }
+
+    /// Wait for the deploy provided to be ready (Netlify processes the deploy
+    /// once all the required files have been uploaded).
+    #[instrument(skip(self), err)]
+    async fn wait_for_deploy(&self, deploy_id: &str) -> Result<Deploy> {
+        let start = Instant::now();
+        loop {
+            let deploy = self.get_deploy(deploy_id).await?;
+            match deploy.state.as_str() {
+                "ready" => return Ok(deploy),
+                "error" => {
+                    let reason = deploy.error_message.unwrap_or_default();
+                    return Err(format_err!("deploy failed: {reason}"));
+                }
+                state => debug!(state, "waiting for deploy to be ready"),
+            }
+            if start.elapsed() > DEPLOY_READY_TIMEOUT {
+                return Err(format_err!("timeout waiting for deploy to be ready"));
+            }
+            sleep(DEPLOY_READY_CHECK_INTERVAL).await;
+        }
+    }
+}
+
+/// Input used to create a new deploy.
+#[derive(Debug, Clone, Serialize)]
+struct CreateDeployInput {
+    draft: bool,
+    files: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// Some information about a Netlify deploy.
+#[derive(Debug, Clone, Deserialize)]
+struct Deploy {
+    id: String,
+    state: String,
+    #[serde(default)]
+    required: Vec<String>,
+    #[serde(rename = "deploy_url")]
+    url: Option<String>,
+    error_message: Option<String>,
+    ssl_url: Option<String>,
+}
+
+/// Some information about a file in the landscape directory.
+#[derive(Debug, Clone)]
+struct LocalFile {
+    digest: String,
+    path: PathBuf,
+}
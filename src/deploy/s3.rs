@@ -1,239 +1,3528 @@
 //! This module defines the functionality of the deploy CLI subcommand for the
 //! AWS S3 provider.
 
-use crate::S3Args;
+use super::{
+    check_landscape_dir, get_local_keys, get_object_key, glob_to_regex, notify_webhook, write_archive,
+    FileStatus, IgnoreRules, Key, Notification, Summary, UploadProgress, INDEX_DOCUMENT, LOGOS_PREFIX,
+};
+use crate::{metrics::metrics_file_for, S3Args, S3BootstrapArgs};
 use anyhow::{format_err, Context, Result};
-use aws_sdk_s3::primitives::{ByteStream, DateTime};
-use futures::stream::{self, StreamExt};
+use aws_config::{sts::AssumeRoleProvider, SdkConfig};
+use aws_sdk_cloudfront::types::{InvalidationBatch, Paths};
+use aws_sdk_s3::{
+    config::{timeout::TimeoutConfig, Region},
+    error::{ProvideErrorMetadata, SdkError},
+    primitives::{ByteStream, DateTime},
+    types::{
+        CompletedMultipartUpload, CompletedPart, Delete, ErrorDocument, IndexDocument, Object,
+        ObjectIdentifier, PublicAccessBlockConfiguration, WebsiteConfiguration,
+    },
+};
+use aws_smithy_http::byte_stream::Length;
+use bytes::Bytes;
+use chrono::Utc;
+use clap::ValueEnum;
+use flate2::{read::GzDecoder, write::GzEncoder};
+use futures::{
+    future,
+    stream::{self, StreamExt, TryStreamExt},
+};
+use lazy_static::lazy_static;
+use md5::{Digest, Md5};
 use mime_guess::mime;
+use regex::Regex;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    env, fs,
+    collections::{BTreeMap, HashMap},
+    env,
+    fmt::{self, Write as _},
+    fs,
+    future::Future,
+    io::{self, IsTerminal, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
-    time::Instant,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use tracing::{debug, info, instrument};
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
+use url::form_urlencoded;
 use walkdir::WalkDir;
 
-/// File name of the index document.
-const INDEX_DOCUMENT: &str = "index.html";
+/// Maximum number of paths to invalidate individually in CloudFront. When more
+/// paths than this have changed, all paths in the distribution are invalidated.
+const CLOUDFRONT_MAX_INVALIDATION_PATHS: usize = 100;
+
+/// Content types used for some extensions that mime_guess doesn't detect (or
+/// detects incorrectly). Content types in the map provided by the user take
+/// precedence over these ones.
+const DEFAULT_CONTENT_TYPES_OVERRIDES: [(&str, &str); 3] = [
+    ("map", "application/json"),
+    ("wasm", "application/wasm"),
+    ("webmanifest", "application/manifest+json"),
+];
+
+/// Headers set by default on the objects whose key matches the glob (after
+/// the ones in the header rules file provided by the user, if any).
+const DEFAULT_HEADER_RULES: [(&str, &str, &str); 2] = [
+    ("docs/*.csv", "content-disposition", "attachment"),
+    ("docs/*.ndjson", "content-disposition", "attachment"),
+];
+
+/// Maximum number of objects that can be deleted in a single request.
+const DELETE_OBJECTS_BATCH_SIZE: usize = 1000;
+
+/// Extensions of the files that will be compressed (when compression is
+/// enabled). Binary files like images or fonts are usually compressed already.
+const COMPRESSIBLE_EXTENSIONS: [&str; 10] = [
+    "css", "csv", "html", "js", "json", "map", "md", "svg", "txt", "xml",
+];
+
+/// Key of the object mapping the old ids of the items moved to the new ones
+/// (relative to the prefix). The web application uses it to follow the items
+/// permalinks redirects, as S3 redirects cannot match query strings.
+const ITEMS_REDIRECTS_KEY: &str = "data/items-redirects.json";
+
+/// Key of the deploy manifest object (relative to the prefix).
+const MANIFEST_KEY: &str = ".deploy-manifest.json";
+
+/// Maximum number of files processed concurrently when the number of bytes in
+/// flight is bounded (the budget is what limits the uploads in this case).
+const MAX_IN_FLIGHT_FILES: usize = 1000;
+
+/// Maximum number of objects to delete listed in the prune plan (all of them
+/// are listed in dry run mode).
+const PRUNE_PLAN_SAMPLE_SIZE: usize = 20;
+
+/// Maximum number of parts of a multipart upload uploaded concurrently.
+const MULTIPART_UPLOAD_CONCURRENCY: usize = 4;
+
+/// Maximum number of parts a multipart upload can have.
+const MULTIPART_UPLOAD_MAX_PARTS: usize = 10_000;
+
+/// Minimum size of each of the parts of a multipart upload (the part size is
+/// increased when needed to stay under the maximum number of parts).
+const MULTIPART_UPLOAD_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Headers managed by S3 (or set using other deploy options) that cannot be set
+/// using the header rules. Headers starting with x-amz- are reserved as well,
+/// except for the user metadata ones (x-amz-meta-).
+const RESERVED_HEADERS: [&str; 11] = [
+    "authorization",
+    "cache-control",
+    "content-encoding",
+    "content-length",
+    "content-md5",
+    "content-type",
+    "date",
+    "etag",
+    "expect",
+    "host",
+    "last-modified",
+];
+
+/// Headers stored by S3 as system metadata of the objects, and served as
+/// regular headers (the only ones, along with the user metadata ones, that can
+/// be set using the header rules).
+const SYSTEM_METADATA_HEADERS: [&str; 2] = ["content-disposition", "content-language"];
+
+/// Content types of some well known files without extension.
+const WELL_KNOWN_CONTENT_TYPES: [(&str, &str); 5] = [
+    ("CNAME", "text/plain"),
+    ("LICENSE", "text/plain"),
+    ("README", "text/plain"),
+    ("_headers", "text/plain"),
+    ("_redirects", "text/plain"),
+];
+
+/// Base delay used to calculate the exponential backoff between retries.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// Maximum delay between retries.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(20);
+
+lazy_static! {
+    /// Cache-Control rules applied by default (after the ones provided by the
+    /// user, if any). Logos and web assets filenames are based on their
+    /// content, so they can be cached indefinitely.
+    static ref DEFAULT_CACHE_CONTROL_RULES: Vec<CacheControlRule> = [
+        ("logos/**", "public, max-age=31536000, immutable"),
+        ("assets/**", "public, max-age=31536000, immutable"),
+        ("**/*.html", "no-cache"),
+    ]
+    .into_iter()
+    .map(|(glob, value)| CacheControlRule {
+        pattern: glob_to_regex(glob).expect("default cache control rules globs to be valid"),
+        value: value.to_string(),
+    })
+    .collect();
+}
+
+/// Options used to deploy the landscape website to AWS S3.
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
+pub struct S3DeployOptions {
+    /// File where an archive with the website files deployed will be written
+    /// to once the deploy has completed.
+    pub archive: Option<PathBuf>,
+
+    /// ARN of the role to assume before deploying.
+    pub assume_role_arn: Option<String>,
+
+    /// Bucket to copy the landscape website files to.
+    pub bucket: String,
+
+    /// Cache-Control rules (they take precedence over the default ones).
+    pub cache_control: Vec<CacheControlRule>,
+
+    /// CloudFront distribution where the paths that changed will be
+    /// invalidated once the deploy has completed.
+    pub cloudfront_distribution_id: Option<String>,
+
+    /// Compare the files' MD5 digest against the deployed objects' ETag
+    /// instead of using their modification time.
+    pub compare_hash: bool,
+
+    /// Compression algorithm used to compress text based files.
+    pub compress: Option<Compression>,
+
+    /// Minimum size (in bytes) a file must have to be compressed.
+    pub compress_min_size: usize,
+
+    /// Number of files to upload concurrently.
+    pub concurrency: usize,
+
+    /// Upload the index document only if it hasn't changed since the bucket
+    /// objects were listed.
+    pub conditional_index: bool,
+
+    /// Confirm the deletion of the stale objects when their number exceeds
+    /// the prune threshold.
+    pub confirm_prune: bool,
+
+    /// File mapping extensions to content types (they take precedence over
+    /// the ones guessed from the extension).
+    pub content_type_map: Option<PathBuf>,
+
+    /// Content type used for the files whose content type cannot be detected.
+    pub default_content_type: String,
+
+    /// Maximum duration of the deploy. When it's exceeded, no new uploads are
+    /// started and the deploy stops once the ones in flight complete.
+    pub deadline: Option<Duration>,
+
+    /// Display the changes that would be made, without making them.
+    pub dry_run: bool,
+
+    /// Custom endpoint url (used with S3 compatible services).
+    pub endpoint_url: Option<String>,
+
+    /// Use path-style addressing.
+    pub force_path_style: bool,
+
+    /// YAML file with the headers to set on the objects whose key matches a
+    /// glob (they take precedence over the default ones).
+    pub header_rules: Option<PathBuf>,
+
+    /// Gitignore-style patterns of the files that should not be deployed.
+    pub ignore: Vec<String>,
+
+    /// Glob patterns of the only files that should be uploaded (when
+    /// provided, the objects in the bucket are not listed).
+    pub include: Vec<String>,
+
+    /// Only upload the index document.
+    pub index_only: bool,
+
+    /// Location of the landscape website files (build output).
+    pub landscape_dir: PathBuf,
+
+    /// Use a manifest with the objects deployed instead of listing all the
+    /// objects in the bucket.
+    pub manifest: bool,
+
+    /// Maximum number of bytes of the files being uploaded at the same time
+    /// (when provided, it's used instead of the files concurrency).
+    pub max_in_flight_bytes: Option<u64>,
+
+    /// Maximum number of times a failed request will be retried.
+    pub max_retries: u32,
+
+    /// File where the deploy metrics will be written to (in the Prometheus
+    /// text format).
+    pub metrics_file: Option<PathBuf>,
+
+    /// Size (in bytes) above which files are uploaded using a multipart
+    /// upload.
+    pub multipart_threshold: usize,
+
+    /// Webhook url notified when the deploy completes or fails.
+    pub notify_webhook: Option<String>,
+
+    /// Prefix to prepend to all objects keys.
+    pub prefix: Option<String>,
+
+    /// Named profile used to load the AWS configuration and credentials.
+    pub profile: Option<String>,
+
+    /// Delete objects that are not present in the landscape directory.
+    pub prune: bool,
+
+    /// Minimum age of the stale objects deleted when pruning.
+    pub prune_min_age: Duration,
+
+    /// Maximum number of objects that can be pruned without confirmation.
+    pub prune_threshold: usize,
+
+    /// Do not display the upload progress bar.
+    pub quiet: bool,
+
+    /// YAML file mapping old paths of the website to new ones.
+    pub redirects_file: Option<PathBuf>,
+
+    /// AWS region of the bucket.
+    pub region: Option<String>,
+
+    /// Timeout (in seconds) of each attempt of the requests to S3.
+    pub request_timeout: Option<u64>,
+
+    /// Resume an interrupted deploy, skipping the files recorded as uploaded
+    /// in the local checkpoint.
+    pub resume: bool,
+
+    /// Restore the previous version of the objects uploaded when some files
+    /// cannot be uploaded.
+    pub rollback_on_failure: bool,
+
+    /// Upload all the website files except the index document.
+    pub skip_index: bool,
+
+    /// Commit SHA of the landscape source data.
+    pub source_commit: Option<String>,
+
+    /// Server-side encryption used to encrypt the objects uploaded.
+    pub sse: Option<ServerSideEncryption>,
+
+    /// ID of the KMS key used to encrypt the objects uploaded (required when
+    /// using the aws:kms server-side encryption).
+    pub sse_kms_key_id: Option<String>,
 
-/// Prefix used in the logos objects keys in S3.
-const LOGOS_PREFIX: &str = "logos/";
+    /// File where the deploy summary will be written to in JSON format.
+    pub summary_json: Option<PathBuf>,
 
-/// Number of files to upload concurrently.
-const UPLOAD_FILES_CONCURRENCY: usize = 20;
+    /// Tags to set on all the objects uploaded.
+    pub tag: Vec<Tag>,
 
-/// Type alias to represent an object key.
-type Key = String;
+    /// Additional targets the landscape website will be deployed to.
+    pub target: Vec<S3Target>,
+
+    /// Verify the integrity of the objects uploaded.
+    pub verify: bool,
+}
+
+impl S3DeployOptions {
+    /// Create a new S3DeployOptions instance for the bucket and landscape
+    /// directory provided, using the default values for the other options.
+    #[must_use]
+    pub fn new(bucket: &str, landscape_dir: &Path) -> Self {
+        Self {
+            archive: None,
+            assume_role_arn: None,
+            bucket: bucket.to_string(),
+            cache_control: vec![],
+            cloudfront_distribution_id: None,
+            compare_hash: false,
+            compress: None,
+            compress_min_size: 1024,
+            concurrency: 20,
+            conditional_index: false,
+            confirm_prune: false,
+            content_type_map: None,
+            deadline: None,
+            default_content_type: mime::APPLICATION_OCTET_STREAM.to_string(),
+            dry_run: false,
+            endpoint_url: None,
+            force_path_style: false,
+            header_rules: None,
+            ignore: vec![],
+            include: vec![],
+            index_only: false,
+            landscape_dir: landscape_dir.to_owned(),
+            manifest: false,
+            max_in_flight_bytes: None,
+            max_retries: 4,
+            metrics_file: None,
+            multipart_threshold: 100 * 1024 * 1024,
+            notify_webhook: None,
+            prefix: None,
+            profile: None,
+            prune: false,
+            prune_min_age: Duration::from_secs(60 * 60),
+            prune_threshold: 100,
+            // Set from the global --quiet flag
+            quiet: false,
+            redirects_file: None,
+            region: None,
+            request_timeout: None,
+            resume: false,
+            rollback_on_failure: false,
+            skip_index: false,
+            source_commit: None,
+            sse: None,
+            sse_kms_key_id: None,
+            summary_json: None,
+            tag: vec![],
+            target: vec![],
+            verify: false,
+        }
+    }
+}
+
+impl From<&S3Args> for S3DeployOptions {
+    fn from(args: &S3Args) -> Self {
+        Self {
+            archive: args.archive.clone(),
+            assume_role_arn: args.assume_role_arn.clone(),
+            bucket: args.bucket.clone().unwrap_or_default(),
+            cache_control: args.cache_control.clone(),
+            cloudfront_distribution_id: args.cloudfront_distribution_id.clone(),
+            compare_hash: args.compare_hash,
+            compress: args.compress,
+            compress_min_size: args.compress_min_size,
+            concurrency: args.concurrency,
+            conditional_index: args.conditional_index,
+            confirm_prune: args.confirm_prune,
+            content_type_map: args.content_type_map.clone(),
+            deadline: args.deadline,
+            default_content_type: args.default_content_type.clone(),
+            dry_run: args.dry_run,
+            endpoint_url: args.endpoint_url.clone(),
+            force_path_style: args.force_path_style,
+            header_rules: args.header_rules.clone(),
+            ignore: args.ignore.clone(),
+            include: args.include.clone(),
+            index_only: args.index_only,
+            landscape_dir: args.landscape_dir.clone().unwrap_or_default(),
+            manifest: args.manifest,
+            max_in_flight_bytes: args.max_in_flight_bytes,
+            max_retries: args.max_retries,
+            metrics_file: args.metrics_file.clone(),
+            multipart_threshold: args.multipart_threshold,
+            notify_webhook: args.notify_webhook.clone(),
+            prefix: args.prefix.clone(),
+            profile: args.profile.clone(),
+            prune: args.prune,
+            prune_min_age: args.prune_min_age,
+            prune_threshold: args.prune_threshold,
+            // Set from the global --quiet flag
+            quiet: false,
+            redirects_file: args.redirects_file.clone(),
+            region: args.region.clone(),
+            request_timeout: args.request_timeout,
+            resume: args.resume,
+            rollback_on_failure: args.rollback_on_failure,
+            skip_index: args.skip_index,
+            source_commit: args.source_commit.clone(),
+            sse: args.sse,
+            sse_kms_key_id: args.sse_kms_key_id.clone(),
+            summary_json: args.summary_json.clone(),
+            tag: args.tag.clone(),
+            target: args.target.clone(),
+            verify: args.verify,
+        }
+    }
+}
 
 /// Deploy landscape website to AWS S3.
+///
+/// # Errors
+///
+/// This function will return an error if the landscape directory or any of
+/// the options provided is not valid, or if the deploy fails. Some specific
+/// failures (like a file that cannot be uploaded) are reported using their own
+/// `DeployError` variant, so that they can be handled by the caller.
 #[instrument(skip_all)]
-pub(crate) async fn deploy(args: &S3Args) -> Result<()> {
+pub async fn deploy(args: &S3DeployOptions) -> Result<(), DeployError> {
+    if args.target.is_empty() {
+        deploy_target(args).await?;
+    } else {
+        deploy_targets(args).await?;
+    }
+    Ok(())
+}
+
+/// Configure the bucket provided for static website hosting, using the index
+/// document as the index and error document (so that the web application
+/// handles the routing on the client side). A public-read bucket policy is
+/// applied as well when requested. The configuration is displayed before
+/// applying it.
+///
+/// # Errors
+///
+/// This function will return an error if the required environment variables
+/// are not provided or if the bucket cannot be configured.
+#[instrument(skip_all, err)]
+pub(crate) async fn bootstrap(args: &S3BootstrapArgs) -> Result<()> {
+    let options = S3DeployOptions {
+        assume_role_arn: args.assume_role_arn.clone(),
+        endpoint_url: args.endpoint_url.clone(),
+        force_path_style: args.force_path_style,
+        profile: args.profile.clone(),
+        region: args.region.clone(),
+        ..S3DeployOptions::new(&args.bucket, Path::new("."))
+    };
+    check_env_vars(&options)?;
+
+    // Display the configuration that will be applied
+    let bucket = &args.bucket;
+    let policy = get_public_read_policy(bucket)?;
+    println!("Bucket {bucket} configuration to be set:\n");
+    println!("- Website configuration:");
+    println!("    index document: {INDEX_DOCUMENT}");
+    println!("    error document: {}", args.error_document);
+    if args.public_read {
+        println!("- Public access block:");
+        println!("    block public acls: true");
+        println!("    ignore public acls: true");
+        println!("    block public policy: false");
+        println!("    restrict public buckets: false");
+        println!("- Bucket policy:\n{policy}");
+    }
+    println!();
+    if args.dry_run {
+        println!("Dry run mode enabled: no changes have been made to the bucket");
+        return Ok(());
+    }
+
+    // Setup AWS S3 client
+    let config = load_aws_config(&options).await;
+    let s3_client = new_s3_client(&options, &config);
+
+    // Configure the bucket for static website hosting
+    let website_configuration = WebsiteConfiguration::builder()
+        .index_document(IndexDocument::builder().suffix(INDEX_DOCUMENT).build())
+        .error_document(ErrorDocument::builder().key(&args.error_document).build())
+        .build();
+    s3_client
+        .put_bucket_website()
+        .bucket(bucket)
+        .website_configuration(website_configuration)
+        .send()
+        .await
+        .context("error setting bucket website configuration")?;
+    info!(bucket, "bucket website configuration set");
+
+    // Apply the public-read bucket policy (when requested). Public policies
+    // are blocked by default in new buckets, so the public access block must
+    // allow them first (public ACLs are still blocked)
+    if args.public_read {
+        let public_access_block = PublicAccessBlockConfiguration::builder()
+            .block_public_acls(true)
+            .ignore_public_acls(true)
+            .block_public_policy(false)
+            .restrict_public_buckets(false)
+            .build();
+        s3_client
+            .put_public_access_block()
+            .bucket(bucket)
+            .public_access_block_configuration(public_access_block)
+            .send()
+            .await
+            .context("error setting bucket public access block")?;
+        s3_client
+            .put_bucket_policy()
+            .bucket(bucket)
+            .policy(policy)
+            .send()
+            .await
+            .context("error setting bucket policy")?;
+        info!(bucket, "bucket public-read policy set");
+    }
+
+    info!("bucket ready to host the landscape website!");
+    Ok(())
+}
+
+/// Deploy landscape website to the bucket provided.
+async fn deploy_target(args: &S3DeployOptions) -> Result<()> {
     info!("deploying landscape website..");
     let start = Instant::now();
 
-    // Check required environment variables
-    check_env_vars()?;
+    // Deploy landscape website, notifying the webhook and writing the deploy
+    // metrics (when requested) about the result, even if the deploy failed
+    let mut summary = Summary::default();
+    let result = deploy_website(args, start, &mut summary).await;
+    summary.duration = start.elapsed().as_secs_f64();
+    if let Some(webhook_url) = &args.notify_webhook {
+        let notification = Notification::new(&args.bucket, &summary, result.as_ref().err());
+        notify_webhook(webhook_url, &notification).await;
+    }
+    if let Some(metrics_file) = &args.metrics_file {
+        if let Err(err) = summary.write_metrics(metrics_file, &args.bucket, result.is_ok()) {
+            error!(?err, "error writing deploy metrics");
+        }
+    }
+
+    result
+}
+
+/// Deploy landscape website to all the targets provided concurrently (the
+/// bucket provided included), reporting the result of each of them. Targets
+/// are deployed independently, so a target failing doesn't prevent the others
+/// from being deployed, but an error is returned at the end.
+async fn deploy_targets(args: &S3DeployOptions) -> Result<()> {
+    let targets = get_targets(args)?;
+    info!("deploying landscape website to {} targets..", targets.len());
+    let start = Instant::now();
+
+    // Deploy to all targets concurrently (logs are tagged with the bucket of
+    // the target they belong to)
+    let results = future::join_all(targets.iter().map(|target| {
+        let span = info_span!("target", bucket = target.bucket);
+        async move { (target.bucket.as_str(), deploy_target(target).await) }.instrument(span)
+    }))
+    .await;
+
+    // Report the result of each target, keeping track of the ones that were
+    // stopped because the deadline was exceeded
+    let mut failed = vec![];
+    let mut deadline_exceeded = None;
+    let mut other_errors = false;
+    for (bucket, result) in results {
+        match result {
+            Ok(()) => info!(bucket, "target deployed"),
+            Err(err) => {
+                error!(bucket, ?err, "error deploying target");
+                failed.push(bucket);
+                match DeployError::from(err) {
+                    DeployError::DeadlineExceeded { uploaded, remaining } => {
+                        let (total_uploaded, total_remaining) = deadline_exceeded.unwrap_or((0, 0));
+                        deadline_exceeded = Some((total_uploaded + uploaded, total_remaining + remaining));
+                    }
+                    _ => other_errors = true,
+                }
+            }
+        }
+    }
+    let duration = start.elapsed().as_secs_f64();
+    if !failed.is_empty() {
+        let err = format_err!(
+            "{} of {} targets could not be deployed: {} (took: {:.3}s)",
+            failed.len(),
+            targets.len(),
+            failed.join(", "),
+            duration
+        );
+
+        // When all the targets that failed were stopped by the deadline, the
+        // deadline error is returned so that its distinct exit code is used
+        // (files uploaded and remaining are the totals across the targets)
+        if let (Some((uploaded, remaining)), false) = (deadline_exceeded, other_errors) {
+            error!("{err}");
+            return Err(DeployError::DeadlineExceeded { uploaded, remaining }.into());
+        }
+        return Err(err);
+    }
+    info!(
+        "landscape website deployed to all targets! (took: {:.3}s)",
+        duration
+    );
+
+    // The same files are deployed to all targets, so a single archive is
+    // written once all of them have been deployed
+    if let Some(archive) = &args.archive {
+        write_archive(&args.landscape_dir, &get_deployed_keys(args)?, archive)?;
+    }
+
+    Ok(())
+}
+
+/// Deploy landscape website to AWS S3, tracking the changes made in the
+/// summary provided.
+#[allow(clippy::too_many_lines)]
+async fn deploy_website(args: &S3DeployOptions, start: Instant, summary: &mut Summary) -> Result<()> {
+    // Check arguments, required environment variables and landscape directory
+    check_args(args)?;
+    check_env_vars(args)?;
+    check_landscape_dir(&args.landscape_dir)?;
+    info!(
+        concurrency = args.concurrency,
+        max_in_flight_bytes = args.max_in_flight_bytes,
+        request_timeout = args.request_timeout,
+        "deploy settings"
+    );
+    if args.dry_run {
+        info!("dry run mode enabled: no changes will be made to the bucket");
+    }
+
+    // Load the content types overrides, the header rules and the redirects
+    let content_types = ContentTypes::new(args)?;
+    let header_rules = HeaderRules::new(args)?;
+    let redirects = Redirects::new(args)?;
 
     // Setup AWS S3 client
-    let config = aws_config::load_from_env().await;
-    let s3_client = aws_sdk_s3::Client::new(&config);
+    let config = load_aws_config(args).await;
+    let s3_client = new_s3_client(args, &config);
 
-    // Get objects already deployed
-    let deployed_objects = get_deployed_objects(&s3_client, &args.bucket).await?;
+    // Get objects already deployed (from the manifest when possible). When
+    // only the files matching the include patterns are deployed, the bucket
+    // is not listed and all the files matching them are uploaded
+    let include = get_include_patterns(args)?;
+    let phase_start = Instant::now();
+    let manifest = if args.manifest {
+        get_manifest(&s3_client, args).await?
+    } else {
+        None
+    };
+    let deployed_objects = match manifest {
+        Some(manifest) => manifest.into_deployed_objects(),
+        None if !include.is_empty() => {
+            info!("include patterns provided: skipping the bucket objects listing");
+            HashMap::new()
+        }
+        None => get_deployed_objects(&s3_client, args).await?,
+    };
+    summary.track_phase("listing", phase_start);
+
+    // Review the objects that will be pruned before uploading any files, so
+    // that the deploy fails early when the deletion must be confirmed
+    let stale_keys = if args.prune {
+        let stale_keys = get_stale_keys(args, &deployed_objects, &redirects)?;
+        check_prune_plan(args, &stale_keys)?;
+        stale_keys
+    } else {
+        vec![]
+    };
 
     // Upload landscape website files (except index document)
-    upload_files(&s3_client, &args.bucket, &args.landscape_dir, &deployed_objects).await?;
+    let phase_start = Instant::now();
+    let provenance = Provenance::new(args);
+    let checkpoint = Checkpoint::new(args)?;
+    let deadline = args.deadline.map(|deadline| start + deadline);
+    let result = if args.index_only {
+        info!("index only deploy: skipping the website files upload");
+        Ok(vec![])
+    } else {
+        upload_files(
+            &s3_client,
+            args,
+            &include,
+            &content_types,
+            &header_rules,
+            &provenance,
+            &checkpoint,
+            &deployed_objects,
+            deadline,
+        )
+        .await
+    };
+    let files_status = match result {
+        Ok(files_status) => files_status,
+        Err(err) => {
+            // Some files may have been uploaded already, but the index
+            // document is intentionally not updated, so the website keeps
+            // using the files deployed previously
+            warn!("some files could not be uploaded: the index document has NOT been updated");
+            if let Some(UploadError { uploaded, source }) = err.downcast_ref::<UploadError>() {
+                summary.uploaded.clone_from(uploaded);
+
+                // When the deadline is exceeded, the files uploaded are kept
+                // (the deploy can be resumed later) and the partial summary
+                // is reported
+                if let Some(DeployError::DeadlineExceeded { remaining, .. }) = source.downcast_ref() {
+                    summary.duration = start.elapsed().as_secs_f64();
+                    warn!(
+                        uploaded = uploaded.len(),
+                        remaining, "deploy deadline exceeded: the deploy can be completed using --resume"
+                    );
+                    if let Some(summary_json) = &args.summary_json {
+                        summary.write_json(summary_json)?;
+                    }
+                } else if args.rollback_on_failure {
+                    rollback_objects(&s3_client, args, &deployed_objects, uploaded).await?;
+                }
+            }
+            return Err(err);
+        }
+    };
+    let mut uploaded_etags = HashMap::new();
+    for (key, status, output) in &files_status {
+        if status.needs_upload() {
+            uploaded_etags.insert(key.clone(), output.etag.clone());
+        }
+        summary.track_file(key.clone(), *status, output.bytes);
+    }
+    summary.track_phase("upload", phase_start);
+
+    // Verify the integrity of the objects uploaded before updating the index
+    // document, so that the website doesn't use any corrupted object
+    if args.verify && !args.dry_run {
+        let phase_start = Instant::now();
+        let objects = files_status.iter().map(|(key, _, output)| (key.as_str(), output)).collect();
+        verify_objects(&s3_client, args, objects).await?;
+        summary.track_phase("verify", phase_start);
+    }
 
     // Upload index document if all the other files were uploaded successfully
-    upload_index_document(&s3_client, &args.bucket, &args.landscape_dir, &deployed_objects).await?;
+    // (unless the files are only being staged, or it doesn't match the include
+    // patterns provided)
+    if args.skip_index {
+        info!(
+            "files staged: the index document has NOT been updated (it can be uploaded using --index-only)"
+        );
+    } else if !is_included(&include, INDEX_DOCUMENT) {
+        info!("index document does not match the include patterns: it has NOT been updated");
+    } else {
+        let phase_start = Instant::now();
+        let (index_document_status, output) = upload_index_document(
+            &s3_client,
+            args,
+            &content_types,
+            &header_rules,
+            &provenance,
+            &deployed_objects,
+        )
+        .await?;
+        if args.verify && !args.dry_run {
+            verify_objects(&s3_client, args, vec![(INDEX_DOCUMENT, &output)]).await?;
+        }
+        if index_document_status.needs_upload() {
+            uploaded_etags.insert(INDEX_DOCUMENT.to_string(), output.etag);
+        }
+        summary.track_file(INDEX_DOCUMENT.to_string(), index_document_status, output.bytes);
+        summary.track_phase("index", phase_start);
+    }
 
-    let duration = start.elapsed().as_secs_f64();
-    info!("landscape website deployed! (took: {:.3}s)", duration);
+    // Create the redirects objects (when provided)
+    if !redirects.is_empty() {
+        let phase_start = Instant::now();
+        uploaded_etags.extend(put_redirects(&s3_client, args, &redirects).await?);
+        summary.track_phase("redirects", phase_start);
+    }
+
+    // Delete objects that don't exist in the landscape directory anymore
+    if args.prune {
+        let phase_start = Instant::now();
+        summary.deleted = delete_stale_objects(&s3_client, args, stale_keys).await?;
+        summary.track_phase("prune", phase_start);
+    }
+
+    // Invalidate CloudFront distribution cache for the paths that changed
+    if let Some(distribution_id) = &args.cloudfront_distribution_id {
+        let phase_start = Instant::now();
+        let cloudfront_client = aws_sdk_cloudfront::Client::new(&config);
+        invalidate_cloudfront_paths(&cloudfront_client, args, distribution_id, summary).await?;
+        summary.track_phase("invalidation", phase_start);
+    }
+
+    // Write manifest with the objects deployed, so that the next deploy can
+    // use it instead of listing all objects in the bucket
+    if args.manifest && !args.dry_run {
+        let manifest = Manifest::new(&deployed_objects, &uploaded_etags, &summary.deleted);
+        put_manifest(&s3_client, args, &manifest).await?;
+    }
+
+    // Write an archive with the website files deployed (when requested)
+    if let Some(archive) = &args.archive {
+        let phase_start = Instant::now();
+        write_archive(&args.landscape_dir, &get_deployed_keys(args)?, archive)?;
+        summary.track_phase("archive", phase_start);
+    }
+
+    // The deploy has completed, so there is nothing to resume anymore
+    if !args.dry_run {
+        checkpoint.clear()?;
+    }
+
+    summary.duration = start.elapsed().as_secs_f64();
+    summary.log(args.dry_run);
+    if let Some(summary_json) = &args.summary_json {
+        summary.write_json(summary_json)?;
+    }
 
     Ok(())
 }
 
-/// Check that the required environment variables have been provided.
+/// Check that the arguments provided are valid.
 #[instrument(skip_all, err)]
-fn check_env_vars() -> Result<()> {
-    let required_env_vars = ["AWS_REGION", "AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY"];
-
-    for var in required_env_vars {
-        let result = env::var(var);
-        if result.is_err() || result.expect("var to be set").is_empty() {
-            return Err(format_err!("required environment variable {var} not provided"));
+fn check_args(args: &S3DeployOptions) -> Result<()> {
+    if args.concurrency == 0 {
+        return Err(format_err!("concurrency must be at least 1"));
+    }
+    if args.max_in_flight_bytes == Some(0) {
+        return Err(format_err!("max in flight bytes must be at least 1"));
+    }
+    if args.request_timeout == Some(0) {
+        return Err(format_err!("request timeout must be at least 1 second"));
+    }
+    if args.deadline == Some(Duration::ZERO) {
+        return Err(format_err!("deadline must be greater than 0"));
+    }
+    if args.index_only && args.skip_index {
+        return Err(format_err!(
+            "index only and skip index cannot be used at the same time"
+        ));
+    }
+    if args.prune && args.skip_index {
+        return Err(format_err!(
+            "prune and skip index cannot be used at the same time (the index document deployed may \
+             still use the stale objects)"
+        ));
+    }
+    if args.archive.is_some() && (args.index_only || args.skip_index || !args.include.is_empty()) {
+        return Err(format_err!(
+            "archive cannot be used when only some of the website files are deployed"
+        ));
+    }
+    match (args.sse, &args.sse_kms_key_id) {
+        (Some(ServerSideEncryption::AwsKms), None) => {
+            return Err(format_err!(
+                "sse kms key id must be provided when using aws:kms server-side encryption"
+            ));
+        }
+        (Some(ServerSideEncryption::Aes256) | None, Some(_)) => {
+            return Err(format_err!(
+                "sse kms key id can only be used with aws:kms server-side encryption"
+            ));
+        }
+        _ => {}
+    }
+    if args.compare_hash && args.sse == Some(ServerSideEncryption::AwsKms) {
+        return Err(format_err!(
+            "compare hash cannot be used with aws:kms server-side encryption (the etag of the objects \
+             is not computed from their content)"
+        ));
+    }
+    if args.verify && args.sse == Some(ServerSideEncryption::AwsKms) {
+        return Err(format_err!(
+            "verify cannot be used with aws:kms server-side encryption (the etag of the objects is \
+             not computed from their content)"
+        ));
+    }
+    if !args.include.is_empty() {
+        // The objects in the bucket are not listed when using include
+        // patterns, so the options that rely on them cannot be used
+        if args.index_only {
+            return Err(format_err!(
+                "include and index only cannot be used at the same time"
+            ));
+        }
+        if args.prune || args.manifest || args.conditional_index || args.rollback_on_failure {
+            return Err(format_err!(
+                "include cannot be used with prune, manifest, conditional index or rollback on failure"
+            ));
         }
     }
 
     Ok(())
 }
 
-/// Get objects already deployed, returning their key and the creation date of
-/// the object.
+/// Log the prune plan (the stale objects that will be deleted), returning an
+/// error when their number exceeds the prune threshold and the deletion has not
+/// been confirmed. This prevents wiping the bucket when, for example, the
+/// prefix provided is not the right one.
 #[instrument(skip_all, err)]
-async fn get_deployed_objects(
-    s3_client: &aws_sdk_s3::Client,
-    bucket: &str,
-) -> Result<HashMap<Key, DateTime>> {
-    let mut deployed_objects = HashMap::new();
+fn check_prune_plan(args: &S3DeployOptions, stale_keys: &[Key]) -> Result<()> {
+    if stale_keys.is_empty() {
+        info!("prune plan: no stale objects found");
+        return Ok(());
+    }
 
-    // List all objects in the bucket provided, collecting their key and
-    // creation timestamp
-    let mut continuation_token = None;
-    loop {
-        let mut request = s3_client.list_objects_v2().bucket(bucket);
-        if let Some(token) = continuation_token {
-            request = request.continuation_token(token);
-        }
-        let output = request.send().await?;
-        if let Some(objects) = output.contents {
-            for object in objects {
-                let Some(key) = object.key else { continue };
-                let Some(created_at) = object.last_modified else {
-                    continue;
-                };
-                deployed_objects.insert(key, created_at);
-            }
-        }
-        if !output.is_truncated {
-            break;
+    // List the objects to delete (only a sample of them unless in dry run mode)
+    info!(
+        bucket = args.bucket,
+        prefix = get_prefix(args),
+        "prune plan: {} stale objects will be deleted",
+        stale_keys.len()
+    );
+    let sample_size = if args.dry_run {
+        stale_keys.len()
+    } else {
+        PRUNE_PLAN_SAMPLE_SIZE
+    };
+    for key in stale_keys.iter().take(sample_size) {
+        info!(?key, reason = "stale", "object to be deleted");
+    }
+    if stale_keys.len() > sample_size {
+        info!("... and {} more objects", stale_keys.len() - sample_size);
+    }
+
+    // Require confirmation when the number of objects to delete is too high
+    if stale_keys.len() > args.prune_threshold && !args.confirm_prune {
+        let msg = format!(
+            "prune would delete {} objects, which exceeds the prune threshold ({}): please review the prune plan and use --confirm-prune to proceed",
+            stale_keys.len(),
+            args.prune_threshold
+        );
+        if args.dry_run {
+            warn!("{msg}");
+            return Ok(());
         }
-        continuation_token = output.next_continuation_token;
+        return Err(format_err!("{msg}"));
     }
 
-    Ok(deployed_objects)
+    Ok(())
 }
 
-/// Upload landscape website files to S3 bucket. Given that logos filenames are
-/// based on their content, we don't need to upload again existing ones.
+/// Check that the required environment variables have been provided. When a
+/// profile or a role to assume is provided, credentials are resolved by the
+/// AWS config loader, so static credentials are not required.
 #[instrument(skip_all, err)]
-async fn upload_files(
-    s3_client: &aws_sdk_s3::Client,
-    bucket: &str,
-    landscape_dir: &PathBuf,
-    deployed_objects: &HashMap<Key, DateTime>,
-) -> Result<()> {
-    // Upload files in the landscape directory to the bucket provided
-    let results: Vec<Result<()>> = stream::iter(WalkDir::new(landscape_dir))
-        .map(|entry| async {
-            // Check if the entry is a regular file
-            let entry = entry?;
-            if !entry.file_type().is_file() {
-                return Ok(());
-            }
+fn check_env_vars(args: &S3DeployOptions) -> Result<()> {
+    let mut required_env_vars = match (&args.profile, &args.assume_role_arn) {
+        (Some(_), _) => vec![],
+        (None, Some(_)) => vec!["AWS_REGION"],
+        (None, None) => vec!["AWS_REGION", "AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY"],
+    };
+    if args.region.is_some() {
+        required_env_vars.retain(|var| *var != "AWS_REGION");
+    }
 
-            // Prepare object key
-            let file_name = entry.path();
-            let key = file_name
-                .display()
-                .to_string()
-                .trim_start_matches(landscape_dir.display().to_string().as_str())
-                .trim_start_matches('/')
-                .to_string();
+    for var in required_env_vars {
+        if !env::var(var).is_ok_and(|value| !value.is_empty()) {
+            return Err(DeployError::MissingEnvVar(var.to_string()).into());
+        }
+    }
 
-            // We'll upload the index document at the end when all the other
-            // files have been uploaded successfully
-            if key == INDEX_DOCUMENT {
-                return Ok(());
-            }
+    Ok(())
+}
 
-            // Skip files that start with a dot
-            if key.starts_with('.') {
-                return Ok(());
-            }
+/// Delete the stale objects provided (objects deployed previously that are not
+/// present in the landscape directory anymore), returning their keys.
+#[instrument(skip_all, err)]
+async fn delete_stale_objects(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    stale_keys: Vec<Key>,
+) -> Result<Vec<Key>> {
+    // In dry run mode the objects that would be deleted are only listed in the
+    // prune plan
+    if stale_keys.is_empty() || args.dry_run {
+        return Ok(stale_keys);
+    }
 
-            // Skip objects that don't need to be uploaded again
-            if deployed_objects.contains_key(&key) {
-                // Skip already deployed logos (logos filenames are based on
-                // their content, we don't need to upload again existing ones)
-                if key.starts_with(LOGOS_PREFIX) {
-                    return Ok(());
-                }
+    // Delete stale objects in batches
+    let prefix = get_prefix(args);
+    for batch in stale_keys.chunks(DELETE_OBJECTS_BATCH_SIZE) {
+        let objects = batch
+            .iter()
+            .map(|key| ObjectIdentifier::builder().key(format!("{prefix}{key}")).build())
+            .collect();
+        let output = s3_client
+            .delete_objects()
+            .bucket(&args.bucket)
+            .delete(Delete::builder().set_objects(Some(objects)).quiet(true).build())
+            .send()
+            .await
+            .context("error deleting stale objects")?;
 
-                // Skip objects when the remote copy is up to date
-                let local_ts = DateTime::from(fs::metadata(file_name)?.modified()?);
-                let remote_ts = deployed_objects.get(&key).expect("object to exist");
-                if remote_ts >= &local_ts {
-                    return Ok(());
+        // Process errors (if any)
+        if let Some(errors) = output.errors {
+            if !errors.is_empty() {
+                let mut errors_msg = String::new();
+                for err in errors {
+                    let key = err.key.unwrap_or_default();
+                    let message = err.message.unwrap_or_default();
+                    let _ = writeln!(errors_msg, "- error deleting object {key}: {message}");
                 }
+                return Err(format_err!("{errors_msg}"));
             }
+        }
+        for key in batch {
+            debug!(?key, "object deleted");
+        }
+    }
 
-            // Prepare object's body and content type
-            let body = ByteStream::from_path(file_name).await?;
-            let content_type = mime_guess::from_path(&key)
-                .first()
-                .ok_or(format_err!("cannot detect content type of key: {})", &key))?;
+    info!("{} stale objects deleted", stale_keys.len());
+    Ok(stale_keys)
+}
 
-            // Upload file
-            s3_client
-                .put_object()
-                .bucket(bucket)
-                .key(&key)
-                .body(body)
-                .content_type(content_type.essence_str())
-                .send()
-                .await
-                .context(format_err!("error uploading file {}", key))?;
+/// Get objects already deployed, returning their key (relative to the prefix
+/// provided, if any) and some information about them (like their creation
+/// date or ETag).
+///
+/// When the bucket contains more objects than the ones returned in a single
+/// page, the top level prefixes are listed first and then each of them is
+/// paged concurrently (logos, for example, usually account for most of the
+/// objects).
+#[instrument(skip_all, err)]
+async fn get_deployed_objects(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+) -> Result<HashMap<Key, DeployedObject>> {
+    let mut deployed_objects = HashMap::new();
 
-            debug!(?key, "file uploaded");
-            Ok(())
-        })
-        .buffer_unordered(UPLOAD_FILES_CONCURRENCY)
+    // List the first page of objects in the bucket provided (under the prefix
+    // provided). If all objects fit in it, we are done.
+    let prefix = get_prefix(args);
+    let mut request = s3_client.list_objects_v2().bucket(&args.bucket);
+    if !prefix.is_empty() {
+        request = request.prefix(&prefix);
+    }
+    let output = send_with_retries(args.max_retries, || request.clone().send()).await.map_err(|err| {
+        DeployError::Listing {
+            prefix: prefix.clone(),
+            source: err.into(),
+        }
+    })?;
+    if !output.is_truncated {
+        for object in output.contents.unwrap_or_default() {
+            track_deployed_object(&mut deployed_objects, &prefix, object);
+        }
+        return Ok(deployed_objects);
+    }
+
+    // List top level objects and prefixes
+    let (objects, common_prefixes) = list_objects(s3_client, args, &prefix, Some("/")).await?;
+    for object in objects {
+        track_deployed_object(&mut deployed_objects, &prefix, object);
+    }
+
+    // List the objects in each of the top level prefixes concurrently
+    let results: Vec<Result<(Vec<Object>, Vec<String>)>> = stream::iter(&common_prefixes)
+        .map(|common_prefix| list_objects(s3_client, args, common_prefix, None))
+        .buffer_unordered(args.concurrency)
         .collect()
         .await;
-
-    // Process results
-    let mut errors_found = false;
-    let mut errors = String::new();
     for result in results {
-        if let Err(err) = result {
-            errors_found = true;
-            errors.push_str(&format!("- {err:?}\n"));
+        let (objects, _) = result?;
+        for object in objects {
+            track_deployed_object(&mut deployed_objects, &prefix, object);
         }
     }
-    if errors_found {
-        return Err(format_err!("{errors}"));
-    }
+    debug!(prefixes = common_prefixes.len(), "objects listed concurrently");
 
-    Ok(())
+    Ok(deployed_objects)
+}
+
+/// Get the Cache-Control header value for the object key provided. Rules
+/// provided by the user take precedence over the default ones.
+fn get_cache_control(key: &str, rules: &[CacheControlRule]) -> Option<String> {
+    rules
+        .iter()
+        .chain(DEFAULT_CACHE_CONTROL_RULES.iter())
+        .find(|rule| rule.pattern.is_match(key))
+        .map(|rule| rule.value.clone())
+}
+
+/// Get the keys of the website files deployed.
+fn get_deployed_keys(args: &S3DeployOptions) -> Result<Vec<Key>> {
+    let ignore_rules = IgnoreRules::new(&args.landscape_dir, &args.ignore)?;
+    let keys = get_local_keys(&args.landscape_dir)?
+        .into_iter()
+        .filter(|key| is_deployable(key, &ignore_rules))
+        .collect();
+    Ok(keys)
+}
+
+/// Check if the file with the key provided is deployed. Files that start with
+/// a dot and the ones matching the ignore rules are not (logos and the index
+/// document are always deployed).
+fn is_deployable(key: &str, ignore_rules: &IgnoreRules) -> bool {
+    key == INDEX_DOCUMENT
+        || (!key.starts_with('.') && (key.starts_with(LOGOS_PREFIX) || !ignore_rules.is_ignored(key)))
+}
+
+/// Get the include patterns provided, converted into regular expressions.
+fn get_include_patterns(args: &S3DeployOptions) -> Result<Vec<Regex>> {
+    args.include
+        .iter()
+        .map(|glob| glob_to_regex(glob.trim()).context(format!("invalid include pattern: {glob}")))
+        .collect()
 }
 
-/// Upload landscape website index document to S3 bucket.
+/// Get the manifest written by the previous deploy, if available. The manifest
+/// is considered stale (and ignored) when the index document deployed doesn't
+/// match the one recorded in it, as this means that the bucket was modified
+/// by a deploy that didn't write the manifest.
 #[instrument(skip_all, err)]
-async fn upload_index_document(
-    s3_client: &aws_sdk_s3::Client,
-    bucket: &str,
-    landscape_dir: &Path,
-    deployed_objects: &HashMap<Key, DateTime>,
-) -> Result<()> {
-    // Prepare object's key, body and content type
-    let file_name = landscape_dir.join(INDEX_DOCUMENT);
-    let key = INDEX_DOCUMENT.to_string();
-    let body = ByteStream::from_path(&file_name).await?;
-    let content_type = mime::TEXT_HTML.essence_str();
+async fn get_manifest(s3_client: &aws_sdk_s3::Client, args: &S3DeployOptions) -> Result<Option<Manifest>> {
+    let prefix = get_prefix(args);
 
-    // Check if the remote copy is up to date
-    if deployed_objects.contains_key(&key) {
-        let local_ts = DateTime::from(fs::metadata(&file_name)?.modified()?);
-        let remote_ts = deployed_objects.get(&key).expect("object to exist");
-        if remote_ts >= &local_ts {
-            return Ok(());
+    // Get manifest
+    let output = match send_with_retries(args.max_retries, || {
+        s3_client.get_object().bucket(&args.bucket).key(format!("{prefix}{MANIFEST_KEY}")).send()
+    })
+    .await
+    {
+        Ok(output) => output,
+        Err(err) if err.code() == Some("NoSuchKey") => {
+            info!("deploy manifest not found, listing all objects");
+            return Ok(None);
+        }
+        Err(err) => {
+            return Err(DeployError::Listing {
+                prefix,
+                source: err.into(),
+            }
+            .into())
         }
+    };
+    let data = match output.body.collect().await {
+        Ok(data) => data.into_bytes(),
+        Err(err) => {
+            return Err(DeployError::Listing {
+                prefix,
+                source: err.into(),
+            }
+            .into())
+        }
+    };
+    let Ok(manifest) = serde_json::from_slice::<Manifest>(&data) else {
+        warn!("invalid deploy manifest, listing all objects");
+        return Ok(None);
+    };
+
+    // Check the manifest is not stale
+    let output = send_with_retries(args.max_retries, || {
+        s3_client
+            .head_object()
+            .bucket(&args.bucket)
+            .key(format!("{prefix}{INDEX_DOCUMENT}"))
+            .send()
+    })
+    .await;
+    let index_etag =
+        output.ok().and_then(|output| output.e_tag).map(|etag| etag.trim_matches('"').to_string());
+    let manifest_index_etag = manifest.objects.get(INDEX_DOCUMENT).and_then(|entry| entry.etag.clone());
+    if index_etag.is_none() || index_etag != manifest_index_etag {
+        info!("deploy manifest is stale, listing all objects");
+        return Ok(None);
     }
 
-    // Upload file
-    s3_client
-        .put_object()
-        .bucket(bucket)
-        .key(key)
-        .body(body)
-        .content_type(content_type)
+    debug!(objects = manifest.objects.len(), "deploy manifest loaded");
+    Ok(Some(manifest))
+}
+
+/// Get the bucket policy that allows anyone to read the objects in the bucket
+/// provided.
+fn get_public_read_policy(bucket: &str) -> Result<String> {
+    let policy = serde_json::json!({
+        "Version": "2012-10-17",
+        "Statement": [{
+            "Sid": "PublicReadGetObject",
+            "Effect": "Allow",
+            "Principal": "*",
+            "Action": "s3:GetObject",
+            "Resource": format!("arn:aws:s3:::{bucket}/*"),
+        }]
+    });
+    Ok(serde_json::to_string_pretty(&policy)?)
+}
+
+/// Get the prefix that will be prepended to all objects keys. The prefix is
+/// normalized so that it never starts with a slash and always ends with one
+/// (unless it's empty).
+fn get_prefix(args: &S3DeployOptions) -> String {
+    let prefix = args.prefix.as_deref().unwrap_or_default().trim_matches('/');
+    if prefix.is_empty() {
+        return String::new();
+    }
+    format!("{prefix}/")
+}
+
+/// Get the keys of the objects deployed previously that are not present in the
+/// landscape directory anymore, sorted alphabetically. The index document and
+/// the deploy manifest are never considered stale, and neither are the
+/// redirects objects. Objects modified within the prune min age window are
+/// skipped, as they may have been uploaded by a concurrent deploy.
+fn get_stale_keys(
+    args: &S3DeployOptions,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+    redirects: &Redirects,
+) -> Result<Vec<Key>> {
+    let local_keys = get_local_keys(&args.landscape_dir)?;
+    let min_age = i64::try_from(args.prune_min_age.as_secs()).unwrap_or(i64::MAX);
+    let cutoff = Utc::now().timestamp().saturating_sub(min_age);
+    let mut recent = 0;
+    let mut stale_keys: Vec<Key> = deployed_objects
+        .iter()
+        .filter(|(key, _)| {
+            *key != INDEX_DOCUMENT
+                && *key != MANIFEST_KEY
+                && !local_keys.contains(*key)
+                && !redirects.contains(key)
+        })
+        .filter(|(_, object)| {
+            let is_old_enough = object.created_at.secs() <= cutoff;
+            if !is_old_enough {
+                recent += 1;
+            }
+            is_old_enough
+        })
+        .map(|(key, _)| key.clone())
+        .collect();
+    stale_keys.sort();
+
+    if recent > 0 {
+        info!(
+            min_age_secs = args.prune_min_age.as_secs(),
+            "{recent} stale objects modified recently will not be deleted"
+        );
+    }
+
+    Ok(stale_keys)
+}
+
+/// Get the status of the file provided compared to its deployed copy (if any).
+/// Given that logos filenames are based on their content, we don't need to
+/// upload again existing ones.
+///
+/// When comparing by hash is enabled, the MD5 digest of the file is compared
+/// against the ETag of the deployed object. Objects uploaded using multipart
+/// uploads don't use the MD5 digest of their content as ETag, so in that case
+/// we fall back to comparing their timestamps.
+fn get_file_status(
+    args: &S3DeployOptions,
+    header_rules: &HeaderRules,
+    key: &Key,
+    file_name: &Path,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+) -> Result<FileStatus> {
+    let Some(deployed_object) = deployed_objects.get(key) else {
+        return Ok(FileStatus::New);
+    };
+
+    // Objects deployed before the header rules were modified are uploaded
+    // again, so that their headers are updated
+    if header_rules.modified_since(deployed_object) {
+        return Ok(FileStatus::Modified);
+    }
+
+    // Skip already deployed logos
+    if key.starts_with(LOGOS_PREFIX) {
+        return Ok(FileStatus::Logo);
+    }
+
+    // Skip objects when the remote copy is up to date (when the file is
+    // compressed before uploading it, the digest is computed on the compressed
+    // data, which is deterministic for a given input)
+    if args.compare_hash {
+        if let Some(etag) = deployed_object.etag.as_ref().filter(|etag| !etag.contains('-')) {
+            let (body, _) = prepare_body(args, key, file_name)?;
+            let digest = hex::encode(body.digest(0, body.len())?);
+            if &digest == etag {
+                return Ok(FileStatus::UpToDate);
+            }
+            return Ok(FileStatus::Modified);
+        }
+    }
+    let local_ts = DateTime::from(fs::metadata(file_name)?.modified()?);
+    if deployed_object.created_at >= local_ts {
+        return Ok(FileStatus::UpToDate);
+    }
+
+    Ok(FileStatus::Modified)
+}
+
+/// Get the ETag S3 is expected to return for the body provided. The ETag of
+/// objects uploaded in a single request is the MD5 digest of their content,
+/// whereas the ETag of multipart uploads is the MD5 digest of the digests of
+/// their parts, followed by the number of parts.
+fn get_expected_etag(body: &Body, multipart: bool) -> Result<String> {
+    if !multipart {
+        return Ok(hex::encode(body.digest(0, body.len())?));
+    }
+    let mut parts_digests = vec![];
+    let mut parts = 0;
+    for (offset, length) in get_multipart_parts(body.len()) {
+        parts_digests.extend_from_slice(&body.digest(offset, length)?);
+        parts += 1;
+    }
+    Ok(format!("{}-{parts}", hex::encode(Md5::digest(&parts_digests))))
+}
+
+/// Get the version of the file provided (its modification time and size), used
+/// to detect the files modified since they were recorded in the checkpoint.
+fn get_file_version(file_name: &Path) -> Result<String> {
+    let md = fs::metadata(file_name)?;
+    let modified_at = md.modified()?.duration_since(UNIX_EPOCH)?.as_nanos();
+    Ok(format!("{modified_at}-{}", md.len()))
+}
+
+/// Get the size of the parts used to upload a body of the size provided using
+/// a multipart upload.
+fn get_multipart_part_size(body_size: usize) -> usize {
+    MULTIPART_UPLOAD_PART_SIZE.max((body_size + MULTIPART_UPLOAD_MAX_PARTS - 1) / MULTIPART_UPLOAD_MAX_PARTS)
+}
+
+/// Get the offset and length of the parts used to upload a body of the size
+/// provided using a multipart upload.
+fn get_multipart_parts(body_size: usize) -> impl Iterator<Item = (usize, usize)> {
+    let part_size = get_multipart_part_size(body_size);
+    (0..body_size)
+        .step_by(part_size)
+        .map(move |offset| (offset, part_size.min(body_size - offset)))
+}
+
+/// Get the delay to wait before the retry attempt provided. The delay grows
+/// exponentially on each attempt, and includes some jitter to avoid retrying
+/// many requests at the same time.
+fn get_retry_delay(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)).min(RETRY_MAX_DELAY);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    let jitter = (delay / 2).mul_f64(f64::from(nanos % 1000) / 1000.0);
+    delay / 2 + jitter
+}
+
+/// Return the deploy options of each of the targets the landscape website will
+/// be deployed to (the bucket provided is the first one). Targets use the same
+/// options, except for the bucket, region and CloudFront distribution.
+fn get_targets(args: &S3DeployOptions) -> Result<Vec<S3DeployOptions>> {
+    let primary = S3Target {
+        bucket: args.bucket.clone(),
+        region: args.region.clone(),
+        cloudfront_distribution_id: args.cloudfront_distribution_id.clone(),
+    };
+
+    let mut targets: Vec<S3DeployOptions> = vec![];
+    for target in std::iter::once(&primary).chain(&args.target) {
+        if targets.iter().any(|t| t.bucket == target.bucket) {
+            return Err(format_err!("duplicate target bucket: {}", target.bucket));
+        }
+
+        // Each target writes its own summary and metrics, and the progress bar
+        // is not displayed as multiple uploads are running at the same time
+        let summary_json = args.summary_json.as_ref().map(|summary_json| {
+            let stem = summary_json.file_stem().unwrap_or_default().to_string_lossy();
+            let mut file_name = format!("{stem}-{}", target.bucket);
+            if let Some(extension) = summary_json.extension() {
+                let _ = write!(file_name, ".{}", extension.to_string_lossy());
+            }
+            summary_json.with_file_name(file_name)
+        });
+        targets.push(S3DeployOptions {
+            archive: None,
+            bucket: target.bucket.clone(),
+            cloudfront_distribution_id: target.cloudfront_distribution_id.clone(),
+            metrics_file: args.metrics_file.as_ref().map(|file| metrics_file_for(file, &target.bucket)),
+            quiet: true,
+            region: target.region.clone().or_else(|| args.region.clone()),
+            summary_json,
+            target: vec![],
+            ..args.clone()
+        });
+    }
+
+    Ok(targets)
+}
+
+/// Invalidate the paths that changed during the deploy in the CloudFront
+/// distribution provided. When too many paths have changed, all the paths in
+/// the distribution are invalidated instead.
+#[instrument(skip_all, err)]
+async fn invalidate_cloudfront_paths(
+    cloudfront_client: &aws_sdk_cloudfront::Client,
+    args: &S3DeployOptions,
+    distribution_id: &str,
+    summary: &Summary,
+) -> Result<()> {
+    // Prepare paths to invalidate
+    let prefix = get_prefix(args);
+    let mut paths: Vec<String> = summary
+        .uploaded
+        .iter()
+        .chain(summary.deleted.iter())
+        .map(|key| format!("/{prefix}{key}"))
+        .collect();
+    if paths.is_empty() {
+        info!("no objects changed: cloudfront invalidation not needed");
+        return Ok(());
+    }
+    if summary.uploaded.iter().any(|key| key == INDEX_DOCUMENT) {
+        paths.push(format!("/{prefix}"));
+    }
+    if paths.len() > CLOUDFRONT_MAX_INVALIDATION_PATHS {
+        paths = vec![format!("/{prefix}*")];
+    }
+
+    // In dry run mode we only log the paths that would be invalidated
+    if args.dry_run {
+        info!(?distribution_id, ?paths, "cloudfront paths would be invalidated");
+        return Ok(());
+    }
+
+    // Create invalidation
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let quantity = paths.len() as i32;
+    let invalidation_batch = InvalidationBatch::builder()
+        .caller_reference(format!("landscape2-{}", Utc::now().timestamp_millis()))
+        .paths(Paths::builder().quantity(quantity).set_items(Some(paths)).build())
+        .build();
+    let output = cloudfront_client
+        .create_invalidation()
+        .distribution_id(distribution_id)
+        .invalidation_batch(invalidation_batch)
         .send()
         .await
-        .context("error uploading index document")?;
+        .map_err(|err| {
+            if err.code() == Some("AccessDenied") {
+                format_err!(
+                    "error creating cloudfront invalidation: access denied (please make sure the \
+                     credentials provided have the cloudfront:CreateInvalidation permission)"
+                )
+            } else {
+                format_err!("error creating cloudfront invalidation: {err}")
+            }
+        })?;
+
+    let invalidation_id = output.invalidation().and_then(|i| i.id()).unwrap_or_default();
+    info!(
+        ?distribution_id,
+        ?invalidation_id,
+        "cloudfront invalidation created"
+    );
+    Ok(())
+}
+
+/// Check if the key provided matches any of the include patterns provided
+/// (all keys are included when no patterns are provided).
+fn is_included(include: &[Regex], key: &str) -> bool {
+    include.is_empty() || include.iter().any(|pattern| pattern.is_match(key))
+}
+
+/// Check if the error provided is worth retrying (i.e. throttling errors,
+/// server errors or network issues).
+fn is_retryable<E: ProvideErrorMetadata>(err: &SdkError<E>) -> bool {
+    match err {
+        SdkError::DispatchFailure(_) | SdkError::ResponseError(_) | SdkError::TimeoutError(_) => true,
+        SdkError::ServiceError(_) => {
+            if matches!(
+                err.code(),
+                Some("RequestTimeout" | "SlowDown" | "Throttling" | "ThrottlingException")
+            ) {
+                return true;
+            }
+            err.raw_response().is_some_and(|resp| {
+                let status = resp.status().as_u16();
+                status == 429 || status >= 500
+            })
+        }
+        _ => false,
+    }
+}
+
+/// List all the objects in the bucket starting with the prefix provided,
+/// returning them along with the common prefixes found (only when a delimiter
+/// is provided).
+async fn list_objects(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    prefix: &str,
+    delimiter: Option<&str>,
+) -> Result<(Vec<Object>, Vec<String>)> {
+    let mut objects = vec![];
+    let mut common_prefixes = vec![];
+
+    let mut continuation_token = None;
+    loop {
+        let mut request = s3_client.list_objects_v2().bucket(&args.bucket);
+        if !prefix.is_empty() {
+            request = request.prefix(prefix);
+        }
+        if let Some(delimiter) = delimiter {
+            request = request.delimiter(delimiter);
+        }
+        if let Some(token) = continuation_token {
+            request = request.continuation_token(token);
+        }
+        let output = send_with_retries(args.max_retries, || request.clone().send()).await.map_err(|err| {
+            DeployError::Listing {
+                prefix: prefix.to_string(),
+                source: err.into(),
+            }
+        })?;
+        objects.extend(output.contents.unwrap_or_default());
+        common_prefixes
+            .extend(output.common_prefixes.unwrap_or_default().into_iter().filter_map(|p| p.prefix));
+        if !output.is_truncated {
+            break;
+        }
+
+        // Some S3 compatible services may report truncated results without
+        // providing a continuation token, which would make us loop forever
+        if output.next_continuation_token.is_none() {
+            warn!(
+                ?prefix,
+                "listing truncated but no continuation token returned, stopping"
+            );
+            break;
+        }
+        continuation_token = output.next_continuation_token;
+    }
+
+    Ok((objects, common_prefixes))
+}
+
+/// Load the AWS configuration from the environment, using the named profile
+/// provided (if any). When a role to assume is provided, the credentials
+/// loaded are used to assume it and the resulting temporary credentials will
+/// be used by the clients.
+async fn load_aws_config(args: &S3DeployOptions) -> SdkConfig {
+    let mut loader = aws_config::from_env();
+    if let Some(profile) = &args.profile {
+        loader = loader.profile_name(profile);
+    }
+    if let Some(region) = &args.region {
+        loader = loader.region(Region::new(region.clone()));
+    }
+    let config = loader.load().await;
+
+    let (Some(role_arn), Some(credentials_provider)) = (&args.assume_role_arn, config.credentials_provider())
+    else {
+        return config;
+    };
+    let mut builder =
+        AssumeRoleProvider::builder(role_arn).session_name(format!("landscape2-{}", Utc::now().timestamp()));
+    if let Some(region) = config.region() {
+        builder = builder.region(region.clone());
+    }
+    let provider = builder.build(credentials_provider);
+    let mut loader = aws_config::from_env().credentials_provider(provider);
+    if let Some(profile) = &args.profile {
+        loader = loader.profile_name(profile);
+    }
+    if let Some(region) = &args.region {
+        loader = loader.region(Region::new(region.clone()));
+    }
+    loader.load().await
+}
+
+/// Create a new AWS S3 client from the configuration provided. A custom
+/// endpoint can be used to deploy to S3 compatible services, like MinIO or
+/// Cloudflare R2.
+fn new_s3_client(args: &S3DeployOptions, config: &SdkConfig) -> aws_sdk_s3::Client {
+    let mut s3_config = aws_sdk_s3::config::Builder::from(config);
+    if let Some(endpoint_url) = &args.endpoint_url {
+        s3_config = s3_config.endpoint_url(endpoint_url);
+    }
+    if args.force_path_style {
+        s3_config = s3_config.force_path_style(true);
+    }
+    if let Some(request_timeout) = args.request_timeout {
+        // The timeout applies to each attempt, so requests timing out are
+        // retried like the ones failing for any other retryable error
+        let timeout_config = TimeoutConfig::builder()
+            .operation_attempt_timeout(Duration::from_secs(request_timeout))
+            .build();
+        s3_config = s3_config.timeout_config(timeout_config);
+    }
+    aws_sdk_s3::Client::from_conf(s3_config.build())
+}
+
+/// Get the compression algorithm that should be used to compress the file of
+/// the size provided, if any (only when compression is enabled and the file is
+/// worth compressing).
+fn get_compression(args: &S3DeployOptions, key: &str, size: usize) -> Option<Compression> {
+    let compression = args.compress?;
+    let compressible = Path::new(key)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| COMPRESSIBLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+    (compressible && size >= args.compress_min_size).then_some(compression)
+}
+
+/// Prepare the body of the object for the file provided, compressing it when
+/// compression is enabled and the file is worth compressing. The content
+/// encoding used to compress the body (if any) is returned along with it.
+/// Files that are not compressed are streamed from disk when uploaded, so
+/// only compressed bodies are kept in memory.
+///
+/// A single object is uploaded per file, using its original key, so the
+/// content encoding must only be returned when the body is actually
+/// compressed (otherwise browsers would get garbage). To ensure this, the
+/// compressed body is decoded and compared to the original content, and the
+/// original content is used when compressing it doesn't make it smaller.
+fn prepare_body(args: &S3DeployOptions, key: &str, file_name: &Path) -> Result<(Body, Option<&'static str>)> {
+    let size = usize::try_from(fs::metadata(file_name)?.len())?;
+    let file = Body::File(file_name.to_path_buf(), size);
+
+    // Check if the file should be compressed
+    let Some(compression) = get_compression(args, key, size) else {
+        return Ok((file, None));
+    };
+
+    // Compress data using the algorithm selected
+    let data = fs::read(file_name)?;
+    let compressed_data = compression.compress(&data)?;
+    if compression.decompress(&compressed_data)? != data {
+        return Err(format_err!(
+            "compressed body of {key} does not match the original content"
+        ));
+    }
+    if compressed_data.len() >= data.len() {
+        debug!(?key, "compressed body is not smaller, uploading original content");
+        return Ok((file, None));
+    }
+
+    Ok((
+        Body::Bytes(Bytes::from(compressed_data)),
+        Some(compression.content_encoding()),
+    ))
+}
+
+/// Upload the manifest provided to the S3 bucket.
+#[instrument(skip_all, err)]
+async fn put_manifest(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    manifest: &Manifest,
+) -> Result<()> {
+    let body = Bytes::from(serde_json::to_vec(manifest)?);
+    send_with_retries(args.max_retries, || {
+        s3_client
+            .put_object()
+            .bucket(&args.bucket)
+            .key(format!("{}{MANIFEST_KEY}", get_prefix(args)))
+            .body(ByteStream::from(body.clone()))
+            .content_type(mime::APPLICATION_JSON.essence_str())
+            .cache_control("no-cache")
+            .set_server_side_encryption(args.sse.map(Into::into))
+            .set_ssekms_key_id(args.sse_kms_key_id.clone())
+            .send()
+    })
+    .await
+    .map_err(|err| DeployError::Upload {
+        key: MANIFEST_KEY.to_string(),
+        source: err.into(),
+    })?;
+
+    debug!(objects = manifest.objects.len(), "deploy manifest uploaded");
+    Ok(())
+}
+
+/// Create the redirects objects provided in the S3 bucket. They don't have any
+/// content, and redirect the requests to them to the new location. The items
+/// permalinks redirects are uploaded as a JSON map used by the web application.
+/// The ETags of the objects created are returned.
+#[instrument(skip_all, err)]
+async fn put_redirects(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    redirects: &Redirects,
+) -> Result<Vec<(Key, Option<String>)>> {
+    let prefix = get_prefix(args);
+    let mut etags: Vec<Option<(Key, Option<String>)>> = stream::iter(&redirects.redirects)
+        .map(|(key, location)| {
+            let prefix = &prefix;
+            async move {
+                if args.dry_run {
+                    info!(?key, location, "redirect would be created");
+                    return Ok(None);
+                }
+
+                let output = send_with_retries(args.max_retries, || {
+                    s3_client
+                        .put_object()
+                        .bucket(&args.bucket)
+                        .key(format!("{prefix}{key}"))
+                        .body(ByteStream::from(Vec::new()))
+                        .content_type(mime::TEXT_HTML.essence_str())
+                        .cache_control("no-cache")
+                        .website_redirect_location(location)
+                        .set_server_side_encryption(args.sse.map(Into::into))
+                        .set_ssekms_key_id(args.sse_kms_key_id.clone())
+                        .send()
+                })
+                .await
+                .map_err(|err| DeployError::Upload {
+                    key: key.clone(),
+                    source: err.into(),
+                })?;
+
+                debug!(?key, location, "redirect created");
+                let etag = output.e_tag().map(|etag| etag.trim_matches('"').to_string());
+                Ok::<_, anyhow::Error>(Some((key.clone(), etag)))
+            }
+        })
+        .buffer_unordered(args.concurrency)
+        .try_collect()
+        .await?;
+
+    // Items permalinks redirects
+    if !redirects.items.is_empty() {
+        let key = ITEMS_REDIRECTS_KEY.to_string();
+        if args.dry_run {
+            info!(
+                ?key,
+                items = redirects.items.len(),
+                "items redirects would be uploaded"
+            );
+        } else {
+            let body = Bytes::from(serde_json::to_vec(&redirects.items)?);
+            let output = send_with_retries(args.max_retries, || {
+                s3_client
+                    .put_object()
+                    .bucket(&args.bucket)
+                    .key(format!("{prefix}{key}"))
+                    .body(ByteStream::from(body.clone()))
+                    .content_type(mime::APPLICATION_JSON.essence_str())
+                    .cache_control("no-cache")
+                    .set_server_side_encryption(args.sse.map(Into::into))
+                    .set_ssekms_key_id(args.sse_kms_key_id.clone())
+                    .send()
+            })
+            .await
+            .map_err(|err| DeployError::Upload {
+                key: key.clone(),
+                source: err.into(),
+            })?;
+
+            debug!(?key, items = redirects.items.len(), "items redirects uploaded");
+            let etag = output.e_tag().map(|etag| etag.trim_matches('"').to_string());
+            etags.push(Some((key, etag)));
+        }
+    }
+
+    info!(
+        redirects = redirects.redirects.len(),
+        items_redirects = redirects.items.len(),
+        "redirects created"
+    );
+    Ok(etags.into_iter().flatten().collect())
+}
+
+/// Upload the file provided to the S3 bucket using the key and content type
+/// provided, returning the number of bytes transferred and the ETag of the
+/// object. When a precondition is provided, the object is only overwritten if
+/// it is met.
+#[allow(clippy::too_many_arguments)]
+async fn put_object(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    header_rules: &HeaderRules,
+    provenance: &Provenance,
+    key: &str,
+    file_name: &Path,
+    content_type: &str,
+    precondition: Option<&Precondition>,
+) -> Result<UploadOutput> {
+    let (body, content_encoding) = prepare_body(args, key, file_name)?;
+    let bytes = body.len() as u64;
+    let object_key = format!("{}{key}", get_prefix(args));
+
+    // Headers stored as user metadata cannot override the provenance ones
+    let mut headers = header_rules.get(key);
+    let content_disposition = headers.remove("content-disposition");
+    let content_language = headers.remove("content-language");
+    let mut metadata: HashMap<String, String> = headers
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.strip_prefix("x-amz-meta-")?.to_string(), value)))
+        .collect();
+    metadata.extend(provenance.metadata.clone());
+
+    let attrs = ObjectAttributes {
+        cache_control: get_cache_control(key, &args.cache_control),
+        content_disposition,
+        content_encoding: content_encoding.map(ToString::to_string),
+        content_language,
+        content_type: content_type.to_string(),
+        metadata,
+        server_side_encryption: args.sse,
+        ssekms_key_id: args.sse_kms_key_id.clone(),
+        tagging: provenance.tagging.clone(),
+    };
+
+    // Large files are uploaded using a multipart upload (conditional uploads
+    // are always sent in a single request)
+    if body.len() > args.multipart_threshold && precondition.is_none() {
+        let etag = put_object_multipart(s3_client, args, &object_key, &body, &attrs).await?;
+        let expected_etag = args.verify.then(|| get_expected_etag(&body, true)).transpose()?;
+        return Ok(UploadOutput {
+            bytes,
+            etag,
+            expected_etag,
+        });
+    }
+
+    let header = precondition.map(Precondition::header).transpose()?;
+    let output = send_with_retries(args.max_retries, || async {
+        let header = header.clone();
+        let stream = body.stream(0, body.len()).await.map_err(SdkError::construction_failure)?;
+        let request = s3_client
+            .put_object()
+            .bucket(&args.bucket)
+            .key(&object_key)
+            .body(stream)
+            .content_type(&attrs.content_type)
+            .set_cache_control(attrs.cache_control.clone())
+            .set_content_disposition(attrs.content_disposition.clone())
+            .set_content_encoding(attrs.content_encoding.clone())
+            .set_content_language(attrs.content_language.clone())
+            .set_metadata(Some(attrs.metadata.clone()))
+            .set_server_side_encryption(attrs.server_side_encryption.map(Into::into))
+            .set_ssekms_key_id(attrs.ssekms_key_id.clone())
+            .set_tagging(attrs.tagging.clone());
+        let operation = request.customize().await.map_err(SdkError::construction_failure)?;
+        operation
+            .mutate_request(move |req| {
+                if let Some((name, value)) = &header {
+                    req.headers_mut().insert(*name, value.clone());
+                }
+            })
+            .send()
+            .await
+    })
+    .await
+    .map_err(|err| {
+        if precondition.is_some() && Precondition::is_failure(&err) {
+            return format_err!(
+                "bucket changed during deploy ({key} was modified by another deploy), please re-run it"
+            );
+        }
+        err.into()
+    })?;
+    let etag = output.e_tag().map(|etag| etag.trim_matches('"').to_string());
+    let expected_etag = args.verify.then(|| get_expected_etag(&body, false)).transpose()?;
+
+    Ok(UploadOutput {
+        bytes,
+        etag,
+        expected_etag,
+    })
+}
+
+/// Upload the body provided to the S3 bucket using a multipart upload. Parts
+/// are uploaded concurrently, and the multipart upload is aborted if any of
+/// them cannot be uploaded, so that no orphan parts are left in the bucket.
+/// Parts are read from the body on each attempt (streamed from disk, or sliced
+/// from the shared buffer), so they are never copied. The ETag of the object
+/// is returned on success.
+async fn put_object_multipart(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    object_key: &str,
+    body: &Body,
+    attrs: &ObjectAttributes,
+) -> Result<Option<String>> {
+    // Create multipart upload
+    let output = send_with_retries(args.max_retries, || {
+        s3_client
+            .create_multipart_upload()
+            .bucket(&args.bucket)
+            .key(object_key)
+            .content_type(&attrs.content_type)
+            .set_cache_control(attrs.cache_control.clone())
+            .set_content_disposition(attrs.content_disposition.clone())
+            .set_content_encoding(attrs.content_encoding.clone())
+            .set_content_language(attrs.content_language.clone())
+            .set_metadata(Some(attrs.metadata.clone()))
+            .set_server_side_encryption(attrs.server_side_encryption.map(Into::into))
+            .set_ssekms_key_id(attrs.ssekms_key_id.clone())
+            .set_tagging(attrs.tagging.clone())
+            .send()
+    })
+    .await?;
+    let upload_id = output.upload_id().ok_or(format_err!("multipart upload id not returned"))?;
+
+    // Upload parts and complete the multipart upload
+    let result: Result<Option<String>> = async {
+        let parts: Vec<Result<CompletedPart>> = stream::iter(get_multipart_parts(body.len()).enumerate())
+            .map(|(i, (offset, length))| async move {
+                let part_number = i32::try_from(i + 1)?;
+                let output = send_with_retries(args.max_retries, || async {
+                    let stream = body.stream(offset, length).await.map_err(SdkError::construction_failure)?;
+                    s3_client
+                        .upload_part()
+                        .bucket(&args.bucket)
+                        .key(object_key)
+                        .upload_id(upload_id)
+                        .part_number(part_number)
+                        .body(stream)
+                        .send()
+                        .await
+                })
+                .await?;
+                Ok(CompletedPart::builder()
+                    .set_e_tag(output.e_tag().map(ToString::to_string))
+                    .part_number(part_number)
+                    .build())
+            })
+            .buffered(MULTIPART_UPLOAD_CONCURRENCY)
+            .collect()
+            .await;
+        let parts = parts.into_iter().collect::<Result<Vec<_>>>()?;
+        debug!(
+            key = object_key,
+            parts = parts.len(),
+            "multipart upload parts uploaded"
+        );
+
+        let output = send_with_retries(args.max_retries, || {
+            s3_client
+                .complete_multipart_upload()
+                .bucket(&args.bucket)
+                .key(object_key)
+                .upload_id(upload_id)
+                .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts.clone())).build())
+                .send()
+        })
+        .await?;
+
+        Ok(output.e_tag().map(|etag| etag.trim_matches('"').to_string()))
+    }
+    .await;
+
+    // Abort the multipart upload if something went wrong
+    if result.is_err() {
+        if let Err(err) = s3_client
+            .abort_multipart_upload()
+            .bucket(&args.bucket)
+            .key(object_key)
+            .upload_id(upload_id)
+            .send()
+            .await
+        {
+            warn!(
+                key = object_key,
+                ?upload_id,
+                "error aborting multipart upload: {err}"
+            );
+        }
+    }
+
+    result
+}
+
+/// Find the version of the object provided that was deployed previously (the
+/// newest one not created after the deployed object). Versions are listed
+/// page by page (from newest to oldest), stopping once it's found.
+async fn find_previous_version(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    key: &str,
+    object_key: &str,
+    deployed_object: &DeployedObject,
+) -> Result<Option<String>> {
+    let mut key_marker = None;
+    let mut version_id_marker = None;
+    loop {
+        let output = s3_client
+            .list_object_versions()
+            .bucket(&args.bucket)
+            .prefix(object_key)
+            .set_key_marker(key_marker)
+            .set_version_id_marker(version_id_marker)
+            .send()
+            .await
+            .context(format!("error listing versions of object {key}"))?;
+        let previous_version_id = output.versions.unwrap_or_default().into_iter().find_map(|version| {
+            if version.key.as_deref() == Some(object_key)
+                && version.last_modified.is_some_and(|ts| ts <= deployed_object.created_at)
+            {
+                version.version_id
+            } else {
+                None
+            }
+        });
+        if previous_version_id.is_some() || !output.is_truncated {
+            return Ok(previous_version_id);
+        }
+
+        // Some S3 compatible services may report truncated results without
+        // providing the markers, which would make us loop forever
+        if output.next_key_marker.is_none() && output.next_version_id_marker.is_none() {
+            warn!(
+                ?key,
+                "versions listing truncated but no markers returned, stopping"
+            );
+            return Ok(None);
+        }
+        key_marker = output.next_key_marker;
+        version_id_marker = output.next_version_id_marker;
+    }
+}
+
+/// Roll back the objects provided to the version deployed before the current
+/// run. Objects that didn't exist previously are deleted. Restoring previous
+/// versions requires versioning to be enabled in the bucket.
+#[instrument(skip_all, err)]
+async fn rollback_objects(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+    keys: &[Key],
+) -> Result<()> {
+    info!("rolling back {} objects uploaded..", keys.len());
+
+    let prefix = get_prefix(args);
+    for key in keys {
+        let object_key = format!("{prefix}{key}");
+
+        // Delete objects that didn't exist before
+        let Some(deployed_object) = deployed_objects.get(key) else {
+            s3_client
+                .delete_object()
+                .bucket(&args.bucket)
+                .key(&object_key)
+                .send()
+                .await
+                .context(format!("error deleting object {key}"))?;
+            debug!(?key, "object deleted");
+            continue;
+        };
+
+        // Restore the version deployed previously
+        let previous_version_id =
+            find_previous_version(s3_client, args, key, &object_key, deployed_object).await?;
+        let Some(version_id) = previous_version_id else {
+            warn!(
+                ?key,
+                "previous version not found (is versioning enabled in the bucket?)"
+            );
+            continue;
+        };
+        let encoded_key = form_urlencoded::byte_serialize(object_key.as_bytes())
+            .collect::<String>()
+            .replace('+', "%20");
+        s3_client
+            .copy_object()
+            .bucket(&args.bucket)
+            .key(&object_key)
+            .copy_source(format!("{}/{encoded_key}?versionId={version_id}", args.bucket))
+            .set_server_side_encryption(args.sse.map(Into::into))
+            .set_ssekms_key_id(args.sse_kms_key_id.clone())
+            .send()
+            .await
+            .context(format!("error restoring previous version of object {key}"))?;
+        debug!(?key, ?version_id, "object restored");
+    }
 
-    debug!("index document uploaded");
+    info!("rollback completed");
     Ok(())
 }
+
+/// Send the request provided, retrying it using an exponential backoff when it
+/// fails with a retryable error (up to `max_retries` times).
+async fn send_with_retries<T, E, F, Fut>(max_retries: u32, send: F) -> Result<T, SdkError<E>>
+where
+    E: ProvideErrorMetadata,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E>>>,
+{
+    let mut attempt = 0;
+    loop {
+        match send().await {
+            Ok(output) => return Ok(output),
+            Err(err) if attempt < max_retries && is_retryable(&err) => {
+                let delay = get_retry_delay(attempt);
+                attempt += 1;
+                warn!(
+                    ?attempt,
+                    ?delay,
+                    code = err.code().unwrap_or_default(),
+                    "request failed, retrying"
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Track the object provided in the deployed objects collection, using its
+/// key relative to the prefix provided.
+fn track_deployed_object(deployed_objects: &mut HashMap<Key, DeployedObject>, prefix: &str, object: Object) {
+    let Some(key) = object.key.as_ref().and_then(|key| key.strip_prefix(prefix)) else {
+        return;
+    };
+    let key = key.to_string();
+    let Some(created_at) = object.last_modified else {
+        return;
+    };
+    let etag = object.e_tag.map(|etag| etag.trim_matches('"').to_string());
+    deployed_objects.insert(key, DeployedObject { created_at, etag });
+}
+
+/// Upload landscape website files to S3 bucket, returning the status of each
+/// of the files processed and the output of the upload (if any). When the
+/// deadline provided is exceeded, no new files are processed and an error is
+/// returned once the uploads in flight complete.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, err)]
+async fn upload_files(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    include: &[Regex],
+    content_types: &ContentTypes,
+    header_rules: &HeaderRules,
+    provenance: &Provenance,
+    checkpoint: &Checkpoint,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+    deadline: Option<Instant>,
+) -> Result<Vec<(Key, FileStatus, UploadOutput)>> {
+    // Load ignore rules
+    let landscape_dir = &args.landscape_dir;
+    let ignore_rules = IgnoreRules::new(landscape_dir, &args.ignore)?;
+
+    // Setup the in-flight bytes budget (when provided). Each file takes as
+    // many permits as bytes are buffered in memory to upload it (files larger
+    // than the budget take all of them, so they are uploaded alone). Files
+    // that are not compressed are streamed from disk, so they don't take any.
+    // When the budget is used, the number of files uploaded concurrently is
+    // only bounded by it.
+    let budget = match args.max_in_flight_bytes {
+        Some(bytes) => {
+            let permits = u32::try_from(bytes).unwrap_or(u32::MAX);
+            Some((permits, Semaphore::new(usize::try_from(permits)?)))
+        }
+        None => None,
+    };
+    let concurrency = if budget.is_some() {
+        MAX_IN_FLIGHT_FILES
+    } else {
+        args.concurrency
+    };
+
+    // Keys of the files whose upload has started (used to know which objects
+    // may have been uploaded when the uploads in flight are cancelled)
+    let started: Mutex<Vec<Key>> = Mutex::new(vec![]);
+
+    // Number of files not processed because the deadline was exceeded
+    let remaining = AtomicUsize::new(0);
+
+    // Setup upload progress bar (only in interactive deploys)
+    let files = WalkDir::new(landscape_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| get_object_key(landscape_dir, entry.path()))
+        .filter(|key| key != INDEX_DOCUMENT && is_included(include, key) && is_deployable(key, &ignore_rules))
+        .count();
+    let enabled = !args.quiet && !args.dry_run && io::stdout().is_terminal();
+    let mut progress = UploadProgress::new(files as u64, enabled);
+
+    // Upload files in the landscape directory to the bucket provided
+    let mut uploads = stream::iter(WalkDir::new(landscape_dir))
+        .map(|entry| async {
+            // Check if the entry is a regular file
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                return Ok(None);
+            }
+
+            // Prepare object key
+            let file_name = entry.path();
+            let key = get_object_key(landscape_dir, file_name);
+
+            // We'll upload the index document at the end when all the other
+            // files have been uploaded successfully
+            if key == INDEX_DOCUMENT {
+                return Ok(None);
+            }
+
+            // Skip files that are not deployed (i.e. matching the ignore rules)
+            if !is_deployable(&key, &ignore_rules) {
+                debug!(?key, "file skipped (not deployable)");
+                return Ok(None);
+            }
+
+            // Skip files not matching the include patterns (if any)
+            if !is_included(include, &key) {
+                return Ok(None);
+            }
+
+            // Don't start processing new files once the deadline is exceeded
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                remaining.fetch_add(1, Ordering::Relaxed);
+                return Ok(None);
+            }
+
+            // Wait until the file fits in the in-flight bytes budget (files
+            // compressed are read in memory, also to check their status)
+            let _permit = match &budget {
+                Some((max_permits, semaphore)) => {
+                    let size = usize::try_from(fs::metadata(file_name)?.len())?;
+                    let buffered = get_compression(args, &key, size).map_or(0, |_| size);
+                    let buffered = u32::try_from(buffered).unwrap_or(u32::MAX);
+                    Some(semaphore.acquire_many(buffered.min(*max_permits)).await?)
+                }
+                None => None,
+            };
+
+            // Skip objects that don't need to be uploaded again
+            let status = get_file_status(args, header_rules, &key, file_name, deployed_objects)?;
+            if !status.needs_upload() {
+                if args.dry_run {
+                    info!(?key, reason = %status, "file would be skipped");
+                }
+                return Ok(Some((key, status, UploadOutput::default())));
+            }
+
+            // Files uploaded before the deploy being resumed was interrupted
+            // are not uploaded again (they are still reported as uploaded)
+            if checkpoint.contains(&key, file_name)? {
+                debug!(
+                    ?key,
+                    "file uploaded before the deploy was interrupted, skipping it"
+                );
+                return Ok(Some((key, status, UploadOutput::default())));
+            }
+
+            // Prepare object's content type
+            let content_type = content_types.get(&key);
+
+            // In dry run mode we only log the files that would be uploaded
+            if args.dry_run {
+                info!(?key, reason = %status, "file would be uploaded");
+                let bytes = fs::metadata(file_name)?.len();
+                return Ok(Some((
+                    key,
+                    status,
+                    UploadOutput {
+                        bytes,
+                        ..Default::default()
+                    },
+                )));
+            }
+
+            // Upload file
+            started.lock().expect("started lock not to be poisoned").push(key.clone());
+            let upload_start = Instant::now();
+            let output = put_object(
+                s3_client,
+                args,
+                header_rules,
+                provenance,
+                &key,
+                file_name,
+                &content_type,
+                None,
+            )
+            .await
+            .map_err(|err| DeployError::Upload {
+                key: key.clone(),
+                source: err,
+            })?;
+            checkpoint.record(&key, file_name)?;
+
+            debug!(
+                ?key,
+                bytes = output.bytes,
+                content_type,
+                elapsed_ms = upload_start.elapsed().as_millis(),
+                "file uploaded"
+            );
+            Ok::<_, anyhow::Error>(Some((key, status, output)))
+        })
+        .buffer_unordered(concurrency);
+
+    // Process results as the uploads complete. Requests failing with a
+    // retryable error have been retried already, so as soon as an upload
+    // fails the stream is dropped, cancelling the uploads in flight
+    let mut files_status = vec![];
+    while let Some(result) = uploads.next().await {
+        match result {
+            Ok(Some(file_status)) => {
+                progress.inc(file_status.2.bytes);
+                files_status.push(file_status);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                drop(uploads);
+                progress.abandon();
+
+                // Uploads cancelled may have been completed by S3, so they are
+                // reported as uploaded too (rolling back an object that was
+                // not uploaded is harmless)
+                let mut uploaded: Vec<Key> = started.into_inner().expect("started lock not to be poisoned");
+                uploaded.sort();
+                uploaded.dedup();
+                return Err(UploadError {
+                    uploaded,
+                    source: err,
+                }
+                .into());
+            }
+        }
+    }
+
+    // Stop the deploy when some files were not processed because the
+    // deadline was exceeded
+    let remaining = remaining.load(Ordering::Relaxed);
+    if remaining > 0 {
+        progress.abandon();
+        let uploaded: Vec<Key> = files_status
+            .iter()
+            .filter(|(_, status, _)| status.needs_upload())
+            .map(|(key, _, _)| key.clone())
+            .collect();
+        return Err(UploadError {
+            source: DeployError::DeadlineExceeded {
+                uploaded: uploaded.len(),
+                remaining,
+            }
+            .into(),
+            uploaded,
+        }
+        .into());
+    }
+
+    progress.finish();
+
+    Ok(files_status)
+}
+
+/// Upload landscape website index document to S3 bucket, returning its status
+/// and the output of the upload (if any).
+#[instrument(skip_all, err)]
+async fn upload_index_document(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    content_types: &ContentTypes,
+    header_rules: &HeaderRules,
+    provenance: &Provenance,
+    deployed_objects: &HashMap<Key, DeployedObject>,
+) -> Result<(FileStatus, UploadOutput)> {
+    // Prepare object's key and content type
+    let file_name = args.landscape_dir.join(INDEX_DOCUMENT);
+    let key = INDEX_DOCUMENT.to_string();
+    let content_type = content_types.get(&key);
+
+    // Check if the remote copy is up to date
+    let status = get_file_status(args, header_rules, &key, &file_name, deployed_objects)?;
+    if !status.needs_upload() {
+        if args.dry_run {
+            info!(?key, reason = %status, "index document would be skipped");
+        }
+        return Ok((status, UploadOutput::default()));
+    }
+
+    // In dry run mode we only log that the index document would be uploaded
+    if args.dry_run {
+        info!(?key, reason = %status, "index document would be uploaded");
+        let bytes = fs::metadata(&file_name)?.len();
+        return Ok((
+            status,
+            UploadOutput {
+                bytes,
+                ..Default::default()
+            },
+        ));
+    }
+
+    // Upload file (only if it hasn't changed since it was listed, when
+    // conditional uploads are enabled)
+    let precondition = if args.conditional_index {
+        match deployed_objects.get(&key) {
+            Some(DeployedObject { etag: Some(etag), .. }) => Some(Precondition::IfMatch(etag.clone())),
+            Some(DeployedObject { etag: None, .. }) => {
+                warn!("index document etag not available, uploading it unconditionally");
+                None
+            }
+            None => Some(Precondition::IfNoneMatch),
+        }
+    } else {
+        None
+    };
+    let upload_start = Instant::now();
+    let output = put_object(
+        s3_client,
+        args,
+        header_rules,
+        provenance,
+        &key,
+        &file_name,
+        &content_type,
+        precondition.as_ref(),
+    )
+    .await
+    .map_err(|err| DeployError::Upload {
+        key: key.clone(),
+        source: err,
+    })?;
+
+    debug!(
+        bytes = output.bytes,
+        content_type,
+        elapsed_ms = upload_start.elapsed().as_millis(),
+        "index document uploaded"
+    );
+    Ok((status, output))
+}
+
+/// Verify the integrity of the objects uploaded provided, checking that their
+/// ETag matches the one expected. When the ETag is not returned by the upload
+/// request, it's obtained from the object's metadata. An error listing all the
+/// mismatches found is returned if any of the objects cannot be verified.
+#[instrument(skip_all, err)]
+async fn verify_objects(
+    s3_client: &aws_sdk_s3::Client,
+    args: &S3DeployOptions,
+    objects: Vec<(&str, &UploadOutput)>,
+) -> Result<()> {
+    let prefix = get_prefix(args);
+
+    // Objects not uploaded in this deploy don't have an expected ETag, so
+    // they are not verified
+    let objects: Vec<(&str, &str, Option<&String>)> = objects
+        .into_iter()
+        .filter_map(|(key, output)| {
+            output
+                .expected_etag
+                .as_deref()
+                .map(|expected_etag| (key, expected_etag, output.etag.as_ref()))
+        })
+        .collect();
+    let verified = objects.len();
+    let mut mismatches: Vec<String> = stream::iter(objects)
+        .map(|(key, expected_etag, etag)| {
+            let prefix = &prefix;
+            async move {
+                let etag = if let Some(etag) = etag {
+                    etag.clone()
+                } else {
+                    let output = send_with_retries(args.max_retries, || {
+                        s3_client.head_object().bucket(&args.bucket).key(format!("{prefix}{key}")).send()
+                    })
+                    .await;
+                    match output {
+                        Ok(output) => output.e_tag.unwrap_or_default().trim_matches('"').to_string(),
+                        Err(err) => return Some(format!("- {key}: error getting object metadata: {err}\n")),
+                    }
+                };
+                if etag == expected_etag {
+                    None
+                } else {
+                    Some(format!("- {key}: expected ETag {expected_etag}, got {etag}\n"))
+                }
+            }
+        })
+        .buffer_unordered(args.concurrency)
+        .filter_map(|mismatch| async move { mismatch })
+        .collect()
+        .await;
+
+    if !mismatches.is_empty() {
+        mismatches.sort();
+        return Err(format_err!(
+            "{} objects failed the integrity verification:\n{}",
+            mismatches.len(),
+            mismatches.concat()
+        ));
+    }
+
+    debug!(objects = verified, "objects integrity verified");
+    Ok(())
+}
+
+/// Cache-Control rule. The value of the rule will be used as the Cache-Control
+/// header of the objects whose key matches the rule's pattern.
+#[derive(Debug, Clone)]
+pub struct CacheControlRule {
+    pattern: Regex,
+    value: String,
+}
+
+impl FromStr for CacheControlRule {
+    type Err = anyhow::Error;
+
+    /// Parse a Cache-Control rule in the `glob=value` format.
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((glob, value)) = s.split_once('=') else {
+            return Err(format_err!(
+                "invalid cache control rule (expected format: glob=value)"
+            ));
+        };
+        if glob.trim().is_empty() || value.trim().is_empty() {
+            return Err(format_err!("cache control rule glob and value cannot be empty"));
+        }
+
+        Ok(Self {
+            pattern: glob_to_regex(glob.trim())?,
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+/// Compression algorithm used to compress the files before uploading them.
+///
+/// Compressed files are uploaded as a single object (using the original key)
+/// with the corresponding Content-Encoding, as S3 cannot negotiate the
+/// encoding of the content served. This means that all clients will receive
+/// the compressed version, so the algorithm selected must be supported by all
+/// of them. Gzip is supported by all browsers, whereas Brotli is only
+/// supported over HTTPS. CloudFront serves the objects that already have a
+/// Content-Encoding header as they are (it doesn't compress them again, nor
+/// decompress them for the clients that don't support the encoding), so no
+/// extra configuration is needed as long as the clients support it.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Compression {
+    Brotli,
+    Gzip,
+}
+
+impl Compression {
+    /// Compress the data provided.
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        let compressed_data = match self {
+            Compression::Brotli => {
+                let mut compressed_data = vec![];
+                {
+                    let mut w = brotli::CompressorWriter::new(&mut compressed_data, 4096, 11, 22);
+                    w.write_all(data)?;
+                }
+                compressed_data
+            }
+            Compression::Gzip => {
+                let mut w = GzEncoder::new(vec![], flate2::Compression::best());
+                w.write_all(data)?;
+                w.finish()?
+            }
+        };
+
+        Ok(compressed_data)
+    }
+
+    /// Decompress the data provided.
+    fn decompress(self, compressed_data: &[u8]) -> Result<Vec<u8>> {
+        let mut data = vec![];
+        match self {
+            Compression::Brotli => {
+                brotli::Decompressor::new(compressed_data, 4096).read_to_end(&mut data)?;
+            }
+            Compression::Gzip => {
+                GzDecoder::new(compressed_data).read_to_end(&mut data)?;
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Get the content encoding corresponding to the compression algorithm.
+    fn content_encoding(self) -> &'static str {
+        match self {
+            Compression::Brotli => "br",
+            Compression::Gzip => "gzip",
+        }
+    }
+}
+
+/// Server-side encryption used to encrypt the objects uploaded. When using
+/// aws:kms, the objects are encrypted with the KMS key provided, and their
+/// ETag is not the MD5 digest of their content.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ServerSideEncryption {
+    #[value(name = "aes256")]
+    Aes256,
+    #[value(name = "aws:kms")]
+    AwsKms,
+}
+
+impl From<ServerSideEncryption> for aws_sdk_s3::types::ServerSideEncryption {
+    fn from(sse: ServerSideEncryption) -> Self {
+        match sse {
+            ServerSideEncryption::Aes256 => aws_sdk_s3::types::ServerSideEncryption::Aes256,
+            ServerSideEncryption::AwsKms => aws_sdk_s3::types::ServerSideEncryption::AwsKms,
+        }
+    }
+}
+
+/// Tag set on the objects uploaded.
+#[derive(Debug, Clone)]
+pub struct Tag {
+    key: String,
+    value: String,
+}
+
+impl FromStr for Tag {
+    type Err = anyhow::Error;
+
+    /// Parse a tag in the `key=value` format.
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((key, value)) = s.split_once('=') else {
+            return Err(format_err!("invalid tag (expected format: key=value)"));
+        };
+        if key.trim().is_empty() {
+            return Err(format_err!("tag key cannot be empty"));
+        }
+
+        Ok(Self {
+            key: key.trim().to_string(),
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+/// Target the landscape website will be deployed to.
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct S3Target {
+    bucket: String,
+    region: Option<String>,
+    cloudfront_distribution_id: Option<String>,
+}
+
+impl FromStr for S3Target {
+    type Err = anyhow::Error;
+
+    /// Parse a target in the `bucket[:region[:cloudfront_distribution_id]]`
+    /// format.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split(':').map(str::trim);
+        let bucket = parts.next().unwrap_or_default().to_string();
+        let region = parts.next().filter(|region| !region.is_empty()).map(ToString::to_string);
+        let cloudfront_distribution_id = parts.next().filter(|id| !id.is_empty()).map(ToString::to_string);
+        if bucket.is_empty() || parts.next().is_some() {
+            return Err(format_err!(
+                "invalid target (expected format: bucket[:region[:cloudfront_distribution_id]])"
+            ));
+        }
+
+        Ok(Self {
+            bucket,
+            region,
+            cloudfront_distribution_id,
+        })
+    }
+}
+
+/// Content types of the objects to upload. Overrides (by extension) are
+/// checked first, then well known filenames, and finally the content type is
+/// guessed from the extension, falling back to the default content type when
+/// it can't be guessed.
+#[derive(Debug, Clone)]
+struct ContentTypes {
+    default: String,
+    overrides: HashMap<String, String>,
+}
+
+impl ContentTypes {
+    /// Create a new ContentTypes instance, loading the content type map file
+    /// provided (if any) on top of the default overrides. The map file is a
+    /// YAML (or JSON) mapping of extensions to content types.
+    fn new(args: &S3DeployOptions) -> Result<Self> {
+        let mut overrides: HashMap<String, String> = DEFAULT_CONTENT_TYPES_OVERRIDES
+            .iter()
+            .map(|(ext, content_type)| ((*ext).to_string(), (*content_type).to_string()))
+            .collect();
+
+        if let Some(path) = &args.content_type_map {
+            let map: HashMap<String, String> = fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_yaml::from_str(&content)?))
+                .map_err(|err| DeployError::ContentTypeMap {
+                    path: path.clone(),
+                    source: err,
+                })?;
+            for (ext, content_type) in map {
+                if content_type.parse::<mime::Mime>().is_err() {
+                    return Err(DeployError::ContentType {
+                        extension: ext,
+                        content_type,
+                    }
+                    .into());
+                }
+                overrides.insert(ext.trim_start_matches('.').to_lowercase(), content_type);
+            }
+        }
+
+        Ok(Self {
+            default: args.default_content_type.clone(),
+            overrides,
+        })
+    }
+
+    /// Get the content type of the object key provided.
+    fn get(&self, key: &str) -> String {
+        let file_name = key.rsplit('/').next().unwrap_or(key);
+        if let Some((_, ext)) = file_name.rsplit_once('.') {
+            if let Some(content_type) = self.overrides.get(&ext.to_lowercase()) {
+                return content_type.clone();
+            }
+        }
+        if let Some((_, content_type)) = WELL_KNOWN_CONTENT_TYPES.iter().find(|(name, _)| *name == file_name)
+        {
+            return (*content_type).to_string();
+        }
+        if let Some(content_type) = mime_guess::from_path(key).first() {
+            return content_type.essence_str().to_string();
+        }
+
+        warn!(
+            ?key,
+            content_type = self.default,
+            "cannot detect content type, using default one"
+        );
+        self.default.clone()
+    }
+}
+
+/// Header rules, loaded from the header rules file. The headers of all the
+/// rules whose glob matches the object key are set on the object (when the
+/// same header is set by multiple rules, the first one takes precedence).
+/// Rules provided by the user take precedence over the default ones.
+///
+/// Content-Disposition and Content-Language are stored by S3 as system
+/// metadata, and served as regular headers. S3 cannot serve any other custom
+/// header, so the only other headers allowed are the user metadata ones
+/// (x-amz-meta-), which are served as is (they can be mapped to the actual
+/// header by the CDN, i.e. using a CloudFront function).
+///
+/// The headers are only set when the objects are uploaded, so the objects
+/// deployed before the header rules file was last modified are uploaded again.
+#[derive(Debug, Clone, Default)]
+struct HeaderRules {
+    rules: Vec<HeaderRule>,
+    updated_at: Option<DateTime>,
+}
+
+impl HeaderRules {
+    /// Create a new HeaderRules instance, loading the header rules file
+    /// provided (if any) on top of the default rules. The header rules file
+    /// is a YAML list of rules, each of them with a glob and the headers to
+    /// set on the objects whose key matches it.
+    fn new(args: &S3DeployOptions) -> Result<Self> {
+        let mut rules = vec![];
+        let mut updated_at = None;
+
+        if let Some(path) = &args.header_rules {
+            updated_at = Some(DateTime::from(fs::metadata(path)?.modified()?));
+            let content = fs::read_to_string(path).context("error reading header rules file")?;
+            let entries: Vec<HeaderRuleEntry> =
+                serde_yaml::from_str(&content).context("invalid header rules file")?;
+            for entry in entries {
+                let mut headers = vec![];
+                for (name, value) in &entry.headers {
+                    let header = check_header(name, value)
+                        .context(format!("invalid header rule for {}", entry.glob))?;
+                    headers.push(header);
+                }
+                rules.push(HeaderRule {
+                    pattern: glob_to_regex(entry.glob.trim())?,
+                    headers,
+                });
+            }
+        }
+
+        for (glob, name, value) in DEFAULT_HEADER_RULES {
+            rules.push(HeaderRule {
+                pattern: glob_to_regex(glob)?,
+                headers: vec![(name.to_string(), value.to_string())],
+            });
+        }
+
+        Ok(Self { rules, updated_at })
+    }
+
+    /// Get the headers to set on the object key provided.
+    fn get(&self, key: &str) -> BTreeMap<String, String> {
+        let mut headers = BTreeMap::new();
+        for rule in self.rules.iter().filter(|rule| rule.pattern.is_match(key)) {
+            for (name, value) in &rule.headers {
+                headers.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+        }
+        headers
+    }
+
+    /// Check if the header rules have been modified since the object provided
+    /// was deployed (so its headers may be outdated).
+    fn modified_since(&self, deployed_object: &DeployedObject) -> bool {
+        self.updated_at.is_some_and(|updated_at| updated_at > deployed_object.created_at)
+    }
+}
+
+/// Header rule. The headers of the rule will be set on the objects whose key
+/// matches the rule's pattern.
+#[derive(Debug, Clone)]
+struct HeaderRule {
+    pattern: Regex,
+    headers: Vec<(String, String)>,
+}
+
+/// Header rule entry, as defined in the header rules file.
+#[derive(Debug, Clone, Deserialize)]
+struct HeaderRuleEntry {
+    glob: String,
+    headers: BTreeMap<String, String>,
+}
+
+/// Check that the header provided is valid and can be set on the objects,
+/// returning its normalized name (lowercase) along with its value.
+fn check_header(name: &str, value: &str) -> Result<(String, String)> {
+    let name = name.trim().to_lowercase();
+    if HeaderName::from_bytes(name.as_bytes()).is_err() {
+        return Err(format_err!("invalid header name: {name}"));
+    }
+    if RESERVED_HEADERS.contains(&name.as_str())
+        || (name.starts_with("x-amz-") && !name.starts_with("x-amz-meta-"))
+    {
+        return Err(format_err!(
+            "header {name} is managed by S3 (or by other deploy options) and cannot be set"
+        ));
+    }
+    if !SYSTEM_METADATA_HEADERS.contains(&name.as_str()) && !name.starts_with("x-amz-meta-") {
+        return Err(format_err!(
+            "header {name} cannot be served by S3 (only content-disposition, content-language and user metadata headers (x-amz-meta-) can be set, other headers must be set by the CDN)"
+        ));
+    }
+    if name == "x-amz-meta-" {
+        return Err(format_err!("user metadata header name cannot be empty"));
+    }
+    let value = value.trim().to_string();
+    if value.is_empty() || HeaderValue::from_str(&value).is_err() {
+        return Err(format_err!("invalid value for header {name}: {value}"));
+    }
+    if !SYSTEM_METADATA_HEADERS.contains(&name.as_str()) && !value.is_ascii() {
+        return Err(format_err!(
+            "invalid value for header {name}: user metadata values must be ASCII"
+        ));
+    }
+    Ok((name, value))
+}
+
+/// Provenance information (as metadata and tags) set on all the objects
+/// uploaded during a deploy, used to know which deploy produced each object.
+#[derive(Debug, Clone)]
+struct Provenance {
+    metadata: HashMap<String, String>,
+    tagging: Option<String>,
+}
+
+impl Provenance {
+    /// Create a new Provenance instance. The source commit will be read from
+    /// the GITHUB_SHA environment variable when not provided explicitly.
+    fn new(args: &S3DeployOptions) -> Self {
+        // Prepare metadata
+        let mut metadata = HashMap::from([
+            ("deployed-at".to_string(), Utc::now().to_rfc3339()),
+            (
+                "landscape2-version".to_string(),
+                env!("CARGO_PKG_VERSION").to_string(),
+            ),
+        ]);
+        let source_commit = args
+            .source_commit
+            .clone()
+            .or_else(|| env::var("GITHUB_SHA").ok().filter(|sha| !sha.is_empty()));
+        if let Some(source_commit) = source_commit {
+            metadata.insert("source-commit".to_string(), source_commit);
+        }
+
+        // Prepare tagging (encoded as url query parameters)
+        let tagging = if args.tag.is_empty() {
+            None
+        } else {
+            let mut serializer = form_urlencoded::Serializer::new(String::new());
+            for tag in &args.tag {
+                serializer.append_pair(&tag.key, &tag.value);
+            }
+            Some(serializer.finish())
+        };
+
+        Self { metadata, tagging }
+    }
+}
+
+/// Attributes of an object to upload.
+#[derive(Debug, Clone)]
+struct ObjectAttributes {
+    cache_control: Option<String>,
+    content_disposition: Option<String>,
+    content_encoding: Option<String>,
+    content_language: Option<String>,
+    content_type: String,
+    metadata: HashMap<String, String>,
+    server_side_encryption: Option<ServerSideEncryption>,
+    ssekms_key_id: Option<String>,
+    tagging: Option<String>,
+}
+
+/// Body of an object to upload.
+#[derive(Debug, Clone, PartialEq)]
+enum Body {
+    /// File streamed from disk (along with its size).
+    File(PathBuf, usize),
+    /// Buffer shared by all the requests sending the body.
+    Bytes(Bytes),
+}
+
+impl Body {
+    /// Get the size of the body.
+    fn len(&self) -> usize {
+        match self {
+            Body::File(_, size) => *size,
+            Body::Bytes(bytes) => bytes.len(),
+        }
+    }
+
+    /// Compute the MD5 digest of the range of the body provided.
+    fn digest(&self, offset: usize, length: usize) -> Result<Vec<u8>> {
+        let mut hasher = Md5::new();
+        match self {
+            Body::File(path, _) => {
+                let mut file = fs::File::open(path)?;
+                file.seek(SeekFrom::Start(offset as u64))?;
+                io::copy(&mut file.take(length as u64), &mut hasher)?;
+            }
+            Body::Bytes(bytes) => hasher.update(&bytes[offset..offset + length]),
+        }
+        Ok(hasher.finalize().to_vec())
+    }
+
+    /// Get a stream with the range of the body provided.
+    async fn stream(&self, offset: usize, length: usize) -> Result<ByteStream> {
+        match self {
+            Body::File(path, _) => Ok(ByteStream::read_from()
+                .path(path)
+                .offset(offset as u64)
+                .length(Length::Exact(length as u64))
+                .build()
+                .await?),
+            Body::Bytes(bytes) => Ok(ByteStream::from(bytes.slice(offset..offset + length))),
+        }
+    }
+}
+
+/// Output of a file upload.
+#[derive(Debug, Clone, Default)]
+struct UploadOutput {
+    /// Number of bytes transferred.
+    bytes: u64,
+    /// ETag of the object uploaded.
+    etag: Option<String>,
+    /// ETag expected for the object uploaded, computed locally from the
+    /// content uploaded (only when verifying the objects uploaded).
+    expected_etag: Option<String>,
+}
+
+/// Manifest with the objects deployed, written to the bucket after each
+/// deploy when enabled.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Manifest {
+    objects: HashMap<Key, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Create a new Manifest instance from the objects deployed previously,
+    /// updated with the changes made in the current deploy.
+    fn new(
+        deployed_objects: &HashMap<Key, DeployedObject>,
+        uploaded_etags: &HashMap<Key, Option<String>>,
+        deleted: &[Key],
+    ) -> Self {
+        let mut objects: HashMap<Key, ManifestEntry> = deployed_objects
+            .iter()
+            .filter(|(key, _)| *key != MANIFEST_KEY)
+            .map(|(key, object)| {
+                let entry = ManifestEntry {
+                    created_at: object.created_at.secs(),
+                    etag: object.etag.clone(),
+                };
+                (key.clone(), entry)
+            })
+            .collect();
+        let now = Utc::now().timestamp();
+        for (key, etag) in uploaded_etags {
+            let entry = ManifestEntry {
+                created_at: now,
+                etag: etag.clone(),
+            };
+            objects.insert(key.clone(), entry);
+        }
+        for key in deleted {
+            objects.remove(key);
+        }
+
+        Self { objects }
+    }
+
+    /// Convert the manifest into a deployed objects collection.
+    fn into_deployed_objects(self) -> HashMap<Key, DeployedObject> {
+        self.objects
+            .into_iter()
+            .map(|(key, entry)| {
+                let object = DeployedObject {
+                    created_at: DateTime::from_secs(entry.created_at),
+                    etag: entry.etag,
+                };
+                (key, object)
+            })
+            .collect()
+    }
+}
+
+/// Manifest entry with some information about a deployed object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEntry {
+    created_at: i64,
+    etag: Option<String>,
+}
+
+/// Error returned when deploying the landscape website to AWS S3.
+#[derive(Debug, thiserror::Error)]
+#[allow(clippy::module_name_repetitions)]
+pub enum DeployError {
+    /// A required environment variable was not provided.
+    #[error("required environment variable {0} not provided")]
+    MissingEnvVar(String),
+
+    /// The objects in the bucket under the prefix provided could not be
+    /// listed.
+    #[error("error listing objects with prefix {prefix:?}")]
+    Listing {
+        prefix: String,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    /// The file with the key provided could not be uploaded.
+    #[error("error uploading file {key}")]
+    Upload {
+        key: Key,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    /// The content type provided for an extension in the content type map
+    /// is not valid, so the content type of the files with that extension
+    /// cannot be detected.
+    #[error("invalid content type for extension {extension}: {content_type}")]
+    ContentType { extension: String, content_type: String },
+
+    /// The content type map file could not be read or parsed.
+    #[error("error loading content type map file {path:?}")]
+    ContentTypeMap {
+        path: PathBuf,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    /// The deadline of the deploy was exceeded before all the files were
+    /// uploaded, so the deploy was stopped (the index document was not
+    /// updated).
+    #[error("deploy deadline exceeded: {uploaded} files uploaded, {remaining} remaining")]
+    DeadlineExceeded { uploaded: usize, remaining: usize },
+
+    /// Any other error.
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl DeployError {
+    /// Return the exit code that should be used when the deploy fails with
+    /// this error (the deadline being exceeded uses a distinct one).
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DeployError::DeadlineExceeded { .. } => 3,
+            _ => 1,
+        }
+    }
+}
+
+impl From<anyhow::Error> for DeployError {
+    /// Convert the error provided into a deploy error, recovering the
+    /// original deploy error when it was propagated as an `anyhow::Error`.
+    fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<UploadError>() {
+            Ok(UploadError { source, .. }) => source,
+            Err(err) => err,
+        };
+        err.downcast::<DeployError>().unwrap_or_else(DeployError::Other)
+    }
+}
+
+/// Error returned when some of the files could not be uploaded, including the
+/// keys of the ones that were uploaded successfully.
+#[derive(Debug)]
+struct UploadError {
+    uploaded: Vec<Key>,
+    source: anyhow::Error,
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "- {:?}", self.source)
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+/// Condition an object must meet to be overwritten, based on the ETag of the
+/// copy listed when the deploy started.
+#[derive(Debug, Clone, PartialEq)]
+enum Precondition {
+    /// The object must still have the ETag provided.
+    IfMatch(String),
+    /// The object must not exist.
+    IfNoneMatch,
+}
+
+impl Precondition {
+    /// Return the header used to send the precondition.
+    fn header(&self) -> Result<(&'static str, HeaderValue)> {
+        let header = match self {
+            Precondition::IfMatch(etag) => ("If-Match", HeaderValue::from_str(&format!(r#""{etag}""#))?),
+            Precondition::IfNoneMatch => ("If-None-Match", HeaderValue::from_static("*")),
+        };
+        Ok(header)
+    }
+
+    /// Check if the error provided was caused by a precondition failure (the
+    /// object was modified, or a conflicting request was in progress).
+    fn is_failure<E: ProvideErrorMetadata>(err: &SdkError<E>) -> bool {
+        matches!(
+            err.code(),
+            Some("PreconditionFailed" | "ConditionalRequestConflict")
+        ) || err.raw_response().is_some_and(|resp| matches!(resp.status().as_u16(), 409 | 412))
+    }
+}
+
+/// Redirects from old paths of the website to new locations, loaded from the
+/// redirects file. Each redirect is created as an object redirecting to the
+/// new location, so that S3 websites respond to the requests to the old path
+/// with a 301 redirect. Items permalinks (`/?item=<id>`) cannot be redirected
+/// this way, so they are collected in a map the web application uses instead.
+#[derive(Debug, Clone, Default)]
+struct Redirects {
+    items: BTreeMap<String, String>,
+    redirects: BTreeMap<Key, String>,
+}
+
+impl Redirects {
+    /// Create a new Redirects instance, loading the redirects file provided
+    /// (if any). The redirects file is a YAML mapping of old paths to new
+    /// locations, which can be paths in the website or absolute urls.
+    fn new(args: &S3DeployOptions) -> Result<Self> {
+        let mut items = BTreeMap::new();
+        let mut redirects = BTreeMap::new();
+        let Some(path) = &args.redirects_file else {
+            return Ok(Self { items, redirects });
+        };
+
+        let content = fs::read_to_string(path).context("error reading redirects file")?;
+        let map: BTreeMap<String, String> =
+            serde_yaml::from_str(&content).context("invalid redirects file")?;
+        let local_keys = get_local_keys(&args.landscape_dir)?;
+        let prefix = get_prefix(args);
+        for (from, to) in map {
+            // Items permalinks are redirected by the web application
+            if let Some(old_id) = get_item_permalink_id(&from) {
+                let Some(new_id) = get_item_permalink_id(&to) else {
+                    return Err(format_err!(
+                        "invalid redirect to {to}: items permalinks can only be redirected to other items permalinks"
+                    ));
+                };
+                items.insert(old_id, new_id);
+                continue;
+            }
+
+            // Paths of directories are served using their index document
+            let mut key = from.trim_start_matches('/').to_string();
+            if key.is_empty() || key.ends_with('/') {
+                key.push_str(INDEX_DOCUMENT);
+            }
+            if key.contains(['?', '#']) {
+                return Err(format_err!(
+                    "invalid redirect from {from}: query strings and fragments are not supported"
+                ));
+            }
+            if local_keys.contains(&key) {
+                return Err(format_err!(
+                    "invalid redirect from {from}: it matches a file in the landscape directory"
+                ));
+            }
+
+            // Paths in the website are relative to the prefix
+            let location = if to.starts_with("https://") || to.starts_with("http://") {
+                to
+            } else if to.starts_with('/') {
+                format!("/{prefix}{}", to.trim_start_matches('/'))
+            } else {
+                return Err(format_err!(
+                    "invalid redirect to {to}: it must be a path starting with / or an absolute url"
+                ));
+            };
+            redirects.insert(key, location);
+        }
+
+        Ok(Self { items, redirects })
+    }
+
+    /// Check if the key provided is the key of a redirect object (or of the
+    /// items redirects object).
+    fn contains(&self, key: &str) -> bool {
+        self.redirects.contains_key(key) || (!self.items.is_empty() && key == ITEMS_REDIRECTS_KEY)
+    }
+
+    /// Check if there are no redirects.
+    fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.redirects.is_empty()
+    }
+}
+
+/// Get the id of the item from the item permalink provided (i.e.
+/// `/?item=<id>`). None is returned when the path provided is not an item
+/// permalink.
+fn get_item_permalink_id(path: &str) -> Option<String> {
+    let query = path.trim_start_matches('/').strip_prefix('?')?;
+    let mut params = form_urlencoded::parse(query.as_bytes());
+    match (params.next(), params.next()) {
+        (Some((name, id)), None) if name == "item" && !id.is_empty() => Some(id.into_owned()),
+        _ => None,
+    }
+}
+
+/// Local checkpoint recording the files uploaded during a deploy, so that an
+/// interrupted deploy can be resumed without uploading them again. Files are
+/// recorded along with their version (modification time and size), so the
+/// ones modified since they were uploaded are not skipped.
+struct Checkpoint {
+    path: PathBuf,
+    file: Option<Mutex<fs::File>>,
+    uploaded: HashMap<Key, String>,
+}
+
+impl Checkpoint {
+    /// Create a new Checkpoint instance for the deploy provided, loading the
+    /// files recorded previously when resuming a deploy.
+    fn new(args: &S3DeployOptions) -> Result<Self> {
+        // Each bucket, prefix and landscape directory uses its own checkpoint
+        let landscape_dir = fs::canonicalize(&args.landscape_dir)?;
+        let id = format!("{}/{}:{}", args.bucket, get_prefix(args), landscape_dir.display());
+        let digest = hex::encode(Md5::digest(id.as_bytes()));
+        let path = env::temp_dir().join(format!("landscape2-deploy-{digest}.checkpoint"));
+
+        // Load the files recorded previously (when resuming)
+        let mut uploaded = HashMap::new();
+        if args.resume {
+            if let Ok(content) = fs::read_to_string(&path) {
+                for line in content.lines() {
+                    if let Some((version, key)) = line.split_once(' ') {
+                        uploaded.insert(key.to_string(), version.to_string());
+                    }
+                }
+                info!(files = uploaded.len(), "resuming deploy from checkpoint");
+            } else {
+                info!("deploy checkpoint not found: there is nothing to resume");
+            }
+        }
+
+        // Open the checkpoint file to record the files uploaded (the ones
+        // recorded previously are kept when resuming)
+        let file = if args.dry_run {
+            None
+        } else {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(args.resume)
+                .truncate(!args.resume)
+                .open(&path)
+                .context("error opening deploy checkpoint")?;
+            Some(Mutex::new(file))
+        };
+
+        Ok(Self { path, file, uploaded })
+    }
+
+    /// Remove the checkpoint (once the deploy has completed).
+    fn clear(self) -> Result<()> {
+        drop(self.file);
+        if self.path.exists() {
+            fs::remove_file(&self.path).context("error removing deploy checkpoint")?;
+        }
+        Ok(())
+    }
+
+    /// Check if the file provided was recorded as uploaded, and it hasn't been
+    /// modified since.
+    fn contains(&self, key: &str, file_name: &Path) -> Result<bool> {
+        match self.uploaded.get(key) {
+            Some(version) => Ok(*version == get_file_version(file_name)?),
+            None => Ok(false),
+        }
+    }
+
+    /// Record the file provided as uploaded.
+    fn record(&self, key: &str, file_name: &Path) -> Result<()> {
+        if let Some(file) = &self.file {
+            let line = format!("{} {key}\n", get_file_version(file_name)?);
+            file.lock().expect("checkpoint lock not to be poisoned").write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Some information about an object already deployed.
+#[derive(Debug, Clone)]
+struct DeployedObject {
+    created_at: DateTime,
+    etag: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a file with the content provided to a temporary directory,
+    /// returning its path.
+    fn write_file(name: &str, content: &[u8]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("landscape2-s3-tests-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_name = dir.join(name);
+        fs::write(&file_name, content).unwrap();
+        file_name
+    }
+
+    /// Prepare some data that can be compressed.
+    fn compressible_data() -> Vec<u8> {
+        "{\"name\":\"landscape\",\"items\":[]}\n".repeat(200).into_bytes()
+    }
+
+    /// Prepare some pseudo-random data that cannot be compressed.
+    fn incompressible_data() -> Vec<u8> {
+        let mut state: u32 = 42;
+        (0..4096)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                state.to_be_bytes()[1]
+            })
+            .collect()
+    }
+
+    /// Prepare some deploy options using the compression algorithm provided.
+    fn options(compress: Option<Compression>) -> S3DeployOptions {
+        S3DeployOptions {
+            compress,
+            ..S3DeployOptions::new("bucket", Path::new("."))
+        }
+    }
+
+    #[test]
+    fn prepare_body_compresses_compressible_files() {
+        let data = compressible_data();
+        let file_name = write_file("compressible.json", &data);
+
+        for compression in [Compression::Brotli, Compression::Gzip] {
+            let (body, content_encoding) =
+                prepare_body(&options(Some(compression)), "data/compressible.json", &file_name).unwrap();
+            assert_eq!(content_encoding, Some(compression.content_encoding()));
+            let Body::Bytes(body) = body else {
+                panic!("compressed body expected");
+            };
+            assert!(body.len() < data.len());
+            assert_eq!(compression.decompress(&body).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn prepare_body_skips_when_compression_is_disabled() {
+        let data = compressible_data();
+        let file_name = write_file("disabled.json", &data);
+
+        let (body, content_encoding) =
+            prepare_body(&options(None), "data/disabled.json", &file_name).unwrap();
+        assert_eq!(content_encoding, None);
+        assert_eq!(body, Body::File(file_name, data.len()));
+    }
+
+    #[test]
+    fn prepare_body_skips_files_not_worth_compressing() {
+        let data = compressible_data();
+        let file_name = write_file("compressible.png", &data);
+
+        for compression in [Compression::Brotli, Compression::Gzip] {
+            let (body, content_encoding) =
+                prepare_body(&options(Some(compression)), "logos/compressible.png", &file_name).unwrap();
+            assert_eq!(content_encoding, None);
+            assert_eq!(body, Body::File(file_name.clone(), data.len()));
+        }
+    }
+
+    #[test]
+    fn prepare_body_skips_small_files() {
+        let data = b"{}".to_vec();
+        let file_name = write_file("small.json", &data);
+
+        let (body, content_encoding) =
+            prepare_body(&options(Some(Compression::Gzip)), "data/small.json", &file_name).unwrap();
+        assert_eq!(content_encoding, None);
+        assert_eq!(body, Body::File(file_name, data.len()));
+    }
+
+    #[test]
+    fn prepare_body_skips_when_compressed_body_is_not_smaller() {
+        let data = incompressible_data();
+        let file_name = write_file("incompressible.json", &data);
+
+        for compression in [Compression::Brotli, Compression::Gzip] {
+            let (body, content_encoding) = prepare_body(
+                &options(Some(compression)),
+                "data/incompressible.json",
+                &file_name,
+            )
+            .unwrap();
+            assert_eq!(content_encoding, None);
+            assert_eq!(body, Body::File(file_name.clone(), data.len()));
+        }
+    }
+
+    #[test]
+    fn get_expected_etag_matches_for_file_and_buffer_bodies() {
+        let data = compressible_data();
+        let file_name = write_file("etag.json", &data);
+
+        let file = Body::File(file_name, data.len());
+        let buffer = Body::Bytes(Bytes::from(data.clone()));
+        for multipart in [false, true] {
+            assert_eq!(
+                get_expected_etag(&file, multipart).unwrap(),
+                get_expected_etag(&buffer, multipart).unwrap()
+            );
+        }
+        assert_eq!(
+            get_expected_etag(&file, false).unwrap(),
+            hex::encode(Md5::digest(&data))
+        );
+    }
+
+    #[test]
+    fn content_types_with_missing_map_file_returns_content_type_map_error() {
+        let args = S3DeployOptions {
+            content_type_map: Some(PathBuf::from("missing-content-type-map.yml")),
+            ..S3DeployOptions::new("bucket", Path::new("."))
+        };
+
+        let err = DeployError::from(ContentTypes::new(&args).unwrap_err());
+        assert!(matches!(err, DeployError::ContentTypeMap { .. }));
+    }
+
+    #[test]
+    fn deploy_error_from_deadline_exceeded_upload_error() {
+        let err = anyhow::Error::new(UploadError {
+            uploaded: vec![],
+            source: DeployError::DeadlineExceeded {
+                uploaded: 2,
+                remaining: 3,
+            }
+            .into(),
+        });
+        let err = DeployError::from(err);
+        assert!(matches!(
+            err,
+            DeployError::DeadlineExceeded {
+                uploaded: 2,
+                remaining: 3
+            }
+        ));
+        assert_eq!(err.exit_code(), 3);
+        assert_eq!(DeployError::from(format_err!("other")).exit_code(), 1);
+    }
+
+    #[test]
+    fn get_item_permalink_id_from_permalinks() {
+        assert_eq!(get_item_permalink_id("/?item=old-id"), Some("old-id".to_string()));
+        assert_eq!(get_item_permalink_id("?item=a%2Fb"), Some("a/b".to_string()));
+        assert_eq!(get_item_permalink_id("/?item="), None);
+        assert_eq!(get_item_permalink_id("/?item=old-id&tab=explore"), None);
+        assert_eq!(get_item_permalink_id("/?category=old"), None);
+        assert_eq!(get_item_permalink_id("/old/path/"), None);
+    }
+}
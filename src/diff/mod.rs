@@ -0,0 +1,368 @@
+//! This module defines the functionality of the diff CLI subcommand, which
+//! compares two built landscapes and reports the changes between them.
+//!
+//! The comparison is done using the full dataset written to the output
+//! directory by the build subcommand, so the changes reported are based on the
+//! items information and not on the textual differences of the files.
+
+use crate::{
+    build::{Full, Item, DATASETS_PATH},
+    DiffArgs,
+};
+use anyhow::{format_err, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    fs,
+    path::Path,
+};
+use tracing::instrument;
+
+/// Name of the full dataset file in the datasets path.
+const FULL_DATASET_FILE: &str = "full.json";
+
+/// Fields that are not considered when checking if an item was modified (they
+/// are reported separately as moves or stats changes).
+const IGNORED_FIELDS: [&str; 3] = ["category", "crunchbase_data", "subcategory"];
+
+/// Compare two built landscapes and report the changes found.
+#[instrument(skip_all)]
+pub(crate) fn diff(args: &DiffArgs) -> Result<()> {
+    // Compare landscapes
+    let old = read_full_dataset(&args.old_landscape_dir)?;
+    let new = read_full_dataset(&args.new_landscape_dir)?;
+    let landscape_diff = LandscapeDiff::new(&old.items, &new.items);
+
+    // Display report and write json file (when requested)
+    print!("{}", landscape_diff.report());
+    if let Some(json_file) = &args.json_file {
+        let json = serde_json::to_vec_pretty(&landscape_diff)?;
+        fs::write(json_file, json).context(format!("error writing file {}", json_file.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Changes found between two built landscapes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct LandscapeDiff {
+    pub added: Vec<ItemRef>,
+    pub modified: Vec<ModifiedItem>,
+    pub moved: Vec<MovedItem>,
+    pub removed: Vec<ItemRef>,
+    pub stats: Vec<ItemStatsChange>,
+    pub totals: Totals,
+}
+
+impl LandscapeDiff {
+    /// Create a new landscape diff from the old and new items provided.
+    pub(crate) fn new(old_items: &[Item], new_items: &[Item]) -> Self {
+        let old_items: BTreeMap<&str, &Item> = old_items.iter().map(|i| (i.id.as_str(), i)).collect();
+        let new_items: BTreeMap<&str, &Item> = new_items.iter().map(|i| (i.id.as_str(), i)).collect();
+
+        let mut landscape_diff = LandscapeDiff {
+            totals: Totals {
+                funding: Change::new(total(&old_items, funding), total(&new_items, funding)),
+                items: Change::new(count(&old_items), count(&new_items)),
+                stars: Change::new(total(&old_items, stars), total(&new_items, stars)),
+            },
+            ..Default::default()
+        };
+
+        // Removed items
+        for (id, old_item) in &old_items {
+            if !new_items.contains_key(id) {
+                landscape_diff.removed.push(ItemRef::from(*old_item));
+            }
+        }
+
+        for (id, new_item) in &new_items {
+            // Added items
+            let Some(old_item) = old_items.get(id) else {
+                landscape_diff.added.push(ItemRef::from(*new_item));
+                continue;
+            };
+
+            // Moved items
+            if old_item.category != new_item.category || old_item.subcategory != new_item.subcategory {
+                landscape_diff.moved.push(MovedItem {
+                    id: new_item.id.clone(),
+                    name: new_item.name.clone(),
+                    from: Location::from(*old_item),
+                    to: Location::from(*new_item),
+                });
+            }
+
+            // Modified items
+            let fields = changed_fields(old_item, new_item);
+            if !fields.is_empty() {
+                landscape_diff.modified.push(ModifiedItem {
+                    id: new_item.id.clone(),
+                    name: new_item.name.clone(),
+                    fields,
+                });
+            }
+
+            // Stats changes
+            let funding = Change::new(funding(old_item), funding(new_item));
+            let stars = Change::new(stars(old_item), stars(new_item));
+            if funding.delta != 0 || stars.delta != 0 {
+                landscape_diff.stats.push(ItemStatsChange {
+                    id: new_item.id.clone(),
+                    name: new_item.name.clone(),
+                    funding,
+                    stars,
+                });
+            }
+        }
+
+        landscape_diff
+    }
+
+    /// Check if any changes were found.
+    fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.modified.is_empty()
+            && self.moved.is_empty()
+            && self.removed.is_empty()
+            && self.stats.is_empty()
+    }
+
+    /// Prepare a human-readable report of the changes (in markdown format, so
+    /// that it can be posted as a comment on a pull request).
+    fn report(&self) -> String {
+        let mut r = String::from("# Landscape changes\n\n");
+        if self.is_empty() {
+            r.push_str("No changes found.\n");
+            return r;
+        }
+
+        // Totals
+        let totals = [
+            ("Items", &self.totals.items),
+            ("GitHub stars", &self.totals.stars),
+            ("Funding", &self.totals.funding),
+        ];
+        r.push_str("| | Old | New | Change |\n|-|-:|-:|-:|\n");
+        for (name, change) in totals {
+            let _ = writeln!(
+                r,
+                "| {name} | {} | {} | {:+} |",
+                change.old, change.new, change.delta
+            );
+        }
+
+        // Items changes
+        if !self.added.is_empty() {
+            let _ = write!(r, "\n## Added items ({})\n\n", self.added.len());
+            for item in &self.added {
+                let _ = writeln!(
+                    r,
+                    "- **{}** ({} / {})",
+                    item.name, item.category, item.subcategory
+                );
+            }
+        }
+        if !self.removed.is_empty() {
+            let _ = write!(r, "\n## Removed items ({})\n\n", self.removed.len());
+            for item in &self.removed {
+                let _ = writeln!(
+                    r,
+                    "- **{}** ({} / {})",
+                    item.name, item.category, item.subcategory
+                );
+            }
+        }
+        if !self.moved.is_empty() {
+            let _ = write!(r, "\n## Moved items ({})\n\n", self.moved.len());
+            for item in &self.moved {
+                let _ = writeln!(
+                    r,
+                    "- **{}**: {} / {} ➜ {} / {}",
+                    item.name,
+                    item.from.category,
+                    item.from.subcategory,
+                    item.to.category,
+                    item.to.subcategory
+                );
+            }
+        }
+        if !self.modified.is_empty() {
+            let _ = write!(r, "\n## Modified items ({})\n\n", self.modified.len());
+            for item in &self.modified {
+                let _ = writeln!(r, "- **{}**: {}", item.name, item.fields.join(", "));
+            }
+        }
+        if !self.stats.is_empty() {
+            let _ = write!(r, "\n## Stats changes ({})\n\n", self.stats.len());
+            r.push_str("| Item | GitHub stars | Funding |\n|-|-:|-:|\n");
+            for item in &self.stats {
+                let _ = writeln!(
+                    r,
+                    "| {} | {:+} | {:+} |",
+                    item.name, item.stars.delta, item.funding.delta
+                );
+            }
+        }
+
+        r
+    }
+}
+
+/// Change of a numeric value between two landscapes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct Change {
+    pub old: i64,
+    pub new: i64,
+    pub delta: i64,
+}
+
+impl Change {
+    /// Create a new change instance.
+    fn new(old: i64, new: i64) -> Self {
+        Self {
+            old,
+            new,
+            delta: new - old,
+        }
+    }
+}
+
+/// Reference to a landscape item.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct ItemRef {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+    pub subcategory: String,
+}
+
+impl From<&Item> for ItemRef {
+    fn from(item: &Item) -> Self {
+        Self {
+            id: item.id.clone(),
+            name: item.name.clone(),
+            category: item.category.clone(),
+            subcategory: item.subcategory.clone(),
+        }
+    }
+}
+
+/// Stats changes of a landscape item.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct ItemStatsChange {
+    pub id: String,
+    pub name: String,
+    pub funding: Change,
+    pub stars: Change,
+}
+
+/// Location of an item in the landscape.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct Location {
+    pub category: String,
+    pub subcategory: String,
+}
+
+impl From<&Item> for Location {
+    fn from(item: &Item) -> Self {
+        Self {
+            category: item.category.clone(),
+            subcategory: item.subcategory.clone(),
+        }
+    }
+}
+
+/// Landscape item that has been modified.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct ModifiedItem {
+    pub id: String,
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+/// Landscape item that has been moved to a different category/subcategory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct MovedItem {
+    pub id: String,
+    pub name: String,
+    pub from: Location,
+    pub to: Location,
+}
+
+/// Landscape totals.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct Totals {
+    pub funding: Change,
+    pub items: Change,
+    pub stars: Change,
+}
+
+/// Return the names of the fields that changed between the two versions of
+/// the item provided. Data collected from external services is not taken into
+/// account (changes in the stats are reported separately).
+fn changed_fields(old_item: &Item, new_item: &Item) -> Vec<String> {
+    let (Value::Object(old), Value::Object(new)) = (item_value(old_item), item_value(new_item)) else {
+        return vec![];
+    };
+
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter(|key| !IGNORED_FIELDS.contains(&key.as_str()) && old.get(*key) != new.get(*key))
+        .cloned()
+        .collect()
+}
+
+/// Return the number of items provided.
+fn count(items: &BTreeMap<&str, &Item>) -> i64 {
+    i64::try_from(items.len()).unwrap_or(i64::MAX)
+}
+
+/// Return the funding of the item provided.
+fn funding(item: &Item) -> i64 {
+    item.crunchbase_data.as_ref().and_then(|org| org.funding).unwrap_or_default()
+}
+
+/// Return the json value of the item provided, without the data collected
+/// from external services.
+fn item_value(item: &Item) -> Value {
+    let mut item = item.clone();
+    item.crunchbase_data = None;
+    for repo in item.repositories.iter_mut().flatten() {
+        repo.github_data = None;
+    }
+    serde_json::to_value(item).unwrap_or_default()
+}
+
+/// Read the full dataset from the built landscape directory provided.
+#[instrument(err)]
+fn read_full_dataset(landscape_dir: &Path) -> Result<Full> {
+    let path = landscape_dir.join(DATASETS_PATH).join(FULL_DATASET_FILE);
+    if !path.exists() {
+        return Err(format_err!(
+            "{} not found (is {} a built landscape directory?)",
+            path.display(),
+            landscape_dir.display()
+        ));
+    }
+    let full =
+        serde_json::from_slice(&fs::read(&path)?).context(format!("error parsing {}", path.display()))?;
+
+    Ok(full)
+}
+
+/// Return the number of GitHub stars of the item provided (all repositories).
+fn stars(item: &Item) -> i64 {
+    item.repositories
+        .iter()
+        .flatten()
+        .filter_map(|repo| repo.github_data.as_ref().map(|gh| gh.stars))
+        .sum()
+}
+
+/// Sum the value returned by the function provided for all the items given.
+fn total(items: &BTreeMap<&str, &Item>, value: fn(&Item) -> i64) -> i64 {
+    items.values().map(|item| value(item)).sum()
+}
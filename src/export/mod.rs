@@ -0,0 +1,287 @@
+//! This module defines the functionality of the export CLI subcommand, which
+//! exports a built landscape as a single self-contained HTML file that can be
+//! opened without a server (i.e. in air-gapped environments or to archive a
+//! snapshot of the landscape).
+//!
+//! All the assets used by the landscape website are inlined in the index
+//! document:
+//!
+//! - Stylesheets are embedded in the document, and the web application
+//!   modules are provided as data URIs using an import map (so that the
+//!   modules loaded dynamically keep working).
+//! - Logos, images and fonts are embedded as data URIs.
+//! - The datasets fetched by the web application (full and guide) are
+//!   embedded in the document and served by a small fetch shim.
+
+use crate::{build::DATASETS_PATH, ExportArgs};
+use anyhow::{format_err, Context, Result};
+use base64::{engine::general_purpose::STANDARD as b64, Engine as _};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+    time::Instant,
+};
+use tracing::{debug, info, instrument, warn};
+use walkdir::WalkDir;
+
+/// Path where the web application assets are located in the landscape
+/// directory.
+const ASSETS_PATH: &str = "assets";
+
+/// Datasets fetched by the web application (they are not included in the
+/// index document).
+const FETCHED_DATASETS: [&str; 2] = ["full.json", "guide.json"];
+
+/// Paths containing the images that will be embedded as data URIs.
+const IMAGES_PATHS: [&str; 2] = ["images", "logos"];
+
+/// Prefix of the specifiers used to import the web application modules.
+const MODULES_SPECIFIER_PREFIX: &str = "@landscape2/";
+
+lazy_static! {
+    /// Image reference regular expression (used in the datasets).
+    static ref IMAGE_REF: Regex =
+        Regex::new(r#""(?P<path>(?:images|logos)/[^"]+)""#).expect("exprs in IMAGE_REF to be valid");
+
+    /// Link tag regular expression.
+    static ref LINK_TAG: Regex =
+        Regex::new(r#"<link\s[^>]*href="(?:\./)?assets/(?P<name>[^"]+)"[^>]*>"#).expect("exprs in LINK_TAG to be valid");
+
+    /// Module script tag regular expression.
+    static ref SCRIPT_TAG: Regex =
+        Regex::new(r#"<script\s[^>]*src="(?:\./)?assets/(?P<name>[^"]+)"[^>]*></script>"#)
+            .expect("exprs in SCRIPT_TAG to be valid");
+}
+
+/// Export a built landscape in the format requested.
+#[instrument(skip_all)]
+pub(crate) fn export(args: &ExportArgs) -> Result<()> {
+    if !args.single_file {
+        return Err(format_err!(
+            "no export mode selected (only --single-file is supported at the moment)"
+        ));
+    }
+
+    info!("exporting landscape to a single file..");
+    let start = Instant::now();
+
+    // Prepare single file and check its size is within the budget
+    let html = export_single_file(&args.landscape_dir)?;
+    let size = html.len() as u64;
+    let size_budget = args.size_budget.saturating_mul(1024 * 1024);
+    if size > size_budget {
+        warn!(
+            size,
+            size_budget, "single file exceeds the size budget, it may be slow to open (or share)"
+        );
+    }
+    fs::write(&args.output_file, html)
+        .context(format!("error writing file {}", args.output_file.display()))?;
+
+    let duration = start.elapsed().as_secs_f64();
+    info!(size, "landscape exported! (took: {:.3}s)", duration);
+
+    Ok(())
+}
+
+/// Prepare a single HTML file including the landscape website located in the
+/// directory provided, inlining all its assets.
+fn export_single_file(landscape_dir: &Path) -> Result<String> {
+    let index_file = landscape_dir.join("index.html");
+    if !index_file.exists() {
+        return Err(format_err!(
+            "index.html not found in landscape dir (is it a built landscape?)"
+        ));
+    }
+    let mut html = fs::read_to_string(index_file)?;
+
+    // Prepare the images data URIs and embed them in the index document (the
+    // base dataset is included in it)
+    let images = read_images(landscape_dir)?;
+    html = embed_images(&html, &images);
+
+    // Prepare the web application assets
+    let assets = read_assets(landscape_dir)?;
+    let mut assets_uris: HashMap<String, String> = HashMap::new();
+    for (name, content) in &assets {
+        if !has_extension(name, "css") && !has_extension(name, "js") {
+            assets_uris.insert(name.clone(), data_uri(name, content));
+        }
+    }
+    let mut stylesheets: HashMap<String, String> = HashMap::new();
+    for (name, content) in &assets {
+        if has_extension(name, "css") {
+            let mut css = String::from_utf8_lossy(content).into_owned();
+            for (asset_name, uri) in &assets_uris {
+                css = css.replace(&format!("./{asset_name}"), uri);
+            }
+            assets_uris.insert(name.clone(), data_uri(name, css.as_bytes()));
+            stylesheets.insert(name.clone(), css);
+        }
+    }
+    let mut modules: BTreeMap<String, String> = BTreeMap::new();
+    for (name, content) in &assets {
+        if has_extension(name, "js") {
+            let js = rewrite_module(&String::from_utf8_lossy(content), &assets, &assets_uris);
+            modules.insert(
+                format!("{MODULES_SPECIFIER_PREFIX}{name}"),
+                data_uri(name, js.as_bytes()),
+            );
+        }
+    }
+
+    // Inline stylesheets and load the entry modules using their specifiers
+    html = LINK_TAG
+        .replace_all(&html, |c: &Captures| match stylesheets.get(&c["name"]) {
+            Some(css) if c[0].contains(r#"rel="stylesheet""#) => format!("<style>{css}</style>"),
+            _ => String::new(),
+        })
+        .into_owned();
+    html = SCRIPT_TAG
+        .replace_all(&html, |c: &Captures| {
+            format!(
+                r#"<script type="module">import "{MODULES_SPECIFIER_PREFIX}{}";</script>"#,
+                &c["name"]
+            )
+        })
+        .into_owned();
+
+    // Add the import map and the fetch shim before any other script
+    let import_map = json!({ "imports": modules });
+    let head = format!(
+        r#"<head><script type="importmap">{}</script><script>{}</script>"#,
+        escape_script(&import_map.to_string()),
+        fetch_shim(landscape_dir, &images)
+    );
+    if !html.contains("<head>") {
+        return Err(format_err!("head element not found in index.html"));
+    }
+    html = html.replacen("<head>", &head, 1);
+
+    Ok(html)
+}
+
+/// Return a data URI including the content of the file provided.
+fn data_uri(name: &str, content: &[u8]) -> String {
+    let mime_type = mime_guess::from_path(name).first_or_octet_stream();
+    format!("data:{};base64,{}", mime_type.essence_str(), b64.encode(content))
+}
+
+/// Replace the images references in the content provided (i.e. a dataset) by
+/// their data URIs.
+fn embed_images(content: &str, images: &HashMap<String, String>) -> String {
+    IMAGE_REF
+        .replace_all(content, |c: &Captures| match images.get(&c["path"]) {
+            Some(uri) => format!(r#""{uri}""#),
+            None => c[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// Escape the content provided so that it can be included in a script
+/// element.
+fn escape_script(content: &str) -> String {
+    content.replace("</", r"<\/")
+}
+
+/// Prepare a fetch shim that serves the datasets fetched by the web
+/// application from the document (with their images embedded).
+fn fetch_shim(landscape_dir: &Path, images: &HashMap<String, String>) -> String {
+    let mut files = serde_json::Map::new();
+    for dataset in FETCHED_DATASETS {
+        let path = Path::new(DATASETS_PATH).join(dataset);
+        let Ok(content) = fs::read_to_string(landscape_dir.join(&path)) else {
+            debug!(?path, "dataset not found, skipping it");
+            continue;
+        };
+        files.insert(
+            path.to_string_lossy().into_owned(),
+            embed_images(&content, images).into(),
+        );
+    }
+
+    format!(
+        r#"(() => {{
+  const files = {};
+  const fetch = window.fetch.bind(window);
+  window.fetch = (input, init) => {{
+    const url = typeof input === "string" ? input : input.url;
+    const file = Object.keys(files).find((f) => url === f || url === "./" + f || url.endsWith("/" + f));
+    if (file !== undefined) {{
+      return Promise.resolve(new Response(files[file], {{ headers: {{ "Content-Type": "application/json" }} }}));
+    }}
+    return fetch(input, init);
+  }};
+}})();"#,
+        escape_script(&serde_json::Value::Object(files).to_string())
+    )
+}
+
+/// Check if the name of the file provided has the extension provided.
+fn has_extension(name: &str, extension: &str) -> bool {
+    Path::new(name).extension().is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Read the web application assets available in the landscape directory.
+fn read_assets(landscape_dir: &Path) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut assets = BTreeMap::new();
+    let assets_path = landscape_dir.join(ASSETS_PATH);
+    for entry in fs::read_dir(assets_path).context("error reading web application assets")? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            assets.insert(name, fs::read(entry.path())?);
+        }
+    }
+    Ok(assets)
+}
+
+/// Read the images (logos included) available in the landscape directory,
+/// returning their data URIs by path.
+fn read_images(landscape_dir: &Path) -> Result<HashMap<String, String>> {
+    let mut images = HashMap::new();
+    for images_path in IMAGES_PATHS {
+        for entry in WalkDir::new(landscape_dir.join(images_path)).into_iter().filter_map(Result::ok) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path().strip_prefix(landscape_dir)?.to_string_lossy().into_owned();
+            images.insert(path.clone(), data_uri(&path, &fs::read(entry.path())?));
+        }
+    }
+    Ok(images)
+}
+
+/// Rewrite the references to other assets in the module provided. Modules
+/// imported are referenced using their specifiers (resolved by the import
+/// map), and the other assets using their data URIs. Modules preloaded by
+/// the web application (referenced by their path) are resolved by the import
+/// map as well, so an empty module is used instead.
+fn rewrite_module(
+    js: &str,
+    assets: &BTreeMap<String, Vec<u8>>,
+    assets_uris: &HashMap<String, String>,
+) -> String {
+    let mut js = js.to_string();
+    for name in assets.keys() {
+        for quote in ['"', '\''] {
+            let (relative_ref, path_ref) = (
+                format!("{quote}./{name}{quote}"),
+                format!("{quote}{ASSETS_PATH}/{name}{quote}"),
+            );
+            if has_extension(name, "js") {
+                let specifier = format!("{quote}{MODULES_SPECIFIER_PREFIX}{name}{quote}");
+                js = js.replace(&relative_ref, &specifier);
+                js = js.replace(&path_ref, &format!("{quote}data:text/javascript,{quote}"));
+            } else if let Some(uri) = assets_uris.get(name) {
+                js = js.replace(&relative_ref, &format!("{quote}{uri}{quote}"));
+                js = js.replace(&path_ref, &format!("{quote}{uri}{quote}"));
+            }
+        }
+    }
+    js
+}
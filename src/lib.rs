@@ -0,0 +1,1200 @@
+//! Landscape2 library, used to build and deploy landscape websites. The
+//! landscape2 CLI tool is a thin wrapper around it, but the build and deploy
+//! functionality can also be used programmatically.
+
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::doc_markdown)]
+
+use anyhow::Result;
+pub use build::{
+    build, BuildConfig, BuildOutput, DataCollector, DataCollectors, DataFormat, ExportFormat, Item,
+    QrCodeEcLevel,
+};
+use build::{build_landscapes, parse_since_date, DEFAULT_CACHE_TTL, DEFAULT_COLLECT_CONCURRENCY};
+use chrono::NaiveDate;
+use clap::{ArgAction, Args, Parser, Subcommand};
+pub use deploy::s3::{
+    deploy as deploy_s3, CacheControlRule, Compression, DeployError, S3DeployOptions, S3Target,
+    ServerSideEncryption, Tag,
+};
+use deploy::{azure, gcs, local, netlify, parse_duration, s3};
+use diff::diff;
+use export::export;
+use new::{new, Template};
+use schema::schema;
+use serde::Deserialize;
+use serve::serve;
+use std::{io, path::PathBuf, time::Duration};
+use tracing_subscriber::EnvFilter;
+use validate::{validate_data, validate_guide, validate_settings, OutputFormat};
+
+mod build;
+mod deploy;
+mod diff;
+mod export;
+mod metrics;
+mod new;
+mod schema;
+mod serve;
+mod validate;
+
+/// CLI arguments.
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Landscape2 CLI tool
+
+https://github.com/cncf/landscape2#usage"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Only display landscape2 warnings and errors in the logs (the deploy
+    /// upload progress bar is not displayed either).
+    #[arg(
+        short,
+        long,
+        global = true,
+        default_value_t = false,
+        conflicts_with = "verbose"
+    )]
+    quiet: bool,
+
+    /// Increase the verbosity of the logs (-v displays the landscape2 debug
+    /// logs, and -vv the trace ones, including the AWS SDK requests and
+    /// retries). Only the landscape2 info logs are displayed by default. The
+    /// RUST_LOG environment variable takes precedence when provided.
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+}
+
+/// Commands available.
+#[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
+enum Command {
+    /// Build landscape website.
+    Build(BuildArgs),
+
+    /// Deploy landscape website (experimental).
+    Deploy(DeployArgs),
+
+    /// Compare two built landscapes and report the changes between them.
+    Diff(DiffArgs),
+
+    /// Export a built landscape as a single self-contained HTML file.
+    Export(ExportArgs),
+
+    /// Create a new landscape from the built-in template.
+    New(NewArgs),
+
+    /// Generate the JSON Schema of the landscape data, settings and guide
+    /// files.
+    Schema(SchemaArgs),
+
+    /// Serve landscape website.
+    Serve(ServeArgs),
+
+    /// Validate landscape data sources files.
+    Validate(ValidateArgs),
+}
+
+/// Build command arguments.
+#[derive(Args)]
+#[allow(clippy::struct_excessive_bools)]
+struct BuildArgs {
+    /// File where the redirects from the categories and subcategories aliases
+    /// (defined in the settings) will be written to, using the format of the
+    /// S3 deploy redirects file (--redirects-file), so that they are served as
+    /// 301 redirects. When not provided, redirect pages are written to the
+    /// output directory instead.
+    #[arg(long)]
+    aliases_redirects_file: Option<PathBuf>,
+
+    /// Report the items logos that are missing (or that are not SVG files) as
+    /// warnings instead of making the build fail (only checked when the logos
+    /// are available locally).
+    #[arg(long, default_value_t = false)]
+    allow_missing_logos: bool,
+
+    /// File containing the analytics (or tracking) snippet to inject into the
+    /// index document, replacing the analytics configured in the settings file
+    /// (i.e. to use a different tracking id for each environment). The snippet
+    /// is validated before being injected.
+    #[arg(long, conflicts_with = "no_analytics")]
+    analytics_snippet: Option<PathBuf>,
+
+    /// Base path the landscape website will be served from (i.e.
+    /// /landscapes/cncf/), when it is not served from the root of the domain.
+    /// A base element is added to the index document and the web application
+    /// links are resolved relative to it.
+    #[arg(long)]
+    base_href: Option<String>,
+
+    /// Write a build-info.json file to the output directory, including the
+    /// git commit of the landscape data, the landscape2 version and the build
+    /// timestamp.
+    #[arg(long, default_value_t = false)]
+    build_info: bool,
+
+    /// Cache directory.
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Number of days the data collected from external services (GitHub,
+    /// Crunchbase, CLOMonitor) is reused from the cache.
+    #[arg(long, default_value_t = DEFAULT_CACHE_TTL)]
+    cache_ttl: u32,
+
+    /// Maximum number of requests to external services (GitHub, Crunchbase,
+    /// CLOMonitor and the packages registries) in flight at the same time,
+    /// shared by all the collectors.
+    #[arg(long, default_value_t = DEFAULT_COLLECT_CONCURRENCY)]
+    collect_concurrency: usize,
+
+    /// Format of the landscape data file (json, toml or yaml). When not
+    /// provided, it's detected from the file extension (yaml is used by
+    /// default).
+    #[arg(long, value_enum)]
+    data_format: Option<DataFormat>,
+
+    /// Data source.
+    #[command(flatten)]
+    data_source: DataSource,
+
+    /// Generate reproducible outputs: the JSON files are written with their
+    /// maps keys sorted, and the build timestamp is pinned to SOURCE_DATE_EPOCH
+    /// (or omitted when it's not set). The time the data was collected from
+    /// external services at is pinned to SOURCE_DATE_EPOCH as well (or to the
+    /// Unix epoch when it's not set).
+    #[arg(long, default_value_t = false)]
+    deterministic: bool,
+
+    /// Category to exclude from the landscape, along with its items (it can be
+    /// provided multiple times).
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Make the build fail when any data quality warnings are found (i.e.
+    /// missing logos allowed using --allow-missing-logos, orphaned logos or
+    /// maturity mismatches). By default, they are only reported.
+    #[arg(long, default_value_t = false)]
+    fail_on_warning: bool,
+
+    /// Formats of the items export files to generate (comma separated list).
+    /// The ndjson format generates a newline delimited JSON file (one item per
+    /// line), which is easier to ingest by other tools.
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [ExportFormat::Csv])]
+    formats: Vec<ExportFormat>,
+
+    /// Guide source.
+    #[command(flatten)]
+    guide_source: GuideSource,
+
+    /// Reuse the web assets and the landscape screenshot of the previous
+    /// build to the same output directory when their inputs haven't changed.
+    /// The rest of the outputs (datasets, logos, exports, etc) are always
+    /// generated.
+    #[arg(long, default_value_t = false)]
+    incremental: bool,
+
+    /// Generate a QR code for each item linking to its permalink (i.e. for
+    /// printed landscape posters). QR codes are written to the qr-codes
+    /// directory, named <item id>.svg. The landscape url must be set in the
+    /// settings file.
+    #[arg(long, default_value_t = false)]
+    items_qr_codes: bool,
+
+    /// YAML file listing the landscapes to build in a single invocation (it
+    /// replaces the data, settings, guide and logos sources). Each landscape
+    /// is built to a directory named after it in the output directory, using
+    /// the other build options provided.
+    #[arg(long, groups = ["DataSource", "LogosSource", "SettingsSource"])]
+    landscapes_file: Option<PathBuf>,
+
+    /// Write a report of the logos with identical content referenced by the
+    /// items using different file names to the file provided (in JSON
+    /// format), including the canonical file name suggested for each of them.
+    #[arg(long)]
+    logos_duplicates_report: Option<PathBuf>,
+
+    /// Generate a PNG version of each logo, scaled so that its longest side
+    /// matches the size provided (in pixels). PNG logos are written next to
+    /// the SVG ones, named <digest>-<size>.png.
+    #[arg(long)]
+    logos_png_size: Option<u32>,
+
+    /// Logos source.
+    #[command(flatten)]
+    logos_source: LogosSource,
+
+    /// File where the build metrics (duration of the build and its phases,
+    /// number of items and warnings and whether the build succeeded) will be
+    /// written to in the Prometheus text format, i.e. to be collected by the
+    /// node_exporter textfile collector. It's written even if the build fails.
+    #[arg(long)]
+    metrics_file: Option<PathBuf>,
+
+    /// Do not optimize the SVG logos (removing comments and metadata and
+    /// collapsing whitespace). Logos are always sanitized.
+    #[arg(long, default_value_t = false)]
+    no_logo_optimize: bool,
+
+    /// Do not inject any analytics snippet into the index document, even if
+    /// analytics are configured in the settings file (i.e. for staging
+    /// deployments).
+    #[arg(long, default_value_t = false)]
+    no_analytics: bool,
+
+    /// Category to include in the landscape (it can be provided multiple
+    /// times). When provided, the landscape website is built only with the
+    /// categories (and items) selected, i.e. to generate focused
+    /// sub-landscapes.
+    #[arg(long)]
+    only: Vec<String>,
+
+    /// Output directory to write files to.
+    #[arg(long)]
+    output_dir: PathBuf,
+
+    /// Landscape data overlay (a data file, or a directory containing a
+    /// landscape.yml file) to merge on top of the data source (it can be
+    /// provided multiple times, later overlays take precedence). Items are
+    /// matched by id and replaced as a whole, and new categories,
+    /// subcategories and items are appended.
+    #[arg(long)]
+    overlay: Vec<PathBuf>,
+
+    /// Authoritative projects status list (local file or url), used to check
+    /// that the maturity (and TAG) declared by the items match the ones in it.
+    /// Mismatches are reported as warnings.
+    #[arg(long)]
+    projects_status: Option<String>,
+
+    /// Remove the logos files in the logos path that are not referenced by
+    /// any item (they are reported as warnings otherwise), i.e. the logos of
+    /// the items removed from the landscape.
+    #[arg(long, default_value_t = false, requires = "logos_path")]
+    prune_orphaned_logos: bool,
+
+    /// Error correction level of the items QR codes.
+    #[arg(long, value_enum, default_value_t = QrCodeEcLevel::Medium)]
+    qr_codes_ec_level: QrCodeEcLevel,
+
+    /// Size (in pixels) of each module (square) of the items QR codes.
+    #[arg(long, default_value_t = 8)]
+    qr_codes_module_size: u32,
+
+    /// Ignore the data collected from external services available in the cache
+    /// and collect it again (the cache will be updated).
+    #[arg(long, default_value_t = false)]
+    refresh: bool,
+
+    /// Rewrite the references to duplicate logos (logos with identical content
+    /// referenced using different file names) in the landscape data file, so
+    /// that all the items use the canonical file name. The duplicate logos
+    /// files can be removed from the logos directory afterwards.
+    #[arg(long, default_value_t = false, requires = "data_file")]
+    rewrite_duplicate_logos: bool,
+
+    /// Category to take a screenshot of, using its permalink (it can be
+    /// provided multiple times). Categories screenshots are written to the
+    /// docs directory, named landscape-<category>.png.
+    #[arg(long)]
+    screenshot_category: Vec<String>,
+
+    /// Take a full-page screenshot of the landscape using Chrome/Chromium in
+    /// headless mode, using the width provided (in pixels). This overrides
+    /// the screenshot_width value in the settings file.
+    #[arg(long)]
+    screenshot_width: Option<u32>,
+
+    /// Generate a precomputed search index (data/search-index.json) that the
+    /// web application can load directly instead of building it in the
+    /// browser.
+    #[arg(long, default_value_t = false)]
+    search_index: bool,
+
+    /// Format of the landscape settings file (json, toml or yaml). When not
+    /// provided, it's detected from the file extension (yaml is used by
+    /// default).
+    #[arg(long, value_enum)]
+    settings_format: Option<DataFormat>,
+
+    /// Landscape settings overlay file (i.e. with the settings specific to an
+    /// environment), merged on top of the settings source. Maps are merged
+    /// deeply, and any other value defined in the overlay replaces the one in
+    /// the settings.
+    #[arg(long)]
+    settings_overlay: Option<PathBuf>,
+
+    /// Settings source.
+    #[command(flatten)]
+    settings_source: SettingsSource,
+
+    /// Generate an additional stats file (data/stats-since-<date>.json) scoped
+    /// to the items added, and the events that happened, since this date. It
+    /// can be provided in ISO-8601 format (2023-10-01) or relative to the
+    /// current date (90d, 12w, 6m, 1y).
+    #[arg(long, value_parser = parse_since_date)]
+    stats_since: Option<NaiveDate>,
+}
+
+/// Landscape data location.
+#[derive(Args, Clone, Debug, Default, Deserialize)]
+#[group(required = true, multiple = false)]
+pub struct DataSource {
+    /// Landscape data file local path.
+    #[arg(long)]
+    pub data_file: Option<PathBuf>,
+
+    /// Git repository containing the landscape data file, using the format
+    /// <url>[#[<ref>][:<path>]] (HEAD and landscape.yml by default). The
+    /// repository is checked out in the cache, and the cached copy is used
+    /// when it cannot be fetched.
+    #[arg(long)]
+    pub data_repo: Option<String>,
+
+    /// Landscape data file url. A copy of the file is cached, and used when
+    /// the url cannot be reached. Its integrity can be verified by including
+    /// the expected SHA-256 digest in the url fragment (#sha256=<digest>).
+    #[arg(long)]
+    pub data_url: Option<String>,
+}
+
+/// Landscape guide location.
+#[derive(Args, Clone, Debug, Default, Deserialize)]
+#[group(required = false, multiple = false)]
+pub struct GuideSource {
+    /// Landscape guide file local path.
+    #[arg(long)]
+    pub guide_file: Option<PathBuf>,
+
+    /// Landscape guide file url.
+    #[arg(long)]
+    pub guide_url: Option<String>,
+}
+
+/// Landscape logos location.
+#[derive(Args, Clone, Debug, Default, Deserialize)]
+#[group(required = true, multiple = false)]
+pub struct LogosSource {
+    /// Local path where the logos are stored.
+    #[arg(long)]
+    pub logos_path: Option<PathBuf>,
+
+    /// Base URL where the logos are hosted.
+    #[arg(long)]
+    pub logos_url: Option<String>,
+}
+
+/// Landscape settings location.
+#[derive(Args, Clone, Debug, Default, Deserialize)]
+#[group(required = true, multiple = false)]
+pub struct SettingsSource {
+    /// Landscape settings file local path.
+    #[arg(long)]
+    pub settings_file: Option<PathBuf>,
+
+    /// Landscape settings file url.
+    #[arg(long)]
+    pub settings_url: Option<String>,
+}
+
+/// Deploy command arguments.
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct DeployArgs {
+    /// Provider used to deploy the landscape website.
+    #[command(subcommand)]
+    provider: Provider,
+}
+
+/// Provider used to deploy the landscape website.
+#[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
+enum Provider {
+    /// Deploy landscape website to Azure Blob Storage.
+    Azure(AzureArgs),
+
+    /// Deploy landscape website to Google Cloud Storage.
+    Gcs(GcsArgs),
+
+    /// Deploy landscape website to a local directory.
+    Local(LocalArgs),
+
+    /// Deploy landscape website to Netlify.
+    Netlify(NetlifyArgs),
+
+    /// Deploy landscape website to AWS S3.
+    S3(S3Args),
+}
+
+/// Azure Blob Storage provider arguments.
+#[derive(Args)]
+struct AzureArgs {
+    /// Maximum number of files to upload concurrently.
+    #[arg(long, default_value_t = 20)]
+    concurrency: usize,
+
+    /// Report the changes that would be made without modifying the container.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Location of the landscape website files (build subcommand output).
+    #[arg(long)]
+    landscape_dir: PathBuf,
+}
+
+/// Google Cloud Storage provider arguments.
+#[derive(Args)]
+struct GcsArgs {
+    /// Bucket to copy the landscape website files to.
+    #[arg(long)]
+    bucket: String,
+
+    /// Maximum number of files to upload concurrently.
+    #[arg(long, default_value_t = 20)]
+    concurrency: usize,
+
+    /// Report the changes that would be made without modifying the bucket.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Location of the landscape website files (build subcommand output).
+    #[arg(long)]
+    landscape_dir: PathBuf,
+
+    /// Delete objects in the bucket that are not present in the landscape
+    /// directory anymore.
+    #[arg(long, default_value_t = false)]
+    prune: bool,
+}
+
+/// Local provider arguments.
+#[derive(Args)]
+struct LocalArgs {
+    /// Maximum number of files to copy concurrently.
+    #[arg(long, default_value_t = 20)]
+    concurrency: usize,
+
+    /// Directory to copy the landscape website files to (i.e. a web server
+    /// document root).
+    #[arg(long)]
+    dest_dir: PathBuf,
+
+    /// Report the changes that would be made without modifying the
+    /// destination directory.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Location of the landscape website files (build subcommand output).
+    #[arg(long)]
+    landscape_dir: PathBuf,
+
+    /// Delete files in the destination directory that are not present in the
+    /// landscape directory anymore.
+    #[arg(long, default_value_t = false)]
+    prune: bool,
+}
+
+/// Netlify provider arguments.
+#[derive(Args)]
+struct NetlifyArgs {
+    /// Maximum number of files to upload concurrently.
+    #[arg(long, default_value_t = 20)]
+    concurrency: usize,
+
+    /// Create a draft deploy (i.e. a preview) instead of publishing it to the
+    /// site's main url.
+    #[arg(long, default_value_t = false)]
+    draft: bool,
+
+    /// Report the files that would be included in the deploy without
+    /// creating it.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Location of the landscape website files (build subcommand output).
+    #[arg(long)]
+    landscape_dir: PathBuf,
+
+    /// Title of the deploy (displayed in the Netlify UI).
+    #[arg(long)]
+    title: Option<String>,
+}
+
+/// AWS S3 provider arguments.
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[allow(clippy::struct_excessive_bools)]
+struct S3Args {
+    /// Write a compressed tarball (tar.gz) with the website files deployed to
+    /// the file provided once the deploy has completed, to keep an artifact of
+    /// each deploy (i.e. to roll it back). The archive is deterministic, so
+    /// the same files always produce the same archive.
+    #[arg(long, conflicts_with_all = ["index_only", "skip_index"])]
+    archive: Option<PathBuf>,
+
+    /// ARN of the role to assume before deploying (the credentials available
+    /// will be used to assume it).
+    #[arg(long)]
+    assume_role_arn: Option<String>,
+
+    /// Bucket to copy the landscape website files to.
+    #[arg(long, required = true)]
+    bucket: Option<String>,
+
+    /// Cache-Control rule in the glob=value format (e.g. "data/*.json=max-age=3600"),
+    /// used to set the Cache-Control header of the objects whose key matches the
+    /// glob. It can be provided multiple times. Rules provided take precedence
+    /// over the default ones.
+    #[arg(long)]
+    cache_control: Vec<CacheControlRule>,
+
+    /// CloudFront distribution where the paths that changed will be
+    /// invalidated once the deploy has completed.
+    #[arg(long)]
+    cloudfront_distribution_id: Option<String>,
+
+    /// Additional S3 command (the landscape website is deployed when none is
+    /// provided).
+    #[command(subcommand)]
+    command: Option<S3Command>,
+
+    /// Compare the files' MD5 digest against the deployed objects' ETag to
+    /// decide if they need to be uploaded again, instead of using their
+    /// modification time. It cannot be used with aws:kms server-side
+    /// encryption, as the ETag is not the digest of the content in that case.
+    #[arg(long, default_value_t = false)]
+    compare_hash: bool,
+
+    /// Compress text based files (like HTML, JS, CSS or JSON) before
+    /// uploading them, using the algorithm provided. Files are uploaded using
+    /// their original key and the corresponding Content-Encoding header, so
+    /// all clients (i.e. CloudFront viewers) will receive the compressed
+    /// version: brotli is only supported by browsers over HTTPS.
+    #[arg(long, value_enum)]
+    compress: Option<Compression>,
+
+    /// Minimum size (in bytes) a file must have to be compressed.
+    #[arg(long, default_value_t = 1024)]
+    compress_min_size: usize,
+
+    /// Number of files to upload concurrently.
+    #[arg(long, default_value_t = 20)]
+    concurrency: usize,
+
+    /// Upload the index document only if it hasn't changed since the bucket
+    /// objects were listed (using its ETag), so that concurrent deploys fail
+    /// instead of silently overwriting each other.
+    #[arg(long, default_value_t = false)]
+    conditional_index: bool,
+
+    /// Confirm the deletion of the stale objects when pruning, required when
+    /// their number exceeds the prune threshold.
+    #[arg(long, default_value_t = false, requires = "prune")]
+    confirm_prune: bool,
+
+    /// YAML (or JSON) file mapping extensions to content types (i.e.
+    /// webmanifest: application/manifest+json). They take precedence over the
+    /// content types guessed from the files extensions.
+    #[arg(long)]
+    content_type_map: Option<PathBuf>,
+
+    /// Content type used for the files whose content type cannot be detected.
+    #[arg(long, default_value = "application/octet-stream")]
+    default_content_type: String,
+
+    /// Maximum duration of the deploy (i.e. 20m). When it's exceeded, no new
+    /// uploads are started, the ones in flight are completed and the deploy
+    /// stops reporting the number of files uploaded and remaining (the index
+    /// document is NOT updated). The process exits with status code 3 in this
+    /// case (when deploying to multiple targets, only if all the targets that
+    /// failed were stopped by the deadline), and the deploy can be completed
+    /// later using --resume. It can be provided in seconds or using the s, m,
+    /// h or d units.
+    #[arg(long, value_parser = parse_duration)]
+    deadline: Option<Duration>,
+
+    /// Display the changes that would be made to the bucket, without
+    /// actually making them.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Custom endpoint url, used to deploy to S3 compatible services like
+    /// MinIO, Cloudflare R2 or Backblaze B2. A valid region must still be set
+    /// in AWS_REGION, but a dummy value can be used for services that don't
+    /// use regions (i.e. "auto" for Cloudflare R2).
+    #[arg(long)]
+    endpoint_url: Option<String>,
+
+    /// Use path-style addressing (required by some S3 compatible services).
+    #[arg(long, default_value_t = false)]
+    force_path_style: bool,
+
+    /// YAML file with the headers to set on the objects whose key matches a
+    /// glob (i.e. Content-Disposition for downloads), as a list of rules with
+    /// a glob and a map of headers. Only Content-Disposition, Content-Language
+    /// and user metadata headers (x-amz-meta-) can be set, as S3 cannot serve
+    /// other custom headers (they must be set by the CDN). The objects deployed
+    /// before the file was last modified are uploaded again.
+    #[arg(long)]
+    header_rules: Option<PathBuf>,
+
+    /// Gitignore-style pattern of the files that should not be deployed. It
+    /// can be provided multiple times, and is applied after the rules in the
+    /// .landscapeignore file in the landscape directory (if any).
+    #[arg(long)]
+    ignore: Vec<String>,
+
+    /// Glob pattern of the files that should be uploaded (i.e. "assets/*.css"),
+    /// used to deploy a small change quickly when the files modified are
+    /// known. It can be provided multiple times. When provided, the objects in
+    /// the bucket are not listed and all the files matching the patterns are
+    /// uploaded (the index document is only uploaded, last, if it matches).
+    #[arg(
+        long,
+        conflicts_with_all = ["archive", "conditional_index", "index_only", "manifest", "prune", "rollback_on_failure"]
+    )]
+    include: Vec<String>,
+
+    /// Only upload the index document, i.e. to make the website files staged
+    /// previously using --skip-index go live (the other files are expected to
+    /// have been uploaded already).
+    #[arg(long, default_value_t = false, conflicts_with = "skip_index")]
+    index_only: bool,
+
+    /// Location of the landscape website files (build subcommand output).
+    #[arg(long, required = true)]
+    landscape_dir: Option<PathBuf>,
+
+    /// Use a manifest with the objects deployed (written to the bucket after
+    /// each deploy) instead of listing all the objects in the bucket.
+    #[arg(long, default_value_t = false)]
+    manifest: bool,
+
+    /// Maximum number of bytes of the files being uploaded at the same time
+    /// (up to 4GiB). When provided, uploads are bounded by the size of the
+    /// files buffered in memory (files compressed before uploading them)
+    /// instead of by their number, preventing memory spikes when deploying
+    /// large files from memory-constrained environments. Files that are not
+    /// compressed are streamed from disk.
+    #[arg(long, conflicts_with = "concurrency")]
+    max_in_flight_bytes: Option<u64>,
+
+    /// Maximum number of times a failed request will be retried (only when
+    /// the error is retryable, like throttling or server errors).
+    #[arg(long, default_value_t = 4)]
+    max_retries: u32,
+
+    /// File where the deploy metrics (duration of the deploy and its phases,
+    /// number of files uploaded and skipped, bytes transferred and whether the
+    /// deploy succeeded) will be written to in the Prometheus text format, i.e.
+    /// to be collected by the node_exporter textfile collector. It's written
+    /// even if the deploy fails. When deploying to multiple targets, each of
+    /// them writes its own file (named after the target bucket).
+    #[arg(long)]
+    metrics_file: Option<PathBuf>,
+
+    /// Size (in bytes) above which files are uploaded using a multipart
+    /// upload.
+    #[arg(long, default_value_t = 100 * 1024 * 1024)]
+    multipart_threshold: usize,
+
+    /// Webhook url (i.e. a Slack incoming webhook) notified when the deploy
+    /// completes or fails. The notification includes the bucket, the deploy
+    /// duration, the number of files uploaded and skipped and the error (if
+    /// any). Errors sending the notification don't make the deploy fail.
+    #[arg(long)]
+    notify_webhook: Option<String>,
+
+    /// Prefix to prepend to all objects keys, used to deploy the landscape
+    /// website under a subpath of the bucket (i.e. landscapes/cncf).
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// Named profile used to load the AWS configuration and credentials (i.e.
+    /// when using SSO). Static credentials are not required when provided.
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Delete objects in the bucket that are not present in the landscape
+    /// directory anymore. It cannot be used when skipping the index document,
+    /// as the one deployed may still use the stale objects.
+    #[arg(long, default_value_t = false, conflicts_with = "skip_index")]
+    prune: bool,
+
+    /// Minimum age of the stale objects deleted when pruning, so that the
+    /// objects recently uploaded by a concurrent deploy are not deleted. It
+    /// can be provided in seconds or using the s, m, h or d units (i.e. 30m).
+    #[arg(long, default_value = "1h", value_parser = parse_duration)]
+    prune_min_age: Duration,
+
+    /// Maximum number of objects that can be deleted when pruning without
+    /// using --confirm-prune. The list of objects to delete (prune plan) is
+    /// always displayed before uploading any files.
+    #[arg(long, default_value_t = 100)]
+    prune_threshold: usize,
+
+    /// YAML file mapping old paths of the website to new ones (i.e. when some
+    /// pages have been moved). An object redirecting to the new location is
+    /// created for each of them, so that S3 websites respond to requests to
+    /// the old paths with a 301 redirect. New locations can be paths in the
+    /// website (relative to the prefix) or absolute urls. Items permalinks
+    /// (`/?item=<id>`) must be redirected to other items permalinks: they are
+    /// uploaded as a map (data/items-redirects.json) the web application uses
+    /// to open the new item.
+    #[arg(long)]
+    redirects_file: Option<PathBuf>,
+
+    /// AWS region of the bucket (the AWS_REGION environment variable is used
+    /// by default).
+    #[arg(long)]
+    region: Option<String>,
+
+    /// Timeout (in seconds) of each attempt of the requests to S3, so that
+    /// hung connections don't block the deploy (requests timing out are
+    /// retried like any other retryable error).
+    #[arg(long)]
+    request_timeout: Option<u64>,
+
+    /// Resume an interrupted deploy, skipping the files uploaded before it
+    /// was interrupted (unless they have been modified since). The files
+    /// uploaded are recorded in a local checkpoint (in the temporary
+    /// directory), which is removed once the deploy completes.
+    #[arg(long, default_value_t = false)]
+    resume: bool,
+
+    /// Restore the previous version of the objects uploaded (or delete them if
+    /// they were new) when some files cannot be uploaded. Requires versioning
+    /// to be enabled in the bucket.
+    #[arg(long, default_value_t = false)]
+    rollback_on_failure: bool,
+
+    /// Upload all the website files except the index document, so that they
+    /// are staged in the bucket without going live (the index document can be
+    /// uploaded later using --index-only).
+    #[arg(long, default_value_t = false)]
+    skip_index: bool,
+
+    /// Commit SHA of the landscape source data, recorded in the objects
+    /// metadata (the GITHUB_SHA environment variable is used by default).
+    #[arg(long)]
+    source_commit: Option<String>,
+
+    /// Server-side encryption used to encrypt the objects uploaded. When
+    /// using aws:kms, the ID of the KMS key must be provided using
+    /// --sse-kms-key-id.
+    #[arg(long, value_enum)]
+    sse: Option<ServerSideEncryption>,
+
+    /// ID (or ARN) of the KMS key used to encrypt the objects uploaded when
+    /// using the aws:kms server-side encryption.
+    #[arg(long, requires = "sse")]
+    sse_kms_key_id: Option<String>,
+
+    /// Write the deploy summary in JSON format to the file provided. When
+    /// deploying to multiple targets, the bucket name is appended to the file
+    /// name of each of the targets summaries (i.e. summary-bucket.json).
+    #[arg(long)]
+    summary_json: Option<PathBuf>,
+
+    /// Tag to set on all the objects uploaded, in the `key=value` format. It
+    /// can be provided multiple times.
+    #[arg(long)]
+    tag: Vec<Tag>,
+
+    /// Additional target the landscape website will be deployed to, in the
+    /// `bucket[:region[:cloudfront_distribution_id]]` format (i.e. to
+    /// replicate it in multiple regions). It can be provided multiple times.
+    /// The website is deployed to all targets concurrently (including the one
+    /// set using --bucket), each of them independently of the others, and the
+    /// deploy fails if any of them fails.
+    #[arg(long)]
+    target: Vec<S3Target>,
+
+    /// Verify the integrity of the objects uploaded, checking that their ETag
+    /// matches the one computed locally from their content. The objects with
+    /// a mismatching ETag are reported as errors once the files are uploaded
+    /// (it cannot be used with buckets encrypting objects with SSE-KMS, as
+    /// their ETag is not computed from their content).
+    #[arg(long, default_value_t = false)]
+    verify: bool,
+}
+
+/// Additional S3 command.
+#[derive(Subcommand)]
+enum S3Command {
+    /// Configure an AWS S3 bucket for static website hosting (before
+    /// deploying the landscape website to it for the first time).
+    Bootstrap(S3BootstrapArgs),
+}
+
+/// AWS S3 bucket bootstrap arguments.
+#[derive(Args)]
+struct S3BootstrapArgs {
+    /// ARN of the role to assume before configuring the bucket (the
+    /// credentials available will be used to assume it).
+    #[arg(long)]
+    assume_role_arn: Option<String>,
+
+    /// Bucket to configure for static website hosting.
+    #[arg(long)]
+    bucket: String,
+
+    /// Display the configuration that would be applied to the bucket, without
+    /// actually applying it.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Custom endpoint url, used with S3 compatible services.
+    #[arg(long)]
+    endpoint_url: Option<String>,
+
+    /// Key of the document returned when an error occurs. The index document
+    /// is used by default, so that the web application handles the routing
+    /// of its paths on the client side.
+    #[arg(long, default_value = "index.html")]
+    error_document: String,
+
+    /// Use path-style addressing (required by some S3 compatible services).
+    #[arg(long, default_value_t = false)]
+    force_path_style: bool,
+
+    /// Named profile used to load the AWS configuration and credentials.
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Apply a bucket policy allowing anyone to read the objects in the
+    /// bucket (the bucket public access block is updated to allow public
+    /// policies, while public ACLs are still blocked). The policy is displayed
+    /// before applying it.
+    #[arg(long, default_value_t = false)]
+    public_read: bool,
+
+    /// AWS region of the bucket (the AWS_REGION environment variable is used
+    /// by default).
+    #[arg(long)]
+    region: Option<String>,
+}
+
+/// Diff command arguments.
+#[derive(Args)]
+struct DiffArgs {
+    /// Write the changes found in JSON format to this file.
+    #[arg(long)]
+    json_file: Option<PathBuf>,
+
+    /// Built landscape directory with the new version of the landscape.
+    #[arg(long)]
+    new_landscape_dir: PathBuf,
+
+    /// Built landscape directory with the old version of the landscape.
+    #[arg(long)]
+    old_landscape_dir: PathBuf,
+}
+
+/// Export command arguments.
+#[derive(Args)]
+struct ExportArgs {
+    /// Location of the landscape website files (build subcommand output).
+    #[arg(long)]
+    landscape_dir: PathBuf,
+
+    /// File to write the exported landscape to.
+    #[arg(long, default_value = "landscape.html")]
+    output_file: PathBuf,
+
+    /// Export the landscape as a single HTML file, inlining all its assets
+    /// (stylesheets, scripts, datasets and images), so that it can be opened
+    /// without a server.
+    #[arg(long, default_value_t = false)]
+    single_file: bool,
+
+    /// Size (in MB) the exported file is expected to fit in. A warning is
+    /// displayed when it's exceeded.
+    #[arg(long, default_value_t = 50)]
+    size_budget: u64,
+}
+
+/// New command arguments.
+#[derive(Args)]
+struct NewArgs {
+    /// Output directory to write files to.
+    #[arg(long)]
+    output_dir: PathBuf,
+
+    /// Template used to create the new landscape.
+    #[arg(long, value_enum, default_value_t = Template::Demo)]
+    template: Template,
+}
+
+/// Schema command arguments.
+#[derive(Args)]
+struct SchemaArgs {
+    /// Output directory to write the schemas files to.
+    #[arg(long)]
+    output_dir: PathBuf,
+}
+
+/// Serve command arguments.
+#[derive(Args)]
+struct ServeArgs {
+    /// Address the web server will listen on.
+    #[arg(long, default_value = "127.0.0.1:8000")]
+    addr: String,
+
+    /// Whether the server should stop gracefully or not. When enabled, the
+    /// server stops accepting new connections when it receives a ctrl+c or
+    /// terminate signal, and waits for the ones in progress to complete.
+    #[arg(long, default_value_t = false)]
+    graceful_shutdown: bool,
+
+    /// Location of the landscape website files (build subcommand output).
+    /// The current path will be used when none is provided.
+    #[arg(long)]
+    landscape_dir: Option<PathBuf>,
+
+    /// Enable silent mode.
+    #[arg(long, default_value_t = false)]
+    silent: bool,
+
+    /// Landscape sources directory to watch for changes (as created by the
+    /// new subcommand: data.yml, settings.yml, guide.yml and logos). When any
+    /// of the sources changes, the landscape will be rebuilt into the
+    /// landscape directory and the browsers connected will be reloaded.
+    #[arg(long)]
+    watch: Option<PathBuf>,
+}
+
+/// Validate command arguments.
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct ValidateArgs {
+    /// Landscape file to validate.
+    #[command(subcommand)]
+    target: ValidateTarget,
+}
+
+/// Validate data command arguments.
+#[derive(Args)]
+#[allow(clippy::struct_excessive_bools)]
+struct ValidateDataArgs {
+    /// Name of an item that is legitimately repeated in the landscape and
+    /// should not be reported as a duplicate (it can be provided multiple
+    /// times).
+    #[arg(long)]
+    allow_duplicate: Vec<String>,
+
+    /// Check that the landscape does not contain items that are likely
+    /// duplicated (items with the same normalized name, homepage or
+    /// repository url).
+    #[arg(long, visible_alias = "duplicates", default_value_t = false)]
+    check_duplicates: bool,
+
+    /// Check that the items' logos are within the size budgets, reporting the
+    /// raster logos found as well (an SVG version should be used instead).
+    #[arg(long, default_value_t = false, requires = "logos_path")]
+    check_logos: bool,
+
+    /// Check that the items' external urls (homepage, repositories, crunchbase
+    /// and twitter) are reachable.
+    #[arg(long, default_value_t = false)]
+    check_urls: bool,
+
+    /// Number of urls to check concurrently.
+    #[arg(long, default_value_t = 10)]
+    concurrency: usize,
+
+    /// Format of the landscape data file (json, toml or yaml). When not
+    /// provided, it's detected from the file extension (yaml is used by
+    /// default).
+    #[arg(long, value_enum)]
+    data_format: Option<DataFormat>,
+
+    /// Data source.
+    #[command(flatten)]
+    data_source: DataSource,
+
+    /// Exit with a non-zero status code when any warnings are found (i.e.
+    /// raster and orphaned logos or maturity mismatches). By default, only
+    /// errors make the validation fail.
+    #[arg(long, default_value_t = false)]
+    fail_on_warning: bool,
+
+    /// Apply some safe automatic fixes to the data file before validating it
+    /// (removing trailing whitespace and urls trailing slashes, lowercasing
+    /// known values and sorting the items of each subcategory by name). The
+    /// file is rewritten preserving comments, and the changes made are
+    /// displayed.
+    #[arg(long, default_value_t = false)]
+    fix: bool,
+
+    /// Local path where the logos are stored (used when checking the logos).
+    #[arg(long)]
+    logos_path: Option<PathBuf>,
+
+    /// Format of the output. The json and sarif formats report the problems
+    /// found as findings, including their location in the data file when
+    /// available (i.e. to be used with GitHub code scanning).
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+
+    /// Authoritative projects status list (local file or url), used to check
+    /// that the maturity (and TAG) declared by the items match the ones in it.
+    /// Mismatches are reported as warnings.
+    #[arg(long)]
+    projects_status: Option<String>,
+
+    /// Size budget for raster logos (in KB).
+    #[arg(long, default_value_t = 100)]
+    raster_logo_size_budget: u64,
+
+    /// Validation rules file (YAML), expressing the fields required or
+    /// forbidden in the items of some categories, the values allowed for some
+    /// fields and the categories that cannot be used. Rules are enforced on
+    /// top of the built-in checks.
+    #[arg(long)]
+    rules_file: Option<PathBuf>,
+
+    /// Urls that will not be checked (urls starting with any of the values
+    /// provided will be skipped).
+    #[arg(long)]
+    skip_url: Vec<String>,
+
+    /// Size budget for SVG logos (in KB).
+    #[arg(long, default_value_t = 50)]
+    svg_logo_size_budget: u64,
+
+    /// Timeout used when checking each url (in seconds).
+    #[arg(long, default_value_t = 10)]
+    timeout: u64,
+}
+
+/// Validate guide command arguments.
+#[derive(Args)]
+struct ValidateGuideArgs {
+    /// Guide source.
+    #[command(flatten)]
+    guide_source: GuideSource,
+
+    /// Format of the output (the json and sarif formats report the problems
+    /// found as findings).
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+}
+
+/// Validate settings command arguments.
+#[derive(Args)]
+struct ValidateSettingsArgs {
+    /// Format of the output (the json and sarif formats report the problems
+    /// found as findings).
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+
+    /// Format of the landscape settings file (json, toml or yaml). When not
+    /// provided, it's detected from the file extension (yaml is used by
+    /// default).
+    #[arg(long, value_enum)]
+    settings_format: Option<DataFormat>,
+
+    /// Settings source.
+    #[command(flatten)]
+    settings_source: SettingsSource,
+}
+
+/// Landscape file to validate.
+#[derive(Subcommand)]
+enum ValidateTarget {
+    /// Validate landscape data file.
+    Data(ValidateDataArgs),
+
+    /// Validate landscape guide file.
+    Guide(ValidateGuideArgs),
+
+    /// Validate landscape settings file.
+    Settings(ValidateSettingsArgs),
+}
+
+/// Run the landscape2 CLI tool, parsing the arguments provided in the command
+/// line and executing the corresponding command.
+///
+/// # Errors
+///
+/// This function will return an error if the command executed fails.
+pub async fn run() -> Result<()> {
+    let cli = Cli::parse();
+
+    // Setup logging
+    setup_logging(&cli);
+
+    // Run command
+    match &cli.command {
+        Command::Build(args) => match &args.landscapes_file {
+            Some(landscapes_file) => build_landscapes(landscapes_file, &args.into()).await?,
+            None => {
+                build(&args.into()).await?;
+            }
+        },
+        Command::Deploy(args) => {
+            match &args.provider {
+                Provider::Azure(args) => azure::deploy(args).await?,
+                Provider::Gcs(args) => gcs::deploy(args).await?,
+                Provider::Local(args) => local::deploy(args).await?,
+                Provider::Netlify(args) => netlify::deploy(args).await?,
+                Provider::S3(args) => match &args.command {
+                    Some(S3Command::Bootstrap(args)) => s3::bootstrap(args).await?,
+                    None => {
+                        // The upload progress bar is hidden in quiet mode
+                        let mut options: S3DeployOptions = args.into();
+                        options.quiet = cli.quiet;
+                        deploy_s3(&options).await?;
+                    }
+                },
+            }
+        }
+        Command::Diff(args) => diff(args)?,
+        Command::Export(args) => export(args)?,
+        Command::New(args) => new(args)?,
+        Command::Schema(args) => schema(args)?,
+        Command::Serve(args) => serve(args).await?,
+        Command::Validate(args) => match &args.target {
+            ValidateTarget::Data(args) => validate_data(args).await?,
+            ValidateTarget::Guide(args) => validate_guide(args).await?,
+            ValidateTarget::Settings(args) => validate_settings(args).await?,
+        },
+    }
+
+    Ok(())
+}
+
+/// Setup the logs subscriber, using the level selected with the verbosity
+/// flags (unless the RUST_LOG environment variable is provided).
+fn setup_logging(cli: &Cli) {
+    let directives = match (cli.quiet, cli.verbose) {
+        (true, _) => "landscape2=warn",
+        (false, 0) => "landscape2=info",
+        (false, 1) => "landscape2=debug",
+        (false, _) => "landscape2=trace,aws_config=debug,aws_smithy_runtime=debug",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(directives));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    // The diff and validate commands report their results in the standard
+    // output (sometimes in a machine-readable format), so logs are only
+    // displayed when requested, and written to the standard error
+    match &cli.command {
+        Command::Build(_)
+        | Command::Deploy(_)
+        | Command::Export(_)
+        | Command::New(_)
+        | Command::Schema(_)
+        | Command::Serve(_) => subscriber.init(),
+        Command::Diff(_) | Command::Validate(_) => {
+            if cli.verbose > 0 {
+                subscriber.with_writer(io::stderr).init();
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! This module defines the functionality used to write some metrics about the
+//! build and deploy processes (durations, counts and the result) to a file in
+//! the Prometheus text format, so that they can be collected by the
+//! node_exporter textfile collector and tracked over time.
+
+use anyhow::{Context, Result};
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Prefix used in the name of all the metrics.
+const METRICS_PREFIX: &str = "landscape2";
+
+/// Set of metrics to be written to the metrics file.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Metrics {
+    metrics: Vec<Metric>,
+}
+
+impl Metrics {
+    /// Add a gauge with the name and value provided.
+    pub(crate) fn gauge(&mut self, name: &str, help: &str, value: f64) -> &mut Self {
+        self.gauge_with_labels(name, help, &[], value)
+    }
+
+    /// Add a sample with the labels and value provided to the gauge with the
+    /// name given (it's created when it doesn't exist yet).
+    pub(crate) fn gauge_with_labels(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        value: f64,
+    ) -> &mut Self {
+        let name = format!("{METRICS_PREFIX}_{name}");
+        let labels = labels.iter().map(|(k, v)| ((*k).to_string(), (*v).to_string())).collect();
+        match self.metrics.iter_mut().find(|metric| metric.name == name) {
+            Some(metric) => metric.samples.push((labels, value)),
+            None => self.metrics.push(Metric {
+                name,
+                help: help.to_string(),
+                samples: vec![(labels, value)],
+            }),
+        }
+        self
+    }
+
+    /// Render the metrics in the Prometheus text format.
+    pub(crate) fn render(&self) -> String {
+        let mut output = String::new();
+        for metric in &self.metrics {
+            let _ = writeln!(output, "# HELP {} {}", metric.name, metric.help);
+            let _ = writeln!(output, "# TYPE {} gauge", metric.name);
+            for (labels, value) in &metric.samples {
+                let labels: Vec<String> =
+                    labels.iter().map(|(k, v)| format!(r#"{k}="{}""#, escape_label_value(v))).collect();
+                if labels.is_empty() {
+                    let _ = writeln!(output, "{} {value}", metric.name);
+                } else {
+                    let _ = writeln!(output, "{}{{{}}} {value}", metric.name, labels.join(","));
+                }
+            }
+        }
+        output
+    }
+
+    /// Write the metrics to the file provided. The file is written atomically
+    /// (using a temporary file that is renamed), so that the textfile
+    /// collector never reads a partially written file.
+    pub(crate) fn write(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, self.render()).context("error writing metrics file")?;
+        fs::rename(&tmp_path, path).context("error writing metrics file")?;
+        Ok(())
+    }
+}
+
+/// Metric (always a gauge) including all its samples.
+#[derive(Debug, Clone)]
+struct Metric {
+    name: String,
+    help: String,
+    samples: Vec<(Vec<(String, String)>, f64)>,
+}
+
+/// Escape the label value provided, as required by the Prometheus text
+/// format.
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', r#"\""#).replace('\n', r"\n")
+}
+
+/// Return the path of the metrics file of the landscape (or target) provided
+/// when multiple ones are processed in the same run, adding its name to the
+/// metrics file name (i.e. metrics-cncf.prom).
+pub(crate) fn metrics_file_for(metrics_file: &Path, name: &str) -> PathBuf {
+    let stem = metrics_file.file_stem().unwrap_or_default().to_string_lossy();
+    let mut file_name = format!("{stem}-{name}");
+    if let Some(extension) = metrics_file.extension() {
+        let _ = write!(file_name, ".{}", extension.to_string_lossy());
+    }
+    metrics_file.with_file_name(file_name)
+}
+
+/// Return the current timestamp (in seconds), used to track when the last run
+/// took place.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn now_timestamp() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as f64)
+        .unwrap_or_default()
+}
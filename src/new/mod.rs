@@ -2,6 +2,7 @@
 
 use crate::NewArgs;
 use anyhow::Result;
+use clap::ValueEnum;
 use rust_embed::RustEmbed;
 use std::{
     fs::{self, File},
@@ -11,15 +12,45 @@ use std::{
 };
 use tracing::{info, instrument};
 
-/// Embed landscape template files into binary.
+/// Embed landscape templates files into binary.
 #[derive(RustEmbed)]
-#[folder = "src/new/template"]
+#[folder = "src/new/templates"]
 struct TemplateFiles;
 
-/// Create a new landscape from the built-in template.
+/// Built-in landscape templates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Template {
+    /// A landscape with a few categories and items, using the most common
+    /// settings.
+    #[default]
+    Demo,
+
+    /// Like the demo landscape, but using most of the data and settings
+    /// options available (members category, TAGs, social cards, etc).
+    Full,
+
+    /// The smallest landscape possible, including only the required fields.
+    Minimal,
+}
+
+impl Template {
+    /// Return the directory of the template in the templates files.
+    fn dir(self) -> &'static str {
+        match self {
+            Template::Demo => "demo",
+            Template::Full => "full",
+            Template::Minimal => "minimal",
+        }
+    }
+}
+
+/// Create a new landscape from the built-in template selected.
 #[instrument(skip_all)]
 pub(crate) fn new(args: &NewArgs) -> Result<()> {
-    info!("creating new landscape from the built-in template..");
+    info!(
+        template = args.template.dir(),
+        "creating new landscape from a built-in template.."
+    );
     let start = Instant::now();
 
     // Setup output directory
@@ -28,12 +59,16 @@ pub(crate) fn new(args: &NewArgs) -> Result<()> {
     }
 
     // Copy template files to the output directory
+    let template_prefix = format!("{}/", args.template.dir());
     for file_path in TemplateFiles::iter() {
+        let Some(relative_path) = file_path.strip_prefix(&template_prefix) else {
+            continue;
+        };
         if let Some(embedded_file) = TemplateFiles::get(&file_path) {
-            if let Some(parent_path) = Path::new(file_path.as_ref()).parent() {
-                fs::create_dir_all(&args.output_dir.join(parent_path))?;
+            if let Some(parent_path) = Path::new(relative_path).parent() {
+                fs::create_dir_all(args.output_dir.join(parent_path))?;
             }
-            let mut file = File::create(&args.output_dir.join(file_path.as_ref()))?;
+            let mut file = File::create(args.output_dir.join(relative_path))?;
             file.write_all(&embedded_file.data)?;
         }
     }
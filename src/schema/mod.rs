@@ -0,0 +1,60 @@
+//! This module defines the functionality of the schema CLI subcommand, which
+//! generates the JSON Schema of the landscape data, settings and guide files.
+//!
+//! The schemas are derived from the types used to deserialize those files, so
+//! they always match what the build and validate subcommands accept.
+
+use crate::{
+    build::{legacy, LandscapeGuide, LandscapeSettings},
+    SchemaArgs,
+};
+use anyhow::{Context, Result};
+use schemars::{schema::RootSchema, schema_for};
+use std::{fs, path::Path, time::Instant};
+use tracing::{debug, info, instrument};
+
+/// Name of the landscape data file schema.
+const DATA_SCHEMA_FILE: &str = "data.schema.json";
+
+/// Name of the landscape guide file schema.
+const GUIDE_SCHEMA_FILE: &str = "guide.schema.json";
+
+/// Name of the landscape settings file schema.
+const SETTINGS_SCHEMA_FILE: &str = "settings.schema.json";
+
+/// Generate the JSON Schema of the landscape data sources files.
+#[instrument(skip_all)]
+pub(crate) fn schema(args: &SchemaArgs) -> Result<()> {
+    info!("generating landscape files schemas..");
+    let start = Instant::now();
+
+    // Setup output directory
+    if !args.output_dir.exists() {
+        fs::create_dir_all(&args.output_dir)?;
+    }
+
+    // Write schemas files
+    let schemas = [
+        (DATA_SCHEMA_FILE, schema_for!(legacy::LandscapeData)),
+        (GUIDE_SCHEMA_FILE, schema_for!(LandscapeGuide)),
+        (SETTINGS_SCHEMA_FILE, schema_for!(LandscapeSettings)),
+    ];
+    for (file_name, schema) in schemas {
+        write_schema(&args.output_dir.join(file_name), &schema)?;
+    }
+
+    let duration = start.elapsed().as_secs_f64();
+    info!("schemas generated! (took: {:.3}s)", duration);
+
+    Ok(())
+}
+
+/// Write the schema provided to the file path given.
+#[instrument(skip(schema), err)]
+fn write_schema(path: &Path, schema: &RootSchema) -> Result<()> {
+    let json = serde_json::to_string_pretty(schema)?;
+    fs::write(path, json).context(format!("error writing schema file {}", path.display()))?;
+    debug!(?path, "schema written");
+
+    Ok(())
+}
@@ -1,32 +1,86 @@
 //! This module defines the functionality of the serve CLI subcommand.
 
-use crate::ServeArgs;
+use self::watch::{inject_live_reload_script, live_reload, watch, LIVE_RELOAD_PATH};
+use crate::{build::CARDS_PATH, ServeArgs};
 use anyhow::Result;
 use axum::{
-    http::{HeaderValue, Request},
+    body::{self, Full},
+    extract::State,
+    http::{header, HeaderValue, Request, StatusCode, Uri},
     middleware::{self, Next},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
+    routing::get,
     Router, Server,
 };
+use lazy_static::lazy_static;
+use regex::Regex;
 use reqwest::header::CACHE_CONTROL;
-use std::{env, net::SocketAddr};
-use tokio::signal;
+use std::{
+    env,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+use tokio::{signal, sync::broadcast};
 use tower_http::services::{ServeDir, ServeFile};
 use tracing::{info, instrument};
 
+mod watch;
+
+/// Path of the health check endpoint.
+const HEALTH_CHECK_PATH: &str = "/healthz";
+
+lazy_static! {
+    /// Open Graph and Twitter image meta tags regular expression.
+    static ref IMAGE_META_TAG: Regex =
+        Regex::new(r#"<meta\s+(?:property="og:image"|name="twitter:image(?::src)?")[^>]*>\s*"#)
+            .expect("exprs in IMAGE_META_TAG to be valid");
+}
+
 /// Serve landscape website.
 #[instrument(skip_all)]
 pub(crate) async fn serve(args: &ServeArgs) -> Result<()> {
-    // Setup router
-    let landscape_dir = args.landscape_dir.clone().unwrap_or(env::current_dir()?);
+    let Some(sources_dir) = &args.watch else {
+        return run_server(args, None).await;
+    };
+
+    // Watch landscape sources for changes while the server is running,
+    // rebuilding the landscape and reloading the browsers connected when they
+    // change
+    let (reload_tx, _) = broadcast::channel(16);
+    let landscape_dir = get_landscape_dir(args)?;
+    tokio::select! {
+        result = run_server(args, Some(reload_tx.clone())) => result,
+        result = watch(sources_dir.clone(), landscape_dir, reload_tx) => result,
+    }
+}
+
+/// Run the HTTP server that serves the landscape website. When a reload
+/// channel is provided, browsers will be notified through it when they should
+/// reload the landscape.
+#[instrument(skip_all)]
+pub(crate) async fn run_server(args: &ServeArgs, reload_tx: Option<broadcast::Sender<()>>) -> Result<()> {
+    // Setup router (files are served supporting range requests, so partial
+    // content is returned when a range is requested, i.e. to seek in media
+    // files or resume downloads)
+    let landscape_dir = get_landscape_dir(args)?;
     let index_path = landscape_dir.join("index.html");
-    let router: Router<()> = Router::new()
+    let mut router: Router<()> = Router::new()
         .nest_service(
             "/",
             ServeDir::new(&landscape_dir).not_found_service(ServeFile::new(&index_path)),
         )
         .fallback_service(ServeFile::new(index_path))
-        .route_layer(middleware::from_fn(set_cache_control_header));
+        .route_layer(middleware::from_fn(set_cache_control_header))
+        .route(HEALTH_CHECK_PATH, get(health_check))
+        .layer(middleware::from_fn_with_state(
+            landscape_dir.clone(),
+            inject_item_card_meta,
+        ));
+    if let Some(reload_tx) = reload_tx {
+        router = router
+            .route(LIVE_RELOAD_PATH, get(move || live_reload(reload_tx.subscribe())))
+            .layer(middleware::from_fn(inject_live_reload_script));
+    }
 
     // Setup and launch HTTP server
     let addr: SocketAddr = args.addr.parse()?;
@@ -41,11 +95,100 @@ pub(crate) async fn serve(args: &ServeArgs) -> Result<()> {
             .await?;
     } else {
         Server::bind(&addr).serve(router.into_make_service()).await?;
-    };
+    }
 
     Ok(())
 }
 
+/// Get the location of the landscape website files (the current path will be
+/// used when none has been provided).
+fn get_landscape_dir(args: &ServeArgs) -> Result<PathBuf> {
+    Ok(args.landscape_dir.clone().unwrap_or(env::current_dir()?))
+}
+
+/// Handler used to check that the server is up and running (i.e. by load
+/// balancers or container orchestrators).
+async fn health_check() -> impl IntoResponse {
+    "OK"
+}
+
+/// Get the id of the item requested when the uri provided is an item permalink
+/// (`/?item=<id>`).
+fn get_item_id(uri: &Uri) -> Option<String> {
+    if uri.path() != "/" {
+        return None;
+    }
+    let mut params = url::form_urlencoded::parse(uri.query()?.as_bytes());
+    match (params.next(), params.next()) {
+        (Some((name, id)), None) if name == "item" && is_valid_item_id(&id) => Some(id.into_owned()),
+        _ => None,
+    }
+}
+
+/// Middleware that sets the item's social card as the Open Graph (and
+/// Twitter) image of the index document served for the items permalinks, so
+/// that the card is displayed when they are shared on social networks.
+async fn inject_item_card_meta<B>(
+    State(landscape_dir): State<PathBuf>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    // Only the permalinks of the items with a social card are updated
+    let Some(item_id) = get_item_id(req.uri()) else {
+        return next.run(req).await;
+    };
+    if !item_card_exists(&landscape_dir, &item_id) {
+        return next.run(req).await;
+    }
+
+    // Prepare the card url (social networks require absolute urls)
+    let Some(host) = req.headers().get(header::HOST).and_then(|value| value.to_str().ok()) else {
+        return next.run(req).await;
+    };
+    let scheme = req
+        .headers()
+        .get("x-forwarded-proto")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("http");
+    let card_url = format!("{scheme}://{host}/{CARDS_PATH}/{item_id}.png");
+
+    let resp = next.run(req).await;
+    if resp.status() != StatusCode::OK {
+        return resp;
+    }
+
+    // Replace the landscape image meta tags with the item's card ones
+    let (mut parts, resp_body) = resp.into_parts();
+    let Ok(html) = hyper::body::to_bytes(resp_body).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let html = IMAGE_META_TAG.replace_all(&String::from_utf8_lossy(&html), "").replace(
+        "</head>",
+        &format!(
+            r#"<meta property="og:image" content="{card_url}" />
+    <meta name="twitter:card" content="summary_large_image" />
+    <meta name="twitter:image" content="{card_url}" />
+  </head>"#
+        ),
+    );
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.remove(header::ACCEPT_RANGES);
+
+    Response::from_parts(parts, body::boxed(Full::from(html)))
+}
+
+/// Check if the social card of the item provided is available.
+fn item_card_exists(landscape_dir: &Path, item_id: &str) -> bool {
+    landscape_dir.join(CARDS_PATH).join(format!("{item_id}.png")).is_file()
+}
+
+/// Check if the item id provided is valid (ids only contain lowercase
+/// alphanumeric characters and dashes), so that it can be used safely to
+/// locate the item's card.
+fn is_valid_item_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
 /// Middleware that sets the cache control header in the response.
 pub(crate) async fn set_cache_control_header<B>(req: Request<B>, next: Next<B>) -> impl IntoResponse {
     // Prepare header value (based on the request uri)
@@ -88,4 +231,24 @@ async fn shutdown_signal() {
         () = ctrl_c => {},
         () = terminate => {},
     }
+    info!("shutdown signal received, waiting for connections to be closed..");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_item_id_from_permalinks() {
+        let item_id = |uri: &str| get_item_id(&uri.parse().unwrap());
+        assert_eq!(
+            item_id("/?item=cat--subcat--item-1"),
+            Some("cat--subcat--item-1".to_string())
+        );
+        assert_eq!(item_id("/"), None);
+        assert_eq!(item_id("/?item="), None);
+        assert_eq!(item_id("/?item=../index"), None);
+        assert_eq!(item_id("/?item=cat--subcat--item&tab=explore"), None);
+        assert_eq!(item_id("/guide?item=cat--subcat--item"), None);
+    }
 }
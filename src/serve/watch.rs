@@ -0,0 +1,228 @@
+//! This module defines the functionality used to watch the landscape sources
+//! for changes, rebuilding the landscape and asking the browsers connected to
+//! reload it when they change.
+
+use crate::{
+    build::{build, BuildConfig},
+    DataSource, GuideSource, LogosSource, SettingsSource,
+};
+use anyhow::{format_err, Result};
+use axum::{
+    body::{self, Full},
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::stream::{self, Stream};
+use std::{
+    convert::Infallible,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    time::sleep,
+};
+use tracing::{error, info, instrument};
+use walkdir::WalkDir;
+
+/// Path of the endpoint used to notify browsers that they should reload.
+pub(crate) const LIVE_RELOAD_PATH: &str = "/_live-reload";
+
+/// Script injected in the html documents served to reload them when the
+/// landscape is rebuilt.
+const LIVE_RELOAD_SCRIPT: &str =
+    r#"<script>new EventSource("/_live-reload").onmessage = () => window.location.reload();</script>"#;
+
+/// How often the landscape sources are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the sources must remain unchanged before triggering a rebuild
+/// (so that multiple rapid saves only trigger one rebuild).
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watch the landscape sources in the directory provided, rebuilding the
+/// landscape when they change and notifying the browsers connected once the
+/// new version is ready. Build errors are reported, but they don't stop the
+/// watcher.
+#[instrument(skip_all, err)]
+pub(crate) async fn watch(
+    sources_dir: PathBuf,
+    output_dir: PathBuf,
+    reload_tx: broadcast::Sender<()>,
+) -> Result<()> {
+    let sources = Sources::new(&sources_dir)?;
+    let build_config = sources.build_config(output_dir);
+    info!(?sources_dir, "watching landscape sources for changes");
+
+    // Build landscape so that the version served is up to date
+    rebuild(&build_config, &reload_tx).await;
+
+    let mut fingerprint = sources.fingerprint();
+    loop {
+        sleep(POLL_INTERVAL).await;
+        let mut current_fingerprint = sources.fingerprint();
+        if current_fingerprint == fingerprint {
+            continue;
+        }
+
+        // Wait until the sources stop changing
+        loop {
+            sleep(DEBOUNCE_INTERVAL).await;
+            let latest_fingerprint = sources.fingerprint();
+            if latest_fingerprint == current_fingerprint {
+                break;
+            }
+            current_fingerprint = latest_fingerprint;
+        }
+        fingerprint = current_fingerprint;
+
+        info!("changes detected in landscape sources, rebuilding..");
+        rebuild(&build_config, &reload_tx).await;
+    }
+}
+
+/// Rebuild the landscape, notifying the browsers connected when done.
+async fn rebuild(build_config: &BuildConfig, reload_tx: &broadcast::Sender<()>) {
+    match build(build_config).await {
+        Ok(_) => {
+            // An error here only means that there are no browsers connected
+            _ = reload_tx.send(());
+        }
+        Err(err) => error!("error rebuilding landscape: {err:?}"),
+    }
+}
+
+/// Handler that streams an event to the browser each time the landscape is
+/// rebuilt (server-sent events).
+pub(crate) async fn live_reload(
+    reload_rx: broadcast::Receiver<()>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = stream::unfold(reload_rx, |mut reload_rx| async move {
+        match reload_rx.recv().await {
+            Ok(()) | Err(RecvError::Lagged(_)) => Some((Ok(Event::default().data("reload")), reload_rx)),
+            Err(RecvError::Closed) => None,
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Middleware that injects the live reload script in the html documents.
+pub(crate) async fn inject_live_reload_script<B>(req: Request<B>, next: Next<B>) -> Response {
+    let resp = next.run(req).await;
+
+    // Only html documents need the script (partial responses to range
+    // requests are returned as is, as the script cannot be injected in them)
+    if resp.status() != StatusCode::OK {
+        return resp;
+    }
+    let is_html = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if !is_html {
+        return resp;
+    }
+
+    // Inject script before the closing body tag
+    let (mut parts, resp_body) = resp.into_parts();
+    let Ok(html) = hyper::body::to_bytes(resp_body).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let html = String::from_utf8_lossy(&html).replace("</body>", &format!("{LIVE_RELOAD_SCRIPT}</body>"));
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    // The document served is not the file anymore, so range requests cannot
+    // be served consistently for it
+    parts.headers.remove(header::ACCEPT_RANGES);
+
+    Response::from_parts(parts, body::boxed(Full::from(html)))
+}
+
+/// Landscape sources files, as laid out by the new subcommand.
+struct Sources {
+    data_file: PathBuf,
+    guide_file: Option<PathBuf>,
+    logos_path: PathBuf,
+    settings_file: PathBuf,
+}
+
+impl Sources {
+    /// Create a new Sources instance from the directory provided.
+    fn new(sources_dir: &Path) -> Result<Self> {
+        let data_file = ["data.yml", "landscape.yml"]
+            .iter()
+            .map(|file| sources_dir.join(file))
+            .find(|path| path.exists())
+            .ok_or_else(|| format_err!("data file (data.yml or landscape.yml) not found in sources dir"))?;
+        let settings_file = sources_dir.join("settings.yml");
+        if !settings_file.exists() {
+            return Err(format_err!(
+                "settings file (settings.yml) not found in sources dir"
+            ));
+        }
+        let guide_file = Some(sources_dir.join("guide.yml")).filter(|path| path.exists());
+
+        Ok(Self {
+            data_file,
+            guide_file,
+            logos_path: sources_dir.join("logos"),
+            settings_file,
+        })
+    }
+
+    /// Prepare the configuration used to build the landscape from these
+    /// sources.
+    fn build_config(&self, output_dir: PathBuf) -> BuildConfig {
+        BuildConfig {
+            data_source: DataSource {
+                data_file: Some(self.data_file.clone()),
+                data_repo: None,
+                data_url: None,
+            },
+            disable_analytics: true,
+            guide_source: GuideSource {
+                guide_file: self.guide_file.clone(),
+                guide_url: None,
+            },
+            incremental: true,
+            logos_source: LogosSource {
+                logos_path: Some(self.logos_path.clone()),
+                logos_url: None,
+            },
+            output_dir,
+            settings_source: SettingsSource {
+                settings_file: Some(self.settings_file.clone()),
+                settings_url: None,
+            },
+            ..BuildConfig::default()
+        }
+    }
+
+    /// Get a fingerprint of the sources files (their paths and modification
+    /// times), used to detect changes.
+    fn fingerprint(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let modified_at = |path: &Path| fs::metadata(path).and_then(|md| md.modified()).ok();
+
+        let mut fingerprint = vec![
+            (self.data_file.clone(), modified_at(&self.data_file)),
+            (self.settings_file.clone(), modified_at(&self.settings_file)),
+        ];
+        if let Some(guide_file) = &self.guide_file {
+            fingerprint.push((guide_file.clone(), modified_at(guide_file)));
+        }
+        for entry in WalkDir::new(&self.logos_path).sort_by_file_name().into_iter().flatten() {
+            if entry.file_type().is_file() {
+                fingerprint.push((entry.path().to_owned(), modified_at(entry.path())));
+            }
+        }
+
+        fingerprint
+    }
+}
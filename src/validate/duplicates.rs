@@ -0,0 +1,179 @@
+//! This module defines the functionality used to detect items that are likely
+//! duplicated in the landscape data file (validate --check-duplicates).
+//!
+//! Items are considered suspected duplicates when their normalized names match
+//! or when they share the same homepage or repository url.
+
+use super::{
+    data_source_location, next_item_line,
+    output::{Finding, Severity, DUPLICATE_ITEM},
+    read_items_lines,
+};
+use crate::{
+    build::{Item, LandscapeData},
+    ValidateDataArgs,
+};
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap};
+use tracing::instrument;
+
+/// Check that the landscape data provided does not contain items that are
+/// likely duplicated, returning a finding for each suspected duplicate.
+#[instrument(skip_all, err)]
+pub(crate) fn duplicates_findings(
+    args: &ValidateDataArgs,
+    landscape_data: &LandscapeData,
+) -> Result<Vec<Finding>> {
+    let mut findings = vec![];
+
+    for cluster in find_duplicates(args, landscape_data)? {
+        for (item, line) in &cluster.items {
+            let others: Vec<&str> = cluster
+                .items
+                .iter()
+                .filter(|(other, other_line)| other.id != item.id || other_line != line)
+                .map(|(other, _)| other.name.as_str())
+                .collect();
+            findings.push(Finding {
+                rule_id: DUPLICATE_ITEM,
+                severity: Severity::Error,
+                message: format!(
+                    "item {} ({} / {}) is likely a duplicate of {} ({})",
+                    item.name,
+                    item.category,
+                    item.subcategory,
+                    others.join(", "),
+                    cluster.reasons.join(", ")
+                ),
+                file: data_source_location(&args.data_source),
+                line: *line,
+                column: None,
+            });
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Cluster of items that are likely duplicated.
+#[derive(Debug, Clone, Default)]
+struct Cluster<'a> {
+    items: Vec<(&'a Item, Option<usize>)>,
+    reasons: Vec<&'static str>,
+}
+
+/// Find the clusters of items that are likely duplicated. Items are grouped
+/// transitively (if A matches B and B matches C, all of them are reported
+/// together).
+fn find_clusters<'a>(items: &[&'a Item]) -> Vec<Cluster<'a>> {
+    // Link items sharing any of their keys
+    let mut parents: Vec<usize> = (0..items.len()).collect();
+    let mut reasons: HashMap<usize, BTreeSet<&'static str>> = HashMap::new();
+    let mut keys_seen: HashMap<(&'static str, String), usize> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        for (reason, key) in item_keys(item) {
+            if key.is_empty() {
+                continue;
+            }
+            match keys_seen.get(&(reason, key.clone())) {
+                Some(&j) => {
+                    let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                    parents[root_i] = root_j;
+                    let mut merged = reasons.remove(&root_i).unwrap_or_default();
+                    merged.extend(reasons.remove(&root_j).unwrap_or_default());
+                    merged.insert(reason);
+                    reasons.insert(root_j, merged);
+                }
+                None => {
+                    keys_seen.insert((reason, key), i);
+                }
+            }
+        }
+    }
+
+    // Group items by cluster, keeping the order in which they are defined
+    let mut clusters: Vec<(usize, Cluster)> = vec![];
+    for (i, item) in items.iter().enumerate() {
+        let root = find_root(&mut parents, i);
+        let Some(cluster_reasons) = reasons.get(&root) else {
+            continue;
+        };
+        if let Some((_, cluster)) = clusters.iter_mut().find(|(r, _)| *r == root) {
+            cluster.items.push((*item, None));
+        } else {
+            clusters.push((
+                root,
+                Cluster {
+                    items: vec![(*item, None)],
+                    reasons: cluster_reasons.iter().copied().collect(),
+                },
+            ));
+        }
+    }
+
+    clusters.into_iter().map(|(_, cluster)| cluster).collect()
+}
+
+/// Find the clusters of suspected duplicate items in the landscape data
+/// provided, including the line where each item is defined in the data file
+/// (when it's available locally).
+fn find_duplicates<'a>(
+    args: &ValidateDataArgs,
+    landscape_data: &'a LandscapeData,
+) -> Result<Vec<Cluster<'a>>> {
+    // Items explicitly allowed to be repeated are not checked
+    let allowed: Vec<String> = args.allow_duplicate.iter().map(|name| name_key(name)).collect();
+    let items: Vec<&Item> = landscape_data
+        .items
+        .iter()
+        .filter(|item| !allowed.contains(&name_key(&item.name)))
+        .collect();
+
+    // Find clusters and set the location of their items
+    let mut items_lines = read_items_lines(&args.data_source)?;
+    let mut clusters = find_clusters(&items);
+    for cluster in &mut clusters {
+        for (item, line) in &mut cluster.items {
+            *line = next_item_line(&mut items_lines, &item.name);
+        }
+    }
+
+    Ok(clusters)
+}
+
+/// Return the root of the cluster the item provided belongs to.
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Return the keys used to detect duplicates for the item provided.
+fn item_keys(item: &Item) -> Vec<(&'static str, String)> {
+    let mut keys = vec![
+        ("same name", name_key(&item.name)),
+        ("same homepage url", url_key(&item.homepage_url)),
+    ];
+    for repo in item.repositories.iter().flatten() {
+        keys.push(("same repository url", url_key(&repo.url)));
+    }
+    keys
+}
+
+/// Return the key used to compare the name provided (lowercase and only
+/// alphanumeric characters, so that "Open Telemetry" and "OpenTelemetry"
+/// match).
+fn name_key(name: &str) -> String {
+    name.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_lowercase()
+}
+
+/// Return the key used to compare the url provided (ignoring the scheme, the
+/// www prefix and trailing slashes or .git suffixes).
+fn url_key(url: &str) -> String {
+    let url = url.trim().to_lowercase();
+    let url = url.trim_start_matches("https://").trim_start_matches("http://");
+    let url = url.trim_start_matches("www.");
+    url.trim_end_matches('/').trim_end_matches(".git").to_string()
+}
@@ -0,0 +1,209 @@
+//! This module defines the functionality used to apply some safe automatic
+//! fixes to the landscape data file (validate --fix).
+//!
+//! Fixes are applied to the file content line by line instead of serializing
+//! the data again, so that comments and formatting are preserved.
+
+use crate::build::legacy;
+use anyhow::{format_err, Context, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{fs, path::Path};
+use tracing::instrument;
+
+/// Keys whose url values should not end with a slash.
+const URL_KEYS: [&str; 3] = ["crunchbase", "repo_url", "twitter"];
+
+/// Valid values of the project key (maturity).
+const PROJECT_VALUES: [&str; 4] = ["archived", "graduated", "incubating", "sandbox"];
+
+lazy_static! {
+    /// Key/value line regular expression.
+    static ref KEY_VALUE: Regex =
+        Regex::new(r"^(?P<prefix>\s*(?:- )?)(?P<key>[a-z_]+):(?P<sep>\s+)(?P<value>\S.*)$")
+            .expect("exprs in KEY_VALUE to be valid");
+
+    /// TAG name regular expression (case insensitive).
+    static ref TAG_NAME: Regex = Regex::new(r"^[a-zA-Z\-]+$").expect("exprs in TAG_NAME to be valid");
+}
+
+/// Apply some safe automatic fixes to the landscape data file provided,
+/// printing the changes made.
+#[instrument(err)]
+pub(crate) fn fix_data_file(file: &Path) -> Result<()> {
+    let original = fs::read_to_string(file).context("error reading data file")?;
+
+    // Apply fixes
+    let mut lines: Vec<String> = original.lines().map(ToString::to_string).collect();
+    let mut changes = fix_lines(&mut lines);
+    changes.extend(sort_items(&mut lines));
+    if changes.is_empty() {
+        println!("No automatic fixes were needed in the landscape data file.");
+        return Ok(());
+    }
+    let mut fixed = lines.join("\n");
+    fixed.push('\n');
+
+    // Make sure the fixed content is still a valid landscape data file before
+    // writing it
+    if serde_yaml::from_str::<legacy::LandscapeData>(&fixed).is_err() {
+        return Err(format_err!(
+            "automatic fixes could not be applied safely (the data file must be parsed successfully first)"
+        ));
+    }
+    fs::write(file, fixed).context("error writing data file")?;
+
+    // Display changes
+    println!("The following fixes were applied to the landscape data file:\n");
+    for change in changes {
+        println!("{change}");
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Apply the fixes that only affect a single line (removing trailing
+/// whitespace, removing the trailing slash of urls and lowercasing known enum
+/// values), returning the changes made.
+fn fix_lines(lines: &mut [String]) -> Vec<String> {
+    let mut changes = vec![];
+
+    for (i, line) in lines.iter_mut().enumerate() {
+        let trimmed = line.trim_end().to_string();
+        let mut fixed = trimmed.clone();
+
+        if let Some(c) = KEY_VALUE.captures(&trimmed) {
+            let (prefix, key, sep, value) = (&c["prefix"], &c["key"], &c["sep"], &c["value"]);
+
+            // Values including comments or quotes are left untouched
+            if !value.contains(" #") && !value.starts_with(['"', '\'']) {
+                let fixed_value = if URL_KEYS.contains(&key) && value.starts_with("http") {
+                    value.trim_end_matches('/').to_string()
+                } else if (key == "project" && PROJECT_VALUES.contains(&value.to_lowercase().as_str()))
+                    || (key == "tag" && TAG_NAME.is_match(value))
+                {
+                    value.to_lowercase()
+                } else {
+                    value.to_string()
+                };
+                fixed = format!("{prefix}{key}:{sep}{fixed_value}");
+            }
+        }
+
+        if fixed != *line {
+            changes.push(format!("line {}:\n  - {line}\n  + {fixed}", i + 1));
+            *line = fixed;
+        }
+    }
+
+    changes
+}
+
+/// Return the indentation of the line provided.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Check if the line provided is blank or a comment.
+fn is_blank_or_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// Sort alphabetically (by name) the items in each subcategory, returning the
+/// changes made. Comments preceding an item are moved along with it. The
+/// order of categories and subcategories is not modified as it defines how
+/// they are displayed.
+fn sort_items(lines: &mut [String]) -> Vec<String> {
+    let mut changes = vec![];
+
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].trim() != "items:" {
+            i += 1;
+            continue;
+        }
+        let items_line = i;
+        let items_indent = indentation(&lines[items_line]);
+        i += 1;
+
+        // Find the items of the list (including the comments preceding them)
+        let mut blocks: Vec<(usize, usize)> = vec![];
+        let mut block_start = i;
+        let mut item_indent = None;
+        let mut end = i;
+        while i < lines.len() {
+            let line = &lines[i];
+            if is_blank_or_comment(line) {
+                i += 1;
+                continue;
+            }
+            let indent = indentation(line);
+            if indent <= items_indent {
+                break;
+            }
+            if line.trim() == "- item:" && item_indent.map_or(true, |item_indent| item_indent == indent) {
+                if item_indent.is_some() {
+                    // Previous block ends before the comments preceding this item
+                    let mut start = i;
+                    while start > block_start && lines[start - 1].trim().starts_with('#') {
+                        start -= 1;
+                    }
+                    blocks.push((block_start, start));
+                    block_start = start;
+                }
+                item_indent = Some(indent);
+            }
+            i += 1;
+            end = i;
+        }
+        let Some(item_indent) = item_indent else {
+            continue;
+        };
+        blocks.push((block_start, end));
+
+        // Sort items by name (only when all of them have one)
+        let names: Option<Vec<String>> = blocks
+            .iter()
+            .map(|(start, end)| item_name(&lines[*start..*end], item_indent + 2))
+            .collect();
+        let Some(names) = names else {
+            continue;
+        };
+        let mut order: Vec<usize> = (0..blocks.len()).collect();
+        order.sort_by_key(|j| names[*j].to_lowercase());
+        if order.iter().enumerate().all(|(pos, j)| pos == *j) {
+            continue;
+        }
+        let sorted: Vec<String> =
+            order.iter().flat_map(|j| lines[blocks[*j].0..blocks[*j].1].to_vec()).collect();
+        let list_start = blocks[0].0;
+        lines[list_start..end].clone_from_slice(&sorted);
+
+        let subcategory = lines[..items_line]
+            .iter()
+            .rev()
+            .find(|line| indentation(line) == items_indent && line.trim_start().starts_with("name:"))
+            .map(|line| line.trim_start().trim_start_matches("name:").trim().to_string())
+            .unwrap_or_default();
+        changes.push(format!(
+            "line {}:\n  items of subcategory {subcategory} sorted alphabetically",
+            items_line + 1
+        ));
+    }
+
+    changes
+}
+
+/// Return the name of the item defined in the lines provided, where the item
+/// keys use the indentation given.
+fn item_name(lines: &[String], keys_indent: usize) -> Option<String> {
+    lines.iter().find_map(|line| {
+        if indentation(line) != keys_indent {
+            return None;
+        }
+        let name = line.trim_start().strip_prefix("name:")?.trim();
+        Some(name.trim_matches(&['"', '\''][..]).to_string())
+    })
+}
@@ -0,0 +1,223 @@
+//! This module defines the functionality used to check that the logos used by
+//! the landscape items are not too big (validate --check-logos).
+//!
+//! Logos are checked against a size budget, which is different for SVG and
+//! raster logos. Raster logos (or SVG logos embedding raster images) are
+//! reported as well, as an SVG version of them should be used instead. The
+//! logos files not referenced by any item are reported as warnings, so that
+//! they can be removed.
+
+use super::{
+    data_source_location, next_item_line,
+    output::{Finding, Severity, MISSING_LOGO, ORPHANED_LOGO, OVERSIZED_LOGO, RASTER_LOGO},
+    read_items_lines,
+};
+use crate::{
+    build::{find_orphaned_logos, LandscapeData},
+    ValidateDataArgs,
+};
+use anyhow::{format_err, Result};
+use lazy_static::lazy_static;
+use mime_guess::mime;
+use regex::bytes::Regex;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs,
+    io::ErrorKind,
+    path::Path,
+};
+use tracing::instrument;
+
+lazy_static! {
+    /// SVG embedded raster image regular expression.
+    static ref SVG_EMBEDDED_RASTER: Regex =
+        Regex::new(r#"(?i)<image\b[^>]*href\s*=\s*["']data:image/(?:png|jpe?g|gif|webp|bmp)"#)
+            .expect("exprs in SVG_EMBEDDED_RASTER to be valid");
+}
+
+/// Check that the logos used by the items in the landscape data provided are
+/// within the size budgets, returning a finding for each problem found.
+#[instrument(skip_all, err)]
+pub(crate) fn logos_findings(
+    args: &ValidateDataArgs,
+    landscape_data: &LandscapeData,
+) -> Result<Vec<Finding>> {
+    let mut findings: Vec<Finding> = find_logos_problems(args, landscape_data)?
+        .into_iter()
+        .map(|problem| Finding {
+            rule_id: problem.kind.rule_id(),
+            severity: problem.kind.severity(),
+            message: problem.message(),
+            file: data_source_location(&args.data_source),
+            line: problem.line,
+            column: None,
+        })
+        .collect();
+    if let Some(logos_path) = &args.logos_path {
+        findings.extend(
+            find_orphaned(args, landscape_data)?.into_iter().map(|logo| Finding {
+                rule_id: ORPHANED_LOGO,
+                severity: Severity::Warning,
+                message: format!("logo {logo} is not referenced by any item"),
+                file: Some(logos_path.join(&logo).display().to_string()),
+                line: None,
+                column: None,
+            }),
+        );
+    }
+
+    Ok(findings)
+}
+
+/// Problem found in the logo of an item.
+#[derive(Debug, Clone)]
+struct LogoProblem<'a> {
+    item_name: &'a str,
+    logo: &'a str,
+    kind: LogoProblemKind,
+    line: Option<usize>,
+}
+
+impl LogoProblem<'_> {
+    /// Return a description of the problem.
+    fn message(&self) -> String {
+        let (item_name, logo) = (self.item_name, self.logo);
+        match &self.kind {
+            LogoProblemKind::Missing => format!("item {item_name}: logo {logo} not found"),
+            LogoProblemKind::Oversized { size, budget } => format!(
+                "item {item_name}: logo {logo} is {} KB (budget: {budget} KB)",
+                to_kb(*size)
+            ),
+            LogoProblemKind::Raster { size, embedded } => {
+                let kind = if *embedded {
+                    "embeds a raster image"
+                } else {
+                    "is a raster image"
+                };
+                format!(
+                    "item {item_name}: logo {logo} {kind} ({} KB), an SVG logo should be used instead",
+                    to_kb(*size)
+                )
+            }
+        }
+    }
+}
+
+/// Kind of problem found in a logo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogoProblemKind {
+    Missing,
+    Oversized { size: u64, budget: u64 },
+    Raster { size: u64, embedded: bool },
+}
+
+impl LogoProblemKind {
+    /// Return the rule used to report the problem as a finding.
+    fn rule_id(self) -> &'static str {
+        match self {
+            LogoProblemKind::Missing => MISSING_LOGO,
+            LogoProblemKind::Oversized { .. } => OVERSIZED_LOGO,
+            LogoProblemKind::Raster { .. } => RASTER_LOGO,
+        }
+    }
+
+    /// Return the severity of the problem.
+    fn severity(self) -> Severity {
+        match self {
+            LogoProblemKind::Missing | LogoProblemKind::Oversized { .. } => Severity::Error,
+            LogoProblemKind::Raster { .. } => Severity::Warning,
+        }
+    }
+}
+
+/// Check the logo file provided, returning the problems found.
+fn check_logo(args: &ValidateDataArgs, logos_path: &Path, logo: &str) -> Result<Vec<LogoProblemKind>> {
+    let data = match fs::read(logos_path.join(logo)) {
+        Ok(data) => data,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![LogoProblemKind::Missing]),
+        Err(err) => return Err(format_err!("error reading logo {logo}: {err}")),
+    };
+    let size = data.len() as u64;
+
+    // Raster logos are checked against their own budget
+    let mut problems = vec![];
+    let raster = is_raster(logo);
+    let budget = if raster {
+        args.raster_logo_size_budget
+    } else {
+        args.svg_logo_size_budget
+    };
+    if size > budget.saturating_mul(1024) {
+        problems.push(LogoProblemKind::Oversized { size, budget });
+    }
+    if raster || SVG_EMBEDDED_RASTER.is_match(&data) {
+        problems.push(LogoProblemKind::Raster {
+            size,
+            embedded: !raster,
+        });
+    }
+
+    Ok(problems)
+}
+
+/// Find the problems in the logos used by the items in the landscape data
+/// provided, including the line where each item is defined in the data file
+/// (when it's available locally). Each logo file is only checked once, even if
+/// it's used by multiple items.
+fn find_logos_problems<'a>(
+    args: &ValidateDataArgs,
+    landscape_data: &'a LandscapeData,
+) -> Result<Vec<LogoProblem<'a>>> {
+    let Some(logos_path) = &args.logos_path else {
+        return Err(format_err!(
+            "checking logos requires a local logos path (--logos-path)"
+        ));
+    };
+
+    let mut logos_checked: HashMap<&str, Vec<LogoProblemKind>> = HashMap::new();
+    let mut items_lines = read_items_lines(&args.data_source)?;
+    let mut problems = vec![];
+    for item in &landscape_data.items {
+        let line = next_item_line(&mut items_lines, &item.name);
+        let kinds = match logos_checked.entry(&item.logo) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(check_logo(args, logos_path, &item.logo)?),
+        };
+        for kind in &*kinds {
+            problems.push(LogoProblem {
+                item_name: &item.name,
+                logo: &item.logo,
+                kind: *kind,
+                line,
+            });
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Find the logos files in the logos path that are not referenced by any of
+/// the items in the landscape data provided.
+fn find_orphaned(args: &ValidateDataArgs, landscape_data: &LandscapeData) -> Result<Vec<String>> {
+    let Some(logos_path) = &args.logos_path else {
+        return Err(format_err!(
+            "checking logos requires a local logos path (--logos-path)"
+        ));
+    };
+    find_orphaned_logos(
+        logos_path,
+        landscape_data.items.iter().map(|item| item.logo.as_str()),
+    )
+}
+
+/// Check if the logo file provided is a raster image (based on its extension).
+fn is_raster(logo: &str) -> bool {
+    mime_guess::from_path(logo)
+        .first()
+        .is_some_and(|mime_type| mime_type.type_() == mime::IMAGE && mime_type.subtype() != mime::SVG)
+}
+
+/// Convert the size provided (in bytes) to KB, rounding up.
+fn to_kb(size: u64) -> u64 {
+    (size + 1023) / 1024
+}
@@ -1,41 +1,356 @@
 //! This module defines the functionality of the validate CLI subcommand.
 
 use crate::{
-    build::{LandscapeData, LandscapeGuide, LandscapeSettings},
-    DataSource, GuideSource, SettingsSource,
+    build::{DataFormat, LandscapeData, LandscapeGuide, LandscapeSettings},
+    DataSource, ValidateDataArgs, ValidateGuideArgs, ValidateSettingsArgs,
 };
-use anyhow::{Context, Result};
+use anyhow::{format_err, Context, Result};
+use duplicates::duplicates_findings;
+use fix::fix_data_file;
+use futures::stream::{self, StreamExt};
+use lazy_static::lazy_static;
+use logos::logos_findings;
+pub(crate) use output::OutputFormat;
+use output::{print_findings, Finding, Severity, BROKEN_URL, INVALID_DATA, INVALID_GUIDE, INVALID_SETTINGS};
+use projects_status::projects_status_findings;
+use regex::Regex;
+use reqwest::StatusCode;
+use rules::rules_findings;
+use std::{collections::HashMap, fs, time::Duration};
 use tracing::instrument;
 
+mod duplicates;
+mod fix;
+mod logos;
+mod output;
+mod projects_status;
+mod rules;
+
+lazy_static! {
+    /// Item context regular expression (used in the data validation errors).
+    static ref ITEM_CONTEXT: Regex =
+        Regex::new(r"item \[(?P<name>.+?)\] is not valid").expect("exprs in ITEM_CONTEXT to be valid");
+}
+
 /// Validate landscape data file.
 #[instrument(skip_all)]
-pub(crate) async fn validate_data(data_source: &DataSource) -> Result<()> {
-    LandscapeData::new(data_source)
-        .await
-        .context("the landscape data file provided is not valid")?;
+pub(crate) async fn validate_data(args: &ValidateDataArgs) -> Result<()> {
+    // Apply automatic fixes to the data file (when requested)
+    if args.fix {
+        if args.output_format != OutputFormat::Text {
+            return Err(format_err!(
+                "fix mode is only supported with the text output format"
+            ));
+        }
+        let Some(file) = &args.data_source.data_file else {
+            return Err(format_err!("fix mode requires a local data file (--data-file)"));
+        };
+        if DataFormat::detect(args.data_format, &file.to_string_lossy()) != DataFormat::Yaml {
+            return Err(format_err!("fix mode is only supported with YAML data files"));
+        }
+        fix_data_file(file)?;
+    }
 
-    println!("The landscape data file provided is valid!");
-    Ok(())
+    // Run all the checks requested and report the problems found, so that all
+    // of them are reported at once
+    let findings = validate_data_findings(args).await?;
+    print_findings(args.output_format, &findings, args.fail_on_warning)
 }
 
-/// Validate landscape settings file.
+/// Validate landscape data file, returning the problems found as findings.
+async fn validate_data_findings(args: &ValidateDataArgs) -> Result<Vec<Finding>> {
+    let mut findings = vec![];
+
+    match LandscapeData::new(&args.data_source, args.data_format, None).await {
+        Ok(landscape_data) => {
+            // Check for duplicate items (when requested)
+            if args.check_duplicates {
+                findings.extend(duplicates_findings(args, &landscape_data)?);
+            }
+
+            // Check landscape validation rules (when provided)
+            if args.rules_file.is_some() {
+                findings.extend(rules_findings(args, &landscape_data)?);
+            }
+
+            // Check items logos (when requested)
+            if args.check_logos {
+                findings.extend(logos_findings(args, &landscape_data)?);
+            }
+
+            // Check items urls (when requested)
+            if args.check_urls {
+                findings.extend(broken_urls_findings(args, &landscape_data).await?);
+            }
+
+            // Check items maturity against the projects status list (when provided)
+            if let Some(src) = &args.projects_status {
+                findings.extend(projects_status_findings(args, &landscape_data, src).await?);
+            }
+        }
+        Err(err) => {
+            // Point to the item that is not valid when the error doesn't
+            // include a location
+            let mut finding =
+                Finding::from_error(INVALID_DATA, &err, data_source_location(&args.data_source));
+            if finding.line.is_none() {
+                let item_name = err.chain().find_map(|cause| {
+                    ITEM_CONTEXT.captures(&cause.to_string()).map(|c| c["name"].to_string())
+                });
+                if let Some(item_name) = item_name {
+                    let items_lines = read_items_lines(&args.data_source)?;
+                    finding.line = items_lines.get(&item_name).and_then(|lines| lines.first()).copied();
+                }
+            }
+            findings.push(finding);
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Check that the items' external urls are reachable, returning a finding for
+/// each of the urls that are not.
+#[instrument(skip_all, err)]
+async fn broken_urls_findings(
+    args: &ValidateDataArgs,
+    landscape_data: &LandscapeData,
+) -> Result<Vec<Finding>> {
+    let items_lines = read_items_lines(&args.data_source)?;
+    let findings = find_broken_urls(args, landscape_data)
+        .await?
+        .into_iter()
+        .map(|broken_url| Finding {
+            rule_id: BROKEN_URL,
+            severity: Severity::Error,
+            message: format!(
+                "{} {} {} is not reachable: {}",
+                broken_url.item_name, broken_url.kind, broken_url.url, broken_url.error
+            ),
+            file: data_source_location(&args.data_source),
+            line: items_lines.get(broken_url.item_name).and_then(|lines| lines.first()).copied(),
+            column: None,
+        })
+        .collect();
+
+    Ok(findings)
+}
+
+/// Check that the url provided is reachable, returning a description of the
+/// problem found otherwise. Some servers do not support HEAD requests, so when
+/// they fail we try again using GET.
+async fn check_url(http_client: &reqwest::Client, url: &str) -> Result<(), String> {
+    let status = match http_client.head(url).send().await {
+        Ok(resp) if is_success(resp.status()) => return Ok(()),
+        Ok(_) | Err(_) => http_client.get(url).send().await.map(|resp| resp.status()),
+    };
+    match status {
+        Ok(status) if is_success(status) => Ok(()),
+        Ok(status) => Err(status.to_string()),
+        Err(err) if err.is_timeout() => Err("timed out".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Return the location of the data source provided (used in the findings).
+fn data_source_location(data_source: &DataSource) -> Option<String> {
+    if let Some(file) = &data_source.data_file {
+        return Some(file.display().to_string());
+    }
+    data_source.data_url.clone().or_else(|| data_source.data_repo.clone())
+}
+
+/// Check the items' external urls, returning the ones that are not reachable.
+/// Each url is checked once, even if it's used by multiple items.
+async fn find_broken_urls<'a>(
+    args: &ValidateDataArgs,
+    landscape_data: &'a LandscapeData,
+) -> Result<Vec<BrokenUrl<'a>>> {
+    if args.concurrency == 0 {
+        return Err(format_err!("concurrency must be greater than 0"));
+    }
+
+    // Collect the urls to check for each item
+    let mut items_urls: Vec<(&str, &'static str, &str)> = vec![];
+    for item in &landscape_data.items {
+        items_urls.push((&item.name, "homepage", &item.homepage_url));
+        for repo in item.repositories.iter().flatten() {
+            items_urls.push((&item.name, "repository", &repo.url));
+        }
+        if let Some(url) = &item.crunchbase_url {
+            items_urls.push((&item.name, "crunchbase", url));
+        }
+        if let Some(url) = &item.twitter_url {
+            items_urls.push((&item.name, "twitter", url));
+        }
+    }
+    items_urls.retain(|(_, _, url)| !args.skip_url.iter().any(|skipped| url.starts_with(skipped)));
+
+    // Check each url once, even if it's used by multiple items
+    let mut urls: Vec<&str> = items_urls.iter().map(|(_, _, url)| *url).collect();
+    urls.sort_unstable();
+    urls.dedup();
+    if args.output_format == OutputFormat::Text {
+        println!("Checking {} urls..", urls.len());
+    }
+    let http_client = reqwest::Client::builder()
+        .user_agent(format!(
+            "{}/{}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ))
+        .timeout(Duration::from_secs(args.timeout))
+        .build()?;
+    let http_client = &http_client;
+    let failures: HashMap<&str, String> = stream::iter(urls)
+        .map(|url| async move {
+            let result = check_url(http_client, url).await;
+            (url, result)
+        })
+        .buffer_unordered(args.concurrency)
+        .filter_map(|(url, result)| async move { result.err().map(|err| (url, err)) })
+        .collect()
+        .await;
+
+    // Match broken urls with the items using them
+    let broken_urls = items_urls
+        .into_iter()
+        .filter_map(|(item_name, kind, url)| {
+            failures.get(url).map(|err| BrokenUrl {
+                item_name,
+                kind,
+                url,
+                error: err.clone(),
+            })
+        })
+        .collect();
+
+    Ok(broken_urls)
+}
+
+/// Check if the status code provided means the url is reachable.
+fn is_success(status: StatusCode) -> bool {
+    !status.is_client_error() && !status.is_server_error()
+}
+
+/// Return the next line in the data file where an item with the name provided
+/// is defined (items with the same name are returned in order).
+fn next_item_line(items_lines: &mut HashMap<String, Vec<usize>>, name: &str) -> Option<usize> {
+    let lines = items_lines.get_mut(name)?;
+    if lines.is_empty() {
+        return None;
+    }
+    Some(lines.remove(0))
+}
+
+/// Return the lines where each of the categories are defined in the data file
+/// provided, grouped by category name (only available for local data files).
+fn read_categories_lines(data_source: &DataSource) -> Result<HashMap<String, Vec<usize>>> {
+    read_entries_lines(data_source, "- category:")
+}
+
+/// Return the lines where the entries (categories or items) starting with the
+/// line provided are defined in the data file, grouped by name (only available
+/// for local data files).
+fn read_entries_lines(data_source: &DataSource, entry_line: &str) -> Result<HashMap<String, Vec<usize>>> {
+    let mut entries_lines: HashMap<String, Vec<usize>> = HashMap::new();
+    let Some(file) = &data_source.data_file else {
+        return Ok(entries_lines);
+    };
+    let content = fs::read_to_string(file).context("error reading data file")?;
+
+    let lines: Vec<&str> = content.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim() != entry_line {
+            continue;
+        }
+        let keys_indent = line.len() - line.trim_start().len() + 2;
+        let name = lines[i + 1..]
+            .iter()
+            .take_while(|l| l.trim().is_empty() || l.len() - l.trim_start().len() >= keys_indent)
+            .find_map(|l| {
+                if l.len() - l.trim_start().len() != keys_indent {
+                    return None;
+                }
+                l.trim_start()
+                    .strip_prefix("name:")
+                    .map(|name| name.trim().trim_matches(&['"', '\''][..]))
+            });
+        if let Some(name) = name {
+            entries_lines.entry(name.to_string()).or_default().push(i + 1);
+        }
+    }
+
+    Ok(entries_lines)
+}
+
+/// Return the lines where each of the items are defined in the data file
+/// provided, grouped by item name (only available for local data files).
+fn read_items_lines(data_source: &DataSource) -> Result<HashMap<String, Vec<usize>>> {
+    read_entries_lines(data_source, "- item:")
+}
+
+/// Validate landscape guide file.
 #[instrument(skip_all)]
-pub(crate) async fn validate_settings(settings_source: &SettingsSource) -> Result<()> {
-    LandscapeSettings::new(settings_source)
+pub(crate) async fn validate_guide(args: &ValidateGuideArgs) -> Result<()> {
+    let result = LandscapeGuide::new(&args.guide_source)
         .await
-        .context("the landscape settings file provided is not valid")?;
+        .context("the landscape guide file provided is not valid");
 
-    println!("The landscape settings file provided is valid!");
+    // Report the problems found as findings (machine-readable formats)
+    if args.output_format != OutputFormat::Text {
+        let location = args
+            .guide_source
+            .guide_file
+            .as_ref()
+            .map(|file| file.display().to_string())
+            .or_else(|| args.guide_source.guide_url.clone());
+        let findings: Vec<Finding> = result
+            .err()
+            .map(|err| Finding::from_error(INVALID_GUIDE, &err, location))
+            .into_iter()
+            .collect();
+        return print_findings(args.output_format, &findings, false);
+    }
+
+    result?;
+    println!("The landscape guide file provided is valid!");
     Ok(())
 }
 
-/// Validate landscape guide file.
+/// Validate landscape settings file.
 #[instrument(skip_all)]
-pub(crate) async fn validate_guide(guide_source: &GuideSource) -> Result<()> {
-    LandscapeGuide::new(guide_source)
+pub(crate) async fn validate_settings(args: &ValidateSettingsArgs) -> Result<()> {
+    let result = LandscapeSettings::new(&args.settings_source, None, args.settings_format)
         .await
-        .context("the landscape guide file provided is not valid")?;
+        .context("the landscape settings file provided is not valid");
 
-    println!("The landscape guide file provided is valid!");
+    // Report the problems found as findings (machine-readable formats)
+    if args.output_format != OutputFormat::Text {
+        let location = args
+            .settings_source
+            .settings_file
+            .as_ref()
+            .map(|file| file.display().to_string())
+            .or_else(|| args.settings_source.settings_url.clone());
+        let findings: Vec<Finding> = result
+            .err()
+            .map(|err| Finding::from_error(INVALID_SETTINGS, &err, location))
+            .into_iter()
+            .collect();
+        return print_findings(args.output_format, &findings, false);
+    }
+
+    result?;
+    println!("The landscape settings file provided is valid!");
     Ok(())
 }
+
+/// Item url that is not reachable.
+#[derive(Debug, Clone)]
+struct BrokenUrl<'a> {
+    item_name: &'a str,
+    kind: &'static str,
+    url: &'a str,
+    error: String,
+}
@@ -0,0 +1,271 @@
+//! This module defines the functionality used to report the problems found by
+//! the validate subcommand, as text or in a machine-readable format (JSON or
+//! SARIF) so that they can be consumed by other tools (i.e. GitHub code
+//! scanning).
+//!
+//! Each problem found is reported as a finding, classified using one of the
+//! rules defined below and including its location in the file validated when
+//! it's known.
+
+use anyhow::{format_err, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+use std::fmt;
+
+/// Rule used to report broken items urls.
+pub(crate) const BROKEN_URL: &str = "broken-url";
+
+/// Rule used to report suspected duplicate items.
+pub(crate) const DUPLICATE_ITEM: &str = "duplicate-item";
+
+/// Rule used to report invalid landscape data.
+pub(crate) const INVALID_DATA: &str = "invalid-data";
+
+/// Rule used to report invalid landscape guides.
+pub(crate) const INVALID_GUIDE: &str = "invalid-guide";
+
+/// Rule used to report invalid landscape settings.
+pub(crate) const INVALID_SETTINGS: &str = "invalid-settings";
+
+/// Rule used to report items whose maturity (or TAG) disagrees with the
+/// projects status list.
+pub(crate) const MATURITY_MISMATCH: &str = "maturity-mismatch";
+
+/// Rule used to report items logos that could not be found.
+pub(crate) const MISSING_LOGO: &str = "missing-logo";
+
+/// Rule used to report logos files not referenced by any item.
+pub(crate) const ORPHANED_LOGO: &str = "orphaned-logo";
+
+/// Rule used to report items logos exceeding the size budget.
+pub(crate) const OVERSIZED_LOGO: &str = "oversized-logo";
+
+/// Rule used to report items raster logos.
+pub(crate) const RASTER_LOGO: &str = "raster-logo";
+
+/// Rule used to report violations of the landscape validation rules.
+pub(crate) const RULE_VIOLATION: &str = "rule-violation";
+
+/// Rule used to report YAML syntax (or structure) errors.
+pub(crate) const INVALID_YAML: &str = "invalid-yaml";
+
+/// Rules used to classify the problems found.
+const RULES: [(&str, &str); 12] = [
+    (BROKEN_URL, "Item url is not reachable"),
+    (DUPLICATE_ITEM, "Item is likely duplicated"),
+    (INVALID_DATA, "Landscape data file is not valid"),
+    (INVALID_GUIDE, "Landscape guide file is not valid"),
+    (INVALID_SETTINGS, "Landscape settings file is not valid"),
+    (INVALID_YAML, "File is not a valid YAML document"),
+    (
+        MATURITY_MISMATCH,
+        "Item maturity disagrees with the projects status list",
+    ),
+    (MISSING_LOGO, "Item logo could not be found"),
+    (ORPHANED_LOGO, "Logo is not referenced by any item"),
+    (OVERSIZED_LOGO, "Item logo exceeds the size budget"),
+    (RASTER_LOGO, "Item logo is a raster image"),
+    (
+        RULE_VIOLATION,
+        "Item does not follow the landscape validation rules",
+    ),
+];
+
+/// SARIF schema url.
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// SARIF format version.
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Format of the validate subcommand output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[allow(clippy::module_name_repetitions)]
+pub enum OutputFormat {
+    Json,
+    Sarif,
+    #[default]
+    Text,
+}
+
+/// Problem found while validating a landscape file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Finding {
+    pub rule_id: &'static str,
+    pub severity: Severity,
+    pub message: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl Finding {
+    /// Create a new finding from the error provided. When the error was
+    /// produced by the YAML parser, its location is used and the invalid YAML
+    /// rule is applied.
+    pub(crate) fn from_error(rule_id: &'static str, err: &anyhow::Error, file: Option<String>) -> Self {
+        let location = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<serde_yaml::Error>())
+            .and_then(serde_yaml::Error::location);
+        Self {
+            rule_id: if location.is_some() { INVALID_YAML } else { rule_id },
+            severity: Severity::Error,
+            message: format!("{err:#}"),
+            file,
+            line: location.as_ref().map(serde_yaml::Location::line),
+            column: location.as_ref().map(serde_yaml::Location::column),
+        }
+    }
+}
+
+/// Severity of a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+}
+
+/// Number of problems found, by severity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct ProblemsSummary {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl ProblemsSummary {
+    /// Create a new summary from the findings provided.
+    pub(crate) fn from_findings(findings: &[Finding]) -> Self {
+        let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+        Self {
+            errors,
+            warnings: findings.len() - errors,
+        }
+    }
+
+    /// Return an error if the problems found should make the command fail.
+    /// Errors always do, whereas warnings only do it when requested.
+    pub(crate) fn check(self, fail_on_warning: bool) -> Result<()> {
+        if self.errors > 0 {
+            return Err(format_err!("{} errors found", self.errors));
+        }
+        if fail_on_warning && self.warnings > 0 {
+            return Err(format_err!(
+                "{} warnings found (and --fail-on-warning was provided)",
+                self.warnings
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check if the problems found should make the command fail.
+    fn is_failure(self, fail_on_warning: bool) -> bool {
+        self.check(fail_on_warning).is_err()
+    }
+}
+
+impl fmt::Display for ProblemsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Summary: {} errors, {} warnings", self.errors, self.warnings)
+    }
+}
+
+/// Print the findings provided using the output format given, returning an
+/// error if any errors were found (or warnings, when failing on warnings has
+/// been requested), so that the command fails in all output formats.
+pub(crate) fn print_findings(
+    output_format: OutputFormat,
+    findings: &[Finding],
+    fail_on_warning: bool,
+) -> Result<()> {
+    let summary = ProblemsSummary::from_findings(findings);
+    match output_format {
+        OutputFormat::Json => {
+            let output = json!({
+                "valid": !summary.is_failure(fail_on_warning),
+                "summary": summary,
+                "findings": findings,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Sarif => {
+            println!("{}", serde_json::to_string_pretty(&sarif_log(findings))?);
+            eprintln!("{summary}");
+        }
+        OutputFormat::Text => {
+            if findings.is_empty() {
+                println!("No problems found!");
+            }
+            for finding in findings {
+                let location = match (&finding.file, finding.line) {
+                    (Some(file), Some(line)) => format!("{file}:{line}: "),
+                    (Some(file), None) => format!("{file}: "),
+                    _ => String::new(),
+                };
+                println!("- {location}{} [{}]", finding.message, finding.rule_id);
+            }
+            println!("{summary}");
+        }
+    }
+
+    summary.check(fail_on_warning)
+}
+
+/// Prepare a SARIF log including the findings provided.
+fn sarif_log(findings: &[Finding]) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = RULES
+        .iter()
+        .map(|(id, description)| json!({ "id": id, "shortDescription": { "text": description } }))
+        .collect();
+    let results: Vec<serde_json::Value> = findings
+        .iter()
+        .map(|finding| {
+            let mut result = json!({
+                "ruleId": finding.rule_id,
+                "level": match finding.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                },
+                "message": { "text": finding.message },
+            });
+            if let Some(file) = &finding.file {
+                let mut region = json!({});
+                if let Some(line) = finding.line {
+                    region["startLine"] = json!(line);
+                }
+                if let Some(column) = finding.column {
+                    region["startColumn"] = json!(column);
+                }
+                let mut physical_location = json!({ "artifactLocation": { "uri": file } });
+                if finding.line.is_some() {
+                    physical_location["region"] = region;
+                }
+                result["locations"] = json!([{ "physicalLocation": physical_location }]);
+            }
+            result
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
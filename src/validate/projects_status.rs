@@ -0,0 +1,42 @@
+//! This module defines the functionality used to check that the maturity (and
+//! TAG) declared by the items match the ones in an authoritative projects
+//! status list (validate --projects-status).
+
+use super::{
+    data_source_location,
+    output::{Finding, Severity, MATURITY_MISMATCH},
+    read_items_lines,
+};
+use crate::{
+    build::{HttpClient, LandscapeData, ProjectsStatus, DEFAULT_COLLECT_CONCURRENCY},
+    ValidateDataArgs,
+};
+use anyhow::Result;
+use tracing::instrument;
+
+/// Check that the items maturity (and TAG) match the projects status list,
+/// returning a finding for each mismatch.
+#[instrument(skip_all, err)]
+pub(crate) async fn projects_status_findings(
+    args: &ValidateDataArgs,
+    landscape_data: &LandscapeData,
+    src: &str,
+) -> Result<Vec<Finding>> {
+    let items_lines = read_items_lines(&args.data_source)?;
+    let http = HttpClient::new(DEFAULT_COLLECT_CONCURRENCY)?;
+    let findings = ProjectsStatus::new(src, &http)
+        .await?
+        .find_mismatches(landscape_data)
+        .into_iter()
+        .map(|mismatch| Finding {
+            rule_id: MATURITY_MISMATCH,
+            severity: Severity::Warning,
+            message: mismatch.to_string(),
+            file: data_source_location(&args.data_source),
+            line: items_lines.get(&mismatch.item_name).and_then(|lines| lines.first()).copied(),
+            column: None,
+        })
+        .collect();
+
+    Ok(findings)
+}
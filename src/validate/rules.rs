@@ -0,0 +1,315 @@
+//! This module defines the functionality used to enforce the validation rules
+//! defined by each landscape in a rules file (validate --rules-file), on top of
+//! the built-in data checks.
+//!
+//! The rules file is a YAML file with the following format, where fields use
+//! the same names as in the landscape data file (fields of the `extra` section
+//! included) and all entries are optional:
+//!
+//! ```yaml
+//! # Categories that cannot be used in the landscape.
+//! forbidden_categories:
+//!   - Category name
+//!
+//! rules:
+//!   - name: Projects must provide a repository
+//!     # Categories and subcategories the rule applies to (all when omitted).
+//!     categories: [Category name]
+//!     subcategories: [Subcategory name]
+//!     # Fields that must (or must not) be provided.
+//!     required: [repo_url]
+//!     forbidden: [crunchbase]
+//!     # Values allowed for some fields.
+//!     allowed_values:
+//!       project: [graduated, incubating]
+//! ```
+
+use super::{
+    data_source_location, next_item_line,
+    output::{Finding, Severity, RULE_VIOLATION},
+    read_categories_lines, read_items_lines,
+};
+use crate::{
+    build::{Item, LandscapeData, PackageRegistry},
+    ValidateDataArgs,
+};
+use anyhow::{format_err, Context, Result};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path};
+use tracing::instrument;
+
+/// Items fields that can be used in the rules (as named in the data file).
+const FIELDS: [&str; 33] = [
+    "accepted",
+    "additional_repos",
+    "artwork_url",
+    "blog_url",
+    "chat_channel",
+    "clomonitor_name",
+    "crate_name",
+    "crunchbase",
+    "description",
+    "dev_stats_url",
+    "discord_url",
+    "docker_image",
+    "docker_url",
+    "enduser",
+    "github_discussions_url",
+    "graduated",
+    "homepage_url",
+    "incubating",
+    "joined",
+    "logo",
+    "mailing_list_url",
+    "npm_package",
+    "project",
+    "repo_url",
+    "slack_url",
+    "stack_overflow_url",
+    "summary_personas",
+    "summary_tags",
+    "tag",
+    "twitter",
+    "unnamed_organization",
+    "url_for_bestpractices",
+    "youtube_url",
+];
+
+/// Landscape validation rules.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ValidationRules {
+    #[serde(default)]
+    pub forbidden_categories: Vec<String>,
+
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl ValidationRules {
+    /// Create a new validation rules instance from the file provided.
+    #[instrument(err)]
+    pub(crate) fn new(file: &Path) -> Result<Self> {
+        let raw_data = fs::read_to_string(file).context("error reading rules file")?;
+        let rules: ValidationRules = serde_yaml::from_str(&raw_data).context("error parsing rules file")?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    /// Validate the rules, making sure they only use known fields.
+    fn validate(&self) -> Result<()> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            let fields = rule.required.iter().chain(&rule.forbidden).chain(rule.allowed_values.keys());
+            for field in fields {
+                if !FIELDS.contains(&field.as_str()) {
+                    return Err(format_err!(
+                        "rule [{}] is not valid: unknown field {field} (supported fields: {})",
+                        rule.name.clone().unwrap_or_else(|| (i + 1).to_string()),
+                        FIELDS.join(", ")
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validation rule applied to the items of some categories or subcategories.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Rule {
+    pub name: Option<String>,
+
+    #[serde(default)]
+    pub categories: Vec<String>,
+
+    #[serde(default)]
+    pub subcategories: Vec<String>,
+
+    #[serde(default)]
+    pub required: Vec<String>,
+
+    #[serde(default)]
+    pub forbidden: Vec<String>,
+
+    #[serde(default)]
+    pub allowed_values: BTreeMap<String, Vec<String>>,
+}
+
+impl Rule {
+    /// Check if the rule applies to the item provided.
+    fn applies_to(&self, item: &Item) -> bool {
+        (self.categories.is_empty() || self.categories.contains(&item.category))
+            && (self.subcategories.is_empty() || self.subcategories.contains(&item.subcategory))
+    }
+
+    /// Return a description of the rule to be used in the violations reported.
+    fn description(&self) -> String {
+        match &self.name {
+            Some(name) => format!(" (rule: {name})"),
+            None => String::new(),
+        }
+    }
+}
+
+/// Check that the landscape data provided follows the validation rules in the
+/// rules file, returning a finding for each violation.
+#[instrument(skip_all, err)]
+pub(crate) fn rules_findings(
+    args: &ValidateDataArgs,
+    landscape_data: &LandscapeData,
+) -> Result<Vec<Finding>> {
+    let findings = find_violations(args, landscape_data)?
+        .into_iter()
+        .map(|violation| Finding {
+            rule_id: RULE_VIOLATION,
+            severity: Severity::Error,
+            message: violation.message,
+            file: data_source_location(&args.data_source),
+            line: violation.line,
+            column: None,
+        })
+        .collect();
+
+    Ok(findings)
+}
+
+/// Validation rule violation.
+#[derive(Debug, Clone)]
+struct Violation {
+    message: String,
+    line: Option<usize>,
+}
+
+/// Find the violations of the validation rules in the landscape data
+/// provided, including the line of the data file where they happen (when
+/// it's available locally).
+fn find_violations(args: &ValidateDataArgs, landscape_data: &LandscapeData) -> Result<Vec<Violation>> {
+    let Some(rules_file) = &args.rules_file else {
+        return Ok(vec![]);
+    };
+    let rules = ValidationRules::new(rules_file)?;
+    let mut violations = vec![];
+
+    // Check forbidden categories
+    let categories_lines = read_categories_lines(&args.data_source)?;
+    for category in &landscape_data.categories {
+        if rules.forbidden_categories.contains(&category.name) {
+            violations.push(Violation {
+                message: format!("category {} is forbidden", category.name),
+                line: categories_lines.get(&category.name).and_then(|lines| lines.first()).copied(),
+            });
+        }
+    }
+
+    // Check items rules (items are processed in the order they are defined,
+    // so that items with the same name are matched with their location)
+    let mut items_lines = read_items_lines(&args.data_source)?;
+    for item in &landscape_data.items {
+        let line = next_item_line(&mut items_lines, &item.name);
+        for rule in rules.rules.iter().filter(|rule| rule.applies_to(item)) {
+            let mut messages = vec![];
+            for field in &rule.required {
+                if field_values(item, field).is_empty() {
+                    messages.push(format!("required field {field} not provided"));
+                }
+            }
+            for field in &rule.forbidden {
+                if !field_values(item, field).is_empty() {
+                    messages.push(format!("forbidden field {field} provided"));
+                }
+            }
+            for (field, allowed_values) in &rule.allowed_values {
+                for value in field_values(item, field) {
+                    if !allowed_values.contains(&value) {
+                        messages.push(format!(
+                            "value {value} not allowed in field {field} (allowed values: {})",
+                            allowed_values.join(", ")
+                        ));
+                    }
+                }
+            }
+            for message in messages {
+                violations.push(Violation {
+                    message: format!(
+                        "item {} ({} / {}): {message}{}",
+                        item.name,
+                        item.category,
+                        item.subcategory,
+                        rule.description()
+                    ),
+                    line,
+                });
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+/// Return the values of the item field provided (the field is named as in the
+/// data file). An empty list is returned when the field is not set.
+fn field_values(item: &Item, field: &str) -> Vec<String> {
+    let summary = item.summary.as_ref();
+    let value = match field {
+        "accepted" => item.accepted_at.map(|date| date.to_string()),
+        "additional_repos" => {
+            return item
+                .repositories
+                .iter()
+                .flatten()
+                .filter(|repo| repo.primary != Some(true))
+                .map(|repo| repo.url.clone())
+                .collect()
+        }
+        "artwork_url" => item.artwork_url.clone(),
+        "blog_url" => item.blog_url.clone(),
+        "chat_channel" => item.chat_channel.clone(),
+        "clomonitor_name" => item.clomonitor_name.clone(),
+        "crate_name" => package_name(item, PackageRegistry::Crates),
+        "crunchbase" => item.crunchbase_url.clone(),
+        "description" => item.description.clone(),
+        "dev_stats_url" => item.devstats_url.clone(),
+        "discord_url" => item.discord_url.clone(),
+        "docker_image" => package_name(item, PackageRegistry::Docker),
+        "docker_url" => item.docker_url.clone(),
+        "enduser" => item.enduser.map(|enduser| enduser.to_string()),
+        "github_discussions_url" => item.github_discussions_url.clone(),
+        "graduated" => item.graduated_at.map(|date| date.to_string()),
+        "homepage_url" => Some(item.homepage_url.clone()),
+        "incubating" => item.incubating_at.map(|date| date.to_string()),
+        "joined" => item.joined_at.map(|date| date.to_string()),
+        "logo" => Some(item.logo.clone()),
+        "mailing_list_url" => item.mailing_list_url.clone(),
+        "npm_package" => package_name(item, PackageRegistry::Npm),
+        "project" => item.maturity.clone(),
+        "repo_url" => item
+            .repositories
+            .iter()
+            .flatten()
+            .find(|repo| repo.primary == Some(true))
+            .map(|repo| repo.url.clone()),
+        "slack_url" => item.slack_url.clone(),
+        "stack_overflow_url" => item.stack_overflow_url.clone(),
+        "summary_personas" => {
+            return summary.and_then(|summary| summary.personas.clone()).unwrap_or_default();
+        }
+        "summary_tags" => return summary.and_then(|summary| summary.tags.clone()).unwrap_or_default(),
+        "tag" => item.tag.clone(),
+        "twitter" => item.twitter_url.clone(),
+        "unnamed_organization" => item.unnamed_organization.map(|unnamed| unnamed.to_string()),
+        "url_for_bestpractices" => item.openssf_best_practices_url.clone(),
+        "youtube_url" => item.youtube_url.clone(),
+        _ => None,
+    };
+    value.into_iter().filter(|value| !value.is_empty()).collect()
+}
+
+/// Return the name of the item package published in the registry provided.
+fn package_name(item: &Item, registry: PackageRegistry) -> Option<String> {
+    item.packages
+        .iter()
+        .flatten()
+        .find(|package| package.registry == registry)
+        .map(|package| package.name.clone())
+}
@@ -61,7 +61,7 @@ const App = () => {
   });
 
   return (
-    <Router>
+    <Router base={window.baseHref}>
       <Routes>
         <Route path="/" element={<Layout data={data()} />}>
           <Route path="/" element={<Explore initialData={data()} />} />
@@ -4,6 +4,9 @@ import './App.css';
 import { render } from 'solid-js/web';
 
 import App from './App';
+import applyCategoriesAliases from './utils/applyCategoriesAliases';
+
+applyCategoriesAliases();
 
 const root = document.getElementById('landscape');
 
@@ -6,7 +6,7 @@ import sortBy from 'lodash/sortBy';
 import moment from 'moment';
 import { createEffect, createSignal, For, Match, on, Show, Switch } from 'solid-js';
 
-import { Item, Repository, SecurityAudit, SVGIconKind } from '../../../types';
+import { FundingRound, Item, Repository, SecurityAudit, SVGIconKind } from '../../../types';
 import formatProfitLabel from '../../../utils/formatLabelProfit';
 import getItemDescription from '../../../utils/getItemDescription';
 import { formatTAGName } from '../../../utils/prepareFilters';
@@ -42,6 +42,16 @@ const Content = (props: Props) => {
     return moment(date).format("MMM 'YY");
   };
 
+  const formatFundingRound = (round: FundingRound): string => {
+    const details = compact([
+      // Crunchbase investment types look like series_a, pre_seed, etc
+      round.kind ? round.kind.replace(/_/g, ' ').replace(/\b\w/g, (c: string) => c.toUpperCase()) : undefined,
+      !isUndefined(round.amount) ? `$${prettifyNumber(round.amount)}` : undefined,
+      round.announced_on ? formatDate(round.announced_on) : undefined,
+    ]);
+    return details.length > 0 ? details.join(' · ') : '-';
+  };
+
   createEffect(
     on(itemInfo, () => {
       if (!isUndefined(itemInfo()) && !isNull(itemInfo())) {
@@ -276,7 +286,9 @@ const Content = (props: Props) => {
                 <div class="row g-4 my-0 mb-2">
                   <Box value={prettifyNumber(mainRepo()!.github_data!.stars, 1)} legend="Stars" />
 
-                  <Box value={prettifyNumber(mainRepo()!.github_data!.contributors.count)} legend="Contributors" />
+                  <Show when={!isUndefined(mainRepo()!.github_data!.contributors)}>
+                    <Box value={prettifyNumber(mainRepo()!.github_data!.contributors!.count)} legend="Contributors" />
+                  </Show>
 
                   <Box value={formatDate(mainRepo()!.github_data!.first_commit.ts)} legend="First commit" />
 
@@ -295,7 +307,7 @@ const Content = (props: Props) => {
                 <Show when={!isUndefined(mainRepo()!.github_data!.participation_stats)}>
                   <div class="mt-4">
                     <div class={`fw-semibold ${styles.subtitleInSection}`}>Participation stats</div>
-                    <ParticipationStats initialStats={mainRepo()!.github_data!.participation_stats} />
+                    <ParticipationStats initialStats={mainRepo()!.github_data!.participation_stats!} />
                   </div>
                 </Show>
 
@@ -528,6 +540,16 @@ const Content = (props: Props) => {
 
               <Box value={itemInfo()!.crunchbase_data!.ticker || '-'} legend="Ticker" />
             </div>
+            <Show when={!isUndefined(itemInfo()!.crunchbase_data!.latest_funding_round)}>
+              <div class="mb-2">
+                <small class="text-muted">
+                  Latest funding round:{' '}
+                  <span class="fw-semibold">
+                    {formatFundingRound(itemInfo()!.crunchbase_data!.latest_funding_round!)}
+                  </span>
+                </small>
+              </div>
+            </Show>
           </div>
         </Show>
         {/* Summary */}
@@ -166,11 +166,13 @@ const MobileContent = (props: Props) => {
               <div class="row g-4 my-0 mx-1 mb-2 justify-content-center">
                 <Box class="col-6" value={prettifyNumber(mainRepo()!.github_data!.stars, 1)} legend="Stars" />
 
-                <Box
-                  class="col-6"
-                  value={prettifyNumber(mainRepo()!.github_data!.contributors.count)}
-                  legend="Contributors"
-                />
+                <Show when={!isUndefined(mainRepo()!.github_data!.contributors)}>
+                  <Box
+                    class="col-6"
+                    value={prettifyNumber(mainRepo()!.github_data!.contributors!.count)}
+                    legend="Contributors"
+                  />
+                </Show>
 
                 <Box class="col-6" value={formatDate(mainRepo()!.github_data!.first_commit.ts)} legend="First commit" />
 
@@ -195,7 +197,7 @@ const MobileContent = (props: Props) => {
                 <div class="mt-4">
                   <div class={`fw-semibold ${styles.subtitleInSection}`}>Participation stats</div>
                   <div class="mx-2">
-                    <ParticipationStats initialStats={mainRepo()!.github_data!.participation_stats} />
+                    <ParticipationStats initialStats={mainRepo()!.github_data!.participation_stats!} />
                   </div>
                 </div>
               </Show>
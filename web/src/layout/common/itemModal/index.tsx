@@ -5,6 +5,7 @@ import { createEffect, createSignal, Show } from 'solid-js';
 import { SMALL_DEVICES_BREAKPOINTS } from '../../../data';
 import useBreakpointDetect from '../../../hooks/useBreakpointDetect';
 import { Item } from '../../../types';
+import getItemRedirect from '../../../utils/getItemRedirect';
 import itemsDataGetter from '../../../utils/itemsDataGetter';
 import { useActiveItemId, useUpdateActiveItemId } from '../../stores/activeItem';
 import { useFullDataReady } from '../../stores/fullData';
@@ -25,6 +26,14 @@ const ItemModal = () => {
     async function fetchItemInfo() {
       try {
         const itemTmp = await itemsDataGetter.findById(visibleItemId()! as string);
+        if (isUndefined(itemTmp)) {
+          // Follow the permalinks of the items that have been moved
+          const newItemId = await getItemRedirect(visibleItemId()! as string);
+          if (newItemId) {
+            updateActiveItemId(newItemId);
+            return;
+          }
+        }
         setItemInfo(itemTmp);
       } catch {
         setItemInfo(null);
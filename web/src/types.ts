@@ -1,3 +1,8 @@
+export interface CategoryAliasTarget {
+  category: string;
+  subcategory?: string;
+}
+
 export interface BaseData {
   foundation: string;
   guide_summary?: GuideSummary;
@@ -11,6 +16,9 @@ export interface BaseData {
   grid_items_size?: string;
   groups?: Group[];
   categories: Category[];
+  categories_aliases?: {
+    [key: string]: CategoryAliasTarget;
+  };
   categories_overridden?: string[];
   items: BaseItem[];
   members_category?: string;
@@ -45,6 +53,7 @@ export interface Category {
 
 export interface BaseItem {
   id: string;
+  additional_categories?: AdditionalCategory[];
   category: string;
   oss?: boolean;
   name: string;
@@ -56,6 +65,11 @@ export interface BaseItem {
   tag?: string;
 }
 
+export interface AdditionalCategory {
+  category: string;
+  subcategory: string;
+}
+
 export interface Featured {
   label?: string;
   order?: number;
@@ -112,6 +126,7 @@ export interface Organization {
   homepage_url?: string;
   categories: string[];
   kind?: string;
+  latest_funding_round?: FundingRound;
   linkedin_url?: string;
   name?: string;
   num_employees_max?: number;
@@ -122,6 +137,12 @@ export interface Organization {
   twitter_url?: string;
 }
 
+export interface FundingRound {
+  amount?: number;
+  announced_on?: string;
+  kind?: string;
+}
+
 export interface ItemSummary {
   business_use_case?: string;
   integration?: string;
@@ -141,7 +162,7 @@ export interface Repository {
 }
 
 export interface GithubRepository {
-  contributors: Contributors;
+  contributors?: Contributors;
   description: string;
   first_commit: Commit;
   generated_at: number;
@@ -149,7 +170,7 @@ export interface GithubRepository {
   latest_commit: Commit;
   latest_release?: Release;
   license: string;
-  participation_stats: number[];
+  participation_stats?: number[];
   stars: number;
   url: string;
 }
@@ -0,0 +1,42 @@
+import { CATEGORY_PARAM, SUBCATEGORY_PARAM } from '../data';
+
+const VALID_CHARS = /[^a-z0-9\- ]/g;
+
+// Normalize the name provided the same way the slugs of the aliases are built
+const normalizeName = (value: string): string => {
+  return value.toLowerCase().replace(/ /g, '-').replace(VALID_CHARS, '').replace(/--/g, '-');
+};
+
+// Replace the previous category (and subcategory) names in the query string
+// with the current ones, so that the permalinks shared before the categories
+// were renamed keep working
+const applyCategoriesAliases = () => {
+  const aliases = window.baseDS.categories_aliases;
+  if (!aliases) return;
+
+  const params = new URLSearchParams(window.location.search);
+  const category = params.get(CATEGORY_PARAM);
+  if (!category) return;
+  const subcategory = params.get(SUBCATEGORY_PARAM);
+
+  const categorySlug = normalizeName(category);
+  let target;
+  if (subcategory) {
+    target = aliases[`${categorySlug}/${normalizeName(subcategory)}`];
+  }
+  if (!target) {
+    const categoryTarget = aliases[categorySlug];
+    if (!categoryTarget) return;
+    target = { ...categoryTarget, subcategory: categoryTarget.subcategory || subcategory || undefined };
+  }
+
+  params.set(CATEGORY_PARAM, target.category);
+  if (target.subcategory) {
+    params.set(SUBCATEGORY_PARAM, target.subcategory);
+  } else {
+    params.delete(SUBCATEGORY_PARAM);
+  }
+  window.history.replaceState(null, '', `${window.location.pathname}?${params.toString()}${window.location.hash}`);
+};
+
+export default applyCategoriesAliases;
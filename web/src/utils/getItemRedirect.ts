@@ -0,0 +1,16 @@
+// Map of the old ids of the items moved to the new ones, uploaded when the
+// landscape is deployed with items permalinks redirects (S3 redirects cannot
+// match the query string). It's only fetched when an item is not found.
+let itemsRedirects: Promise<{ [key: string]: string }> | undefined;
+
+// Get the new id of the item provided (when it has been moved)
+const getItemRedirect = async (itemId: string): Promise<string | undefined> => {
+  if (!itemsRedirects) {
+    itemsRedirects = fetch('./data/items-redirects.json')
+      .then((res) => (res.ok ? res.json() : {}))
+      .catch(() => ({}));
+  }
+  return (await itemsRedirects)[itemId];
+};
+
+export default getItemRedirect;
@@ -1,4 +1,4 @@
-import { ActiveSection, FilterOption, Item, LandscapeData } from '../types';
+import { ActiveSection, AdditionalCategory, FilterOption, Item, LandscapeData } from '../types';
 import capitalizeFirstLetter from './capitalizeFirstLetter';
 
 export interface ItemsDataStatus {
@@ -54,7 +54,12 @@ export class ItemsDataGetter {
   public filterItemsBySection(activeSection: ActiveSection): Item[] | undefined {
     if (this.ready && this.landscapeData && this.landscapeData.items) {
       return this.landscapeData.items.filter(
-        (i: Item) => activeSection.subcategory === i.subcategory && activeSection.category === i.category
+        (i: Item) =>
+          (activeSection.subcategory === i.subcategory && activeSection.category === i.category) ||
+          (i.additional_categories || []).some(
+            (ac: AdditionalCategory) =>
+              activeSection.subcategory === ac.subcategory && activeSection.category === ac.category
+          )
       );
     }
   }
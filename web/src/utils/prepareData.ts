@@ -1,4 +1,4 @@
-import { BaseData, BaseItem, Category, Group, Item } from '../types';
+import { AdditionalCategory, BaseData, BaseItem, Category, Group, Item } from '../types';
 
 export interface GroupData {
   [key: string]: CategoriesData;
@@ -33,13 +33,20 @@ const getCategoriesData = (categoriesList: Category[], items: (BaseItem | Item)[
   });
 
   items.forEach((item: BaseItem) => {
-    if (categories[item.category] && categories[item.category][item.subcategory]) {
-      categories[item.category][item.subcategory].items.push(item);
-      categories[item.category][item.subcategory].itemsCount++;
-      if (item.featured) {
-        categories[item.category][item.subcategory].itemsFeaturedCount++;
+    // Items are also listed in their additional categories
+    const itemCategories: AdditionalCategory[] = [
+      { category: item.category, subcategory: item.subcategory },
+      ...(item.additional_categories || []),
+    ];
+    itemCategories.forEach((ac: AdditionalCategory) => {
+      if (categories[ac.category] && categories[ac.category][ac.subcategory]) {
+        categories[ac.category][ac.subcategory].items.push(item);
+        categories[ac.category][ac.subcategory].itemsCount++;
+        if (item.featured) {
+          categories[ac.category][ac.subcategory].itemsFeaturedCount++;
+        }
       }
-    }
+    });
   });
 
   return categories;
@@ -3,6 +3,7 @@ import { BaseData, Stats } from './types';
 declare global {
   interface Window {
     baseDS: BaseData;
+    baseHref?: string;
     statsDS: Stats;
   }
 }